@@ -0,0 +1,14 @@
+//! Compile the gRPC control interface definition
+//!
+//! Uses protox so building doesn't need a system `protoc`.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto/control.proto");
+
+    let descriptors = protox::compile(["proto/control.proto"], ["proto"])?;
+    tonic_build::configure()
+        .build_client(false)
+        .compile_fds(descriptors)?;
+
+    Ok(())
+}
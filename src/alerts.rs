@@ -0,0 +1,964 @@
+//! Failure alerts and event notifications
+//!
+//! Notifies operators when sync cycles keep failing or first-boot setup
+//! fails, through every configured channel: email for appliances without
+//! chat-webhook infrastructure, a JSON webhook, an ntfy topic, or a D-Bus
+//! signal for agents on the device itself. Channels implement [`Notifier`],
+//! so adding one doesn't touch the sync code. Alert subjects and bodies are
+//! Tera templates over the alert's details and the sync summary; the
+//! built-in wording can be replaced per kind of alert in `[alerts.templates]`.
+//!
+//! `[notifications]` webhooks (JSON, ntfy or Gotify) are channels too,
+//! subscribed to events rather than failure streaks: setup completing or
+//! failing, the first failed sync of a streak, and apps added to or taken
+//! off the dashboard.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use lettre::message::Message;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use serde::Serialize;
+
+use crate::config::{
+    AlertTemplates, AlertsConfig, Config, DbusAlertConfig, EmailAlertConfig, MessageTemplate,
+    NotificationEvent, NotificationWebhook, NotificationsConfig, NtfyAlertConfig, SmtpSecurity,
+    WebhookAlertConfig, WebhookFormat,
+};
+use crate::error::{AdapterError, Result};
+
+/// Time allowed for a webhook or ntfy request
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Object path, interface and member of the D-Bus alert signal
+const DBUS_PATH: &str = "/org/halos/HomarrContainerAdapter";
+const DBUS_INTERFACE: &str = "org.halos.HomarrContainerAdapter";
+const DBUS_SIGNAL: &str = "Alert";
+
+const SYNC_FAILING_SUBJECT: &str =
+    "[homarr-container-adapter] {{ host }}: {{ failures }} consecutive sync failures";
+
+const SYNC_FAILING_BODY: &str = "\
+The Homarr dashboard on {{ host }} has failed to sync {{ failures }} times in a row.
+
+Last error: {{ error }}
+
+Check the service logs with: journalctl -u homarr-container-adapter
+";
+
+const SETUP_FAILED_SUBJECT: &str = "[homarr-container-adapter] {{ host }}: first-boot setup failed";
+
+const SETUP_FAILED_BODY: &str = "\
+First-boot setup of the Homarr dashboard on {{ host }} failed.
+
+Error: {{ error }}
+
+Check the service logs with: journalctl -u homarr-container-adapter
+";
+
+const SETUP_COMPLETED_SUBJECT: &str =
+    "[homarr-container-adapter] {{ host }}: first-boot setup complete";
+
+const SETUP_COMPLETED_BODY: &str = "\
+First-boot setup of the Homarr dashboard on {{ host }} completed.
+";
+
+const APPS_CHANGED_SUBJECT: &str =
+    "[homarr-container-adapter] {{ host }}: {{ added | length }} app(s) added, \
+{{ removed | length }} removed";
+
+const APPS_CHANGED_BODY: &str = "\
+The Homarr dashboard on {{ host }} changed.
+{% if added %}
+Added: {{ added | join(sep=\", \") }}
+{% endif %}{% if removed %}
+Removed: {{ removed | join(sep=\", \") }}
+{% endif %}";
+
+/// Wording of events without configurable templates
+static BUILTIN_TEMPLATE: MessageTemplate = MessageTemplate {
+    subject: None,
+    body: None,
+};
+
+/// An event worth alerting an operator about
+#[derive(Debug)]
+pub enum Alert<'a> {
+    /// Sync has failed this many times in a row
+    SyncFailing {
+        failures: u32,
+        error: &'a str,
+        /// When a sync last succeeded, if ever
+        last_success: Option<DateTime<Utc>>,
+    },
+    /// First-boot setup failed
+    SetupFailed { error: &'a str },
+    /// First-boot setup finished (notifications only)
+    SetupCompleted,
+    /// A sync created apps or took them off the dashboard (notifications
+    /// only); removals read like "Plex (hidden)"
+    AppsChanged {
+        added: &'a [String],
+        removed: &'a [String],
+        summary: SyncSummary<'a>,
+    },
+}
+
+/// What a sync did, for templates of the alerts it sends
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct SyncSummary<'a> {
+    /// Apps the sync wanted on the dashboard
+    pub apps: usize,
+    /// Writable boards it synced
+    pub boards: usize,
+    /// App-board combinations synced
+    pub synced: usize,
+    /// Change log lines, each with its time and reason
+    pub changes: &'a [String],
+}
+
+/// What alert templates can use
+#[derive(Debug, Serialize)]
+struct TemplateContext<'a> {
+    /// `sync_failing`, `setup_failed`, `setup_completed` or `apps_changed`
+    event: &'static str,
+    host: &'a str,
+    error: &'a str,
+    /// Consecutive failed syncs (0 for other events)
+    failures: u32,
+    /// RFC 3339 time of the last successful sync, empty if none
+    last_success: String,
+    /// Apps added and removed (empty for other events)
+    added: &'a [String],
+    removed: &'a [String],
+    /// The sync that sent the alert (zero and empty for other events)
+    summary: SyncSummary<'a>,
+}
+
+impl Alert<'_> {
+    fn event(&self) -> &'static str {
+        match self {
+            Alert::SyncFailing { .. } => "sync_failing",
+            Alert::SetupFailed { .. } => "setup_failed",
+            Alert::SetupCompleted => "setup_completed",
+            Alert::AppsChanged { .. } => "apps_changed",
+        }
+    }
+
+    /// The `[notifications]` event this alert is
+    fn notification_event(&self) -> NotificationEvent {
+        match self {
+            Alert::SyncFailing { .. } => NotificationEvent::SyncFailed,
+            Alert::SetupFailed { .. } => NotificationEvent::SetupFailed,
+            Alert::SetupCompleted => NotificationEvent::SetupCompleted,
+            Alert::AppsChanged { .. } => NotificationEvent::AppsChanged,
+        }
+    }
+
+    /// Whether the alert reports something going wrong
+    fn is_failure(&self) -> bool {
+        matches!(self, Alert::SyncFailing { .. } | Alert::SetupFailed { .. })
+    }
+
+    fn context<'a>(&'a self, host: &'a str) -> TemplateContext<'a> {
+        match self {
+            Alert::SyncFailing {
+                failures,
+                error,
+                last_success,
+            } => TemplateContext {
+                event: self.event(),
+                host,
+                error,
+                failures: *failures,
+                last_success: last_success.map(|t| t.to_rfc3339()).unwrap_or_default(),
+                added: &[],
+                removed: &[],
+                summary: SyncSummary::default(),
+            },
+            Alert::SetupFailed { error } => TemplateContext {
+                event: self.event(),
+                host,
+                error,
+                failures: 0,
+                last_success: String::new(),
+                added: &[],
+                removed: &[],
+                summary: SyncSummary::default(),
+            },
+            Alert::SetupCompleted => TemplateContext {
+                event: self.event(),
+                host,
+                error: "",
+                failures: 0,
+                last_success: String::new(),
+                added: &[],
+                removed: &[],
+                summary: SyncSummary::default(),
+            },
+            Alert::AppsChanged {
+                added,
+                removed,
+                summary,
+            } => TemplateContext {
+                event: self.event(),
+                host,
+                error: "",
+                failures: 0,
+                last_success: String::new(),
+                added,
+                removed,
+                summary: *summary,
+            },
+        }
+    }
+
+    /// Configured templates for this kind of alert, and the built-in ones
+    fn templates<'a>(
+        &self,
+        templates: &'a AlertTemplates,
+    ) -> (&'a MessageTemplate, &'static str, &'static str) {
+        match self {
+            Alert::SyncFailing { .. } => (
+                &templates.sync_failing,
+                SYNC_FAILING_SUBJECT,
+                SYNC_FAILING_BODY,
+            ),
+            Alert::SetupFailed { .. } => (
+                &templates.setup_failed,
+                SETUP_FAILED_SUBJECT,
+                SETUP_FAILED_BODY,
+            ),
+            Alert::SetupCompleted => (
+                &BUILTIN_TEMPLATE,
+                SETUP_COMPLETED_SUBJECT,
+                SETUP_COMPLETED_BODY,
+            ),
+            Alert::AppsChanged { .. } => (
+                &templates.apps_changed,
+                APPS_CHANGED_SUBJECT,
+                APPS_CHANGED_BODY,
+            ),
+        }
+    }
+}
+
+/// An alert rendered for delivery
+#[derive(Debug, Serialize)]
+pub struct Notification {
+    /// `sync_failing`, `setup_failed`, `setup_completed` or `apps_changed`
+    pub event: &'static str,
+    pub host: String,
+    pub subject: String,
+    pub body: String,
+    /// Apps added and removed, for `apps_changed`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<String>,
+    /// Whether the event reports something going wrong
+    #[serde(skip)]
+    pub failure: bool,
+}
+
+/// Render an alert with the configured templates, falling back to the
+/// built-in wording when one fails to render
+fn render(templates: &AlertTemplates, alert: &Alert<'_>, host: &str) -> Notification {
+    let (configured, subject, body) = alert.templates(templates);
+    let context = match tera::Context::from_serialize(alert.context(host)) {
+        Ok(context) => context,
+        Err(e) => {
+            tracing::warn!("Alert template context failed: {}", e);
+            tera::Context::new()
+        }
+    };
+    let render_one = |template: Option<&String>, builtin: &str| {
+        if let Some(template) = template {
+            match tera::Tera::one_off(template, &context, false) {
+                Ok(text) => return text,
+                Err(e) => tracing::warn!(
+                    "Alert template for {} failed, using the built-in one: {}",
+                    alert.event(),
+                    e
+                ),
+            }
+        }
+        tera::Tera::one_off(builtin, &context, false).unwrap_or_else(|_| builtin.to_string())
+    };
+    let (added, removed) = context_apps(alert);
+    Notification {
+        event: alert.event(),
+        host: host.to_string(),
+        subject: render_one(configured.subject.as_ref(), subject),
+        body: render_one(configured.body.as_ref(), body),
+        added: added.to_vec(),
+        removed: removed.to_vec(),
+        failure: alert.is_failure(),
+    }
+}
+
+/// Apps added and removed by an `AppsChanged` alert
+fn context_apps<'a>(alert: &Alert<'a>) -> (&'a [String], &'a [String]) {
+    match alert {
+        Alert::AppsChanged { added, removed, .. } => (added, removed),
+        _ => (&[], &[]),
+    }
+}
+
+/// Check that the configured alert templates parse
+pub fn check_templates(templates: &AlertTemplates) -> Result<()> {
+    let configured = [
+        ("sync_failing.subject", &templates.sync_failing.subject),
+        ("sync_failing.body", &templates.sync_failing.body),
+        ("setup_failed.subject", &templates.setup_failed.subject),
+        ("setup_failed.body", &templates.setup_failed.body),
+        ("apps_changed.subject", &templates.apps_changed.subject),
+        ("apps_changed.body", &templates.apps_changed.body),
+    ];
+    let mut tera = tera::Tera::default();
+    for (name, template) in configured {
+        if let Some(template) = template {
+            tera.add_raw_template(name, template).map_err(|e| {
+                AdapterError::Config(format!("Invalid alert template {}: {}", name, e))
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// A channel alerts are delivered through
+#[async_trait::async_trait]
+pub trait Notifier: Send + Sync {
+    /// Channel name, for logs
+    fn name(&self) -> &'static str;
+
+    /// Consecutive failed syncs before the channel alerts (0: never)
+    fn failure_threshold(&self) -> u32;
+
+    /// Deliver a rendered alert
+    async fn notify(&self, notification: &Notification) -> Result<()>;
+
+    /// Whether the channel should get this alert
+    fn wants(&self, alert: &Alert<'_>) -> bool {
+        match alert {
+            Alert::SyncFailing { failures, .. } => {
+                should_alert_sync(*failures, self.failure_threshold())
+            }
+            Alert::SetupFailed { .. } => true,
+            Alert::SetupCompleted | Alert::AppsChanged { .. } => false,
+        }
+    }
+}
+
+/// Whether a sync failure streak has just reached the alert threshold
+///
+/// Alerts fire once per streak; a threshold of 0 disables sync alerts.
+pub fn should_alert_sync(failures: u32, threshold: u32) -> bool {
+    threshold > 0 && failures == threshold
+}
+
+/// The configured channels
+pub fn notifiers(config: &AlertsConfig) -> Vec<Box<dyn Notifier>> {
+    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+    if let Some(ref email) = config.email {
+        notifiers.push(Box::new(EmailNotifier(email.clone())));
+    }
+    if let Some(ref webhook) = config.webhook {
+        notifiers.push(Box::new(WebhookNotifier(webhook.clone())));
+    }
+    if let Some(ref ntfy) = config.ntfy {
+        notifiers.push(Box::new(NtfyNotifier(ntfy.clone())));
+    }
+    if let Some(ref dbus) = config.dbus {
+        notifiers.push(Box::new(DbusNotifier(dbus.clone())));
+    }
+    notifiers
+}
+
+/// The `[notifications]` webhooks
+pub fn subscribers(config: &NotificationsConfig) -> Vec<Box<dyn Notifier>> {
+    config
+        .webhooks
+        .iter()
+        .map(|webhook| {
+            let channel: Box<dyn Notifier> = match webhook.format {
+                WebhookFormat::Json => Box::new(WebhookNotifier(WebhookAlertConfig {
+                    url: webhook.url.clone(),
+                    headers: webhook.headers.clone(),
+                    failure_threshold: 1,
+                })),
+                WebhookFormat::Ntfy => Box::new(NtfyNotifier(NtfyAlertConfig {
+                    url: webhook.url.clone(),
+                    token: webhook.token.clone(),
+                    priority: None,
+                    failure_threshold: 1,
+                })),
+                WebhookFormat::Gotify => Box::new(GotifyNotifier(webhook.clone())),
+            };
+            Box::new(Subscribed {
+                channel,
+                events: config.events.clone(),
+            }) as Box<dyn Notifier>
+        })
+        .collect()
+}
+
+/// Send an alert through every configured channel that wants it, alert
+/// channels and notification webhooks alike
+///
+/// Every channel is tried; the error lists those that failed.
+pub async fn send(config: &Config, alert: &Alert<'_>) -> Result<()> {
+    let notifiers: Vec<Box<dyn Notifier>> = notifiers(&config.alerts)
+        .into_iter()
+        .chain(subscribers(&config.notifications))
+        .filter(|notifier| notifier.wants(alert))
+        .collect();
+    if notifiers.is_empty() {
+        return Ok(());
+    }
+
+    let notification = render(&config.alerts.templates, alert, &hostname());
+    let mut failed = Vec::new();
+    for notifier in &notifiers {
+        match notifier.notify(&notification).await {
+            Ok(()) => tracing::info!("Sent {} alert via {}", notification.event, notifier.name()),
+            Err(e) => failed.push(format!("{}: {}", notifier.name(), e)),
+        }
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(AdapterError::Alert(failed.join("; ")))
+    }
+}
+
+fn hostname() -> String {
+    gethostname::gethostname()
+        .into_string()
+        .unwrap_or_else(|_| "localhost".to_string())
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder().timeout(HTTP_TIMEOUT).build()?)
+}
+
+/// Turn a non-success HTTP status into an alert error
+fn check_status(response: reqwest::Response) -> Result<()> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(AdapterError::Alert(format!("HTTP {}", status)))
+    }
+}
+
+/// Email over SMTP
+struct EmailNotifier(EmailAlertConfig);
+
+#[async_trait::async_trait]
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn failure_threshold(&self) -> u32 {
+        self.0.failure_threshold
+    }
+
+    async fn notify(&self, notification: &Notification) -> Result<()> {
+        send_email(&self.0, notification).await
+    }
+}
+
+fn build_message(config: &EmailAlertConfig, notification: &Notification) -> Result<Message> {
+    let from = config
+        .from
+        .parse()
+        .map_err(|e| AdapterError::Alert(format!("Invalid sender '{}': {}", config.from, e)))?;
+
+    let mut builder = Message::builder()
+        .from(from)
+        .subject(notification.subject.clone());
+    for to in &config.to {
+        let mailbox = to
+            .parse()
+            .map_err(|e| AdapterError::Alert(format!("Invalid recipient '{}': {}", to, e)))?;
+        builder = builder.to(mailbox);
+    }
+
+    builder
+        .body(notification.body.clone())
+        .map_err(|e| AdapterError::Alert(e.to_string()))
+}
+
+async fn send_email(config: &EmailAlertConfig, notification: &Notification) -> Result<()> {
+    let message = build_message(config, notification)?;
+
+    let mut transport = match config.security {
+        SmtpSecurity::Starttls => {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)
+                .map_err(|e| AdapterError::Alert(e.to_string()))?
+        }
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host)
+            .map_err(|e| AdapterError::Alert(e.to_string()))?,
+        SmtpSecurity::None => {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host)
+        }
+    }
+    .port(config.smtp_port);
+
+    if let (Some(username), Some(password)) = (&config.username, &config.password) {
+        transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+
+    transport
+        .build()
+        .send(message)
+        .await
+        .map_err(|e| AdapterError::Alert(e.to_string()))?;
+
+    tracing::debug!("Sent alert email to {}", config.to.join(", "));
+    Ok(())
+}
+
+/// The notification as JSON, POSTed to a URL
+struct WebhookNotifier(WebhookAlertConfig);
+
+#[async_trait::async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn failure_threshold(&self) -> u32 {
+        self.0.failure_threshold
+    }
+
+    async fn notify(&self, notification: &Notification) -> Result<()> {
+        let mut request = http_client()?.post(&self.0.url).json(notification);
+        for (name, value) in &self.0.headers {
+            request = request.header(name, value);
+        }
+        check_status(request.send().await?)
+    }
+}
+
+/// A message published to an ntfy topic
+struct NtfyNotifier(NtfyAlertConfig);
+
+#[async_trait::async_trait]
+impl Notifier for NtfyNotifier {
+    fn name(&self) -> &'static str {
+        "ntfy"
+    }
+
+    fn failure_threshold(&self) -> u32 {
+        self.0.failure_threshold
+    }
+
+    async fn notify(&self, notification: &Notification) -> Result<()> {
+        let tags = if notification.failure {
+            "warning"
+        } else {
+            "information_source"
+        };
+        let mut request = http_client()?
+            .post(&self.0.url)
+            .header("Title", &notification.subject)
+            .header("Tags", tags)
+            .body(notification.body.clone());
+        if let Some(ref priority) = self.0.priority {
+            request = request.header("Priority", priority);
+        }
+        if let Some(ref token) = self.0.token {
+            request = request.bearer_auth(token);
+        }
+        check_status(request.send().await?)
+    }
+}
+
+/// A signal on the system bus, for agents on the device (a desktop
+/// notifier, a cockpit plugin) to pick up
+///
+/// Emitted with `busctl` as `Alert(event, subject, body)` from
+/// `/org/halos/HomarrContainerAdapter`.
+struct DbusNotifier(DbusAlertConfig);
+
+#[async_trait::async_trait]
+impl Notifier for DbusNotifier {
+    fn name(&self) -> &'static str {
+        "dbus"
+    }
+
+    fn failure_threshold(&self) -> u32 {
+        self.0.failure_threshold
+    }
+
+    async fn notify(&self, notification: &Notification) -> Result<()> {
+        let output = tokio::process::Command::new("busctl")
+            .args(dbus_signal_args(notification))
+            .output()
+            .await
+            .map_err(|e| AdapterError::Alert(format!("Cannot run busctl: {}", e)))?;
+        if !output.status.success() {
+            return Err(AdapterError::Alert(format!(
+                "busctl emit failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// `busctl` arguments emitting the alert signal
+fn dbus_signal_args(notification: &Notification) -> Vec<String> {
+    [
+        "--system",
+        "emit",
+        DBUS_PATH,
+        DBUS_INTERFACE,
+        DBUS_SIGNAL,
+        "sss",
+        notification.event,
+        &notification.subject,
+        &notification.body,
+    ]
+    .map(str::to_string)
+    .to_vec()
+}
+
+/// A message posted to a Gotify server
+struct GotifyNotifier(NotificationWebhook);
+
+#[async_trait::async_trait]
+impl Notifier for GotifyNotifier {
+    fn name(&self) -> &'static str {
+        "gotify"
+    }
+
+    fn failure_threshold(&self) -> u32 {
+        1
+    }
+
+    async fn notify(&self, notification: &Notification) -> Result<()> {
+        let url = format!("{}/message", self.0.url.trim_end_matches('/'));
+        let mut request = http_client()?.post(url).json(&serde_json::json!({
+            "title": notification.subject,
+            "message": notification.body,
+            "priority": if notification.failure { 8 } else { 4 },
+        }));
+        if let Some(ref token) = self.0.token {
+            request = request.header("X-Gotify-Key", token);
+        }
+        for (name, value) in &self.0.headers {
+            request = request.header(name, value);
+        }
+        check_status(request.send().await?)
+    }
+}
+
+/// A notification webhook, sent only the events it subscribes to
+///
+/// Sync failures reach it once per streak, on the first failed sync.
+struct Subscribed {
+    channel: Box<dyn Notifier>,
+    events: Vec<NotificationEvent>,
+}
+
+#[async_trait::async_trait]
+impl Notifier for Subscribed {
+    fn name(&self) -> &'static str {
+        self.channel.name()
+    }
+
+    fn failure_threshold(&self) -> u32 {
+        1
+    }
+
+    async fn notify(&self, notification: &Notification) -> Result<()> {
+        self.channel.notify(notification).await
+    }
+
+    fn wants(&self, alert: &Alert<'_>) -> bool {
+        if !self.events.contains(&alert.notification_event()) {
+            return false;
+        }
+        match alert {
+            Alert::SyncFailing { failures, .. } => {
+                should_alert_sync(*failures, self.failure_threshold())
+            }
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn email_config(from: &str, to: &[&str]) -> EmailAlertConfig {
+        EmailAlertConfig {
+            smtp_host: "smtp.example.com".to_string(),
+            smtp_port: 587,
+            security: SmtpSecurity::Starttls,
+            username: None,
+            password: None,
+            from: from.to_string(),
+            to: to.iter().map(|s| s.to_string()).collect(),
+            failure_threshold: 3,
+        }
+    }
+
+    #[test]
+    fn test_should_alert_sync_once_per_streak() {
+        assert!(!should_alert_sync(1, 3));
+        assert!(!should_alert_sync(2, 3));
+        assert!(should_alert_sync(3, 3));
+        assert!(!should_alert_sync(4, 3));
+    }
+
+    #[test]
+    fn test_should_alert_sync_disabled() {
+        assert!(!should_alert_sync(0, 0));
+        assert!(!should_alert_sync(5, 0));
+    }
+
+    #[test]
+    fn test_alert_text() {
+        let templates = AlertTemplates::default();
+        let alert = Alert::SyncFailing {
+            failures: 3,
+            error: "connection refused",
+            last_success: None,
+        };
+        let notification = render(&templates, &alert, "boat");
+        assert_eq!(notification.event, "sync_failing");
+        assert!(notification
+            .subject
+            .contains("boat: 3 consecutive sync failures"));
+        assert!(notification.body.contains("Last error: connection refused"));
+
+        let alert = Alert::SetupFailed { error: "timeout" };
+        let notification = render(&templates, &alert, "boat");
+        assert!(notification.subject.contains("first-boot setup failed"));
+        assert!(notification.body.contains("Error: timeout"));
+    }
+
+    #[test]
+    fn test_custom_templates() {
+        let mut templates = AlertTemplates::default();
+        templates.sync_failing.subject = Some("{{ host | upper }} sync down".to_string());
+        templates.sync_failing.body =
+            Some("{{ failures }} failures since {{ last_success }}".to_string());
+        templates.setup_failed.subject = Some("{{ missing }}".to_string());
+        check_templates(&templates).unwrap();
+
+        let last_success = "2026-10-15T08:00:00Z".parse().unwrap();
+        let alert = Alert::SyncFailing {
+            failures: 3,
+            error: "boom",
+            last_success: Some(last_success),
+        };
+        let notification = render(&templates, &alert, "boat");
+        assert_eq!(notification.subject, "BOAT sync down");
+        assert_eq!(
+            notification.body,
+            "3 failures since 2026-10-15T08:00:00+00:00"
+        );
+
+        // An unknown variable falls back to the built-in wording
+        let notification = render(&templates, &Alert::SetupFailed { error: "boom" }, "boat");
+        assert!(notification.subject.contains("first-boot setup failed"));
+
+        templates.setup_failed.body = Some("{% if %}".to_string());
+        assert!(matches!(
+            check_templates(&templates),
+            Err(AdapterError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_notifiers_from_config() {
+        let config = AlertsConfig {
+            email: Some(email_config("adapter@example.com", &["ops@example.com"])),
+            ntfy: Some(NtfyAlertConfig {
+                url: "https://ntfy.sh/boat".to_string(),
+                token: None,
+                priority: None,
+                failure_threshold: 1,
+            }),
+            dbus: Some(DbusAlertConfig {
+                failure_threshold: 3,
+            }),
+            ..Default::default()
+        };
+        let notifiers = notifiers(&config);
+        let names: Vec<&str> = notifiers.iter().map(|n| n.name()).collect();
+        assert_eq!(names, vec!["email", "ntfy", "dbus"]);
+
+        let alert = Alert::SyncFailing {
+            failures: 1,
+            error: "boom",
+            last_success: None,
+        };
+        let wanted: Vec<&str> = notifiers
+            .iter()
+            .filter(|n| n.wants(&alert))
+            .map(|n| n.name())
+            .collect();
+        assert_eq!(wanted, vec!["ntfy"]);
+        assert!(notifiers
+            .iter()
+            .all(|n| n.wants(&Alert::SetupFailed { error: "boom" })));
+    }
+
+    #[test]
+    fn test_subscribers() {
+        let webhook = |url: &str, format| NotificationWebhook {
+            url: url.to_string(),
+            format,
+            token: Some("secret".to_string()),
+            headers: HashMap::new(),
+        };
+        let config = NotificationsConfig {
+            events: vec![
+                NotificationEvent::SyncFailed,
+                NotificationEvent::AppsChanged,
+            ],
+            webhooks: vec![
+                webhook("https://hooks.example.com/hca", WebhookFormat::Json),
+                webhook("https://ntfy.sh/fleet", WebhookFormat::Ntfy),
+                webhook("https://gotify.example.com", WebhookFormat::Gotify),
+            ],
+        };
+        let subscribers = subscribers(&config);
+        let names: Vec<&str> = subscribers.iter().map(|n| n.name()).collect();
+        assert_eq!(names, vec!["webhook", "ntfy", "gotify"]);
+
+        let failing = |failures| Alert::SyncFailing {
+            failures,
+            error: "boom",
+            last_success: None,
+        };
+        let added = vec!["Grafana".to_string()];
+        let changed = Alert::AppsChanged {
+            added: &added,
+            removed: &[],
+            summary: SyncSummary::default(),
+        };
+        assert!(subscribers.iter().all(|n| n.wants(&failing(1))));
+        assert!(subscribers.iter().all(|n| !n.wants(&failing(2))));
+        assert!(subscribers.iter().all(|n| n.wants(&changed)));
+        assert!(subscribers
+            .iter()
+            .all(|n| !n.wants(&Alert::SetupFailed { error: "boom" })));
+
+        // Alert channels don't get notification-only events
+        let alerts = AlertsConfig {
+            email: Some(email_config("adapter@example.com", &["ops@example.com"])),
+            ..Default::default()
+        };
+        assert!(notifiers(&alerts)
+            .iter()
+            .all(|n| !n.wants(&Alert::SetupCompleted) && !n.wants(&changed)));
+    }
+
+    #[test]
+    fn test_apps_changed_text() {
+        let added = vec!["Grafana".to_string(), "Node Red".to_string()];
+        let removed = vec!["Plex (hidden)".to_string()];
+        let alert = Alert::AppsChanged {
+            added: &added,
+            removed: &removed,
+            summary: SyncSummary::default(),
+        };
+        let notification = render(&AlertTemplates::default(), &alert, "boat");
+        assert_eq!(notification.event, "apps_changed");
+        assert!(notification
+            .subject
+            .ends_with("boat: 2 app(s) added, 1 removed"));
+        assert!(notification.body.contains("Added: Grafana, Node Red\n"));
+        assert!(notification.body.contains("Removed: Plex (hidden)\n"));
+        assert!(!notification.failure);
+
+        let json = serde_json::to_value(&notification).unwrap();
+        assert_eq!(json["added"][1], "Node Red");
+        assert!(json.get("failure").is_none());
+    }
+
+    #[test]
+    fn test_apps_changed_template_over_summary() {
+        let mut templates = AlertTemplates::default();
+        templates.apps_changed.body = Some(
+            "{{ summary.synced }} on {{ summary.boards }} board(s)\n\
+             {% for change in summary.changes %}{{ change }}\n{% endfor %}"
+                .to_string(),
+        );
+        check_templates(&templates).unwrap();
+
+        let added = vec!["Grafana".to_string()];
+        let changes = vec!["Created app 'Grafana' (from container 'grafana')".to_string()];
+        let alert = Alert::AppsChanged {
+            added: &added,
+            removed: &[],
+            summary: SyncSummary {
+                apps: 4,
+                boards: 2,
+                synced: 8,
+                changes: &changes,
+            },
+        };
+        let notification = render(&templates, &alert, "boat");
+        assert_eq!(
+            notification.body,
+            "8 on 2 board(s)\nCreated app 'Grafana' (from container 'grafana')\n"
+        );
+    }
+
+    #[test]
+    fn test_dbus_signal_args() {
+        let args = dbus_signal_args(&notification());
+        assert_eq!(
+            args[..6],
+            [
+                "--system",
+                "emit",
+                DBUS_PATH,
+                DBUS_INTERFACE,
+                "Alert",
+                "sss"
+            ]
+        );
+        assert_eq!(args[6], "setup_failed");
+        assert!(args[7].ends_with("first-boot setup failed"));
+    }
+
+    fn notification() -> Notification {
+        render(
+            &AlertTemplates::default(),
+            &Alert::SetupFailed { error: "boom" },
+            "boat",
+        )
+    }
+
+    #[test]
+    fn test_build_message() {
+        let config = email_config(
+            "adapter@example.com",
+            &["ops@example.com", "me@example.com"],
+        );
+        let message = build_message(&config, &notification()).unwrap();
+        assert_eq!(message.envelope().to().len(), 2);
+    }
+
+    #[test]
+    fn test_build_message_invalid_address() {
+        let config = email_config("not an address", &["ops@example.com"]);
+        assert!(matches!(
+            build_message(&config, &notification()),
+            Err(AdapterError::Alert(_))
+        ));
+    }
+}
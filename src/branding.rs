@@ -1,8 +1,9 @@
 //! Branding configuration from halos-homarr-branding package
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::error::{AdapterError, Result};
 
@@ -15,6 +16,9 @@ pub struct BrandingConfig {
     pub credentials: Credentials,
     pub board: Board,
     pub settings: Settings,
+    /// Search engines for the search bar (`[search]`)
+    #[serde(default)]
+    pub search: Search,
 }
 
 #[derive(Debug, Deserialize)]
@@ -48,9 +52,31 @@ pub struct Theme {
     /// Background image URL served via /branding/ prefix
     #[serde(default)]
     pub background_image_url: Option<String>,
+    /// Local background image, uploaded like `logo_path` and used instead
+    /// of `background_image_url`
+    #[serde(default)]
+    pub background_image_path: Option<PathBuf>,
+    /// Whether the background scrolls with the board or stays fixed
+    #[serde(default)]
+    pub background_image_attachment: Option<BackgroundAttachment>,
+    #[serde(default)]
+    pub background_image_repeat: Option<BackgroundRepeat>,
+    #[serde(default)]
+    pub background_image_size: Option<BackgroundSize>,
     /// Custom CSS to inject into the dashboard
     #[serde(default)]
     pub custom_css: Option<String>,
+    /// File with more custom CSS, added after `custom_css` (relative to the
+    /// branding file)
+    #[serde(default)]
+    pub custom_css_file: Option<PathBuf>,
+    /// Local logo image, uploaded to Homarr during setup and used instead
+    /// of `identity.logo_image_url` (relative to the branding file)
+    #[serde(default)]
+    pub logo_path: Option<PathBuf>,
+    /// Local favicon, uploaded like `logo_path`
+    #[serde(default)]
+    pub favicon_path: Option<PathBuf>,
 }
 
 fn default_item_radius() -> String {
@@ -61,9 +87,37 @@ fn default_opacity() -> u8 {
     100
 }
 
+/// Homarr's `backgroundImageAttachment` board setting
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackgroundAttachment {
+    Fixed,
+    Scroll,
+}
+
+/// Homarr's `backgroundImageRepeat` board setting
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackgroundRepeat {
+    Repeat,
+    RepeatX,
+    RepeatY,
+    NoRepeat,
+}
+
+/// Homarr's `backgroundImageSize` board setting
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackgroundSize {
+    Cover,
+    Contain,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Credentials {
     pub admin_username: String,
+    /// May be left out when the password is kept in the keyring
+    #[serde(default)]
     pub admin_password: String,
 }
 
@@ -73,7 +127,128 @@ pub struct Board {
     pub name: String,
     pub display_name: String,
     pub column_count: u8,
+    /// Column counts of other boards, by board name
+    #[serde(default)]
+    pub column_counts: HashMap<String, u8>,
     pub is_public: bool,
+    /// Named category sections, created in order (e.g. "System", "Apps")
+    #[serde(default)]
+    pub sections: Vec<String>,
+    /// Section for apps that don't name one (default: first board section)
+    #[serde(default)]
+    pub default_section: Option<String>,
+    /// Existing board whose sections and appearance new boards copy,
+    /// instead of the theme settings above
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Access for Homarr's built-in "everyone" group, applied on every setup
+    /// run; unset leaves the board's permissions and visibility alone
+    #[serde(default)]
+    pub everyone: Option<EveryoneAccess>,
+    /// File with more of the board's initial contents (`sections`,
+    /// `[[apps]]`, `[[widgets]]`), relative to the branding file
+    #[serde(default)]
+    pub contents_file: Option<String>,
+    /// Apps setup places on the board (`[[board.apps]]`)
+    #[serde(default)]
+    pub apps: Vec<TemplateApp>,
+    /// Widgets setup places on the board (`[[board.widgets]]`)
+    #[serde(default)]
+    pub widgets: Vec<TemplateWidget>,
+}
+
+/// Initial board contents kept in `board.contents_file`
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct BoardContents {
+    #[serde(default)]
+    pub sections: Vec<String>,
+    #[serde(default)]
+    pub apps: Vec<TemplateApp>,
+    #[serde(default)]
+    pub widgets: Vec<TemplateWidget>,
+}
+
+/// Where a template tile goes: grid position and size within a section
+#[derive(Debug, Clone, Deserialize)]
+pub struct TilePosition {
+    /// Category section by name (default: the board's first section)
+    #[serde(default)]
+    pub section: Option<String>,
+    pub x: i32,
+    pub y: i32,
+    #[serde(default = "default_tile_size")]
+    pub width: i32,
+    #[serde(default = "default_tile_size")]
+    pub height: i32,
+}
+
+fn default_tile_size() -> i32 {
+    1
+}
+
+/// An app setup creates (unless Homarr has it) and places on the board
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplateApp {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub icon_url: Option<String>,
+    #[serde(flatten)]
+    pub position: TilePosition,
+}
+
+/// A widget setup places on the board, e.g. `clock` or `weather`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplateWidget {
+    /// Homarr widget kind
+    pub kind: String,
+    /// Names the widget on the board, so setup recognises it however the
+    /// widgets are ordered (default: its kind and position)
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Widget options as Homarr stores them (e.g. `is24HourFormat = true`)
+    #[serde(default)]
+    pub options: serde_json::Map<String, serde_json::Value>,
+    #[serde(flatten)]
+    pub position: TilePosition,
+}
+
+impl Board {
+    /// Columns a board should have: `column_count` for this board, the
+    /// `column_counts` entry for others
+    pub fn columns_for(&self, board_name: &str) -> Option<u8> {
+        if board_name == self.name {
+            Some(self.column_count)
+        } else {
+            self.column_counts.get(board_name).copied()
+        }
+    }
+}
+
+/// Board access for every user, signed in or not (`[board.everyone]`)
+///
+/// A kiosk board can be `public` with `view` access while an admin board
+/// stays private with no access.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct EveryoneAccess {
+    /// Permission of the "everyone" group on the board
+    pub permission: EveryonePermission,
+    /// Viewable without signing in and listed for every user (default:
+    /// `board.is_public`)
+    #[serde(default)]
+    pub public: Option<bool>,
+}
+
+/// Permission of the "everyone" group on a board
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EveryonePermission {
+    /// No group permission (only users and other groups with access)
+    None,
+    View,
+    Modify,
 }
 
 #[derive(Debug, Deserialize)]
@@ -98,8 +273,36 @@ pub struct CrawlingSettings {
     pub no_sitelinks_search_box: bool,
 }
 
+/// Search engines created during setup, and the default one
+#[derive(Debug, Default, Deserialize)]
+pub struct Search {
+    /// Engine the search bar uses by default, by `short` or name: one of
+    /// `engines` or one Homarr already has
+    #[serde(default)]
+    pub default_engine: Option<String>,
+    /// Engines created, or updated when Homarr has one with their `short`
+    #[serde(default)]
+    pub engines: Vec<SearchEngine>,
+}
+
+/// A search engine queried through a URL template (`[[search.engines]]`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchEngine {
+    pub name: String,
+    /// Short name typed before a query to pick the engine (e.g. "sk")
+    pub short: String,
+    /// URL with `%s` where the query goes
+    pub url_template: String,
+    pub icon_url: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
 impl BrandingConfig {
     /// Load branding configuration from file
+    ///
+    /// As in the adapter config, `${VAR}` references are expanded and the
+    /// admin password may come from `admin_password_file`.
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
         let path = path.as_ref();
 
@@ -110,8 +313,63 @@ impl BrandingConfig {
             )));
         }
 
-        let contents = fs::read_to_string(path)?;
-        let config: BrandingConfig = toml::from_str(&contents)?;
+        let mut table: toml::Table = toml::from_str(&fs::read_to_string(path)?)?;
+        crate::config::expand_env_vars(&mut table, &|name| std::env::var(name).ok())?;
+        crate::config::read_secret_files(&mut table, &["credentials.admin_password"])?;
+        let mut config: BrandingConfig = table.try_into()?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+
+        let theme = &mut config.theme;
+        for image in [
+            &mut theme.logo_path,
+            &mut theme.favicon_path,
+            &mut theme.background_image_path,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *image = dir.join(&*image);
+        }
+        if let Some(file) = &theme.custom_css_file {
+            let file = dir.join(file);
+            let css = fs::read_to_string(&file)
+                .map_err(|e| AdapterError::Config(format!("Custom CSS file {:?}: {}", file, e)))?;
+            theme.custom_css = Some(match theme.custom_css.take() {
+                Some(inline) => format!("{}\n{}", inline, css),
+                None => css,
+            });
+        }
+
+        for engine in &config.search.engines {
+            if !engine.url_template.contains("%s") {
+                return Err(AdapterError::Config(format!(
+                    "Search engine '{}': url_template has no %s for the query",
+                    engine.name
+                )));
+            }
+        }
+
+        if let Some(file) = config.board.contents_file.clone() {
+            let file = dir.join(file);
+            let mut table: toml::Table =
+                toml::from_str(&fs::read_to_string(&file).map_err(|e| {
+                    AdapterError::Config(format!("Board contents file {:?}: {}", file, e))
+                })?)?;
+            crate::config::expand_env_vars(&mut table, &|name| std::env::var(name).ok())?;
+            let contents: BoardContents = table.try_into()?;
+            let board = &mut config.board;
+            for section in contents.sections {
+                if !board
+                    .sections
+                    .iter()
+                    .any(|s| s.eq_ignore_ascii_case(&section))
+                {
+                    board.sections.push(section);
+                }
+            }
+            board.apps.extend(contents.apps);
+            board.widgets.extend(contents.widgets);
+        }
 
         Ok(config)
     }
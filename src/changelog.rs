@@ -0,0 +1,113 @@
+//! Per-boot log of dashboard changes (`last-changes.txt`)
+//!
+//! One line per change the adapter made to Homarr and why, so support
+//! bundles show what the adapter did without the journal. The file starts
+//! over on each boot; the previous boot's log is kept with a `.1` suffix.
+//! With signing enabled, lines carry chained signatures (see `signing`).
+
+use std::fmt::Display;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::error::Result;
+use crate::signing::{self, Verdict};
+
+/// Identifies the current boot (changes on every reboot)
+const BOOT_ID_FILE: &str = "/proc/sys/kernel/random/boot_id";
+
+/// Changes made during one sync, written out when the sync ends
+#[derive(Debug, Default)]
+pub struct Changelog {
+    entries: Vec<String>,
+    /// Apps created in Homarr, for notifications
+    pub added_apps: Vec<String>,
+    /// Apps taken off the dashboard, like "Plex (hidden)", for notifications
+    pub removed_apps: Vec<String>,
+}
+
+impl Changelog {
+    /// Note a change and the reason it was made
+    pub fn record(&mut self, change: impl Display, reason: impl Display) {
+        self.entries.push(format!(
+            "{} {} ({})",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            change,
+            reason
+        ));
+    }
+
+    /// The changes recorded so far, each with its time and reason
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    /// Append the recorded changes to `path`, starting a new file first if
+    /// it belongs to an earlier boot
+    pub fn write(&mut self, path: &Path) -> Result<()> {
+        let boot = fs::read_to_string(BOOT_ID_FILE).unwrap_or_default();
+        write_for_boot(path, boot.trim(), &std::mem::take(&mut self.entries))
+    }
+}
+
+fn write_for_boot(path: &Path, boot: &str, entries: &[String]) -> Result<()> {
+    let header = format!("# homarr-container-adapter changes, boot {}", boot);
+    let existing = fs::read_to_string(path)
+        .ok()
+        .filter(|log| log.lines().next() == Some(header.as_str()));
+    if let Some(Verdict::Tampered(detail)) = existing.as_deref().and_then(signing::check_log) {
+        tracing::error!(
+            "Changelog {:?} was changed outside the adapter: {}",
+            path,
+            detail
+        );
+    }
+    if existing.is_none() {
+        if path.exists() {
+            let mut previous = path.as_os_str().to_owned();
+            previous.push(".1");
+            fs::rename(path, previous)?;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, format!("{}\n", header))?;
+    }
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let previous = existing
+        .as_deref()
+        .and_then(|log| log.lines().last())
+        .unwrap_or(&header);
+    let mut file = OpenOptions::new().append(true).open(path)?;
+    for line in signing::sign_lines(previous, entries) {
+        writeln!(file, "{}", line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_changelog_rotates_per_boot() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("last-changes.txt");
+
+        write_for_boot(&path, "boot-a", &["placed Grafana".to_string()]).unwrap();
+        write_for_boot(&path, "boot-a", &["hid InfluxDB".to_string()]).unwrap();
+        let log = fs::read_to_string(&path).unwrap();
+        assert_eq!(log.lines().count(), 3);
+        assert!(log.ends_with("hid InfluxDB\n"));
+
+        // A new boot starts an empty log, even without changes
+        write_for_boot(&path, "boot-b", &[]).unwrap();
+        let log = fs::read_to_string(&path).unwrap();
+        assert_eq!(log, "# homarr-container-adapter changes, boot boot-b\n");
+        let previous = fs::read_to_string(dir.path().join("last-changes.txt.1")).unwrap();
+        assert!(previous.contains("placed Grafana"));
+    }
+}
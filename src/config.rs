@@ -1,10 +1,19 @@
 //! Adapter configuration
 
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
 
-use crate::error::Result;
+use crate::docker::{ContainerRuntime, LabelScheme, ReplicaDisplay};
+use crate::error::{AdapterError, Result};
+use crate::homarr::{PoolOptions, RequestPolicies, RequestPolicy};
+use crate::keyring::CredentialStore;
+use crate::layout::{BoardFull, SectionLimits};
+use crate::registry::{self, DuplicateNameStrategy};
+use crate::state::{RemovalPolicy, StateBackend};
 
 /// Main adapter configuration
 #[derive(Debug, Clone, Deserialize)]
@@ -14,6 +23,18 @@ pub struct Config {
     #[serde(default = "default_homarr_url")]
     pub homarr_url: String,
 
+    /// More Homarr instances kept in sync with the same apps (`[[homarr]]`)
+    #[serde(default)]
+    pub homarr: Vec<HomarrTarget>,
+
+    /// How many `[[homarr]]` targets sync at the same time
+    #[serde(default = "default_max_concurrent_targets")]
+    pub max_concurrent_targets: usize,
+
+    /// Only board tiles are placed on (set for a `[[homarr]]` target)
+    #[serde(skip)]
+    pub target_board: Option<String>,
+
     /// Path to branding config file
     #[serde(default = "default_branding_file")]
     pub branding_file: String,
@@ -22,14 +43,99 @@ pub struct Config {
     #[serde(default = "default_state_file")]
     pub state_file: String,
 
-    /// Docker socket path
+    /// How state is kept: `json` (the state file) or `sqlite` (a database
+    /// next to it)
+    #[serde(default)]
+    pub state_backend: StateBackend,
+
+    /// Per-boot log of changes made to Homarr
+    /// (default: `last-changes.txt` next to the state file)
+    #[serde(default)]
+    pub changelog_file: Option<String>,
+
+    /// Key file for encrypting the state file at rest (32 bytes, raw or hex)
+    #[serde(default)]
+    pub state_key_file: Option<String>,
+
+    /// systemd credential holding the state key (`LoadCredential=` name),
+    /// used when `state_key_file` is not set
+    #[serde(default)]
+    pub state_key_credential: Option<String>,
+
+    /// Key file for signing the state file and changelog (32 bytes, raw or
+    /// hex), so changes made outside the adapter can be detected
+    #[serde(default)]
+    pub signing_key_file: Option<String>,
+
+    /// systemd credential holding the signing key, used when
+    /// `signing_key_file` is not set
+    #[serde(default)]
+    pub signing_key_credential: Option<String>,
+
+    /// Docker socket path (named pipe on Windows)
     #[serde(default = "default_docker_socket")]
     pub docker_socket: String,
 
+    /// Remote Docker daemon (`tcp://host:2376`), used instead of
+    /// `docker_socket`
+    #[serde(default)]
+    pub docker_host: Option<String>,
+
+    /// Client certificates for a `docker_host` secured with mutual TLS
+    /// (`[docker_tls]`)
+    #[serde(default)]
+    pub docker_tls: Option<DockerTlsConfig>,
+
+    /// Kubernetes discovery (`[kubernetes]`, needs the `kubernetes` feature)
+    #[serde(default)]
+    pub kubernetes: Option<KubernetesConfig>,
+
+    /// Other label schemes read besides `homarr.*`, e.g. ["homepage"] for
+    /// gethomepage's `homepage.*` labels
+    #[serde(default)]
+    pub label_schemes: Vec<LabelScheme>,
+
+    /// Container engine behind `docker_socket`: "docker" or "podman"
+    /// (falls back to Podman's rootless, then rootful socket when
+    /// `docker_socket` doesn't exist)
+    #[serde(default)]
+    pub container_runtime: ContainerRuntime,
+
+    /// Running against Docker Desktop (macOS/Windows developer setup): fall
+    /// back to Docker Desktop's socket and adapt discovered URLs to its VM
+    #[serde(default)]
+    pub docker_desktop: bool,
+
     /// Path to app registry directory
     #[serde(default = "default_registry_dir")]
     pub registry_dir: String,
 
+    /// Services outside Docker (`[[apps]]`), synced like discovered apps
+    #[serde(default)]
+    pub apps: Vec<StaticApp>,
+
+    /// Target an already-onboarded Homarr: skip onboarding and user
+    /// creation, and authenticate with the existing credentials below
+    #[serde(default)]
+    pub existing_instance: bool,
+
+    /// API key of an existing Homarr admin (used as-is, never rotated)
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    /// Existing admin username, used when no API key is configured
+    #[serde(default)]
+    pub admin_username: Option<String>,
+
+    /// Existing admin password, used when no API key is configured
+    #[serde(default)]
+    pub admin_password: Option<String>,
+
+    /// Keyring for the admin password and API key ("none",
+    /// "secret-service" or "systemd-creds")
+    #[serde(default)]
+    pub credential_store: CredentialStore,
+
     /// Path to bootstrap API key file (from halos-homarr-branding package)
     #[serde(default = "default_bootstrap_api_key_file")]
     pub bootstrap_api_key_file: String,
@@ -46,31 +152,1046 @@ pub struct Config {
     #[serde(default = "default_sync_interval")]
     pub sync_interval: u64,
 
+    /// Minimum seconds between saves of the same board (for watch mode);
+    /// changes made in between are merged into the next save
+    #[serde(default = "default_board_save_interval")]
+    pub board_save_interval: u64,
+
     /// Startup delay in seconds before first sync (for watch mode)
     #[serde(default = "default_startup_delay")]
     pub startup_delay: u64,
+
+    /// Seconds first-boot setup waits for Homarr to answer before failing
+    /// (0: don't wait)
+    #[serde(default = "default_homarr_ready_timeout")]
+    pub homarr_ready_timeout: u64,
+
+    /// Seconds an idle Homarr connection stays in the pool
+    #[serde(default = "default_pool_idle_timeout")]
+    pub pool_idle_timeout: u64,
+
+    /// Maximum idle Homarr connections kept in the pool
+    #[serde(default = "default_pool_max_idle_per_host")]
+    pub pool_max_idle_per_host: usize,
+
+    /// TCP keepalive interval in seconds for Homarr connections
+    #[serde(default = "default_tcp_keepalive")]
+    pub tcp_keepalive: u64,
+
+    /// Timeouts and retries for Homarr reads and mutations
+    #[serde(default)]
+    pub requests: RequestsConfig,
+
+    /// Seconds between icon re-validations during sync (0 to disable)
+    #[serde(default = "default_icon_refresh_interval")]
+    pub icon_refresh_interval: u64,
+
+    /// What to do when several apps share a display name
+    /// ("suffix", "keep-first" or "strict")
+    #[serde(default)]
+    pub duplicate_names: DuplicateNameStrategy,
+
+    /// Only place apps with HTTPS URLs on boards
+    #[serde(default)]
+    pub require_https: bool,
+
+    /// With `require_https`, rewrite `http://` URLs to `https://` instead of
+    /// skipping those apps
+    #[serde(default)]
+    pub https_upgrade: bool,
+
+    /// Container or app names exempt from `require_https`
+    #[serde(default)]
+    pub https_exceptions: Vec<String>,
+
+    /// Description of container tiles without a `homarr.description` label;
+    /// a template like the label itself, e.g. "{image} ({compose_project})"
+    #[serde(default)]
+    pub description_template: Option<String>,
+
+    /// Place container tiles without a `homarr.section` label in a category
+    /// section named after their compose project, created when missing
+    #[serde(default)]
+    pub compose_sections: bool,
+
+    /// Place tiles without a section in a category section named after
+    /// their `homarr.category`, created when missing
+    #[serde(default = "default_category_sections")]
+    pub category_sections: bool,
+
+    /// Rows a section may hold before it counts as full (default: no limit)
+    #[serde(default)]
+    pub section_rows: Option<u32>,
+
+    /// What happens to a new tile when its section is full ("grow" or
+    /// "overflow")
+    #[serde(default)]
+    pub board_full: BoardFull,
+
+    /// Section for tiles that don't fit a full section (with
+    /// `board_full = "overflow"`), created when missing
+    #[serde(default = "default_overflow_section")]
+    pub overflow_section: String,
+
+    /// What a sync does with tiles of containers that are gone
+    /// ("keep", "hide" or "remove")
+    #[serde(default)]
+    pub removal_policy: RemovalPolicy,
+
+    /// Days a container's app must go unseen before `removal_policy`
+    /// applies to it (0: the first sync that misses it)
+    #[serde(default)]
+    pub removal_grace_days: u32,
+
+    /// Host of tile URLs inferred from published ports, for containers
+    /// without a `homarr.url` label (default: this host's mDNS name)
+    #[serde(default)]
+    pub port_url_host: Option<String>,
+
+    /// Give containers without a `homarr.icon` label the dashboard-icons
+    /// icon matching their image
+    #[serde(default = "default_catalog_icons")]
+    pub catalog_icons: bool,
+
+    /// Language of tile names (e.g. "de" or "de-AT"), picking the
+    /// `homarr.name.<locale>` label over `homarr.name`
+    #[serde(default)]
+    pub locale: Option<String>,
+
+    /// Where to show replica counts of scaled services
+    /// ("description", "name" or "off")
+    #[serde(default)]
+    pub replica_count: ReplicaDisplay,
+
+    /// Daily windows (local time, e.g. "03:00-04:30") when Homarr is left
+    /// alone for backups or upgrades: syncs are skipped and removals queued
+    #[serde(default)]
+    pub maintenance_windows: Option<String>,
+
+    /// Oldest Homarr version the adapter may change (e.g. "1.0.0"); older
+    /// versions are only read from. Not enforced when the version can't be
+    /// detected
+    #[serde(default)]
+    pub min_homarr_version: Option<String>,
+
+    /// Address for the gRPC control interface in watch mode, e.g.
+    /// "127.0.0.1:50051" (None to disable)
+    #[serde(default)]
+    pub grpc_listen: Option<String>,
+
+    /// Address for the `/healthz` and `/readyz` HTTP endpoints in watch
+    /// mode, e.g. "127.0.0.1:8099" (None to disable)
+    #[serde(default)]
+    pub health_listen: Option<String>,
+
+    /// Failure alerts
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+
+    /// Event notifications for fleet operators (`[notifications]`)
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+}
+
+/// Overlay the `[profiles.<name>]` table onto the top-level settings
+///
+/// A profile may set any top-level setting; nested tables (e.g.
+/// `[profiles.full.requests.read]`) are merged key by key. The `profiles`
+/// table itself is dropped either way.
+fn apply_profile(table: &mut toml::Table, profile: Option<&str>) -> Result<()> {
+    let mut profiles = match table.remove("profiles") {
+        Some(toml::Value::Table(profiles)) => profiles,
+        Some(_) => {
+            return Err(AdapterError::Config(
+                "profiles must be a table of [profiles.<name>] sections".to_string(),
+            ))
+        }
+        None => toml::Table::new(),
+    };
+    let Some(name) = profile else {
+        return Ok(());
+    };
+    match profiles.remove(name) {
+        Some(toml::Value::Table(overlay)) => {
+            tracing::info!("Using config profile '{}'", name);
+            merge_tables(table, overlay);
+            Ok(())
+        }
+        _ => {
+            let known: Vec<&str> = profiles.keys().map(String::as_str).collect();
+            Err(AdapterError::Config(format!(
+                "Unknown profile '{}' (configured: {})",
+                name,
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            )))
+        }
+    }
+}
+
+/// Prefix of environment variables overriding config settings
+const ENV_PREFIX: &str = "HCA_";
+
+/// Overlay `HCA_*` environment variables onto the settings
+///
+/// `HCA_HOMARR_URL` sets `homarr_url`; a double underscore reaches into a
+/// table (`HCA_ALERTS__NTFY__URL` sets `url` in `[alerts.ntfy]`). Values
+/// are read as TOML (`30`, `true`, `["a", "b"]`, `{ url = "..." }`) where
+/// they parse as a number, boolean, array, inline table or quoted string,
+/// and as plain strings otherwise. Variables apply in name order, so an
+/// override reaching into a table another one sets always fails.
+///
+/// Returns the overrides that parsed as a number or boolean, which
+/// [`deserialize_config`] turns back into strings for string settings
+/// (`HCA_ADMIN_PASSWORD=1234`).
+fn apply_env(
+    table: &mut toml::Table,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<Vec<EnvScalar>> {
+    let mut vars: Vec<(String, String)> = vars
+        .into_iter()
+        .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+        .collect();
+    vars.sort();
+    let mut overlay = toml::Table::new();
+    let mut scalars = Vec::new();
+    for (name, raw) in vars {
+        let key = &name[ENV_PREFIX.len()..];
+        let path: Vec<String> = key.split("__").map(str::to_lowercase).collect();
+        if path.iter().any(String::is_empty) {
+            return Err(AdapterError::Config(format!(
+                "Invalid config override {}: empty setting name",
+                name
+            )));
+        }
+        let (leaf, tables) = path.split_last().expect("split yields one part");
+        let mut target = &mut overlay;
+        for part in tables {
+            target = match target
+                .entry(part.as_str())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            {
+                toml::Value::Table(table) => table,
+                _ => {
+                    return Err(AdapterError::Config(format!(
+                        "Config override {} reaches into '{}', which another override sets",
+                        name, part
+                    )))
+                }
+            };
+        }
+        tracing::debug!("Config setting '{}' from {}", path.join("."), name);
+        let value = env_value(&raw);
+        if matches!(
+            value,
+            toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_)
+        ) {
+            scalars.push(EnvScalar {
+                path: path.clone(),
+                raw,
+            });
+        }
+        target.insert(leaf.clone(), value);
+    }
+    merge_tables(table, overlay);
+    Ok(scalars)
+}
+
+/// An environment override that parsed as a number or boolean
+#[derive(Debug)]
+struct EnvScalar {
+    path: Vec<String>,
+    raw: String,
+}
+
+impl EnvScalar {
+    /// Set the override's setting to the variable's text instead
+    fn set_string(&self, table: &mut toml::Table) {
+        let (leaf, tables) = self.path.split_last().expect("path has a part");
+        let mut overlay = toml::Table::new();
+        overlay.insert(leaf.clone(), toml::Value::String(self.raw.clone()));
+        for part in tables.iter().rev() {
+            let mut outer = toml::Table::new();
+            outer.insert(part.clone(), toml::Value::Table(overlay));
+            overlay = outer;
+        }
+        merge_tables(table, overlay);
+    }
+}
+
+/// Deserialize the settings, with environment overrides that parsed as a
+/// number or boolean read as text where the setting they set is text
+/// (`HCA_ADMIN_PASSWORD=1234`)
+fn deserialize_config(mut table: toml::Table, scalars: Vec<EnvScalar>) -> Result<Config> {
+    if !scalars.is_empty() {
+        let text = std::cell::RefCell::new(Vec::new());
+        // Errors here are the settings' own and come again below
+        let _ = Config::deserialize(SettingProbe {
+            value: toml::Value::Table(table.clone()),
+            path: Vec::new(),
+            scalars: &scalars,
+            text: &text,
+        });
+        for scalar in scalars.iter().filter(|s| text.borrow().contains(&s.path)) {
+            scalar.set_string(&mut table);
+        }
+    }
+    Ok(table.try_into()?)
+}
+
+/// Deserializer over the settings that notes which environment overrides
+/// set a text setting
+///
+/// Serde asks for a string when it deserializes a `String`; at an override's
+/// path that request is answered with the variable's text and noted in
+/// `text`. Everything else is read from the TOML value as usual.
+struct SettingProbe<'a> {
+    value: toml::Value,
+    path: Vec<String>,
+    scalars: &'a [EnvScalar],
+    text: &'a std::cell::RefCell<Vec<Vec<String>>>,
+}
+
+impl SettingProbe<'_> {
+    /// The value to read a text setting from: the variable's text at an
+    /// override's path
+    fn text_value(self) -> toml::Value {
+        match self.scalars.iter().find(|s| s.path == self.path) {
+            Some(scalar) => {
+                self.text.borrow_mut().push(self.path);
+                toml::Value::String(scalar.raw.clone())
+            }
+            None => self.value,
+        }
+    }
+}
+
+impl<'de> serde::Deserializer<'de> for SettingProbe<'_> {
+    type Error = toml::de::Error;
+
+    fn deserialize_any<V: serde::de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        match self.value {
+            toml::Value::Table(table) => visitor.visit_map(SettingProbeMap {
+                entries: table.into_iter(),
+                value: None,
+                path: self.path,
+                scalars: self.scalars,
+                text: self.text,
+            }),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_str<V: serde::de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        self.text_value().deserialize_any(visitor)
+    }
+
+    fn deserialize_string<V: serde::de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        self.text_value().deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: serde::de::Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: serde::de::Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: serde::de::Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        self.value.deserialize_enum(name, variants, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char bytes byte_buf
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// The entries of a settings table, each read through a [`SettingProbe`]
+struct SettingProbeMap<'a> {
+    entries: toml::map::IntoIter,
+    value: Option<(String, toml::Value)>,
+    path: Vec<String>,
+    scalars: &'a [EnvScalar],
+    text: &'a std::cell::RefCell<Vec<Vec<String>>>,
+}
+
+impl<'de> serde::de::MapAccess<'de> for SettingProbeMap<'_> {
+    type Error = toml::de::Error;
+
+    fn next_key_seed<K: serde::de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> std::result::Result<Option<K::Value>, Self::Error> {
+        use serde::de::IntoDeserializer;
+
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.value = Some((key.clone(), value));
+        seed.deserialize(key.into_deserializer()).map(Some)
+    }
+
+    fn next_value_seed<V: serde::de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        let (key, value) = self.value.take().expect("value follows its key");
+        let mut path = self.path.clone();
+        path.push(key);
+        seed.deserialize(SettingProbe {
+            value,
+            path,
+            scalars: self.scalars,
+            text: self.text,
+        })
+    }
+}
+
+/// An environment variable's value as a TOML value
+fn env_value(raw: &str) -> toml::Value {
+    match toml::from_str::<toml::Table>(&format!("v = {}", raw))
+        .ok()
+        .and_then(|mut parsed| parsed.remove("v"))
+    {
+        // Times and dates are settings' own string formats (e.g. 03:00)
+        Some(toml::Value::Datetime(_)) | None => toml::Value::String(raw.to_string()),
+        Some(value) => value,
+    }
+}
+
+/// Expand `${VAR}` references in every string setting from the environment
+///
+/// `$${` stands for a literal `${`. A reference to an unset variable is an
+/// error rather than an empty value, so a missing secret fails loudly.
+pub(crate) fn expand_env_vars(
+    table: &mut toml::Table,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<()> {
+    for (key, value) in table.iter_mut() {
+        expand_value(value, key, lookup)?;
+    }
+    Ok(())
+}
+
+fn expand_value(
+    value: &mut toml::Value,
+    key: &str,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<()> {
+    match value {
+        toml::Value::String(text) if text.contains("${") => *text = expand_vars(text, key, lookup)?,
+        toml::Value::Array(items) => {
+            for item in items {
+                expand_value(item, key, lookup)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (name, value) in table.iter_mut() {
+                expand_value(value, &format!("{}.{}", key, name), lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// `text` with its `${VAR}` references expanded; errors name the setting
+/// and variable but never the value, which may be a secret
+fn expand_vars(text: &str, key: &str, lookup: &impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            out.push_str(&rest[..start - 1]);
+            out.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        out.push_str(&rest[..start]);
+        let reference = &rest[start + 2..];
+        let end = reference.find('}').ok_or_else(|| {
+            AdapterError::Config(format!("Setting '{}' has an unclosed '${{'", key))
+        })?;
+        let name = &reference[..end];
+        let value = lookup(name).ok_or_else(|| {
+            AdapterError::Config(format!(
+                "Setting '{}' uses ${{{}}}, which is not set",
+                key, name
+            ))
+        })?;
+        out.push_str(&value);
+        rest = &reference[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Config settings that may be read from the file named by `<setting>_file`
+const SECRET_SETTINGS: &[&str] = &[
+    "api_key",
+    "admin_password",
+    "alerts.email.password",
+    "alerts.ntfy.token",
+    "notifications.webhooks.token",
+    "homarr.api_key",
+    "homarr.admin_password",
+];
+
+/// Replace each `<secret>_file` setting by the contents of the file it
+/// names, e.g. a Docker secret (`/run/secrets/...`) or a systemd credential
+/// (`${CREDENTIALS_DIRECTORY}/...`)
+///
+/// `secrets` are dotted paths of settings, where a part naming an array of
+/// tables (`[[notifications.webhooks]]`) applies to each entry; trailing
+/// line breaks in the file are dropped.
+pub(crate) fn read_secret_files(table: &mut toml::Table, secrets: &[&str]) -> Result<()> {
+    for secret in secrets {
+        let parts: Vec<&str> = secret.split('.').collect();
+        read_secret_file(table, &parts, secret)?;
+    }
+    Ok(())
+}
+
+/// Read the secret at `parts` below `table` from its `_file` setting
+fn read_secret_file(table: &mut toml::Table, parts: &[&str], secret: &str) -> Result<()> {
+    let [first, rest @ ..] = parts else {
+        return Ok(());
+    };
+    if !rest.is_empty() {
+        match table.get_mut(*first) {
+            Some(toml::Value::Table(inner)) => read_secret_file(inner, rest, secret)?,
+            Some(toml::Value::Array(entries)) => {
+                for entry in entries {
+                    if let toml::Value::Table(inner) = entry {
+                        read_secret_file(inner, rest, secret)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    let key = *first;
+    let Some(file) = table.remove(&format!("{}_file", key)) else {
+        return Ok(());
+    };
+    let toml::Value::String(file) = file else {
+        return Err(AdapterError::Config(format!(
+            "{}_file must be a file path",
+            secret
+        )));
+    };
+    if table.contains_key(key) {
+        return Err(AdapterError::Config(format!(
+            "Both {} and {}_file are set",
+            secret, secret
+        )));
+    }
+    let value = fs::read_to_string(&file).map_err(|e| {
+        AdapterError::Config(format!("Can't read {}_file '{}': {}", secret, file, e))
+    })?;
+    table.insert(
+        key.to_string(),
+        toml::Value::String(value.trim_end_matches(['\r', '\n']).to_string()),
+    );
+    Ok(())
+}
+
+/// Merge `overlay` into `base`, recursing into tables present in both
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => {
+                merge_tables(base, overlay)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Kubernetes API access for discovery; inside a pod every setting defaults
+/// to the in-cluster service account
+#[derive(Debug, Clone, Default, Deserialize)]
+#[cfg_attr(not(feature = "kubernetes"), allow(dead_code))]
+pub struct KubernetesConfig {
+    /// API server URL, e.g. "https://127.0.0.1:6443"
+    #[serde(default)]
+    pub api_url: Option<String>,
+    /// File holding a service account token with list access to pods and
+    /// ingresses
+    #[serde(default)]
+    pub token_file: Option<String>,
+    /// CA certificate of the API server (PEM)
+    #[serde(default)]
+    pub ca_file: Option<String>,
+    /// Only discover in this namespace (default: all namespaces)
+    #[serde(default)]
+    pub namespace: Option<String>,
+}
+
+/// PEM files for connecting to a TLS-secured Docker daemon
+#[derive(Debug, Clone, Deserialize)]
+pub struct DockerTlsConfig {
+    /// CA certificate the daemon's certificate must be signed by
+    pub ca: String,
+    /// Client certificate
+    pub cert: String,
+    /// Client private key
+    pub key: String,
+}
+
+/// Homarr request policies (`[requests.read]`, `[requests.mutation]`)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RequestsConfig {
+    /// Idempotent queries such as board and app fetches
+    #[serde(default)]
+    pub read: RequestClassConfig,
+
+    /// Changes such as saveBoard; only retried when no connection was made
+    #[serde(default)]
+    pub mutation: RequestClassConfig,
+}
+
+/// Timeout and retries for one class of request (unset: built-in default)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RequestClassConfig {
+    /// Request timeout in seconds
+    #[serde(default)]
+    pub timeout: Option<u64>,
+
+    /// Extra attempts after a retryable failure
+    #[serde(default)]
+    pub retries: Option<u32>,
+
+    /// Delay before the first retry in milliseconds, doubled for each
+    /// further one
+    #[serde(default)]
+    pub backoff_ms: Option<u64>,
+
+    /// Longest delay between retries in milliseconds
+    #[serde(default)]
+    pub max_backoff_ms: Option<u64>,
+
+    /// HTTP statuses worth another attempt
+    #[serde(default)]
+    pub retry_statuses: Option<Vec<u16>>,
+}
+
+impl RequestClassConfig {
+    fn policy(&self, default: RequestPolicy) -> RequestPolicy {
+        RequestPolicy {
+            timeout: self
+                .timeout
+                .map(Duration::from_secs)
+                .unwrap_or(default.timeout),
+            retries: self.retries.unwrap_or(default.retries),
+            backoff: self
+                .backoff_ms
+                .map(Duration::from_millis)
+                .unwrap_or(default.backoff),
+            max_backoff: self
+                .max_backoff_ms
+                .map(Duration::from_millis)
+                .unwrap_or(default.max_backoff),
+            retry_statuses: match &self.retry_statuses {
+                Some(statuses) => statuses
+                    .iter()
+                    .filter_map(|&status| reqwest::StatusCode::from_u16(status).ok())
+                    .collect(),
+                None => default.retry_statuses,
+            },
+        }
+    }
+}
+
+/// Another Homarr instance the adapter syncs, e.g. a VPN-facing one next to
+/// the LAN dashboard
+///
+/// Targets are already-onboarded instances, like `existing_instance`, each
+/// with its own state file (`state-<name>.json` next to the main one).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HomarrTarget {
+    /// Names the target in logs and in its state file name
+    pub name: String,
+    pub url: String,
+    /// API key of an admin (used as-is, never rotated)
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Admin login, swapped for an API key on first use
+    #[serde(default)]
+    pub admin_username: Option<String>,
+    #[serde(default)]
+    pub admin_password: Option<String>,
+    /// Only place tiles on this board (default: every writable board)
+    #[serde(default)]
+    pub board: Option<String>,
+}
+
+/// A service that doesn't run in a container (NAS, router, plotter), listed
+/// in `[[apps]]`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StaticApp {
+    pub name: String,
+    pub url: String,
+    /// Icon URL, as `homarr.icon`
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl StaticApp {
+    /// The app as the sync engine sees it; always visible
+    pub fn app(&self) -> registry::AppDefinition {
+        registry::AppDefinition {
+            name: self.name.clone(),
+            url: self.url.clone(),
+            description: self.description.clone(),
+            icon_url: self.icon.clone(),
+            category: self.category.clone(),
+            visible: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Failure alert channels (`[alerts]`)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AlertsConfig {
+    /// Email alerts over SMTP (`[alerts.email]`)
+    #[serde(default)]
+    pub email: Option<EmailAlertConfig>,
+
+    /// JSON alerts posted to a URL (`[alerts.webhook]`)
+    #[serde(default)]
+    pub webhook: Option<WebhookAlertConfig>,
+
+    /// Push alerts through an ntfy topic (`[alerts.ntfy]`)
+    #[serde(default)]
+    pub ntfy: Option<NtfyAlertConfig>,
+
+    /// Alerts emitted as a D-Bus signal on the system bus (`[alerts.dbus]`)
+    #[serde(default)]
+    pub dbus: Option<DbusAlertConfig>,
+
+    /// Tera templates replacing the built-in alert wording
+    /// (`[alerts.templates.sync_failing]`, `[alerts.templates.setup_failed]`,
+    /// `[alerts.templates.apps_changed]`)
+    #[serde(default)]
+    pub templates: AlertTemplates,
+}
+
+/// Subject and body templates per kind of alert
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AlertTemplates {
+    #[serde(default)]
+    pub sync_failing: MessageTemplate,
+    #[serde(default)]
+    pub setup_failed: MessageTemplate,
+    #[serde(default)]
+    pub apps_changed: MessageTemplate,
+}
+
+/// Templates for one kind of alert (unset: built-in wording)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MessageTemplate {
+    #[serde(default)]
+    pub subject: Option<String>,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+/// Webhook alert settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookAlertConfig {
+    /// URL the alert is POSTed to as JSON
+    pub url: String,
+
+    /// Extra request headers, e.g. `Authorization`
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+
+    /// Consecutive failed syncs before an alert is sent
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+}
+
+/// ntfy alert settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct NtfyAlertConfig {
+    /// Topic URL, e.g. `https://ntfy.sh/my-boat-alerts`
+    pub url: String,
+
+    /// Access token for a protected topic
+    #[serde(default)]
+    pub token: Option<String>,
+
+    /// Message priority: `min`, `low`, `default`, `high` or `urgent`
+    #[serde(default)]
+    pub priority: Option<String>,
+
+    /// Consecutive failed syncs before an alert is sent
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+}
+
+/// D-Bus alert settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct DbusAlertConfig {
+    /// Consecutive failed syncs before an alert is sent
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+}
+
+/// Webhooks told about setup, sync failures and app changes
+/// (`[notifications]`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotificationsConfig {
+    /// Events sent to the webhooks (default: all)
+    #[serde(default = "default_notification_events")]
+    pub events: Vec<NotificationEvent>,
+
+    /// Where events are sent (`[[notifications.webhooks]]`)
+    #[serde(default)]
+    pub webhooks: Vec<NotificationWebhook>,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            events: default_notification_events(),
+            webhooks: Vec::new(),
+        }
+    }
+}
+
+/// Something a notification webhook can be told about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    /// First-boot setup finished
+    SetupCompleted,
+    /// First-boot setup failed
+    SetupFailed,
+    /// A sync failed after the previous one succeeded
+    SyncFailed,
+    /// A sync created apps or took them off the dashboard
+    AppsChanged,
+}
+
+/// One notification webhook
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotificationWebhook {
+    /// URL the notification is POSTed to: any URL for `json`, the topic URL
+    /// for `ntfy`, the server URL for `gotify`
+    pub url: String,
+
+    /// Payload the receiver expects
+    #[serde(default)]
+    pub format: WebhookFormat,
+
+    /// ntfy access token or Gotify application token
+    #[serde(default)]
+    pub token: Option<String>,
+
+    /// Extra request headers, e.g. `Authorization`
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+/// Payload of a notification webhook
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    /// `{"event", "host", "subject", "body", ...}` as JSON
+    #[default]
+    Json,
+    /// An ntfy message
+    Ntfy,
+    /// A Gotify message
+    Gotify,
+}
+
+/// SMTP email alert settings
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailAlertConfig {
+    /// SMTP server hostname
+    pub smtp_host: String,
+
+    /// SMTP server port
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+
+    /// Transport security
+    #[serde(default)]
+    pub security: SmtpSecurity,
+
+    /// SMTP username (optional)
+    #[serde(default)]
+    pub username: Option<String>,
+
+    /// SMTP password (optional)
+    #[serde(default)]
+    pub password: Option<String>,
+
+    /// Sender address
+    pub from: String,
+
+    /// Recipient addresses
+    pub to: Vec<String>,
+
+    /// Consecutive failed syncs before an alert is sent
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+}
+
+/// SMTP transport security
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// Upgrade a plain connection with STARTTLS
+    #[default]
+    Starttls,
+    /// Implicit TLS (SMTPS, usually port 465)
+    Tls,
+    /// Plain SMTP (local relays only)
+    None,
+}
+
+/// Marker files created by container runtimes inside containers
+const CONTAINER_MARKERS: &[&str] = &[".dockerenv", "run/.containerenv"];
+
+/// Check for container runtime marker files under `root`
+fn detect_container(root: &Path) -> bool {
+    CONTAINER_MARKERS
+        .iter()
+        .any(|marker| root.join(marker).exists())
+}
+
+/// Whether the adapter itself is running inside a container (cached)
+pub fn in_container() -> bool {
+    static IN_CONTAINER: OnceLock<bool> = OnceLock::new();
+    *IN_CONTAINER.get_or_init(|| detect_container(Path::new("/")))
 }
 
 fn default_homarr_url() -> String {
-    "http://localhost:7575".to_string()
+    if in_container() {
+        // localhost is the adapter's own container; reach Homarr by its
+        // compose service name through Docker's embedded DNS
+        "http://homarr:7575".to_string()
+    } else {
+        "http://localhost:7575".to_string()
+    }
 }
 
 fn default_branding_file() -> String {
     "/etc/halos-homarr-branding/branding.toml".to_string()
 }
 
+/// State file name inside a state directory
+const STATE_FILE_NAME: &str = "state.json";
+
+/// Per-application directory under the XDG state directory
+const XDG_APP_DIR: &str = "homarr-container-adapter";
+
 fn default_state_file() -> String {
+    if in_container() {
+        // Conventional volume mount point for the containerized distribution
+        return "/data/state.json".to_string();
+    }
+    if !is_root() {
+        // Unprivileged runs (development) keep clear of the system state
+        let xdg = std::env::var_os("XDG_STATE_HOME").map(PathBuf::from);
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        if let Some(path) = user_state_file(xdg.as_deref(), home.as_deref()) {
+            return path.display().to_string();
+        }
+    }
     "/var/lib/homarr-container-adapter/state.json".to_string()
 }
 
+/// XDG state file: under `$XDG_STATE_HOME`, or else `~/.local/state`
+fn user_state_file(xdg_state_home: Option<&Path>, home: Option<&Path>) -> Option<PathBuf> {
+    let base = match xdg_state_home.filter(|dir| dir.is_absolute()) {
+        Some(dir) => dir.to_path_buf(),
+        None => home?.join(".local/state"),
+    };
+    Some(base.join(XDG_APP_DIR).join(STATE_FILE_NAME))
+}
+
+#[cfg(unix)]
+fn is_root() -> bool {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_root() -> bool {
+    true
+}
+
 fn default_docker_socket() -> String {
-    "/var/run/docker.sock".to_string()
+    if cfg!(windows) {
+        "npipe:////./pipe/docker_engine".to_string()
+    } else {
+        "/var/run/docker.sock".to_string()
+    }
+}
+
+fn default_max_concurrent_targets() -> usize {
+    4
 }
 
 fn default_registry_dir() -> String {
     "/etc/halos/webapps.d".to_string()
 }
 
+fn default_category_sections() -> bool {
+    true
+}
+
+fn default_catalog_icons() -> bool {
+    true
+}
+
+fn default_overflow_section() -> String {
+    "More".to_string()
+}
+
 fn default_bootstrap_api_key_file() -> String {
     "/etc/halos-homarr-branding/bootstrap-api-key".to_string()
 }
@@ -79,6 +1200,10 @@ fn default_signalk_url() -> Option<String> {
     Some("http://localhost:3000".to_string())
 }
 
+fn default_board_save_interval() -> u64 {
+    10
+}
+
 fn default_sync_interval() -> u64 {
     300 // 5 minutes - fallback for missed Docker events
 }
@@ -87,36 +1212,794 @@ fn default_startup_delay() -> u64 {
     10 // 10 seconds
 }
 
+fn default_homarr_ready_timeout() -> u64 {
+    300 // 5 minutes - database migrations on first start
+}
+
+fn default_pool_idle_timeout() -> u64 {
+    90
+}
+
+fn default_pool_max_idle_per_host() -> usize {
+    4
+}
+
+fn default_tcp_keepalive() -> u64 {
+    60
+}
+
+fn default_icon_refresh_interval() -> u64 {
+    86400 // daily
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_notification_events() -> Vec<NotificationEvent> {
+    vec![
+        NotificationEvent::SetupCompleted,
+        NotificationEvent::SetupFailed,
+        NotificationEvent::SyncFailed,
+        NotificationEvent::AppsChanged,
+    ]
+}
+
+fn default_failure_threshold() -> u32 {
+    3
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             homarr_url: default_homarr_url(),
+            homarr: Vec::new(),
+            max_concurrent_targets: default_max_concurrent_targets(),
+            target_board: None,
             branding_file: default_branding_file(),
             state_file: default_state_file(),
+            state_backend: StateBackend::default(),
+            changelog_file: None,
+            state_key_file: None,
+            state_key_credential: None,
+            signing_key_file: None,
+            signing_key_credential: None,
             docker_socket: default_docker_socket(),
+            docker_host: None,
+            docker_tls: None,
+            kubernetes: None,
+            label_schemes: vec![],
+            container_runtime: ContainerRuntime::default(),
+            docker_desktop: false,
             registry_dir: default_registry_dir(),
+            apps: Vec::new(),
+            existing_instance: false,
+            api_key: None,
+            admin_username: None,
+            admin_password: None,
+            credential_store: CredentialStore::default(),
             bootstrap_api_key_file: default_bootstrap_api_key_file(),
             signalk_url: default_signalk_url(),
             debug: false,
             sync_interval: default_sync_interval(),
+            board_save_interval: default_board_save_interval(),
             startup_delay: default_startup_delay(),
+            homarr_ready_timeout: default_homarr_ready_timeout(),
+            pool_idle_timeout: default_pool_idle_timeout(),
+            pool_max_idle_per_host: default_pool_max_idle_per_host(),
+            tcp_keepalive: default_tcp_keepalive(),
+            requests: RequestsConfig::default(),
+            icon_refresh_interval: default_icon_refresh_interval(),
+            duplicate_names: DuplicateNameStrategy::default(),
+            require_https: false,
+            https_upgrade: false,
+            https_exceptions: Vec::new(),
+            description_template: None,
+            compose_sections: false,
+            category_sections: default_category_sections(),
+            section_rows: None,
+            board_full: BoardFull::default(),
+            overflow_section: default_overflow_section(),
+            removal_policy: RemovalPolicy::default(),
+            removal_grace_days: 0,
+            port_url_host: None,
+            catalog_icons: default_catalog_icons(),
+            locale: None,
+            replica_count: ReplicaDisplay::default(),
+            maintenance_windows: None,
+            min_homarr_version: None,
+            grpc_listen: None,
+            health_listen: None,
+            alerts: AlertsConfig::default(),
+            notifications: NotificationsConfig::default(),
         }
     }
 }
 
 impl Config {
-    /// Load configuration from file
-    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+    /// Keep state in `dir` instead of `state_file` (`--state-dir`)
+    pub fn set_state_dir(&mut self, dir: &Path) {
+        self.state_file = dir.join(STATE_FILE_NAME).display().to_string();
+    }
+
+    /// Load configuration from file, with the profile and `HCA_*`
+    /// environment overrides applied, `${VAR}` references expanded and
+    /// secrets read from their `*_file` settings
+    pub fn load<P: AsRef<Path>>(path: P, profile: Option<&str>) -> Result<Self> {
         let path = path.as_ref();
 
-        if !path.exists() {
+        if in_container() {
+            tracing::debug!("Running inside a container, using container defaults");
+        }
+
+        let mut table: toml::Table = if path.exists() {
+            toml::from_str(&fs::read_to_string(path)?)?
+        } else {
+            if let Some(profile) = profile {
+                return Err(AdapterError::Config(format!(
+                    "Profile '{}' requested but config file {:?} not found",
+                    profile, path
+                )));
+            }
             tracing::warn!("Config file not found at {:?}, using defaults", path);
-            return Ok(Self::default());
+            toml::Table::new()
+        };
+        apply_profile(&mut table, profile)?;
+        let scalars = apply_env(&mut table, std::env::vars())?;
+        expand_env_vars(&mut table, &|name| std::env::var(name).ok())?;
+        read_secret_files(&mut table, SECRET_SETTINGS)?;
+        let config = deserialize_config(table, scalars)?;
+
+        match (&config.docker_host, &config.docker_tls) {
+            (Some(host), _) if !host.starts_with("tcp://") => {
+                return Err(AdapterError::Config(format!(
+                    "Invalid docker_host '{}': expected tcp://host:port",
+                    host
+                )));
+            }
+            (None, Some(_)) => {
+                return Err(AdapterError::Config(
+                    "docker_tls is set but docker_host is not".to_string(),
+                ));
+            }
+            _ => {}
         }
 
-        let contents = fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&contents)?;
+        if let Some(minimum) = &config.min_homarr_version {
+            if crate::homarr::parse_version(minimum).is_none() {
+                return Err(AdapterError::Config(format!(
+                    "Invalid min_homarr_version '{}'",
+                    minimum
+                )));
+            }
+        }
+
+        if let Some(windows) = &config.maintenance_windows {
+            registry::parse_visible_hours(windows).map_err(|_| {
+                AdapterError::Config(format!("Invalid maintenance_windows '{}'", windows))
+            })?;
+        }
+
+        for app in &config.apps {
+            if app.name.is_empty() {
+                return Err(AdapterError::Config(format!(
+                    "App with URL '{}' in [[apps]] has no name",
+                    app.url
+                )));
+            }
+            registry::validate_app_url(&app.url).map_err(|e| {
+                AdapterError::Config(format!(
+                    "Invalid URL '{}' of app '{}' in [[apps]]: {}",
+                    app.url, app.name, e
+                ))
+            })?;
+        }
+
+        let mut target_names = std::collections::HashSet::new();
+        for target in &config.homarr {
+            if target.name.is_empty()
+                || !target
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(AdapterError::Config(format!(
+                    "Invalid name '{}' of Homarr target '{}' in [[homarr]]: use letters, \
+                     digits, '-' and '_'",
+                    target.name, target.url
+                )));
+            }
+            if !target_names.insert(target.name.as_str()) {
+                return Err(AdapterError::Config(format!(
+                    "Homarr target '{}' is listed twice in [[homarr]]",
+                    target.name
+                )));
+            }
+            if target.api_key.is_none()
+                && (target.admin_username.is_none() || target.admin_password.is_none())
+            {
+                return Err(AdapterError::Config(format!(
+                    "Homarr target '{}' needs api_key or admin_username and admin_password",
+                    target.name
+                )));
+            }
+        }
+        if config.max_concurrent_targets == 0 {
+            return Err(AdapterError::Config(
+                "max_concurrent_targets must be at least 1".to_string(),
+            ));
+        }
+
+        crate::alerts::check_templates(&config.alerts.templates)?;
+
+        for webhook in &config.notifications.webhooks {
+            if !webhook.url.starts_with("http://") && !webhook.url.starts_with("https://") {
+                return Err(AdapterError::Config(format!(
+                    "Invalid notification webhook URL '{}': must start with http:// or https://",
+                    webhook.url
+                )));
+            }
+            if webhook.format == WebhookFormat::Gotify && webhook.token.is_none() {
+                return Err(AdapterError::Config(format!(
+                    "Gotify notification webhook '{}' needs the application token",
+                    webhook.url
+                )));
+            }
+        }
+
+        if config.state_backend == StateBackend::Sqlite
+            && (config.state_key_file.is_some() || config.state_key_credential.is_some())
+        {
+            return Err(AdapterError::Config(
+                "State encryption (state_key_file, state_key_credential) needs \
+                 state_backend = \"json\""
+                    .to_string(),
+            ));
+        }
+        if config.state_backend == StateBackend::Sqlite
+            && (config.signing_key_file.is_some() || config.signing_key_credential.is_some())
+        {
+            return Err(AdapterError::Config(
+                "State signing (signing_key_file, signing_key_credential) needs \
+                 state_backend = \"json\""
+                    .to_string(),
+            ));
+        }
 
         Ok(config)
     }
+
+    /// End of the maintenance window `now` falls in, if any
+    pub fn maintenance_until(
+        &self,
+        now: chrono::DateTime<chrono::Local>,
+    ) -> Option<chrono::DateTime<chrono::Local>> {
+        let windows = registry::parse_visible_hours(self.maintenance_windows.as_deref()?).ok()?;
+        registry::window_closes(&windows, now)
+    }
+
+    /// Where the per-boot change log is written
+    pub fn changelog_file(&self) -> PathBuf {
+        match &self.changelog_file {
+            Some(file) => PathBuf::from(file),
+            None => Path::new(&self.state_file).with_file_name("last-changes.txt"),
+        }
+    }
+
+    /// Configuration for syncing one `[[homarr]]` target: its URL and
+    /// credentials, and state and change log files of its own
+    pub fn for_target(&self, target: &HomarrTarget) -> Config {
+        let state_file = Path::new(&self.state_file);
+        let sibling = |name: String| state_file.with_file_name(name).display().to_string();
+        Config {
+            homarr_url: target.url.clone(),
+            homarr: Vec::new(),
+            target_board: target.board.clone(),
+            existing_instance: true,
+            api_key: target.api_key.clone(),
+            admin_username: target.admin_username.clone(),
+            admin_password: target.admin_password.clone(),
+            credential_store: CredentialStore::None,
+            state_file: sibling(format!("state-{}.json", target.name)),
+            changelog_file: Some(sibling(format!("last-changes-{}.txt", target.name))),
+            grpc_listen: None,
+            health_listen: None,
+            ..self.clone()
+        }
+    }
+
+    /// Host for URLs inferred from published container ports
+    pub fn port_url_host(&self) -> String {
+        self.port_url_host
+            .clone()
+            .unwrap_or_else(registry::get_domain)
+    }
+
+    /// Connection pool settings for the Homarr client
+    pub fn pool_options(&self) -> PoolOptions {
+        PoolOptions {
+            idle_timeout: Duration::from_secs(self.pool_idle_timeout),
+            max_idle_per_host: self.pool_max_idle_per_host,
+            tcp_keepalive: Duration::from_secs(self.tcp_keepalive),
+        }
+    }
+
+    /// Section row limit and what to do with tiles beyond it
+    pub fn section_limits(&self) -> SectionLimits {
+        SectionLimits {
+            max_rows: self
+                .section_rows
+                .map(|rows| i32::try_from(rows).unwrap_or(i32::MAX)),
+            when_full: self.board_full,
+            overflow_section: self.overflow_section.clone(),
+        }
+    }
+
+    /// Timeout and retry policies for the Homarr client
+    pub fn request_policies(&self) -> RequestPolicies {
+        let defaults = RequestPolicies::default();
+        RequestPolicies {
+            read: self.requests.read.policy(defaults.read),
+            mutation: self.requests.mutation.policy(defaults.mutation),
+        }
+    }
+
+    /// Settings that differ in `new` but are only read at startup, so a
+    /// reload can't apply them
+    pub fn changed_startup_settings(&self, new: &Config) -> Vec<&'static str> {
+        [
+            ("docker_socket", self.docker_socket != new.docker_socket),
+            ("docker_host", self.docker_host != new.docker_host),
+            (
+                "container_runtime",
+                self.container_runtime != new.container_runtime,
+            ),
+            ("state_file", self.state_file != new.state_file),
+            ("state_backend", self.state_backend != new.state_backend),
+            ("state_key_file", self.state_key_file != new.state_key_file),
+            (
+                "state_key_credential",
+                self.state_key_credential != new.state_key_credential,
+            ),
+            (
+                "signing_key_file",
+                self.signing_key_file != new.signing_key_file,
+            ),
+            (
+                "signing_key_credential",
+                self.signing_key_credential != new.signing_key_credential,
+            ),
+            ("grpc_listen", self.grpc_listen != new.grpc_listen),
+            ("health_listen", self.health_listen != new.health_listen),
+        ]
+        .into_iter()
+        .filter_map(|(setting, changed)| changed.then_some(setting))
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_changed_startup_settings() {
+        let config = Config::default();
+        let mut new = Config {
+            sync_interval: 60,
+            ..Config::default()
+        };
+        assert!(config.changed_startup_settings(&new).is_empty());
+        new.grpc_listen = Some("127.0.0.1:50051".to_string());
+        new.set_state_dir(Path::new("/tmp/adapter"));
+        assert_eq!(
+            config.changed_startup_settings(&new),
+            vec!["state_file", "grpc_listen"]
+        );
+    }
+
+    #[test]
+    fn test_detect_container_dockerenv() {
+        let root = TempDir::new().unwrap();
+        assert!(!detect_container(root.path()));
+        fs::write(root.path().join(".dockerenv"), "").unwrap();
+        assert!(detect_container(root.path()));
+    }
+
+    #[test]
+    fn test_request_policies_override_per_class() {
+        let config: Config = toml::from_str(
+            r#"
+[requests.read]
+timeout = 3
+backoff_ms = 2000
+retry_statuses = [503, 1000]
+
+[requests.mutation]
+retries = 2
+"#,
+        )
+        .unwrap();
+        let defaults = RequestPolicies::default();
+        let policies = config.request_policies();
+
+        assert_eq!(policies.read.timeout, Duration::from_secs(3));
+        assert_eq!(policies.read.retries, defaults.read.retries);
+        assert_eq!(policies.mutation.timeout, defaults.mutation.timeout);
+        assert_eq!(policies.read.backoff, Duration::from_secs(2));
+        assert_eq!(policies.read.max_backoff, defaults.read.max_backoff);
+        assert_eq!(
+            policies.read.retry_statuses,
+            vec![reqwest::StatusCode::SERVICE_UNAVAILABLE]
+        );
+        assert_eq!(policies.mutation.retries, 2);
+        assert_eq!(
+            policies.mutation.retry_statuses,
+            defaults.mutation.retry_statuses
+        );
+        assert_eq!(Config::default().request_policies(), defaults);
+    }
+
+    #[test]
+    fn test_maintenance_windows() {
+        use chrono::TimeZone;
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "maintenance_windows = \"3:00-4\"\n").unwrap();
+        assert!(Config::load(&path, None).is_err());
+
+        fs::write(&path, "maintenance_windows = \"03:00-04:30\"\n").unwrap();
+        let config = Config::load(&path, None).unwrap();
+        let at = |h, m| chrono::Local.with_ymd_and_hms(2025, 6, 1, h, m, 0).unwrap();
+        assert_eq!(config.maintenance_until(at(3, 15)), Some(at(4, 30)));
+        assert_eq!(config.maintenance_until(at(5, 0)), None);
+        assert_eq!(Config::default().maintenance_until(at(3, 15)), None);
+    }
+
+    #[test]
+    fn test_docker_host_validation() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        fs::write(&path, "docker_host = \"unix:///var/run/docker.sock\"\n").unwrap();
+        assert!(Config::load(&path, None).is_err());
+
+        fs::write(
+            &path,
+            "[docker_tls]\nca = \"ca.pem\"\ncert = \"cert.pem\"\nkey = \"key.pem\"\n",
+        )
+        .unwrap();
+        assert!(Config::load(&path, None).is_err());
+
+        fs::write(
+            &path,
+            "docker_host = \"tcp://nas.local:2376\"\n\n\
+             [docker_tls]\nca = \"ca.pem\"\ncert = \"cert.pem\"\nkey = \"key.pem\"\n",
+        )
+        .unwrap();
+        let config = Config::load(&path, None).unwrap();
+        assert_eq!(config.docker_host.as_deref(), Some("tcp://nas.local:2376"));
+        assert_eq!(config.docker_tls.unwrap().key, "key.pem");
+    }
+
+    #[test]
+    fn test_static_apps() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "[[apps]]\nname = \"NAS\"\nurl = \"http://nas.local:5000\"\n\
+             icon = \"/icons/nas.png\"\ncategory = \"Network\"\n\n\
+             [[apps]]\nname = \"Router\"\nurl = \"http://192.168.1.1\"\n",
+        )
+        .unwrap();
+        let config = Config::load(&path, None).unwrap();
+        assert_eq!(config.apps.len(), 2);
+        let nas = config.apps[0].app();
+        assert_eq!(nas.icon_url.as_deref(), Some("/icons/nas.png"));
+        assert_eq!(nas.category.as_deref(), Some("Network"));
+        assert!(nas.is_visible());
+        assert!(nas.container_name().is_none());
+
+        fs::write(&path, "[[apps]]\nname = \"NAS\"\nurl = \"nas.local\"\n").unwrap();
+        assert!(Config::load(&path, None).is_err());
+        fs::write(&path, "[[apps]]\nname = \"\"\nurl = \"http://nas.local\"\n").unwrap();
+        assert!(Config::load(&path, None).is_err());
+    }
+
+    #[test]
+    fn test_homarr_targets() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "state_file = \"/var/lib/hca/state.json\"\n\n\
+             [[homarr]]\nname = \"vpn\"\nurl = \"http://10.8.0.1:7575\"\n\
+             api_key = \"abc\"\nboard = \"Remote\"\n",
+        )
+        .unwrap();
+        let config = Config::load(&path, None).unwrap();
+        assert_eq!(config.homarr.len(), 1);
+        let vpn = config.for_target(&config.homarr[0]);
+        assert_eq!(vpn.homarr_url, "http://10.8.0.1:7575");
+        assert!(vpn.existing_instance);
+        assert_eq!(vpn.api_key.as_deref(), Some("abc"));
+        assert_eq!(vpn.target_board.as_deref(), Some("Remote"));
+        assert_eq!(vpn.state_file, "/var/lib/hca/state-vpn.json");
+        assert_eq!(
+            vpn.changelog_file(),
+            PathBuf::from("/var/lib/hca/last-changes-vpn.txt")
+        );
+        assert!(vpn.homarr.is_empty());
+
+        fs::write(
+            &path,
+            "[[homarr]]\nname = \"vpn\"\nurl = \"http://10.8.0.1:7575\"\n",
+        )
+        .unwrap();
+        assert!(Config::load(&path, None).is_err());
+        fs::write(
+            &path,
+            "[[homarr]]\nname = \"vpn/lan\"\nurl = \"http://10.8.0.1:7575\"\napi_key = \"abc\"\n",
+        )
+        .unwrap();
+        assert!(Config::load(&path, None).is_err());
+    }
+
+    #[test]
+    fn test_notifications() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "").unwrap();
+        let config = Config::load(&path, None).unwrap();
+        assert_eq!(config.notifications.events.len(), 4);
+
+        fs::write(
+            &path,
+            "[notifications]\nevents = [\"apps_changed\"]\n\n\
+             [[notifications.webhooks]]\nurl = \"https://ntfy.sh/fleet\"\nformat = \"ntfy\"\n",
+        )
+        .unwrap();
+        let config = Config::load(&path, None).unwrap();
+        assert_eq!(
+            config.notifications.events,
+            vec![NotificationEvent::AppsChanged]
+        );
+        assert_eq!(config.notifications.webhooks[0].format, WebhookFormat::Ntfy);
+
+        fs::write(
+            &path,
+            "[[notifications.webhooks]]\nurl = \"https://gotify.example.com\"\nformat = \"gotify\"\n",
+        )
+        .unwrap();
+        assert!(Config::load(&path, None).is_err());
+    }
+
+    #[test]
+    fn test_expand_env_vars() {
+        let lookup = |name: &str| match name {
+            "HOMARR_HOST" => Some("homarr".to_string()),
+            "CREDENTIALS_DIRECTORY" => Some("/run/credentials/hca.service".to_string()),
+            _ => None,
+        };
+        let mut table: toml::Table = toml::from_str(
+            r#"homarr_url = "http://${HOMARR_HOST}:7575"
+               admin_password = "pa$${literal}"
+               tags = ["${HOMARR_HOST}"]
+
+               [alerts.ntfy]
+               token_file = "${CREDENTIALS_DIRECTORY}/ntfy"
+"#,
+        )
+        .unwrap();
+        expand_env_vars(&mut table, &lookup).unwrap();
+        assert_eq!(table["homarr_url"].as_str(), Some("http://homarr:7575"));
+        assert_eq!(table["admin_password"].as_str(), Some("pa${literal}"));
+        assert_eq!(table["tags"][0].as_str(), Some("homarr"));
+        assert_eq!(
+            table["alerts"]["ntfy"]["token_file"].as_str(),
+            Some("/run/credentials/hca.service/ntfy")
+        );
+
+        let mut unset: toml::Table = toml::from_str("api_key = \"${API_KEY}\"").unwrap();
+        let err = expand_env_vars(&mut unset, &lookup).unwrap_err();
+        assert!(err.to_string().contains("'api_key' uses ${API_KEY}"));
+        let mut unclosed: toml::Table = toml::from_str("api_key = \"${API_KEY\"").unwrap();
+        assert!(expand_env_vars(&mut unclosed, &lookup).is_err());
+    }
+
+    #[test]
+    fn test_read_secret_files() {
+        let dir = TempDir::new().unwrap();
+        let secret = dir.path().join("homarr_admin");
+        fs::write(&secret, "s3cret\n").unwrap();
+
+        let mut table = toml::Table::new();
+        table.insert(
+            "admin_password_file".to_string(),
+            toml::Value::String(secret.display().to_string()),
+        );
+        table.insert("alerts".to_string(), toml::Value::Table(toml::Table::new()));
+        read_secret_files(&mut table, SECRET_SETTINGS).unwrap();
+        assert_eq!(table["admin_password"].as_str(), Some("s3cret"));
+        assert!(!table.contains_key("admin_password_file"));
+
+        table.insert(
+            "admin_password_file".to_string(),
+            toml::Value::String(secret.display().to_string()),
+        );
+        let err = read_secret_files(&mut table, SECRET_SETTINGS).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Both admin_password and admin_password_file"));
+
+        // Each entry of an array of tables has its own secret
+        let mut webhooks: toml::Table = toml::from_str(&format!(
+            "[[notifications.webhooks]]\nurl = \"https://ntfy.sh/a\"\ntoken_file = {:?}\n\n\
+             [[notifications.webhooks]]\nurl = \"https://ntfy.sh/b\"\n",
+            secret.display().to_string()
+        ))
+        .unwrap();
+        read_secret_files(&mut webhooks, SECRET_SETTINGS).unwrap();
+        let entries = webhooks["notifications"]["webhooks"].as_array().unwrap();
+        assert_eq!(entries[0]["token"].as_str(), Some("s3cret"));
+        assert!(entries[1].get("token").is_none());
+
+        let mut targets: toml::Table = toml::from_str(&format!(
+            "[[homarr]]\nname = \"vpn\"\nurl = \"http://10.8.0.1:7575\"\n\
+             admin_username = \"admin\"\nadmin_password_file = {:?}\n",
+            secret.display().to_string()
+        ))
+        .unwrap();
+        read_secret_files(&mut targets, SECRET_SETTINGS).unwrap();
+        assert_eq!(
+            targets["homarr"][0]["admin_password"].as_str(),
+            Some("s3cret")
+        );
+
+        let mut missing = toml::Table::new();
+        missing.insert(
+            "api_key_file".to_string(),
+            toml::Value::String("/nonexistent/key".to_string()),
+        );
+        assert!(read_secret_files(&mut missing, SECRET_SETTINGS).is_err());
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let mut table: toml::Table = toml::from_str(
+            "homarr_url = \"http://localhost:7575\"
+             sync_interval = 300
+
+             [requests.read]
+timeout = 5
+",
+        )
+        .unwrap();
+        let vars = [
+            ("HCA_HOMARR_URL", "http://homarr:7575"),
+            ("HCA_SYNC_INTERVAL", "60"),
+            ("HCA_COMPOSE_SECTIONS", "false"),
+            ("HCA_MAINTENANCE_WINDOWS", "03:00-04:30"),
+            ("HCA_REQUESTS__READ__RETRIES", "4"),
+            ("HCA_ALERTS__NTFY__URL", "https://ntfy.sh/halos"),
+            ("HCA_ALERTS__NTFY__TOKEN", "\"1234\""),
+            // Text settings, one sharing a numeric setting's value
+            ("HCA_ADMIN_PASSWORD", "123456"),
+            ("HCA_API_KEY", "60"),
+            ("HCA_LOCALE", "true"),
+            ("PATH", "/usr/bin"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        let scalars = apply_env(&mut table, vars).unwrap();
+
+        let config = deserialize_config(table, scalars).unwrap();
+        assert_eq!(config.homarr_url, "http://homarr:7575");
+        assert_eq!(config.sync_interval, 60);
+        assert!(!config.compose_sections);
+        assert_eq!(config.maintenance_windows.as_deref(), Some("03:00-04:30"));
+        let read = config.request_policies().read;
+        assert_eq!(read.timeout, Duration::from_secs(5));
+        assert_eq!(read.retries, 4);
+        let ntfy = config.alerts.ntfy.unwrap();
+        assert_eq!(ntfy.url, "https://ntfy.sh/halos");
+        assert_eq!(ntfy.token.as_deref(), Some("1234"));
+        assert_eq!(config.admin_password.as_deref(), Some("123456"));
+        assert_eq!(config.api_key.as_deref(), Some("60"));
+        assert_eq!(config.locale.as_deref(), Some("true"));
+
+        // A clash fails whichever order the environment lists it in
+        let clash = [("HCA_ALERTS", "1"), ("HCA_ALERTS__NTFY__URL", "x")]
+            .map(|(k, v)| (k.to_string(), v.to_string()));
+        let mut table = toml::Table::new();
+        assert!(apply_env(&mut table, clash.clone()).is_err());
+        let mut table = toml::Table::new();
+        assert!(apply_env(&mut table, clash.into_iter().rev()).is_err());
+        let empty = [("HCA_ALERTS____URL".to_string(), "x".to_string())];
+        assert!(apply_env(&mut table, empty).is_err());
+
+        // Without a config file, overrides apply to the defaults
+        let dir = TempDir::new().unwrap();
+        let config = Config::load(dir.path().join("missing.toml"), None).unwrap();
+        let defaults = Config::default();
+        assert_eq!(config.homarr_url, defaults.homarr_url);
+        assert_eq!(config.state_file, defaults.state_file);
+        assert_eq!(config.sync_interval, defaults.sync_interval);
+    }
+
+    #[test]
+    fn test_profiles_overlay_settings() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(
+            &path,
+            "compose_sections = false
+             icon_refresh_interval = 86400
+
+             [requests.read]
+timeout = 5
+
+             [profiles.minimal]
+icon_refresh_interval = 0
+
+             [profiles.full]
+compose_sections = true
+
+             [profiles.full.requests.read]
+retries = 4
+",
+        )
+        .unwrap();
+
+        let config = Config::load(&path, None).unwrap();
+        assert!(!config.compose_sections);
+        assert!(config.category_sections);
+        assert_eq!(config.icon_refresh_interval, 86400);
+
+        let config = Config::load(&path, Some("minimal")).unwrap();
+        assert_eq!(config.icon_refresh_interval, 0);
+        assert!(!config.compose_sections);
+
+        let config = Config::load(&path, Some("full")).unwrap();
+        assert!(config.compose_sections);
+        let read = config.request_policies().read;
+        assert_eq!(read.timeout, Duration::from_secs(5));
+        assert_eq!(read.retries, 4);
+
+        let err = Config::load(&path, Some("nightly")).unwrap_err();
+        assert!(err.to_string().contains("full, minimal"));
+    }
+
+    #[test]
+    fn test_user_state_file() {
+        let home = Path::new("/home/dev");
+        assert_eq!(
+            user_state_file(None, Some(home)),
+            Some(PathBuf::from(
+                "/home/dev/.local/state/homarr-container-adapter/state.json"
+            ))
+        );
+        assert_eq!(
+            user_state_file(Some(Path::new("/tmp/xdg")), Some(home)),
+            Some(PathBuf::from(
+                "/tmp/xdg/homarr-container-adapter/state.json"
+            ))
+        );
+        // Relative XDG_STATE_HOME is invalid per the spec and ignored
+        assert_eq!(user_state_file(Some(Path::new("xdg")), None), None);
+
+        let mut config = Config::default();
+        config.set_state_dir(Path::new("/tmp/adapter"));
+        assert_eq!(config.state_file, "/tmp/adapter/state.json");
+    }
+
+    #[test]
+    fn test_detect_container_podman() {
+        let root = TempDir::new().unwrap();
+        fs::create_dir_all(root.path().join("run")).unwrap();
+        fs::write(root.path().join("run/.containerenv"), "").unwrap();
+        assert!(detect_container(root.path()));
+    }
 }
@@ -0,0 +1,230 @@
+//! `diff` command: drift between Docker, Homarr and the state file
+//!
+//! One row per app URL that is running in Docker (labelled containers) or
+//! recorded in the state file (apps synced before), showing whether Homarr
+//! has it. Apps in Homarr that the adapter never managed are left out.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::homarr::{self, HomarrClient, SelectableApp};
+use crate::registry::AppDefinition;
+use crate::state::State;
+use crate::table;
+
+/// How an app's three records disagree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Drift {
+    /// Running (or not a container app), in Homarr and in state
+    InSync,
+    /// Running but not in Homarr; the next sync adds it
+    Missing,
+    /// In Homarr under the app's name, with a different URL
+    UrlMismatch,
+    /// In Homarr, but its container is no longer running
+    ContainerGone,
+    /// Running and in Homarr, but not recorded in state
+    Untracked,
+    /// Only recorded in state
+    Stale,
+}
+
+impl Drift {
+    /// Marker in the table: `=` in sync, `+` to add, `-` gone, `~` differs
+    fn marker(self) -> char {
+        match self {
+            Drift::InSync => '=',
+            Drift::Missing => '+',
+            Drift::ContainerGone | Drift::Stale => '-',
+            Drift::UrlMismatch | Drift::Untracked => '~',
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Drift::InSync => "in sync",
+            Drift::Missing => "not in Homarr yet",
+            Drift::UrlMismatch => "URL differs in Homarr",
+            Drift::ContainerGone => "container not running",
+            Drift::Untracked => "not recorded in state",
+            Drift::Stale => "only in state",
+        }
+    }
+}
+
+/// One app as Docker, Homarr and the state file see it
+#[derive(Debug, Serialize)]
+pub struct DriftRow {
+    pub name: String,
+    pub url: String,
+    /// Running container the app comes from
+    pub container: Option<String>,
+    /// Whether the app comes from a container at all (registry, Signal K
+    /// and manual apps don't)
+    pub container_app: bool,
+    /// Matching Homarr app's ID and URL
+    pub homarr_id: Option<String>,
+    pub homarr_url: Option<String>,
+    /// Recorded in the state file's discovered apps
+    pub in_state: bool,
+    pub drift: Drift,
+}
+
+/// Compare running container apps, Homarr's apps and the state file
+pub fn diff(
+    container_apps: &[AppDefinition],
+    homarr_apps: &[SelectableApp],
+    state: &State,
+) -> Vec<DriftRow> {
+    let mut rows = Vec::new();
+    let mut seen = HashSet::new();
+
+    let running = container_apps
+        .iter()
+        .map(|app| (app.name.clone(), app.url.clone(), app.container_name()));
+    let recorded = state
+        .discovered_apps
+        .iter()
+        .map(|(url, app)| (app.name.clone(), url.clone(), None));
+    for (name, url, container) in running.chain(recorded) {
+        if !seen.insert(homarr::normalize_url(&url)) {
+            continue;
+        }
+        let recorded = state.discovered_apps.get(&url);
+        let container_app =
+            container.is_some() || recorded.is_some_and(|app| !app.container_id.is_empty());
+        let by_url = HomarrClient::find_app_by_url(homarr_apps, &url);
+        let homarr = by_url.or_else(|| HomarrClient::find_app_by_name(homarr_apps, &name));
+
+        let drift = if homarr.is_some() && by_url.is_none() {
+            Drift::UrlMismatch
+        } else {
+            match (container.is_some() || !container_app, homarr.is_some()) {
+                (true, true) if recorded.is_some() => Drift::InSync,
+                (true, true) => Drift::Untracked,
+                (true, false) if container.is_some() || recorded.is_none() => Drift::Missing,
+                (false, true) => Drift::ContainerGone,
+                _ => Drift::Stale,
+            }
+        };
+        rows.push(DriftRow {
+            name,
+            url,
+            container: container.map(str::to_string),
+            container_app,
+            homarr_id: homarr.map(|app| app.id.clone()),
+            homarr_url: homarr.and_then(|app| app.href.clone()),
+            in_state: recorded.is_some(),
+            drift,
+        });
+    }
+    rows.sort_by_key(|row| row.name.to_lowercase());
+    rows
+}
+
+/// Render rows as a table with one marker column and one per source
+pub fn render_table(rows: &[DriftRow]) -> String {
+    let cells: Vec<[String; 5]> = rows
+        .iter()
+        .map(|row| {
+            let docker = match (&row.container, row.container_app) {
+                (Some(container), _) => container.clone(),
+                (None, true) => "-".to_string(),
+                (None, false) => "n/a".to_string(),
+            };
+            let homarr = match (&row.homarr_url, &row.homarr_id) {
+                (_, None) => "-".to_string(),
+                (Some(url), Some(_)) if row.drift == Drift::UrlMismatch => url.clone(),
+                (_, Some(_)) => "yes".to_string(),
+            };
+            let state = if row.in_state { "yes" } else { "-" };
+            [
+                format!("{} {}", row.drift.marker(), row.name),
+                docker,
+                homarr,
+                state.to_string(),
+                row.drift.describe().to_string(),
+            ]
+        })
+        .collect();
+
+    table::render(["  APP", "DOCKER", "HOMARR", "STATE", ""], cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::DiscoveredApp;
+    use chrono::Utc;
+
+    fn container_app(name: &str, url: &str, container: &str) -> AppDefinition {
+        let mut labels = std::collections::HashMap::new();
+        labels.insert("homarr.enable".to_string(), "true".to_string());
+        labels.insert("homarr.name".to_string(), name.to_string());
+        labels.insert("homarr.url".to_string(), url.to_string());
+        crate::docker::apps_from_labels(container, &labels).remove(0)
+    }
+
+    fn homarr_app(id: &str, name: &str, href: &str) -> SelectableApp {
+        SelectableApp {
+            id: id.to_string(),
+            name: name.to_string(),
+            icon_url: String::new(),
+            href: Some(href.to_string()),
+        }
+    }
+
+    fn record(state: &mut State, name: &str, url: &str, container: &str) {
+        state.discovered_apps.insert(
+            url.to_string(),
+            DiscoveredApp {
+                name: name.to_string(),
+                container_id: container.to_string(),
+                added_at: Utc::now(),
+                last_seen: None,
+                source: crate::state::AppSource::Container,
+                pinned: false,
+            },
+        );
+    }
+
+    #[test]
+    fn test_diff_classifies_drift() {
+        let running = vec![
+            container_app("Grafana", "http://grafana.local", "grafana"),
+            container_app("InfluxDB", "http://influx.local", "influxdb"),
+            container_app("Node-RED", "http://nodered.local:1880", "nodered"),
+            container_app("Portainer", "http://portainer.local", "portainer"),
+        ];
+        let homarr = vec![
+            homarr_app("a1", "Grafana", "http://grafana.local/"),
+            homarr_app("a2", "Node-RED", "http://nodered.local:1881"),
+            homarr_app("a3", "AvNav", "http://avnav.local"),
+            homarr_app("a4", "Portainer", "http://portainer.local"),
+            homarr_app("a5", "Router", "http://192.168.1.1"),
+        ];
+        let mut state = State::default();
+        record(&mut state, "Grafana", "http://grafana.local", "grafana");
+        record(&mut state, "AvNav", "http://avnav.local", "avnav");
+        record(&mut state, "Signal K", "http://signalk.local", "signalk");
+        record(&mut state, "Router", "http://192.168.1.1", "");
+
+        let rows = diff(&running, &homarr, &state);
+        let drift = |name: &str| rows.iter().find(|r| r.name == name).unwrap().drift;
+        assert_eq!(rows.len(), 7);
+        assert_eq!(drift("Grafana"), Drift::InSync);
+        assert_eq!(drift("InfluxDB"), Drift::Missing);
+        assert_eq!(drift("Node-RED"), Drift::UrlMismatch);
+        assert_eq!(drift("Portainer"), Drift::Untracked);
+        assert_eq!(drift("AvNav"), Drift::ContainerGone);
+        assert_eq!(drift("Signal K"), Drift::Stale);
+        assert_eq!(drift("Router"), Drift::InSync);
+
+        let table = render_table(&rows);
+        assert!(table.starts_with("  APP"));
+        assert!(table.contains("+ InfluxDB"));
+        assert!(table.contains("http://nodered.local:1881"));
+    }
+}
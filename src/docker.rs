@@ -0,0 +1,1565 @@
+//! Docker container discovery
+//!
+//! Builds `AppDefinition` objects from `homarr.*` labels on running
+//! containers. A container opts in with `homarr.enable=true`; each tile is
+//! described by a label group:
+//!
+//! - `homarr.name`, `homarr.url`, ... — the container's main tile
+//! - `homarr.1.name`, `homarr.1.url`, ... — additional tiles, one per index
+//!
+//! Indexed groups inherit shared fields they don't set (icon, category, ...)
+//! from the unindexed group, so those only need to be given once. Fields that
+//! identify a tile (name, URL, ping URL, widget) are never inherited.
+//!
+//! Containers labelled for other dashboards (gethomepage's `homepage.*`) are
+//! read too when their scheme is enabled in `label_schemes`; their labels
+//! are translated to `homarr.*` first (see `translate_labels`).
+//!
+//! Descriptions are templates over container fields (see
+//! `render_descriptions`). A container without any URL label links to its
+//! published web port (see `infer_url`), and one without an icon label gets
+//! the dashboard-icons icon for its image (see `infer_icon`).
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use bollard::container::ListContainersOptions;
+use bollard::models::{Port, PortTypeEnum};
+use bollard::Docker;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::config::{Config, DockerTlsConfig};
+use crate::error::Result;
+use crate::icons;
+use crate::registry::{self, AppDefinition, AppType, LayoutConfig};
+use crate::state::ContainerStop;
+
+/// Namespace for adapter labels
+const LABEL_PREFIX: &str = "homarr.";
+
+/// Fields of one tile's label group, keyed by field name (e.g. "url")
+type LabelGroup<'a> = HashMap<&'a str, &'a str>;
+
+/// Fields indexed groups don't inherit from the unindexed group
+const PER_TILE_FIELDS: &[&str] = &["name", "url", "ping", "ping_url", "widget"];
+
+/// Docker Desktop's per-user sockets, relative to the home directory
+/// (macOS, then Linux)
+const DESKTOP_SOCKETS: &[&str] = &[".docker/run/docker.sock", ".docker/desktop/docker.sock"];
+
+/// Podman's rootless socket, relative to `$XDG_RUNTIME_DIR`
+const PODMAN_USER_SOCKET: &str = "podman/podman.sock";
+
+/// Podman's rootful socket
+const PODMAN_SYSTEM_SOCKET: &str = "/run/podman/podman.sock";
+
+/// Hostname containers use to reach the Docker host
+const HOST_GATEWAY: &str = "host.docker.internal";
+
+/// Container engine the adapter talks to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerRuntime {
+    #[default]
+    Docker,
+    /// Podman's Docker-compatible API, rootless or rootful
+    Podman,
+}
+
+/// Docker socket to connect to
+///
+/// A missing configured socket falls back to the runtime's usual sockets:
+/// Docker Desktop's per-user socket in Docker Desktop mode (since
+/// `/var/run/docker.sock` only exists when the privileged helper is
+/// enabled), and Podman's rootless then rootful socket for Podman.
+fn socket_path(config: &Config, home: Option<&Path>, runtime_dir: Option<&Path>) -> String {
+    if Path::new(&config.docker_socket).exists() {
+        return config.docker_socket.clone();
+    }
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    if config.docker_desktop {
+        candidates.extend(
+            home.into_iter()
+                .flat_map(|home| DESKTOP_SOCKETS.iter().map(move |s| home.join(s))),
+        );
+    }
+    if config.container_runtime == ContainerRuntime::Podman {
+        candidates.extend(runtime_dir.map(|dir| dir.join(PODMAN_USER_SOCKET)));
+        candidates.push(PathBuf::from(PODMAN_SYSTEM_SOCKET));
+    }
+    candidates
+        .into_iter()
+        .find(|path| path.exists())
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|| config.docker_socket.clone())
+}
+
+/// Seconds before a Docker API request times out
+const TIMEOUT_SECS: u64 = 120;
+
+/// Connect to the Docker daemon (Unix socket, Windows named pipe or
+/// `docker_host` over TCP)
+pub fn connect(config: &Config) -> Result<Docker> {
+    if let Some(host) = &config.docker_host {
+        return connect_remote(host, config.docker_tls.as_ref());
+    }
+    Ok(Docker::connect_with_socket(
+        &local_socket(config),
+        TIMEOUT_SECS,
+        bollard::API_DEFAULT_VERSION,
+    )?)
+}
+
+/// Socket of the local daemon, after runtime and Docker Desktop fallbacks
+fn local_socket(config: &Config) -> String {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+    socket_path(config, home.as_deref(), runtime_dir.as_deref())
+}
+
+/// The local daemon's socket, if connecting to it fails with EACCES
+///
+/// Usually a packaging mistake: the service user isn't in the `docker`
+/// group. Remote daemons (`docker_host`) are never reported.
+#[cfg(unix)]
+pub fn socket_permission_denied(config: &Config) -> Option<String> {
+    if config.docker_host.is_some() {
+        return None;
+    }
+    let socket = local_socket(config);
+    match std::os::unix::net::UnixStream::connect(&socket) {
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => Some(socket),
+        _ => None,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn socket_permission_denied(_config: &Config) -> Option<String> {
+    None
+}
+
+/// Connect to a remote daemon, with mutual TLS when certificates are given
+fn connect_remote(host: &str, tls: Option<&DockerTlsConfig>) -> Result<Docker> {
+    let docker = match tls {
+        Some(tls) => Docker::connect_with_ssl(
+            host,
+            Path::new(&tls.key),
+            Path::new(&tls.cert),
+            Path::new(&tls.ca),
+            TIMEOUT_SECS,
+            bollard::API_DEFAULT_VERSION,
+        )?,
+        None => Docker::connect_with_http(host, TIMEOUT_SECS, bollard::API_DEFAULT_VERSION)?,
+    };
+    Ok(docker)
+}
+
+/// Replace the host of a URL, if it is one of `from`
+fn rewrite_host(url: &str, from: &[&str], to: &str) -> Option<String> {
+    let mut parsed = url::Url::parse(url).ok()?;
+    if !from.contains(&parsed.host_str()?) {
+        return None;
+    }
+    parsed.set_host(Some(to)).ok()?;
+    Some(parsed.to_string())
+}
+
+/// Adapt a discovered app's URLs to Docker Desktop
+///
+/// The browser runs on the host, where published ports are on `localhost`,
+/// while Homarr runs inside Docker Desktop's VM, where `localhost` is its own
+/// container and the host is `host.docker.internal`. So tile URLs point at
+/// `localhost` and ping URLs at `host.docker.internal`.
+pub fn adapt_for_desktop(app: &mut AppDefinition) {
+    if let Some(url) = rewrite_host(&app.url, &[HOST_GATEWAY], "localhost") {
+        app.url = url;
+    }
+    if let Some(ping_url) = app
+        .ping_url
+        .as_deref()
+        .and_then(|u| rewrite_host(u, &["localhost", "127.0.0.1"], HOST_GATEWAY))
+    {
+        app.ping_url = Some(ping_url);
+    }
+}
+
+/// Where to show the replica count of scaled services
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplicaDisplay {
+    /// Append "(N replicas)" to the tile description
+    #[default]
+    Description,
+    /// Append "×N" to the tile name
+    Name,
+    /// Don't show replica counts
+    Off,
+}
+
+/// Label scheme of another dashboard, translated to `homarr.*` labels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelScheme {
+    /// gethomepage: `homepage.name`, `homepage.href`, `homepage.group`, ...
+    Homepage,
+}
+
+/// `homepage.*` labels and the `homarr.*` fields they map to
+const HOMEPAGE_FIELDS: &[(&str, &str)] = &[
+    ("homepage.name", "name"),
+    ("homepage.href", "url"),
+    ("homepage.description", "description"),
+    ("homepage.group", "section"),
+    ("homepage.siteMonitor", "ping_url"),
+];
+
+/// Icon URL for a homepage icon: URLs and paths as-is, bare names from the
+/// dashboard icons set. Material and Simple Icons (`mdi-`, `si-`) have no
+/// Homarr equivalent and are dropped.
+fn homepage_icon(icon: &str) -> Option<String> {
+    if icon.contains("://") || icon.starts_with('/') {
+        return Some(icon.to_string());
+    }
+    if icon.starts_with("mdi-") || icon.starts_with("si-") {
+        return None;
+    }
+    let (name, ext) = match icon.rsplit_once('.') {
+        Some((name, ext @ ("png" | "svg" | "webp"))) => (name, ext),
+        _ => (icon, "png"),
+    };
+    Some(icons::icon_url(name, ext))
+}
+
+/// Add `homarr.*` labels translated from the enabled label schemes
+///
+/// Containers that carry any `homarr.*` label are left alone: the native
+/// labels win over a translation.
+pub fn translate_labels(labels: &mut HashMap<String, String>, schemes: &[LabelScheme]) {
+    if labels.keys().any(|key| key.starts_with("homarr.")) {
+        return;
+    }
+    for scheme in schemes {
+        match scheme {
+            LabelScheme::Homepage => {
+                if !labels.contains_key("homepage.name") {
+                    continue;
+                }
+                let mut translated: HashMap<String, String> = HOMEPAGE_FIELDS
+                    .iter()
+                    .filter_map(|(from, to)| {
+                        Some((format!("homarr.{}", to), labels.get(*from)?.clone()))
+                    })
+                    .collect();
+                if let Some(icon) = labels.get("homepage.icon").and_then(|i| homepage_icon(i)) {
+                    translated.insert("homarr.icon".to_string(), icon);
+                }
+                translated.insert("homarr.enable".to_string(), "true".to_string());
+                labels.extend(translated);
+                return;
+            }
+        }
+    }
+}
+
+/// Key identifying the service a container is a replica of, if any
+///
+/// Swarm tasks carry the service name; compose containers carry project and
+/// service labels.
+fn service_key(labels: &HashMap<String, String>) -> Option<String> {
+    if let Some(service) = labels.get("com.docker.swarm.service.name") {
+        return Some(format!("swarm/{}", service));
+    }
+    let project = labels.get("com.docker.compose.project")?;
+    let service = labels.get("com.docker.compose.service")?;
+    Some(format!("compose/{}/{}", project, service))
+}
+
+/// Collapse replicas of the same service into one entry with a count
+///
+/// Each entry keeps the alphabetically first container name, so tiles stay
+/// stable as replicas come and go. Containers outside any service count as
+/// a single replica.
+fn group_replicas(
+    containers: Vec<(String, HashMap<String, String>)>,
+) -> Vec<(String, HashMap<String, String>, usize)> {
+    let mut groups: Vec<(String, HashMap<String, String>, usize)> = Vec::new();
+    let mut by_service: HashMap<String, usize> = HashMap::new();
+
+    for (name, labels) in containers {
+        match service_key(&labels) {
+            Some(key) => match by_service.get(&key) {
+                Some(&i) => {
+                    let group = &mut groups[i];
+                    group.2 += 1;
+                    if name < group.0 {
+                        group.0 = name;
+                    }
+                }
+                None => {
+                    by_service.insert(key, groups.len());
+                    groups.push((name, labels, 1));
+                }
+            },
+            None => groups.push((name, labels, 1)),
+        }
+    }
+
+    groups
+}
+
+/// Show a replica count on an app
+pub fn apply_replica_count(app: &mut AppDefinition, replicas: usize, display: ReplicaDisplay) {
+    if replicas < 2 {
+        return;
+    }
+    match display {
+        ReplicaDisplay::Description => {
+            let count = format!("({} replicas)", replicas);
+            app.description = Some(match app.description.take() {
+                Some(description) if !description.is_empty() => {
+                    format!("{} {}", description, count)
+                }
+                _ => count,
+            });
+        }
+        ReplicaDisplay::Name => app.name = format!("{} ×{}", app.name, replicas),
+        ReplicaDisplay::Off => {}
+    }
+}
+
+/// Container fields available to description templates
+#[derive(Debug, Clone, Default)]
+pub struct ContainerMeta {
+    /// Image reference the container was created from
+    pub image: String,
+    /// When the container was created
+    pub created: Option<DateTime<Utc>>,
+    /// Published TCP ports as (container port, host port)
+    pub ports: Vec<(u16, u16)>,
+}
+
+/// Container ports served over https
+const HTTPS_PORTS: [u16; 2] = [443, 8443];
+
+/// URL of a container's published web port, on `host`
+///
+/// Prefers container port 80, then 443, then the lowest published one.
+pub fn published_port_url(ports: &[(u16, u16)], host: &str) -> Option<String> {
+    let (private, public) = ports.iter().min_by_key(|(private, public)| match private {
+        80 => (0, *public),
+        443 => (1, *public),
+        _ => (2, *public),
+    })?;
+    let scheme = if HTTPS_PORTS.contains(private) {
+        "https"
+    } else {
+        "http"
+    };
+    Some(format!("{}://{}:{}", scheme, host, public))
+}
+
+/// Fill in `homarr.url` from the container's published ports when no label
+/// gives a URL, so such containers get a tile instead of being skipped
+pub fn infer_url(
+    container_name: &str,
+    labels: &mut HashMap<String, String>,
+    meta: &ContainerMeta,
+    host: &str,
+) {
+    let has_url = labels
+        .keys()
+        .any(|key| key.starts_with("homarr.") && key.ends_with(".url"));
+    if has_url || !is_labelled(labels) {
+        return;
+    }
+    if let Some(url) = published_port_url(&meta.ports, host) {
+        tracing::info!(
+            "Container '{}' has no homarr.url, linking its published port: {}",
+            container_name,
+            url
+        );
+        labels.insert("homarr.url".to_string(), url);
+    }
+}
+
+/// Fill in `homarr.icon` from the dashboard-icons catalog, matching the
+/// container's image, when the container has no icon label
+pub fn infer_icon(
+    container_name: &str,
+    labels: &mut HashMap<String, String>,
+    meta: &ContainerMeta,
+) {
+    // Indexed tiles without their own icon inherit this one
+    if labels.contains_key("homarr.icon") || !is_labelled(labels) {
+        return;
+    }
+    if let Some(slug) = icons::slug_for_image(&meta.image) {
+        tracing::debug!(
+            "Container '{}' has no homarr.icon, using catalog icon '{}'",
+            container_name,
+            slug
+        );
+        labels.insert("homarr.icon".to_string(), icons::icon_url(slug, "png"));
+    }
+}
+
+/// Render `{field}` placeholders in a container's description labels
+///
+/// Groups without a description get `default`, if given (indexed groups
+/// inherit it from the main group). Fields: `{name}`, `{image}`,
+/// `{created}` (creation date), `{compose_project}` and `{compose_service}`;
+/// missing values render empty and unknown placeholders are kept.
+pub fn render_descriptions(
+    container_name: &str,
+    labels: &mut HashMap<String, String>,
+    meta: &ContainerMeta,
+    default: Option<&str>,
+) {
+    if let Some(default) = default {
+        labels
+            .entry(format!("{}description", LABEL_PREFIX))
+            .or_insert_with(|| default.to_string());
+    }
+
+    let created = meta
+        .created
+        .map(|c| c.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+    let label = |key: &str| labels.get(key).cloned().unwrap_or_default();
+    let fields = [
+        ("name", container_name.to_string()),
+        ("image", meta.image.clone()),
+        ("created", created),
+        ("compose_project", label("com.docker.compose.project")),
+        ("compose_service", label("com.docker.compose.service")),
+    ];
+
+    let is_description = |key: &str| {
+        key.strip_prefix(LABEL_PREFIX)
+            .is_some_and(|field| field == "description" || field.ends_with(".description"))
+    };
+    for (key, value) in labels.iter_mut() {
+        if !is_description(key) || !value.contains('{') {
+            continue;
+        }
+        for (field, replacement) in &fields {
+            *value = value.replace(&format!("{{{}}}", field), replacement);
+        }
+        *value = value.trim().to_string();
+    }
+    labels.retain(|key, value| !(is_description(key) && value.is_empty()));
+}
+
+/// Discover apps from the labels of running containers
+///
+/// Replicas of a scaled compose or swarm service produce one set of tiles,
+/// showing the replica count as configured. `description_template` is the
+/// description of tiles without a `homarr.description` label, and
+/// `port_url_host` the host of URLs inferred from published ports. With
+/// `catalog_icons`, containers without an icon label get the catalog icon
+/// for their image.
+pub async fn discover_apps(
+    docker: &Docker,
+    replicas: ReplicaDisplay,
+    description_template: Option<&str>,
+    port_url_host: &str,
+    label_schemes: &[LabelScheme],
+    catalog_icons: bool,
+) -> Result<Vec<AppDefinition>> {
+    // Other schemes have no enable label to filter on; their containers are
+    // picked out after translation
+    let mut filters = HashMap::new();
+    if label_schemes.is_empty() {
+        filters.insert("label", vec!["homarr.enable=true"]);
+    }
+    let options = ListContainersOptions::<&str> {
+        all: false,
+        filters,
+        ..Default::default()
+    };
+
+    let containers: Vec<(String, HashMap<String, String>)> = docker
+        .list_containers(Some(options))
+        .await?
+        .into_iter()
+        .filter_map(|container| {
+            let name = container
+                .names
+                .as_ref()?
+                .first()?
+                .trim_start_matches('/')
+                .to_string();
+            let meta = ContainerMeta {
+                image: container.image.unwrap_or_default(),
+                created: container
+                    .created
+                    .and_then(|secs| DateTime::from_timestamp(secs, 0)),
+                ports: published_ports(container.ports),
+            };
+            let mut labels = container.labels.unwrap_or_default();
+            translate_labels(&mut labels, label_schemes);
+            if !is_labelled(&labels) {
+                return None;
+            }
+            render_descriptions(&name, &mut labels, &meta, description_template);
+            infer_url(&name, &mut labels, &meta, port_url_host);
+            if catalog_icons {
+                infer_icon(&name, &mut labels, &meta);
+            }
+            Some((name, labels))
+        })
+        .collect();
+
+    let mut apps = Vec::new();
+    for (name, labels, count) in group_replicas(containers) {
+        for mut app in apps_from_labels(&name, &labels) {
+            apply_replica_count(&mut app, count, replicas);
+            apps.push(app);
+        }
+    }
+
+    tracing::info!("Discovered {} app(s) from container labels", apps.len());
+    Ok(apps)
+}
+
+/// Published TCP ports as (container port, host port)
+fn published_ports(ports: Option<Vec<Port>>) -> Vec<(u16, u16)> {
+    let mut ports: Vec<(u16, u16)> = ports
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|port| port.typ == Some(PortTypeEnum::TCP))
+        .filter_map(|port| Some((port.private_port, port.public_port?)))
+        .collect();
+    // IPv4 and IPv6 bindings are listed separately
+    ports.sort_unstable();
+    ports.dedup();
+    ports
+}
+
+/// A running container, labelled or not, as seen by `export-labels` and
+/// `label-gen`
+#[derive(Debug, Clone, Default)]
+pub struct RunningContainer {
+    pub name: String,
+    /// Image reference the container was created from
+    pub image: String,
+    /// Published TCP ports as (container port, host port)
+    pub ports: Vec<(u16, u16)>,
+    /// Whether the container already opts in (with `homarr.enable=true`, or
+    /// the labels of another scheme in `label_schemes`)
+    pub labelled: bool,
+    /// Compose project and service, for containers started by compose
+    pub compose_project: Option<String>,
+    pub compose_service: Option<String>,
+}
+
+/// List every running container with its published ports
+pub async fn running_containers(
+    docker: &Docker,
+    label_schemes: &[LabelScheme],
+) -> Result<Vec<RunningContainer>> {
+    let options = ListContainersOptions::<&str> {
+        all: false,
+        ..Default::default()
+    };
+    Ok(docker
+        .list_containers(Some(options))
+        .await?
+        .into_iter()
+        .filter_map(|container| {
+            let name = container
+                .names
+                .as_ref()?
+                .first()?
+                .trim_start_matches('/')
+                .to_string();
+            let mut labels = container.labels.unwrap_or_default();
+            let compose = |key: &str| labels.get(key).cloned();
+            let compose_project = compose("com.docker.compose.project");
+            let compose_service = compose("com.docker.compose.service");
+            translate_labels(&mut labels, label_schemes);
+            Some(RunningContainer {
+                name,
+                image: container.image.unwrap_or_default(),
+                labelled: is_labelled(&labels),
+                ports: published_ports(container.ports),
+                compose_project,
+                compose_service,
+            })
+        })
+        .collect())
+}
+
+/// A single container as seen by `explain`
+pub struct InspectedContainer {
+    pub name: String,
+    pub id: String,
+    pub running: bool,
+    /// Labels as set on the container (description templates unrendered)
+    pub labels: HashMap<String, String>,
+    pub meta: ContainerMeta,
+}
+
+/// Look up a container by name or ID, running or not
+pub async fn inspect(docker: &Docker, name_or_id: &str) -> Result<InspectedContainer> {
+    let container = docker.inspect_container(name_or_id, None).await?;
+    let mut ports: Vec<(u16, u16)> = container
+        .network_settings
+        .as_ref()
+        .and_then(|network| network.ports.as_ref())
+        .into_iter()
+        .flatten()
+        .filter_map(|(port, bindings)| {
+            let private = port.strip_suffix("/tcp")?.parse().ok()?;
+            Some((private, bindings.as_ref()?))
+        })
+        .flat_map(|(private, bindings)| {
+            bindings
+                .iter()
+                .filter_map(move |b| Some((private, b.host_port.as_ref()?.parse().ok()?)))
+        })
+        .collect();
+    ports.sort_unstable();
+    ports.dedup();
+    Ok(InspectedContainer {
+        name: container
+            .name
+            .unwrap_or_default()
+            .trim_start_matches('/')
+            .to_string(),
+        id: container.id.unwrap_or_default(),
+        running: container
+            .state
+            .and_then(|state| state.running)
+            .unwrap_or(false),
+        meta: ContainerMeta {
+            image: container
+                .config
+                .as_ref()
+                .and_then(|config| config.image.clone())
+                .unwrap_or_default(),
+            created: container
+                .created
+                .and_then(|c| DateTime::parse_from_rfc3339(&c).ok())
+                .map(|c| c.to_utc()),
+            ports,
+        },
+        labels: container
+            .config
+            .and_then(|config| config.labels)
+            .unwrap_or_default(),
+    })
+}
+
+/// Image repository of Homarr's official container
+const HOMARR_IMAGE: &str = "homarr-labs/homarr";
+
+/// Version of a Homarr container, from its OCI version label or else a
+/// version-like image tag
+fn homarr_image_version(image: &str, labels: &HashMap<String, String>) -> Option<String> {
+    if !image.contains(HOMARR_IMAGE) {
+        return None;
+    }
+    labels
+        .get("org.opencontainers.image.version")
+        .cloned()
+        .or_else(|| {
+            let (_, tag) = image.rsplit_once(':')?;
+            let is_version = !tag.contains('/')
+                && tag
+                    .trim_start_matches('v')
+                    .starts_with(|c: char| c.is_ascii_digit());
+            is_version.then(|| tag.to_string())
+        })
+}
+
+/// Version of the running Homarr container, if there is one and it can be
+/// told
+pub async fn homarr_version(docker: &Docker) -> Option<String> {
+    let containers = docker
+        .list_containers(Some(ListContainersOptions::<&str>::default()))
+        .await
+        .ok()?;
+    containers.into_iter().find_map(|container| {
+        homarr_image_version(
+            container.image.as_deref().unwrap_or_default(),
+            &container.labels.unwrap_or_default(),
+        )
+    })
+}
+
+/// Container event actions the watch loop subscribes to
+///
+/// Depending on its version, Podman's compatible API reports deaths and
+/// removals under its own names as well.
+pub fn watched_events(runtime: ContainerRuntime) -> Vec<&'static str> {
+    let mut events = vec!["start", "stop", "die", "destroy"];
+    if runtime == ContainerRuntime::Podman {
+        events.extend(["died", "remove"]);
+    }
+    events
+}
+
+/// Docker's name for a container event action
+pub fn event_action(action: &str) -> &str {
+    match action {
+        "died" => "die",
+        "remove" => "destroy",
+        other => other,
+    }
+}
+
+/// Whether a Docker event concerns a container with dashboard tiles
+///
+/// Event attributes carry the container's labels, so events of unlabelled
+/// containers can be ignored without inspecting anything.
+pub fn is_labelled(attributes: &HashMap<String, String>) -> bool {
+    attributes.get("homarr.enable").map(|v| v.as_str()) == Some("true")
+}
+
+/// How a container stopped, from its `die` event attributes
+///
+/// The event carries the exit code and the container's labels but not
+/// whether it was OOM-killed; `stop_details` adds that.
+pub fn stop_from_event(attributes: &HashMap<String, String>) -> ContainerStop {
+    ContainerStop {
+        exit_code: attributes
+            .get("exitCode")
+            .or_else(|| attributes.get("containerExitCode"))
+            .and_then(|c| c.parse().ok()),
+        oom_killed: false,
+        at: chrono::Utc::now(),
+    }
+}
+
+/// How a container stopped, with the OOM flag from inspecting it
+///
+/// Containers removed right after dying (`--rm`) can't be inspected; the
+/// event's exit code is used alone then.
+pub async fn stop_details(
+    docker: &Docker,
+    name: &str,
+    attributes: &HashMap<String, String>,
+) -> ContainerStop {
+    let mut stop = stop_from_event(attributes);
+    if let Ok(container) = docker.inspect_container(name, None).await {
+        if let Some(state) = container.state {
+            stop.oom_killed = state.oom_killed.unwrap_or(false);
+            stop.exit_code = stop.exit_code.or(state.exit_code);
+        }
+    }
+    stop
+}
+
+/// Running replicas of the service a container belongs to, with its key
+///
+/// Containers outside any service are their own single replica.
+pub async fn service_replicas(
+    docker: &Docker,
+    labels: &HashMap<String, String>,
+) -> Result<(Option<String>, usize)> {
+    let Some(key) = service_key(labels) else {
+        return Ok((None, 1));
+    };
+
+    let mut filters = HashMap::new();
+    filters.insert("label", vec!["homarr.enable=true"]);
+    let options = ListContainersOptions::<&str> {
+        all: false,
+        filters,
+        ..Default::default()
+    };
+    let count = docker
+        .list_containers(Some(options))
+        .await?
+        .iter()
+        .filter(|c| {
+            c.labels
+                .as_ref()
+                .and_then(service_key)
+                .is_some_and(|k| k == key)
+        })
+        .count();
+    Ok((Some(key), count.max(1)))
+}
+
+/// Build the apps declared by one container's labels
+pub fn apps_from_labels(
+    container_name: &str,
+    labels: &HashMap<String, String>,
+) -> Vec<AppDefinition> {
+    tiles_from_labels(container_name, labels)
+        .into_iter()
+        .filter_map(|(_, tile)| {
+            tile.map_err(|reason| {
+                tracing::warn!("Container '{}' {}, skipping", container_name, reason)
+            })
+            .ok()
+        })
+        .collect()
+}
+
+/// Build each label group of a container into an app, or the reason it
+/// can't be
+///
+/// Groups are named after their label prefix (`homarr`, `homarr.1`, ...).
+/// Containers without `homarr.enable=true` have no groups.
+pub fn tiles_from_labels(
+    container_name: &str,
+    labels: &HashMap<String, String>,
+) -> Vec<(String, std::result::Result<AppDefinition, String>)> {
+    if labels.get("homarr.enable").map(|v| v.as_str()) != Some("true") {
+        return vec![];
+    }
+
+    // Split labels into the unindexed group and numbered groups
+    let mut base: LabelGroup = HashMap::new();
+    let mut indexed: BTreeMap<u32, LabelGroup> = BTreeMap::new();
+    for (key, value) in labels {
+        let Some(field) = key.strip_prefix(LABEL_PREFIX) else {
+            continue;
+        };
+        // Dotted fields of the unindexed group (`name.de`) stay whole
+        let indexed_field = field
+            .split_once('.')
+            .and_then(|(index, rest)| Some((index.parse::<u32>().ok()?, rest)));
+        match indexed_field {
+            Some((index, field)) => {
+                indexed.entry(index).or_default().insert(field, value);
+            }
+            None => {
+                base.insert(field, value);
+            }
+        }
+    }
+
+    let mut apps = Vec::new();
+
+    // The main tile needs a URL of its own when indexed groups exist;
+    // otherwise a missing URL is an error worth reporting
+    if base.contains_key("url") || indexed.is_empty() {
+        apps.push((
+            "homarr".to_string(),
+            app_from_group(container_name, container_name, &base),
+        ));
+    }
+
+    let project = labels.get("com.docker.compose.project");
+    let with_project = |mut app: AppDefinition| {
+        app.app_type.compose_project = project.cloned();
+        app
+    };
+    if let Some((_, tile)) = apps.first_mut() {
+        *tile = tile.clone().map(with_project);
+    }
+
+    let base_name = base.get("name").copied().unwrap_or(container_name);
+    for (index, group) in &indexed {
+        let mut merged: LabelGroup = base
+            .iter()
+            .filter(|(field, _)| {
+                let field = field.split('.').next().unwrap_or_default();
+                !PER_TILE_FIELDS.contains(&field)
+            })
+            .map(|(k, v)| (*k, *v))
+            .collect();
+        merged.extend(group.iter().map(|(k, v)| (*k, *v)));
+        let default_name = format!("{} {}", base_name, index);
+        apps.push((
+            format!("homarr.{}", index),
+            app_from_group(container_name, &default_name, &merged).map(with_project),
+        ));
+    }
+
+    apps
+}
+
+/// Build one app from a label group, or the reason it is invalid
+fn app_from_group(
+    container_name: &str,
+    default_name: &str,
+    group: &LabelGroup,
+) -> std::result::Result<AppDefinition, String> {
+    let Some(url) = group.get("url") else {
+        return Err("has homarr.enable=true but no homarr.url".to_string());
+    };
+    let url = registry::normalize_app_url(url)
+        .map_err(|e| format!("has invalid URL '{}': {}", url, e))?;
+
+    let text = |field: &str| group.get(field).map(|v| v.to_string());
+
+    // `homarr.ping` is "true", "false" or the URL to ping
+    let (ping, ping_target) = match group.get("ping").map(|v| v.trim()) {
+        Some(v) if v.eq_ignore_ascii_case("true") => (Some(true), None),
+        Some(v) if v.eq_ignore_ascii_case("false") => (Some(false), None),
+        Some(v) if url::Url::parse(v).is_ok_and(|u| u.has_host()) => (Some(true), Some(v)),
+        Some(v) => {
+            tracing::warn!(
+                "Container '{}' has invalid homarr.ping '{}', ignoring",
+                container_name,
+                v
+            );
+            (None, None)
+        }
+        None => (None, None),
+    };
+
+    Ok(AppDefinition {
+        name: text("name").unwrap_or_else(|| default_name.to_string()),
+        icon_url: text("icon").map(|icon| registry::resolve_icon_url(&icon, &url)),
+        url,
+        description: text("description"),
+        category: text("category"),
+        visible: true,
+        app_type: AppType {
+            container_name: Some(container_name.to_string()),
+            ..Default::default()
+        },
+        ping_url: text("ping_url").or(ping_target.map(str::to_string)),
+        ping,
+        layout: LayoutConfig {
+            section: text("section"),
+            ..Default::default()
+        },
+        css_classes: group
+            .get("css_classes")
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|c| !c.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        widget: text("widget"),
+        board: text("board"),
+        localized_names: group
+            .iter()
+            .filter_map(|(field, name)| {
+                let locale = field.strip_prefix("name.")?;
+                Some((locale.to_lowercase().replace('_', "-"), name.to_string()))
+            })
+            .collect(),
+        hide: group
+            .get("hide")
+            .is_some_and(|v| v.eq_ignore_ascii_case("true")),
+        visible_hours: text("visible_hours").filter(|hours| {
+            let valid = registry::parse_visible_hours(hours).is_ok();
+            if !valid {
+                tracing::warn!(
+                    "Container '{}' has invalid homarr.visible_hours '{}', ignoring",
+                    container_name,
+                    hours
+                );
+            }
+            valid
+        }),
+        pinned: group
+            .get("managed")
+            .is_some_and(|v| v.eq_ignore_ascii_case("false")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_apps_from_labels_basic() {
+        let apps = apps_from_labels(
+            "grafana",
+            &labels(&[
+                ("homarr.enable", "true"),
+                ("homarr.name", "Grafana"),
+                ("homarr.url", "http://localhost:3001"),
+                ("homarr.category", "Monitoring"),
+                ("homarr.css_classes", "wide, highlight"),
+            ]),
+        );
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0].name, "Grafana");
+        assert_eq!(apps[0].category.as_deref(), Some("Monitoring"));
+        assert_eq!(apps[0].container_name(), Some("grafana"));
+        assert_eq!(apps[0].css_classes, vec!["wide", "highlight"]);
+    }
+
+    #[test]
+    fn test_apps_from_labels_requires_enable() {
+        let apps = apps_from_labels(
+            "grafana",
+            &labels(&[("homarr.url", "http://localhost:3001")]),
+        );
+        assert!(apps.is_empty());
+    }
+
+    #[test]
+    fn test_apps_from_labels_name_defaults_to_container() {
+        let apps = apps_from_labels(
+            "grafana",
+            &labels(&[
+                ("homarr.enable", "true"),
+                ("homarr.url", "http://localhost:3001"),
+            ]),
+        );
+        assert_eq!(apps[0].name, "grafana");
+    }
+
+    #[test]
+    fn test_apps_from_labels_indexed_groups() {
+        let apps = apps_from_labels(
+            "proxy",
+            &labels(&[
+                ("homarr.enable", "true"),
+                ("homarr.icon", "https://example.com/proxy.png"),
+                ("homarr.category", "Network"),
+                ("homarr.1.name", "Proxy Admin"),
+                ("homarr.1.url", "http://localhost:81"),
+                ("homarr.2.name", "Proxy Stats"),
+                ("homarr.2.url", "http://localhost:8404"),
+                ("homarr.2.category", "Monitoring"),
+            ]),
+        );
+        // No unindexed URL, so only the indexed tiles
+        assert_eq!(apps.len(), 2);
+        assert_eq!(apps[0].name, "Proxy Admin");
+        assert_eq!(apps[0].url, "http://localhost:81");
+        assert_eq!(apps[0].category.as_deref(), Some("Network"));
+        assert_eq!(
+            apps[0].icon_url.as_deref(),
+            Some("https://example.com/proxy.png")
+        );
+        assert_eq!(apps[1].name, "Proxy Stats");
+        assert_eq!(apps[1].category.as_deref(), Some("Monitoring"));
+        assert!(apps.iter().all(|a| a.container_name() == Some("proxy")));
+    }
+
+    #[test]
+    fn test_apps_from_labels_main_and_indexed() {
+        let apps = apps_from_labels(
+            "app",
+            &labels(&[
+                ("homarr.enable", "true"),
+                ("homarr.name", "App"),
+                ("homarr.url", "http://localhost:8080"),
+                ("homarr.1.url", "http://localhost:8081/admin"),
+            ]),
+        );
+        assert_eq!(apps.len(), 2);
+        assert_eq!(apps[0].name, "App");
+        // Indexed groups without a name are numbered after the main tile
+        assert_eq!(apps[1].name, "App 1");
+        assert_eq!(apps[1].url, "http://localhost:8081/admin");
+    }
+
+    #[test]
+    fn test_apps_from_labels_skips_invalid_url() {
+        let apps = apps_from_labels(
+            "app",
+            &labels(&[("homarr.enable", "true"), ("homarr.url", "not a url")]),
+        );
+        assert!(apps.is_empty());
+
+        let apps = apps_from_labels(
+            "app",
+            &labels(&[("homarr.enable", "true"), ("homarr.url", "ftp://nas")]),
+        );
+        assert!(apps.is_empty());
+    }
+
+    #[test]
+    fn test_apps_from_labels_board() {
+        let apps = apps_from_labels(
+            "jellyfin",
+            &labels(&[
+                ("homarr.enable", "true"),
+                ("homarr.url", "http://jellyfin.local"),
+                ("homarr.board", "media"),
+            ]),
+        );
+        assert_eq!(apps[0].board.as_deref(), Some("media"));
+        assert!(apps[0].targets_board("media"));
+        assert!(!apps[0].targets_board("halos"));
+    }
+
+    #[test]
+    fn test_apps_from_labels_ping() {
+        let apps = apps_from_labels(
+            "grafana",
+            &labels(&[
+                ("homarr.enable", "true"),
+                ("homarr.url", "http://grafana.local"),
+                ("homarr.ping", "true"),
+                ("homarr.1.url", "http://grafana.local/explore"),
+                ("homarr.1.ping", "http://grafana:3000/api/health"),
+                ("homarr.2.url", "http://grafana.local/admin"),
+                ("homarr.2.ping", "False"),
+                ("homarr.3.url", "http://grafana.local/alerting"),
+                ("homarr.3.ping", "sometimes"),
+            ]),
+        );
+        assert_eq!(
+            (apps[0].ping, apps[0].ping_url.as_deref()),
+            (Some(true), None)
+        );
+        assert_eq!(
+            apps[1].ping_url.as_deref(),
+            Some("http://grafana:3000/api/health")
+        );
+        assert_eq!(apps[2].ping, Some(false));
+        assert_eq!(apps[3].ping, None);
+    }
+
+    #[test]
+    fn test_apps_from_labels_localized_names() {
+        let apps = apps_from_labels(
+            "grafana",
+            &labels(&[
+                ("homarr.enable", "true"),
+                ("homarr.name", "Charts"),
+                ("homarr.name.de", "Diagramme"),
+                ("homarr.name.pt_BR", "Gráficos"),
+                ("homarr.url", "http://grafana.local"),
+                ("homarr.1.name", "Alerts"),
+                ("homarr.1.url", "http://grafana.local/alerting"),
+            ]),
+        );
+        assert_eq!(apps.len(), 2);
+        // Translations of the main tile's name aren't inherited
+        assert!(apps[1].localized_names.is_empty());
+
+        let mut app = apps[0].clone();
+        app.localize("de-AT");
+        assert_eq!(app.name, "Diagramme");
+        let mut app = apps[0].clone();
+        app.localize("pt_BR");
+        assert_eq!(app.name, "Gráficos");
+        let mut app = apps[0].clone();
+        app.localize("fr");
+        assert_eq!(app.name, "Charts");
+    }
+
+    #[test]
+    fn test_apps_from_labels_normalizes_url() {
+        let apps = apps_from_labels(
+            "grafana",
+            &labels(&[
+                ("homarr.enable", "true"),
+                ("homarr.url", "http://Grafana.Local:80/"),
+                ("homarr.icon", "public/img/logo.svg"),
+            ]),
+        );
+        assert_eq!(apps[0].url, "http://grafana.local");
+        assert_eq!(
+            apps[0].icon_url.as_deref(),
+            Some("http://grafana.local/public/img/logo.svg")
+        );
+    }
+
+    fn compose_labels(project: &str, service: &str) -> HashMap<String, String> {
+        labels(&[
+            ("com.docker.compose.project", project),
+            ("com.docker.compose.service", service),
+        ])
+    }
+
+    #[test]
+    fn test_apps_from_labels_compose_project() {
+        let mut l = compose_labels("marine", "grafana");
+        l.extend(labels(&[
+            ("homarr.enable", "true"),
+            ("homarr.url", "http://localhost:3001"),
+            ("homarr.1.url", "http://localhost:3001/admin"),
+        ]));
+        let apps = apps_from_labels("marine-grafana-1", &l);
+        assert_eq!(apps.len(), 2);
+        assert!(apps.iter().all(|a| a.compose_project() == Some("marine")));
+
+        let apps = apps_from_labels(
+            "grafana",
+            &labels(&[("homarr.enable", "true"), ("homarr.url", "http://x")]),
+        );
+        assert_eq!(apps[0].compose_project(), None);
+    }
+
+    #[test]
+    fn test_group_replicas_compose_and_swarm() {
+        let groups = group_replicas(vec![
+            ("web-app-2".to_string(), compose_labels("web", "app")),
+            ("web-app-1".to_string(), compose_labels("web", "app")),
+            ("web-db-1".to_string(), compose_labels("web", "db")),
+            (
+                "api.1.abc".to_string(),
+                labels(&[("com.docker.swarm.service.name", "api")]),
+            ),
+            (
+                "api.2.def".to_string(),
+                labels(&[("com.docker.swarm.service.name", "api")]),
+            ),
+            ("standalone".to_string(), HashMap::new()),
+        ]);
+        let summary: Vec<(&str, usize)> = groups.iter().map(|(n, _, c)| (n.as_str(), *c)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("web-app-1", 2),
+                ("web-db-1", 1),
+                ("api.1.abc", 2),
+                ("standalone", 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_replica_count() {
+        let mut app = AppDefinition {
+            name: "Web".to_string(),
+            description: Some("Frontend".to_string()),
+            ..Default::default()
+        };
+        apply_replica_count(&mut app, 1, ReplicaDisplay::Description);
+        assert_eq!(app.description.as_deref(), Some("Frontend"));
+
+        apply_replica_count(&mut app, 3, ReplicaDisplay::Description);
+        assert_eq!(app.description.as_deref(), Some("Frontend (3 replicas)"));
+
+        let mut app = AppDefinition {
+            name: "Web".to_string(),
+            ..Default::default()
+        };
+        apply_replica_count(&mut app, 3, ReplicaDisplay::Description);
+        assert_eq!(app.description.as_deref(), Some("(3 replicas)"));
+
+        apply_replica_count(&mut app, 3, ReplicaDisplay::Name);
+        assert_eq!(app.name, "Web ×3");
+
+        apply_replica_count(&mut app, 3, ReplicaDisplay::Off);
+        assert_eq!(app.name, "Web ×3");
+    }
+
+    #[test]
+    fn test_socket_path_falls_back_to_desktop_socket() {
+        let home = tempfile::tempdir().unwrap();
+        let mut config = Config {
+            docker_socket: home.path().join("missing.sock").display().to_string(),
+            ..Default::default()
+        };
+
+        // Not in Docker Desktop mode: always the configured socket
+        assert_eq!(
+            socket_path(&config, Some(home.path()), None),
+            config.docker_socket
+        );
+
+        config.docker_desktop = true;
+        assert_eq!(
+            socket_path(&config, Some(home.path()), None),
+            config.docker_socket
+        );
+
+        let desktop = home.path().join(".docker/run/docker.sock");
+        std::fs::create_dir_all(desktop.parent().unwrap()).unwrap();
+        std::fs::write(&desktop, "").unwrap();
+        assert_eq!(
+            socket_path(&config, Some(home.path()), None),
+            desktop.display().to_string()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socket_permission_denied_only_for_eacces() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("docker.sock");
+        let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        let mut config = Config {
+            docker_socket: socket.display().to_string(),
+            ..Default::default()
+        };
+        assert_eq!(socket_permission_denied(&config), None);
+
+        // A missing socket is a different problem
+        config.docker_socket = dir.path().join("missing.sock").display().to_string();
+        assert_eq!(socket_permission_denied(&config), None);
+
+        config.docker_host = Some("tcp://10.0.0.2:2376".to_string());
+        assert_eq!(socket_permission_denied(&config), None);
+    }
+
+    #[test]
+    fn test_socket_path_falls_back_to_podman_socket() {
+        let runtime_dir = tempfile::tempdir().unwrap();
+        let mut config = Config {
+            docker_socket: runtime_dir
+                .path()
+                .join("missing.sock")
+                .display()
+                .to_string(),
+            ..Default::default()
+        };
+        let podman = runtime_dir.path().join("podman/podman.sock");
+        std::fs::create_dir_all(podman.parent().unwrap()).unwrap();
+        std::fs::write(&podman, "").unwrap();
+
+        assert_eq!(
+            socket_path(&config, None, Some(runtime_dir.path())),
+            config.docker_socket
+        );
+        config.container_runtime = ContainerRuntime::Podman;
+        assert_eq!(
+            socket_path(&config, None, Some(runtime_dir.path())),
+            podman.display().to_string()
+        );
+    }
+
+    #[test]
+    fn test_podman_event_names() {
+        assert!(!watched_events(ContainerRuntime::Docker).contains(&"died"));
+        assert!(watched_events(ContainerRuntime::Podman).contains(&"died"));
+        assert_eq!(event_action("died"), "die");
+        assert_eq!(event_action("remove"), "destroy");
+        assert_eq!(event_action("start"), "start");
+
+        let stop = stop_from_event(&labels(&[("containerExitCode", "137")]));
+        assert_eq!(stop.exit_code, Some(137));
+    }
+
+    #[test]
+    fn test_adapt_for_desktop() {
+        let mut app = AppDefinition {
+            url: "http://host.docker.internal:3000/admin".to_string(),
+            ping_url: Some("http://localhost:3000/health".to_string()),
+            ..Default::default()
+        };
+        adapt_for_desktop(&mut app);
+        assert_eq!(app.url, "http://localhost:3000/admin");
+        assert_eq!(
+            app.ping_url.as_deref(),
+            Some("http://host.docker.internal:3000/health")
+        );
+
+        let mut app = AppDefinition {
+            url: "http://grafana.local:3000".to_string(),
+            ping_url: Some("http://grafana:3000".to_string()),
+            ..Default::default()
+        };
+        adapt_for_desktop(&mut app);
+        assert_eq!(app.url, "http://grafana.local:3000");
+        assert_eq!(app.ping_url.as_deref(), Some("http://grafana:3000"));
+    }
+
+    #[test]
+    fn test_apps_from_labels_hide() {
+        let apps = apps_from_labels(
+            "grafana",
+            &labels(&[
+                ("homarr.enable", "true"),
+                ("homarr.url", "http://grafana.local"),
+                ("homarr.hide", "TRUE"),
+                ("homarr.1.url", "http://grafana.local/explore"),
+                ("homarr.1.hide", "false"),
+            ]),
+        );
+        assert_eq!(apps.len(), 2);
+        assert!(apps[0].hide);
+        assert!(!apps[1].hide);
+    }
+
+    #[test]
+    fn test_apps_from_labels_managed() {
+        let apps = apps_from_labels(
+            "grafana",
+            &labels(&[
+                ("homarr.enable", "true"),
+                ("homarr.url", "http://grafana.local"),
+                ("homarr.managed", "False"),
+                ("homarr.1.url", "http://grafana.local/explore"),
+                ("homarr.1.managed", "true"),
+            ]),
+        );
+        assert!(apps[0].pinned);
+        assert!(!apps[1].pinned);
+    }
+
+    #[test]
+    fn test_stop_from_event() {
+        let stop = stop_from_event(&labels(&[("exitCode", "137"), ("homarr.enable", "true")]));
+        assert_eq!(stop.exit_code, Some(137));
+        assert!(!stop.oom_killed);
+        assert_eq!(stop_from_event(&labels(&[])).exit_code, None);
+    }
+
+    #[test]
+    fn test_homarr_image_version() {
+        let none = labels(&[]);
+        let oci = labels(&[("org.opencontainers.image.version", "1.12.0")]);
+        assert_eq!(
+            homarr_image_version("ghcr.io/homarr-labs/homarr:latest", &oci),
+            Some("1.12.0".to_string())
+        );
+        assert_eq!(
+            homarr_image_version("ghcr.io/homarr-labs/homarr:v1.3.1", &none),
+            Some("v1.3.1".to_string())
+        );
+        assert_eq!(
+            homarr_image_version("ghcr.io/homarr-labs/homarr:latest", &none),
+            None
+        );
+        assert_eq!(homarr_image_version("grafana/grafana:11.2", &oci), None);
+    }
+
+    #[test]
+    fn test_is_labelled() {
+        assert!(is_labelled(&labels(&[
+            ("name", "grafana"),
+            ("homarr.enable", "true")
+        ])));
+        assert!(!is_labelled(&labels(&[("homarr.enable", "false")])));
+        assert!(!is_labelled(&labels(&[("name", "postgres")])));
+    }
+
+    #[test]
+    fn test_translate_homepage_labels() {
+        let schemes = [LabelScheme::Homepage];
+        let mut l = labels(&[
+            ("homepage.group", "Monitoring"),
+            ("homepage.name", "Grafana"),
+            ("homepage.href", "http://grafana.local"),
+            ("homepage.icon", "grafana.svg"),
+        ]);
+        translate_labels(&mut l, &schemes);
+        let apps = apps_from_labels("grafana", &l);
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0].name, "Grafana");
+        assert_eq!(apps[0].url, "http://grafana.local");
+        assert_eq!(apps[0].layout.section.as_deref(), Some("Monitoring"));
+        assert_eq!(
+            apps[0].icon_url.as_deref(),
+            Some("https://cdn.jsdelivr.net/gh/homarr-labs/dashboard-icons/svg/grafana.svg")
+        );
+
+        // Not enabled, or overridden by native labels
+        let homepage = labels(&[("homepage.name", "Grafana")]);
+        let mut l = homepage.clone();
+        translate_labels(&mut l, &[]);
+        assert!(!is_labelled(&l));
+        let mut l = homepage.clone();
+        l.insert("homarr.enable".to_string(), "false".to_string());
+        translate_labels(&mut l, &schemes);
+        assert!(!is_labelled(&l));
+    }
+
+    #[test]
+    fn test_homepage_icon() {
+        assert_eq!(
+            homepage_icon("sonarr").as_deref(),
+            Some("https://cdn.jsdelivr.net/gh/homarr-labs/dashboard-icons/png/sonarr.png")
+        );
+        assert_eq!(
+            homepage_icon("https://example.com/a.png").as_deref(),
+            Some("https://example.com/a.png")
+        );
+        assert_eq!(homepage_icon("mdi-flask-outline"), None);
+    }
+
+    #[test]
+    fn test_published_port_url() {
+        assert_eq!(
+            published_port_url(&[(3000, 3000), (80, 8080)], "halos.local").as_deref(),
+            Some("http://halos.local:8080")
+        );
+        assert_eq!(
+            published_port_url(&[(9000, 9000), (443, 9443)], "halos.local").as_deref(),
+            Some("https://halos.local:9443")
+        );
+        assert_eq!(
+            published_port_url(&[(5432, 15432), (3000, 3001)], "halos.local").as_deref(),
+            Some("http://halos.local:3001")
+        );
+        assert_eq!(published_port_url(&[], "halos.local"), None);
+    }
+
+    #[test]
+    fn test_infer_url_only_without_url_labels() {
+        let meta = ContainerMeta {
+            ports: vec![(3000, 3000)],
+            ..Default::default()
+        };
+        let mut l = labels(&[("homarr.enable", "true"), ("homarr.name", "Signal K")]);
+        infer_url("signalk", &mut l, &meta, "halos.local");
+        assert_eq!(l["homarr.url"], "http://halos.local:3000");
+
+        // Indexed tiles with their own URLs don't get a main tile added
+        let mut l = labels(&[("homarr.enable", "true"), ("homarr.1.url", "http://x:81")]);
+        infer_url("proxy", &mut l, &meta, "halos.local");
+        assert!(!l.contains_key("homarr.url"));
+
+        let mut l = labels(&[("homarr.enable", "false")]);
+        infer_url("db", &mut l, &meta, "halos.local");
+        assert!(!l.contains_key("homarr.url"));
+    }
+
+    #[test]
+    fn test_infer_icon_from_image() {
+        let meta = ContainerMeta {
+            image: "lscr.io/linuxserver/sonarr:latest".to_string(),
+            ..Default::default()
+        };
+        let mut l = labels(&[("homarr.enable", "true")]);
+        infer_icon("sonarr", &mut l, &meta);
+        assert_eq!(
+            l["homarr.icon"],
+            "https://cdn.jsdelivr.net/gh/homarr-labs/dashboard-icons/png/sonarr.png"
+        );
+
+        let mut l = labels(&[("homarr.enable", "true"), ("homarr.icon", "/icons/x.png")]);
+        infer_icon("sonarr", &mut l, &meta);
+        assert_eq!(l["homarr.icon"], "/icons/x.png");
+
+        let mut l = labels(&[("homarr.enable", "true")]);
+        let custom = ContainerMeta {
+            image: "ghcr.io/hatlabs/halos-custom:1".to_string(),
+            ..Default::default()
+        };
+        infer_icon("custom", &mut l, &custom);
+        assert!(!l.contains_key("homarr.icon"));
+    }
+
+    #[test]
+    fn test_render_descriptions() {
+        let meta = ContainerMeta {
+            image: "grafana/grafana:11.2".to_string(),
+            created: DateTime::from_timestamp(1_700_000_000, 0),
+            ports: vec![],
+        };
+
+        let mut l = compose_labels("marine", "grafana");
+        l.insert(
+            "homarr.description".into(),
+            "{image} in {compose_project}".into(),
+        );
+        l.insert(
+            "homarr.1.description".into(),
+            "Since {created} {unknown}".into(),
+        );
+        render_descriptions("grafana-1", &mut l, &meta, Some("ignored"));
+        assert_eq!(l["homarr.description"], "grafana/grafana:11.2 in marine");
+        assert_eq!(l["homarr.1.description"], "Since 2023-11-14 {unknown}");
+
+        // The default fills in a missing description; empty results are dropped
+        let mut l = labels(&[("homarr.enable", "true")]);
+        render_descriptions("db", &mut l, &meta, Some("{name} ({image})"));
+        assert_eq!(l["homarr.description"], "db (grafana/grafana:11.2)");
+
+        let mut l = labels(&[("homarr.description", "{compose_service}")]);
+        render_descriptions("db", &mut l, &meta, None);
+        assert!(!l.contains_key("homarr.description"));
+    }
+}
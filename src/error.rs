@@ -30,6 +30,44 @@ pub enum AdapterError {
 
     #[error("Docker error: {0}")]
     Docker(#[from] bollard::errors::Error),
+
+    #[error("Permission denied on Docker socket {0} (is the adapter's user in the docker group?)")]
+    DockerPermissionDenied(String),
+
+    #[error("Alert delivery error: {0}")]
+    Alert(String),
+
+    #[error("Interrupted")]
+    Cancelled,
+
+    #[cfg(feature = "kubernetes")]
+    #[error("Kubernetes API error: {0}")]
+    Kubernetes(String),
+
+    #[error("Board '{0}' was changed in Homarr since the sync planned its edits")]
+    BoardChanged(String),
+
+    #[error("Unsupported Homarr version: {0}")]
+    UnsupportedHomarr(String),
+
+    #[error("Homarr is rate limiting requests, retry after {0} s")]
+    RateLimited(u64),
+
+    #[error("Homarr wasn't ready within {0} s")]
+    HomarrNotReady(u64),
+
+    #[error("Another sync is still running (lock {0} held)")]
+    SyncLocked(String),
+
+    #[error("Sync failed for Homarr target(s): {0}")]
+    TargetsFailed(String),
+}
+
+impl AdapterError {
+    /// Whether the request failed because the server couldn't be reached
+    pub fn is_unreachable(&self) -> bool {
+        matches!(self, AdapterError::Http(e) if e.is_connect() || e.is_timeout())
+    }
 }
 
 pub type Result<T> = std::result::Result<T, AdapterError>;
@@ -0,0 +1,405 @@
+//! `explain` command: how the adapter interprets one container
+//!
+//! Walks a container's labels through the same steps as a sync (label
+//! groups, replica counts, Docker Desktop rewrites, HTTPS enforcement,
+//! duplicate names, visibility) and reports the resulting tiles, where they
+//! would go and, for tiles that wouldn't be placed, why.
+
+use std::fmt::Write;
+
+use chrono::NaiveTime;
+
+use crate::config::Config;
+use crate::docker::{self, InspectedContainer};
+use crate::registry::{self, AppDefinition};
+use crate::state::State;
+
+/// Everything besides the container that decides what a sync does with it
+pub struct Context<'a> {
+    pub config: &'a Config,
+    pub state: &'a State,
+    /// Service the container is a replica of and its running replica count
+    pub service: Option<String>,
+    pub replicas: usize,
+    /// Apps a sync would place, after duplicate names are resolved
+    pub desired: &'a [AppDefinition],
+    /// Branding default section for apps without one
+    pub default_section: Option<&'a str>,
+    /// Writable boards as (name, id), or why they couldn't be listed
+    pub boards: std::result::Result<Vec<(String, String)>, String>,
+    /// Icon URL a sync would send for an app
+    pub icon: &'a dyn Fn(&AppDefinition) -> String,
+    pub now: NaiveTime,
+}
+
+/// Render the explanation for a container
+pub fn explain(container: &InspectedContainer, ctx: &Context) -> String {
+    let mut out = String::new();
+    let status = if container.running {
+        "running"
+    } else {
+        "not running"
+    };
+    let _ = writeln!(
+        out,
+        "Container '{}' ({}), {}",
+        container.name,
+        short_id(&container.id),
+        status
+    );
+
+    // Labels of other enabled schemes are shown as their homarr.* translation
+    let mut translated = container.labels.clone();
+    docker::translate_labels(&mut translated, &ctx.config.label_schemes);
+    let mut labels: Vec<_> = translated
+        .iter()
+        .filter(|(key, _)| key.starts_with("homarr."))
+        .collect();
+    labels.sort();
+    if labels.is_empty() {
+        let _ = writeln!(out, "Labels: none in the homarr.* namespace");
+    } else {
+        let _ = writeln!(out, "Labels:");
+        for (key, value) in labels {
+            let _ = writeln!(out, "  {} = {}", key, value);
+        }
+    }
+    if let Some(service) = &ctx.service {
+        let _ = writeln!(
+            out,
+            "Service: {} ({} running replica(s))",
+            service, ctx.replicas
+        );
+    }
+
+    if !docker::is_labelled(&translated) {
+        let _ = writeln!(out, "\nSkipped: no homarr.enable=true label");
+        return out;
+    }
+    if !container.running {
+        let _ = writeln!(out, "\nSkipped: only running containers are synced");
+        return out;
+    }
+
+    let mut rendered = translated.clone();
+    docker::render_descriptions(
+        &container.name,
+        &mut rendered,
+        &container.meta,
+        ctx.config.description_template.as_deref(),
+    );
+    docker::infer_url(
+        &container.name,
+        &mut rendered,
+        &container.meta,
+        &ctx.config.port_url_host(),
+    );
+    if ctx.config.catalog_icons {
+        docker::infer_icon(&container.name, &mut rendered, &container.meta);
+    }
+    for (group, tile) in docker::tiles_from_labels(&container.name, &rendered) {
+        let _ = writeln!(out, "\nTile from {}.* labels:", group);
+        match tile {
+            Ok(app) => explain_tile(&mut out, &group, app, container, ctx),
+            Err(reason) => {
+                let _ = writeln!(out, "  Skipped: container {}", reason);
+            }
+        }
+    }
+
+    out
+}
+
+/// Follow one tile through the sync pipeline
+fn explain_tile(
+    out: &mut String,
+    group: &str,
+    mut app: AppDefinition,
+    container: &InspectedContainer,
+    ctx: &Context,
+) {
+    let config = ctx.config;
+    docker::apply_replica_count(&mut app, ctx.replicas, config.replica_count);
+    if config.docker_desktop {
+        docker::adapt_for_desktop(&mut app);
+    }
+
+    if let Some(hours) = container.labels.get(&format!("{}.visible_hours", group)) {
+        if app.visible_hours.is_none() {
+            let _ = writeln!(out, "  Note: visible_hours '{}' is invalid, ignored", hours);
+        }
+    }
+
+    if config.require_https {
+        let url = app.url.clone();
+        match registry::enforce_https(vec![app], config.https_upgrade, &config.https_exceptions)
+            .pop()
+        {
+            Some(upgraded) => {
+                if upgraded.url != url {
+                    let _ = writeln!(out, "  Note: upgraded {} to HTTPS (https_upgrade)", url);
+                }
+                app = upgraded;
+            }
+            None => {
+                let _ = writeln!(out, "  Skipped: {} is not HTTPS (require_https)", url);
+                return;
+            }
+        }
+    }
+
+    // Duplicate names are resolved across all sources; find what became of
+    // this tile by its URL
+    let Some(resolved) = ctx.desired.iter().find(|a| a.url == app.url) else {
+        let _ = writeln!(
+            out,
+            "  Skipped: another app already uses the name '{}' (duplicate_names)",
+            app.name
+        );
+        return;
+    };
+    if resolved.name != app.name {
+        let _ = writeln!(
+            out,
+            "  Note: renamed from '{}' to '{}' (duplicate_names)",
+            app.name, resolved.name
+        );
+    }
+    if let Some(replica) = resolved
+        .container_name()
+        .filter(|name| *name != container.name)
+    {
+        let _ = writeln!(
+            out,
+            "  Note: the service's tiles are built from replica '{}'",
+            replica
+        );
+    }
+
+    print_fields(out, resolved, ctx);
+    if resolved.pinned || ctx.state.pinned_apps.contains(&resolved.url) {
+        let _ = writeln!(
+            out,
+            "  Note: pinned, the app is created once and then left as it is"
+        );
+    }
+
+    let hidden = if resolved.hide {
+        Some("homarr.hide=true".to_string())
+    } else if !resolved.in_visible_hours(ctx.now) {
+        Some(format!(
+            "outside visible_hours {}",
+            resolved.visible_hours.as_deref().unwrap_or_default()
+        ))
+    } else {
+        None
+    };
+
+    match &ctx.boards {
+        Err(e) => {
+            let _ = writeln!(out, "  Boards: unknown ({})", e);
+        }
+        Ok(boards) if boards.is_empty() => {
+            let _ = writeln!(out, "  Boards: none writable, nothing is placed");
+        }
+        Ok(boards) => {
+            let _ = writeln!(out, "  Boards:");
+            for (name, id) in boards {
+                let verdict = if !resolved.targets_board(name) {
+                    format!(
+                        "skipped, routed to board '{}'",
+                        resolved.board.as_deref().unwrap_or_default()
+                    )
+                } else if ctx.state.is_removed_from_board(id, &resolved.url) {
+                    "skipped, removed from this board by a user".to_string()
+                } else if let Some(reason) = &hidden {
+                    format!("app kept, tile hidden ({})", reason)
+                } else {
+                    "placed".to_string()
+                };
+                let _ = writeln!(out, "    {}: {}", name, verdict);
+            }
+            if let Some(board) = resolved
+                .board
+                .as_deref()
+                .filter(|board| boards.iter().all(|(name, _)| name != board))
+            {
+                let _ = writeln!(out, "    {}: placed, board created by the next sync", board);
+            }
+        }
+    }
+}
+
+/// Print the resolved fields of a tile
+fn print_fields(out: &mut String, app: &AppDefinition, ctx: &Context) {
+    let layout = app.effective_layout();
+    let project = app
+        .compose_project()
+        .filter(|_| ctx.config.compose_sections);
+    let category = app
+        .category
+        .as_deref()
+        .filter(|c| ctx.config.category_sections && !c.is_empty());
+    let section = match (&layout.section, project, category, ctx.default_section) {
+        (Some(section), _, _, _) => section.clone(),
+        (None, Some(project), _, _) => format!("{} (compose project)", project),
+        (None, None, Some(category), _) => format!("{} (category)", category),
+        (None, None, None, Some(default)) => format!("{} (branding default)", default),
+        (None, None, None, None) => "first board section".to_string(),
+    };
+    let position = match (layout.x_offset, layout.y_offset) {
+        (Some(x), Some(y)) => format!("column {}, row {}", x, y),
+        (Some(x), None) => format!("column {}, first free row", x),
+        (None, Some(y)) => format!("row {}, first free column", y),
+        (None, None) => "auto".to_string(),
+    };
+
+    let mut field = |name: &str, value: &str| {
+        let _ = writeln!(out, "  {:<12} {}", format!("{}:", name), value);
+    };
+    field("Name", &app.name);
+    field("URL", &app.url);
+    field(
+        "Ping URL",
+        crate::homarr::ping_url_for(app)
+            .as_deref()
+            .unwrap_or("(off)"),
+    );
+    if let Some(description) = &app.description {
+        field("Description", description);
+    }
+    field("Icon", &(ctx.icon)(app));
+    field("Category", app.category.as_deref().unwrap_or("(none)"));
+    field("Section", &section);
+    field(
+        "Size",
+        &format!(
+            "{}x{} (priority {})",
+            layout.width, layout.height, layout.priority
+        ),
+    );
+    field("Position", &position);
+    if !app.css_classes.is_empty() {
+        field("CSS classes", &app.css_classes.join(", "));
+    }
+    if let Some(widget) = &app.widget {
+        field("Widget", widget);
+    }
+}
+
+/// The 12-character form `docker ps` shows
+fn short_id(id: &str) -> &str {
+    &id[..id.len().min(12)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn container(running: bool, labels: &[(&str, &str)]) -> InspectedContainer {
+        InspectedContainer {
+            name: "grafana".to_string(),
+            id: "0123456789abcdef0123".to_string(),
+            running,
+            meta: Default::default(),
+            labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    fn render(
+        container: &InspectedContainer,
+        config: &Config,
+        state: &State,
+        desired: &[AppDefinition],
+    ) -> String {
+        let icon = |app: &AppDefinition| app.icon_url.clone().unwrap_or_default();
+        let ctx = Context {
+            config,
+            state,
+            service: None,
+            replicas: 1,
+            desired,
+            default_section: Some("Apps"),
+            boards: Ok(vec![
+                ("Default".to_string(), "b1".to_string()),
+                ("Kiosk".to_string(), "b2".to_string()),
+            ]),
+            icon: &icon,
+            now: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+        };
+        explain(container, &ctx)
+    }
+
+    const LABELS: &[(&str, &str)] = &[
+        ("homarr.enable", "true"),
+        ("homarr.name", "Grafana"),
+        ("homarr.url", "http://localhost:3001"),
+        ("homarr.icon", "/icons/grafana.svg"),
+        ("com.docker.compose.project", "marine"),
+    ];
+
+    #[test]
+    fn test_explain_placed_tile() {
+        let c = container(true, LABELS);
+        let desired = docker::apps_from_labels(&c.name, &c.labels);
+        let mut state = State::default();
+        state.mark_removed_from_board("b2", "http://localhost:3001");
+
+        let out = render(&c, &Config::default(), &state, &desired);
+        assert!(out.contains("Container 'grafana' (0123456789ab), running"));
+        assert!(out.contains("  homarr.url = http://localhost:3001"));
+        assert!(!out.contains("com.docker.compose.project"));
+        assert!(out.contains("Icon:        /icons/grafana.svg"));
+        assert!(out.contains("Section:     Apps (branding default)"));
+        assert!(out.contains("Default: placed"));
+        assert!(out.contains("Kiosk: skipped, removed from this board by a user"));
+    }
+
+    #[test]
+    fn test_explain_skip_reasons() {
+        let config = Config::default();
+        let state = State::default();
+
+        let out = render(&container(false, LABELS), &config, &state, &[]);
+        assert!(out.contains("Skipped: only running containers are synced"));
+
+        let out = render(&container(true, &LABELS[1..]), &config, &state, &[]);
+        assert!(out.contains("Skipped: no homarr.enable=true label"));
+
+        let out = render(
+            &container(true, &[("homarr.enable", "true")]),
+            &config,
+            &state,
+            &[],
+        );
+        assert!(out.contains("Skipped: container has homarr.enable=true but no homarr.url"));
+
+        // Not among the desired apps: lost a duplicate name conflict
+        let out = render(&container(true, LABELS), &config, &state, &[]);
+        assert!(out.contains("Skipped: another app already uses the name 'Grafana'"));
+
+        let config = Config {
+            require_https: true,
+            ..Default::default()
+        };
+        let out = render(&container(true, LABELS), &config, &state, &[]);
+        assert!(out.contains("Skipped: http://localhost:3001 is not HTTPS (require_https)"));
+    }
+
+    #[test]
+    fn test_explain_hidden_and_renamed() {
+        let mut labels = LABELS.to_vec();
+        labels.push(("homarr.visible_hours", "20:00-06:00"));
+        let c = container(true, &labels);
+        let mut desired = docker::apps_from_labels(&c.name, &c.labels);
+        desired[0].name = "Grafana (localhost)".to_string();
+
+        let out = render(&c, &Config::default(), &State::default(), &desired);
+        assert!(out.contains("renamed from 'Grafana' to 'Grafana (localhost)'"));
+        assert!(out.contains("Default: app kept, tile hidden (outside visible_hours 20:00-06:00)"));
+    }
+}
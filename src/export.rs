@@ -0,0 +1,232 @@
+//! `export-labels` command: label suggestions for hand-made Homarr apps
+//!
+//! Apps created in the Homarr UI, rather than by the adapter, are matched to
+//! running containers (by published port, then by the URL's host name) and
+//! printed as `homarr.*` label blocks to paste into compose files. Once the
+//! labels are in place the next sync adopts the existing apps by URL.
+
+use std::fmt::Write;
+
+use url::Url;
+
+use crate::docker::RunningContainer;
+use crate::homarr::{self, SelectableApp};
+use crate::state::State;
+
+/// How an app was matched to a container
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchedBy {
+    /// The URL's port is published by the container
+    Port(u16),
+    /// The URL's host name is the container name
+    HostName,
+}
+
+/// Homarr apps suggested for one container's labels
+#[derive(Debug)]
+pub struct ContainerLabels<'a> {
+    pub container: &'a RunningContainer,
+    pub apps: Vec<(&'a SelectableApp, MatchedBy)>,
+}
+
+/// Hand-made apps by container, and those no container matched
+#[derive(Debug, Default)]
+pub struct Suggestions<'a> {
+    pub containers: Vec<ContainerLabels<'a>>,
+    pub unmatched: Vec<&'a SelectableApp>,
+}
+
+/// Match Homarr apps the adapter doesn't manage to running containers
+pub fn suggest<'a>(
+    homarr_apps: &'a [SelectableApp],
+    state: &State,
+    containers: &'a [RunningContainer],
+) -> Suggestions<'a> {
+    let managed: std::collections::HashSet<String> = state
+        .discovered_apps
+        .keys()
+        .chain(state.manual_apps.iter().map(|app| &app.url))
+        .map(|url| homarr::normalize_url(url))
+        .collect();
+
+    let mut suggestions = Suggestions::default();
+    for app in homarr_apps {
+        let Some(href) = app.href.as_deref() else {
+            continue;
+        };
+        if managed.contains(&homarr::normalize_url(href)) {
+            continue;
+        }
+        match match_container(href, containers) {
+            Some((container, by)) => {
+                match suggestions
+                    .containers
+                    .iter_mut()
+                    .find(|c| c.container.name == container.name)
+                {
+                    Some(labels) => labels.apps.push((app, by)),
+                    None => suggestions.containers.push(ContainerLabels {
+                        container,
+                        apps: vec![(app, by)],
+                    }),
+                }
+            }
+            None => suggestions.unmatched.push(app),
+        }
+    }
+    suggestions
+}
+
+/// The container serving `href`: one publishing its port, or else one
+/// named like its host (`http://grafana.local` → `grafana`)
+fn match_container<'a>(
+    href: &str,
+    containers: &'a [RunningContainer],
+) -> Option<(&'a RunningContainer, MatchedBy)> {
+    let url = Url::parse(href).ok()?;
+    let by_port = url.port_or_known_default().and_then(|port| {
+        containers
+            .iter()
+            .find(|c| c.ports.iter().any(|&(_, public)| public == port))
+            .map(|c| (c, MatchedBy::Port(port)))
+    });
+    by_port.or_else(|| {
+        let host = url.host_str()?.split('.').next()?;
+        containers
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(host))
+            .map(|c| (c, MatchedBy::HostName))
+    })
+}
+
+/// Render suggestions as compose-style label blocks
+pub fn render(suggestions: &Suggestions) -> String {
+    let mut out = String::new();
+    if suggestions.containers.is_empty() && suggestions.unmatched.is_empty() {
+        out.push_str("# No hand-made apps in Homarr: every app is managed by the adapter\n");
+        return out;
+    }
+    out.push_str("# Suggested homarr.* labels for apps created by hand in Homarr.\n");
+    out.push_str("# Add each block to the service's labels; the next sync adopts the app.\n");
+
+    let quote = |value: &str| serde_json::to_string(value).unwrap_or_default();
+    for labels in &suggestions.containers {
+        let _ = writeln!(out);
+        if labels.container.labelled {
+            let _ = writeln!(
+                out,
+                "# {} already has homarr.* labels",
+                labels.container.name
+            );
+        }
+        let _ = writeln!(out, "{}:", labels.container.name);
+        let _ = writeln!(out, "  labels:");
+        let _ = writeln!(out, "    homarr.enable: \"true\"");
+        for (index, (app, by)) in labels.apps.iter().enumerate() {
+            let prefix = match index {
+                0 => "homarr.".to_string(),
+                n => format!("homarr.{}.", n),
+            };
+            let matched = match by {
+                MatchedBy::Port(port) => format!("published port {}", port),
+                MatchedBy::HostName => "host name".to_string(),
+            };
+            let _ = writeln!(out, "    # {} (matched by {})", app.name, matched);
+            let _ = writeln!(out, "    {}name: {}", prefix, quote(&app.name));
+            let _ = writeln!(
+                out,
+                "    {}url: {}",
+                prefix,
+                quote(app.href.as_deref().unwrap_or_default())
+            );
+            if !app.icon_url.is_empty() {
+                let _ = writeln!(out, "    {}icon: {}", prefix, quote(&app.icon_url));
+            }
+        }
+    }
+
+    if !suggestions.unmatched.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "# No running container matched (add these to the registry):"
+        );
+        for app in &suggestions.unmatched {
+            let _ = writeln!(
+                out,
+                "#   {} ({})",
+                app.name,
+                app.href.as_deref().unwrap_or_default()
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{AppSource, DiscoveredApp};
+    use chrono::Utc;
+
+    fn homarr_app(name: &str, href: &str) -> SelectableApp {
+        SelectableApp {
+            id: name.to_lowercase(),
+            name: name.to_string(),
+            icon_url: String::new(),
+            href: Some(href.to_string()),
+        }
+    }
+
+    fn container(name: &str, ports: &[(u16, u16)]) -> RunningContainer {
+        RunningContainer {
+            name: name.to_string(),
+            ports: ports.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_suggest_labels() {
+        let apps = vec![
+            homarr_app("Grafana", "http://halos.local:3000"),
+            homarr_app("Grafana Admin", "http://halos.local:3000/admin"),
+            homarr_app("Portainer", "https://portainer.local"),
+            homarr_app("Router", "http://192.168.1.1"),
+            homarr_app("Signal K", "http://halos.local:3100/"),
+        ];
+        let containers = vec![
+            container("grafana", &[(3000, 3000)]),
+            container("portainer", &[(9443, 9443)]),
+        ];
+        let mut state = State::default();
+        state.discovered_apps.insert(
+            "http://halos.local:3100".to_string(),
+            DiscoveredApp {
+                name: "Signal K".to_string(),
+                container_id: "signalk".to_string(),
+                added_at: Utc::now(),
+                last_seen: None,
+                source: AppSource::Container,
+                pinned: false,
+            },
+        );
+
+        let suggestions = suggest(&apps, &state, &containers);
+        assert_eq!(suggestions.containers.len(), 2);
+        assert_eq!(suggestions.containers[0].apps.len(), 2);
+        assert_eq!(suggestions.containers[1].apps[0].1, MatchedBy::HostName);
+        let unmatched: Vec<&str> = suggestions
+            .unmatched
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect();
+        assert_eq!(unmatched, vec!["Router"]);
+
+        let text = render(&suggestions);
+        assert!(text.contains("    homarr.url: \"http://halos.local:3000\""));
+        assert!(text.contains("    homarr.1.name: \"Grafana Admin\""));
+        assert!(text.contains("#   Router (http://192.168.1.1)"));
+        assert!(!text.contains("Signal K"));
+    }
+}
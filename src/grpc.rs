@@ -0,0 +1,276 @@
+//! gRPC control interface
+//!
+//! Lets the HaLOS management plane trigger syncs, query status and the sync
+//! plan, and stream events from a running watch daemon. The service definition
+//! lives in `proto/control.proto`.
+
+use std::net::SocketAddr;
+use std::pin::Pin;
+
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::{Request, Response, Status};
+
+use crate::config::Config;
+use crate::error::{AdapterError, Result};
+use crate::state::State;
+
+pub mod proto {
+    tonic::include_proto!("homarr_adapter.v1");
+}
+
+use proto::control_server::{Control, ControlServer};
+use proto::{
+    Event, EventKind, GetPlanRequest, GetStatusRequest, PlanResponse, PlannedApp, StatusResponse,
+    StreamEventsRequest, SyncTimings, TargetStatus, TriggerSyncRequest, TriggerSyncResponse,
+};
+
+/// Events buffered per subscriber before slow readers start missing some
+const EVENT_BUFFER: usize = 64;
+
+/// A sync requested over gRPC, answered once the sync has run
+pub struct SyncRequest {
+    pub refresh_icons: bool,
+    pub reply: oneshot::Sender<std::result::Result<(), String>>,
+}
+
+/// Channels connecting the gRPC service to the watch loop
+///
+/// The watch loop owns the Homarr client, so syncs requested over gRPC are
+/// queued here and run by the loop; the loop publishes events back.
+pub struct ControlHub {
+    sync_tx: mpsc::Sender<SyncRequest>,
+    pub sync_requests: mpsc::Receiver<SyncRequest>,
+    events: broadcast::Sender<Event>,
+}
+
+impl ControlHub {
+    pub fn new() -> Self {
+        let (sync_tx, sync_requests) = mpsc::channel(8);
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        Self {
+            sync_tx,
+            sync_requests,
+            events,
+        }
+    }
+
+    /// Publish an event to every streaming client
+    pub fn publish(&self, kind: EventKind, message: impl Into<String>) {
+        self.publish_for_target(kind, "", message);
+    }
+
+    /// Publish a sync result of one Homarr instance of a multi-target sync
+    pub fn publish_for_target(&self, kind: EventKind, target: &str, message: impl Into<String>) {
+        // No subscribers is not an error
+        let _ = self.events.send(Event {
+            kind: kind as i32,
+            message: message.into(),
+            timestamp_unix: chrono::Utc::now().timestamp(),
+            target: target.to_string(),
+        });
+    }
+
+    /// Start serving the control interface in the background
+    pub fn serve(&self, config: &Config, listen: &str) -> Result<()> {
+        let addr: SocketAddr = listen.parse().map_err(|e| {
+            AdapterError::Config(format!("Invalid grpc_listen '{}': {}", listen, e))
+        })?;
+
+        let service = ControlService {
+            config: config.clone(),
+            sync_tx: self.sync_tx.clone(),
+            events: self.events.clone(),
+        };
+
+        tokio::spawn(async move {
+            tracing::info!("gRPC control interface listening on {}", addr);
+            if let Err(e) = tonic::transport::Server::builder()
+                .add_service(ControlServer::new(service))
+                .serve(addr)
+                .await
+            {
+                tracing::error!("gRPC control interface stopped: {}", e);
+            }
+        });
+
+        Ok(())
+    }
+}
+
+struct ControlService {
+    config: Config,
+    sync_tx: mpsc::Sender<SyncRequest>,
+    events: broadcast::Sender<Event>,
+}
+
+fn status_from_state(state: &State) -> StatusResponse {
+    StatusResponse {
+        first_boot_completed: state.first_boot_completed,
+        last_sync_unix: state.last_sync.map(|t| t.timestamp()).unwrap_or(0),
+        consecutive_sync_failures: state.consecutive_sync_failures,
+        discovered_apps: state.discovered_apps.len() as u32,
+        manual_apps: state.manual_apps.len() as u32,
+        docker_permission_denied: state.docker_permission_denied,
+        last_sync_timings: state.last_sync_timings.map(|t| SyncTimings {
+            discovery_ms: t.discovery_ms,
+            planning_ms: t.planning_ms,
+            apps_ms: t.apps_ms,
+            board_save_ms: t.board_save_ms,
+            total_ms: t.total_ms,
+        }),
+        rate_limited_until_unix: state
+            .rate_limit_pause(chrono::Utc::now())
+            .map(|t| t.timestamp())
+            .unwrap_or(0),
+        rate_limited_responses: state.rate_limited_responses,
+        targets: Vec::new(),
+    }
+}
+
+#[tonic::async_trait]
+impl Control for ControlService {
+    async fn trigger_sync(
+        &self,
+        request: Request<TriggerSyncRequest>,
+    ) -> std::result::Result<Response<TriggerSyncResponse>, Status> {
+        let (reply, result) = oneshot::channel();
+        self.sync_tx
+            .send(SyncRequest {
+                refresh_icons: request.into_inner().refresh_icons,
+                reply,
+            })
+            .await
+            .map_err(|_| Status::unavailable("watch loop is not running"))?;
+
+        let result = result
+            .await
+            .map_err(|_| Status::aborted("sync was dropped before completing"))?;
+        Ok(Response::new(TriggerSyncResponse {
+            success: result.is_ok(),
+            error: result.err().unwrap_or_default(),
+        }))
+    }
+
+    async fn get_status(
+        &self,
+        _request: Request<GetStatusRequest>,
+    ) -> std::result::Result<Response<StatusResponse>, Status> {
+        let state =
+            State::load(&self.config.state_file).map_err(|e| Status::internal(e.to_string()))?;
+        let mut status = status_from_state(&state);
+        for target in &self.config.homarr {
+            let state = State::load(&self.config.for_target(target).state_file)
+                .map_err(|e| Status::internal(e.to_string()))?;
+            status.targets.push(TargetStatus {
+                name: target.name.clone(),
+                url: target.url.clone(),
+                last_sync_unix: state.last_sync.map(|t| t.timestamp()).unwrap_or(0),
+                consecutive_sync_failures: state.consecutive_sync_failures,
+            });
+        }
+        Ok(Response::new(status))
+    }
+
+    async fn get_plan(
+        &self,
+        _request: Request<GetPlanRequest>,
+    ) -> std::result::Result<Response<PlanResponse>, Status> {
+        let state =
+            State::load(&self.config.state_file).map_err(|e| Status::internal(e.to_string()))?;
+        let apps = crate::load_desired_apps(&self.config, &state)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        let apps = apps
+            .into_iter()
+            .map(|app| PlannedApp {
+                section: app.effective_layout().section.clone().unwrap_or_default(),
+                container_name: app.container_name().unwrap_or_default().to_string(),
+                name: app.name,
+                url: app.url,
+                category: app.category.unwrap_or_default(),
+            })
+            .collect();
+        Ok(Response::new(PlanResponse { apps }))
+    }
+
+    type StreamEventsStream =
+        Pin<Box<dyn Stream<Item = std::result::Result<Event, Status>> + Send>>;
+
+    async fn stream_events(
+        &self,
+        _request: Request<StreamEventsRequest>,
+    ) -> std::result::Result<Response<Self::StreamEventsStream>, Status> {
+        // Lagged readers skip the events they missed rather than failing
+        let stream = BroadcastStream::new(self.events.subscribe())
+            .filter_map(|event| event.ok())
+            .map(Ok);
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_from_state() {
+        let mut state = State {
+            first_boot_completed: true,
+            consecutive_sync_failures: 2,
+            ..Default::default()
+        };
+        assert_eq!(status_from_state(&state).last_sync_unix, 0);
+
+        state.update_sync_time();
+        let status = status_from_state(&state);
+        assert!(status.first_boot_completed);
+        assert!(status.last_sync_unix > 0);
+        assert_eq!(status.consecutive_sync_failures, 2);
+        assert!(!status.docker_permission_denied);
+        assert_eq!(status.last_sync_timings, None);
+
+        state.last_sync_timings = Some(crate::state::SyncTimings {
+            apps_ms: 120,
+            total_ms: 150,
+            ..Default::default()
+        });
+        let timings = status_from_state(&state).last_sync_timings.unwrap();
+        assert_eq!((timings.apps_ms, timings.total_ms), (120, 150));
+    }
+
+    #[tokio::test]
+    async fn test_publish_reaches_subscribers() {
+        let hub = ControlHub::new();
+        let mut events = hub.events.subscribe();
+        hub.publish(EventKind::SyncStarted, "periodic");
+
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.kind, EventKind::SyncStarted as i32);
+        assert_eq!(event.message, "periodic");
+        assert_eq!(event.target, "");
+
+        hub.publish_for_target(EventKind::SyncFailed, "cabin", "unreachable");
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.kind, EventKind::SyncFailed as i32);
+        assert_eq!(event.target, "cabin");
+    }
+
+    #[tokio::test]
+    async fn test_trigger_sync_without_watch_loop() {
+        let hub = ControlHub::new();
+        let service = ControlService {
+            config: Config::default(),
+            sync_tx: hub.sync_tx.clone(),
+            events: hub.events.clone(),
+        };
+        drop(hub);
+
+        let result = service
+            .trigger_sync(Request::new(TriggerSyncRequest::default()))
+            .await;
+        assert_eq!(result.unwrap_err().code(), tonic::Code::Unavailable);
+    }
+}
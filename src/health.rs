@@ -0,0 +1,265 @@
+//! HTTP health endpoints for supervising the watch daemon
+//!
+//! `/healthz` (liveness) fails only when syncs have stalled: none has
+//! succeeded for three sync intervals (counted from daemon start at first).
+//! `/readyz` (readiness) also needs the Docker daemon and Homarr to answer.
+//! Both return a JSON report of every check, with status 200 or 503. The
+//! server speaks just enough HTTP/1.1 for probes (`curl`, Docker
+//! `HEALTHCHECK`, Kubernetes) and answers one request per connection.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use bollard::Docker;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::config::Config;
+use crate::error::{AdapterError, Result};
+use crate::state::State;
+
+/// How long a probe waits for Docker or Homarr
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sync intervals without a finished sync before the daemon counts as stalled
+const STALE_INTERVALS: u64 = 3;
+
+/// Outcome of one health check
+#[derive(Debug, Serialize)]
+struct Check {
+    ok: bool,
+    detail: String,
+}
+
+/// Every check, as returned by both endpoints
+#[derive(Debug, Serialize)]
+struct Report {
+    docker: Check,
+    homarr: Check,
+    last_sync: Check,
+    /// Seconds since the last finished sync, if there was one
+    last_sync_age_secs: Option<i64>,
+}
+
+impl Report {
+    fn live(&self) -> bool {
+        self.last_sync.ok
+    }
+
+    fn ready(&self) -> bool {
+        self.live() && self.docker.ok && self.homarr.ok
+    }
+}
+
+/// What the probes need, shared by all connections
+struct Probes {
+    config: Config,
+    docker: Docker,
+    http: reqwest::Client,
+    started: DateTime<Utc>,
+}
+
+/// Serve the health endpoints on `listen` in the background
+pub fn serve(config: &Config, listen: &str, docker: Docker) -> Result<()> {
+    let addr: SocketAddr = listen
+        .parse()
+        .map_err(|e| AdapterError::Config(format!("Invalid health_listen '{}': {}", listen, e)))?;
+    let probes = std::sync::Arc::new(Probes {
+        config: config.clone(),
+        docker,
+        http: reqwest::Client::builder()
+            .timeout(PROBE_TIMEOUT)
+            // Same as the Homarr client: local instances use self-signed certificates
+            .danger_accept_invalid_certs(true)
+            .build()?,
+        started: Utc::now(),
+    });
+
+    tokio::spawn(async move {
+        let listener = match TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!("Health endpoints not served on {}: {}", addr, e);
+                return;
+            }
+        };
+        tracing::info!("Health endpoints listening on {}", addr);
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let probes = probes.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = handle(stream, &probes).await {
+                            tracing::debug!("Health request failed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Health endpoint accept failed: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Answer one request
+async fn handle(mut stream: TcpStream, probes: &Probes) -> std::io::Result<()> {
+    let mut buf = [0u8; 1024];
+    let read = tokio::time::timeout(PROBE_TIMEOUT, stream.read(&mut buf))
+        .await
+        .unwrap_or(Ok(0))?;
+    let request = String::from_utf8_lossy(&buf[..read]);
+
+    let (status, body) = match request_path(&request) {
+        Some(path @ ("/healthz" | "/readyz")) => {
+            let report = probe(probes).await;
+            let ok = if path == "/healthz" {
+                report.live()
+            } else {
+                report.ready()
+            };
+            let body = serde_json::to_string(&report).unwrap_or_default();
+            (
+                if ok {
+                    "200 OK"
+                } else {
+                    "503 Service Unavailable"
+                },
+                body,
+            )
+        }
+        Some(_) => ("404 Not Found", r#"{"error":"not found"}"#.to_string()),
+        None => ("400 Bad Request", r#"{"error":"bad request"}"#.to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Path of a GET or HEAD request line, without the query string
+fn request_path(request: &str) -> Option<&str> {
+    let mut parts = request.lines().next()?.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+    matches!(method, "GET" | "HEAD").then(|| target.split('?').next().unwrap_or(target))
+}
+
+/// Run the checks
+async fn probe(probes: &Probes) -> Report {
+    let docker = match tokio::time::timeout(PROBE_TIMEOUT, probes.docker.ping()).await {
+        Ok(Ok(_)) => Check {
+            ok: true,
+            detail: "connected".to_string(),
+        },
+        Ok(Err(e)) => Check {
+            ok: false,
+            detail: e.to_string(),
+        },
+        Err(_) => Check {
+            ok: false,
+            detail: "timed out".to_string(),
+        },
+    };
+
+    // Any HTTP answer means Homarr is up; API access is `api-check`'s job
+    let homarr = match probes.http.get(&probes.config.homarr_url).send().await {
+        Ok(response) => Check {
+            ok: true,
+            detail: format!("HTTP {}", response.status().as_u16()),
+        },
+        Err(e) => Check {
+            ok: false,
+            detail: e.to_string(),
+        },
+    };
+
+    let last_sync = State::load(&probes.config.state_file)
+        .ok()
+        .and_then(|state| state.last_sync);
+    let max_age = chrono::Duration::seconds((STALE_INTERVALS * probes.config.sync_interval) as i64);
+    let (last_sync, age) = sync_check(last_sync, probes.started, Utc::now(), max_age);
+
+    Report {
+        docker,
+        homarr,
+        last_sync,
+        last_sync_age_secs: age,
+    }
+}
+
+/// Whether syncs are keeping up: the last one finished within `max_age`,
+/// counting from daemon start when it is older (or there was none)
+fn sync_check(
+    last_sync: Option<DateTime<Utc>>,
+    started: DateTime<Utc>,
+    now: DateTime<Utc>,
+    max_age: chrono::Duration,
+) -> (Check, Option<i64>) {
+    let age = last_sync.map(|at| (now - at).num_seconds());
+    let since = last_sync.map_or(started, |at| at.max(started));
+    let check = if now - since <= max_age {
+        Check {
+            ok: true,
+            detail: match age {
+                Some(age) => format!("{} s ago", age),
+                None => "none yet, daemon starting".to_string(),
+            },
+        }
+    } else {
+        Check {
+            ok: false,
+            detail: match age {
+                Some(age) => format!("{} s ago, over the {} s limit", age, max_age.num_seconds()),
+                None => format!("none in {} s", (now - started).num_seconds()),
+            },
+        }
+    };
+    (check, age)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_path() {
+        assert_eq!(request_path("GET /healthz HTTP/1.1\r\n"), Some("/healthz"));
+        assert_eq!(request_path("HEAD /readyz?v=1 HTTP/1.1"), Some("/readyz"));
+        assert_eq!(request_path("POST /healthz HTTP/1.1"), None);
+        assert_eq!(request_path(""), None);
+    }
+
+    #[test]
+    fn test_sync_check() {
+        let started = Utc::now() - chrono::Duration::seconds(100);
+        let now = Utc::now();
+        let max_age = chrono::Duration::seconds(900);
+
+        // Fresh daemon, no sync yet
+        assert!(sync_check(None, started, now, max_age).0.ok);
+        // Recent sync
+        let (check, age) = sync_check(
+            Some(now - chrono::Duration::seconds(60)),
+            started,
+            now,
+            max_age,
+        );
+        assert!(check.ok);
+        assert_eq!(age, Some(60));
+        // A sync from before a recent start doesn't count against the daemon
+        let old = Some(now - chrono::Duration::days(1));
+        assert!(sync_check(old, started, now, max_age).0.ok);
+
+        // Stalled: nothing since start, long ago
+        let started = now - chrono::Duration::seconds(1000);
+        assert!(!sync_check(old, started, now, max_age).0.ok);
+        assert!(!sync_check(None, started, now, max_age).0.ok);
+    }
+}
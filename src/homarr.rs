@@ -1,12 +1,21 @@
 //! Homarr API client
 
-use reqwest::{cookie::Jar, Client};
+use reqwest::{cookie::Jar, Client, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::sync::Arc;
-
-use crate::branding::BrandingConfig;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::branding::{
+    BrandingConfig, EveryoneAccess, EveryonePermission, Search, TemplateApp, TemplateWidget,
+    TilePosition,
+};
 use crate::error::{AdapterError, Result};
+use crate::layout::{self, layout_rect, BoardFull, Grid, SectionLimits};
 use crate::registry::AppDefinition;
 
 /// Homarr API client
@@ -15,6 +24,289 @@ pub struct HomarrClient {
     base_url: String,
     /// API key for authentication (format: "{id}.{token}")
     api_key: Option<String>,
+    /// Section for tiles that don't request one (from branding)
+    default_section: Option<String>,
+    /// Group container tiles into a section per compose project
+    compose_sections: bool,
+    /// Group tiles into a section per category
+    category_sections: bool,
+    /// IDs of sections created on demand, by board ID, then lowercased
+    /// section name
+    section_ids: Mutex<HashMap<String, HashMap<String, String>>>,
+    /// Rows per section, and where new tiles go when a section is full
+    section_limits: SectionLimits,
+    /// Replacement icons for apps whose configured icon no longer resolves
+    /// Key: app URL
+    icon_overrides: HashMap<String, String>,
+    /// Homarr media URLs for uploaded `file://` icons
+    /// Key: configured icon (`file://...`)
+    uploaded_icons: HashMap<String, String>,
+    /// Staged board edits and the time of each board's last save
+    board_writes: Mutex<BoardWrites>,
+    /// Timeouts and retries for reads and mutations
+    policies: RequestPolicies,
+    /// Why mutations are refused (Homarr older than the supported minimum)
+    refuse_mutations: Option<String>,
+    /// Signed-in session, when authenticating with credentials
+    session: tokio::sync::Mutex<Option<Session>>,
+    /// Boards by name, as last listed or created
+    boards: Mutex<HashMap<String, BoardWithPermission>>,
+    /// Rate-limited (429) responses since last taken
+    rate_limited: AtomicU32,
+    /// Integrations as listed in this sync cycle, for integration widgets
+    integrations: Mutex<Option<Vec<Integration>>>,
+}
+
+/// Sign in again this long before a session expires
+const SESSION_REFRESH_MARGIN: Duration = Duration::from_secs(300);
+
+/// A credentials session, with what's needed to sign in again
+struct Session {
+    username: String,
+    password: String,
+    /// When Homarr will end the session, if it said
+    expires: Option<chrono::DateTime<chrono::Utc>>,
+    /// Bumped on every sign-in, so concurrent requests rejected with the
+    /// same session sign in again only once
+    generation: u64,
+}
+
+impl Session {
+    /// Whether the session ends within `margin` of `now`
+    fn expires_within(&self, margin: Duration, now: chrono::DateTime<chrono::Utc>) -> bool {
+        let margin = chrono::Duration::from_std(margin).unwrap_or_default();
+        self.expires.is_some_and(|expires| expires - margin <= now)
+    }
+}
+
+/// A board with its items, as read by `get_board` and written by `save_items`
+#[derive(Debug, Clone)]
+pub struct Board {
+    board: BoardResponse,
+    pub items: Vec<serde_json::Value>,
+    /// `board_version` of the board as read from Homarr, before any edits
+    version: u64,
+}
+
+impl Board {
+    /// A board as just read from Homarr
+    fn read(board: BoardResponse, items: Vec<serde_json::Value>) -> Self {
+        let version = board_version(&board.sections, &items);
+        Self {
+            board,
+            items,
+            version,
+        }
+    }
+}
+
+/// Fingerprint of a board's sections and items
+///
+/// Homarr keeps no revision counter for boards, so before saving, the
+/// fingerprint of the board read when planning is compared with the board
+/// as it is now. A mismatch means someone edited it in the Homarr UI.
+fn board_version(sections: &[Section], items: &[serde_json::Value]) -> u64 {
+    string_hash(&json!({ "sections": sections, "items": items }).to_string())
+}
+
+/// Coalesced board saves
+///
+/// Tile placements are staged per board and written in a single `saveBoard`
+/// call, at most once per `min_interval` per board, to keep Homarr's
+/// database churn low when syncs run in quick succession.
+#[derive(Debug, Default)]
+struct BoardWrites {
+    min_interval: Duration,
+    /// Key: board name
+    pending: HashMap<String, Board>,
+    /// Key: board name
+    last_write: HashMap<String, Instant>,
+}
+
+impl BoardWrites {
+    /// Time until a board may be saved again (zero if it may be saved now)
+    fn wait_for(&self, board_name: &str, now: Instant) -> Duration {
+        self.last_write
+            .get(board_name)
+            .map(|last| (*last + self.min_interval).saturating_duration_since(now))
+            .unwrap_or_default()
+    }
+
+    /// Time until the next staged board may be saved
+    fn next_due(&self, now: Instant) -> Option<Duration> {
+        self.pending
+            .keys()
+            .map(|name| self.wait_for(name, now))
+            .min()
+    }
+
+    /// Take the staged boards that may be saved now
+    fn take_due(&mut self, now: Instant) -> Vec<(String, Board)> {
+        let due: Vec<String> = self
+            .pending
+            .keys()
+            .filter(|name| self.wait_for(name, now).is_zero())
+            .cloned()
+            .collect();
+        due.into_iter()
+            .filter_map(|name| self.pending.remove_entry(&name))
+            .collect()
+    }
+
+    /// Stage a board again after its save failed, unless it was staged anew
+    /// meanwhile
+    fn restage(&mut self, board_name: String, board: Board) {
+        self.pending.entry(board_name).or_insert(board);
+    }
+
+    /// Put the boards still staged back as they were in `snapshot`:
+    /// edits staged since are dropped, earlier ones kept
+    fn restore(&mut self, mut snapshot: HashMap<String, Board>) {
+        self.pending = std::mem::take(&mut self.pending)
+            .into_keys()
+            .filter_map(|name| snapshot.remove_entry(&name))
+            .collect();
+    }
+}
+
+/// HTTP connection pool settings for the Homarr client
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    /// How long an idle pooled connection is kept open
+    pub idle_timeout: Duration,
+    /// Maximum idle connections kept per host
+    pub max_idle_per_host: usize,
+    /// TCP keepalive probe interval
+    pub tcp_keepalive: Duration,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self {
+            idle_timeout: Duration::from_secs(90),
+            max_idle_per_host: 4,
+            tcp_keepalive: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Timeout and retry settings for one class of Homarr request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestPolicy {
+    /// Time allowed for the whole request, response included
+    pub timeout: Duration,
+    /// Extra attempts after a retryable failure
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each further one
+    pub backoff: Duration,
+    /// Longest delay between retries
+    pub max_backoff: Duration,
+    /// Response statuses worth another attempt
+    pub retry_statuses: Vec<StatusCode>,
+}
+
+impl RequestPolicy {
+    /// Delay before retry number `attempt` (1-based), without jitter
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1).min(16)))
+            .min(self.max_backoff)
+    }
+
+    /// Delay before retry number `attempt`: the backoff delay less up to
+    /// half at random, so clients failing together don't retry in step
+    fn retry_delay(&self, attempt: u32) -> Duration {
+        use rand::Rng;
+        self.backoff_delay(attempt)
+            .mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+}
+
+/// Request policies for reads (tRPC queries) and mutations
+///
+/// Reads are idempotent and retried after timeouts, connection failures and
+/// gateway errors. Mutations are retried only when no connection could be
+/// made, so a change the server may have received is never sent twice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestPolicies {
+    pub read: RequestPolicy,
+    pub mutation: RequestPolicy,
+}
+
+impl Default for RequestPolicies {
+    fn default() -> Self {
+        Self {
+            read: RequestPolicy {
+                timeout: Duration::from_secs(10),
+                retries: 2,
+                backoff: Duration::from_millis(500),
+                max_backoff: Duration::from_secs(30),
+                retry_statuses: vec![
+                    StatusCode::BAD_GATEWAY,
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    StatusCode::GATEWAY_TIMEOUT,
+                ],
+            },
+            mutation: RequestPolicy {
+                timeout: Duration::from_secs(60),
+                retries: 0,
+                backoff: Duration::from_millis(500),
+                max_backoff: Duration::from_secs(30),
+                retry_statuses: Vec::new(),
+            },
+        }
+    }
+}
+
+/// How a request attempt ended, for retry decisions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttemptOutcome {
+    Response(StatusCode),
+    ConnectFailed,
+    TimedOut,
+    OtherError,
+}
+
+impl AttemptOutcome {
+    fn of(result: &reqwest::Result<reqwest::Response>) -> Self {
+        match result {
+            Ok(response) => AttemptOutcome::Response(response.status()),
+            Err(e) if e.is_connect() => AttemptOutcome::ConnectFailed,
+            Err(e) if e.is_timeout() => AttemptOutcome::TimedOut,
+            Err(_) => AttemptOutcome::OtherError,
+        }
+    }
+
+    /// Whether another attempt is safe and may succeed
+    fn retryable(self, idempotent: bool, policy: &RequestPolicy) -> bool {
+        match self {
+            AttemptOutcome::ConnectFailed => true,
+            AttemptOutcome::TimedOut => idempotent,
+            AttemptOutcome::Response(status) => policy.retry_statuses.contains(&status),
+            AttemptOutcome::OtherError => false,
+        }
+    }
+}
+
+/// Rate-limited (429) answers waited out per request before giving up
+const RATE_LIMIT_RETRIES: u32 = 3;
+
+/// Longest `Retry-After` waited out within a sync; a longer one ends the sync
+/// and pauses syncing until it has passed
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Wait asked for by a `Retry-After` header: delay-seconds or an HTTP date
+fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (at.with_timezone(&chrono::Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
 }
 
 #[derive(Debug, Deserialize)]
@@ -25,18 +317,169 @@ pub struct OnboardingStep {
 }
 
 #[derive(Debug, Deserialize)]
-struct TrpcResponse<T> {
-    result: TrpcResult<T>,
+struct TrpcResponse {
+    result: TrpcResult,
 }
 
 #[derive(Debug, Deserialize)]
-struct TrpcResult<T> {
-    data: TrpcData<T>,
+struct TrpcResult {
+    data: SuperJson,
 }
 
-#[derive(Debug, Deserialize)]
-struct TrpcData<T> {
-    json: T,
+/// Superjson-encoded tRPC payload
+///
+/// Homarr's tRPC router uses superjson, which sends plain JSON alongside a
+/// `meta.values` map describing values JSON can't represent (dates,
+/// `undefined`, Maps, Sets, bigints). Annotation keys are dot-separated paths
+/// into `json` (literal dots escaped as `\.`); an annotation on the root value
+/// is stored directly in `values`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SuperJson {
+    pub json: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<SuperJsonMeta>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SuperJsonMeta {
+    #[serde(default)]
+    pub values: serde_json::Value,
+}
+
+impl SuperJson {
+    /// Wrap a plain JSON input with no annotations
+    pub fn new(json: serde_json::Value) -> Self {
+        Self { json, meta: None }
+    }
+
+    /// Apply the `meta` annotations and deserialize the result
+    pub fn decode<T: DeserializeOwned>(self) -> Result<T> {
+        let mut json = self.json;
+        if let Some(meta) = self.meta {
+            match meta.values {
+                serde_json::Value::Array(annotation) => {
+                    apply_superjson_annotation(&mut json, &[], &annotation)
+                }
+                serde_json::Value::Object(values) => {
+                    for (path, annotation) in values {
+                        if let Some(annotation) = annotation.as_array() {
+                            let segments = split_superjson_path(&path);
+                            apply_superjson_annotation(&mut json, &segments, annotation);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(serde_json::from_value(json)?)
+    }
+}
+
+/// Split a superjson path on unescaped dots
+fn split_superjson_path(path: &str) -> Vec<String> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut chars = path.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'.') => {
+                current.push('.');
+                chars.next();
+            }
+            '.' => segments.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    if !path.is_empty() {
+        segments.push(current);
+    }
+    segments
+}
+
+fn value_at_mut<'a>(
+    value: &'a mut serde_json::Value,
+    segments: &[String],
+) -> Option<&'a mut serde_json::Value> {
+    segments.iter().try_fold(value, |v, segment| match v {
+        serde_json::Value::Object(map) => map.get_mut(segment),
+        serde_json::Value::Array(items) => {
+            segment.parse::<usize>().ok().and_then(|i| items.get_mut(i))
+        }
+        _ => None,
+    })
+}
+
+/// Convert a superjson-annotated value back into plain JSON serde can read
+///
+/// Dates are left as ISO strings (chrono parses them), Maps become objects,
+/// Sets stay arrays, bigints become numbers and `undefined` becomes absent.
+fn apply_superjson_annotation(
+    root: &mut serde_json::Value,
+    segments: &[String],
+    annotation: &[serde_json::Value],
+) {
+    let kind = annotation
+        .first()
+        .and_then(|k| k.as_str())
+        .unwrap_or_default();
+
+    if kind == "undefined" {
+        match segments.split_last() {
+            Some((last, parents)) => match value_at_mut(root, parents) {
+                Some(serde_json::Value::Object(map)) => {
+                    map.remove(last);
+                }
+                Some(serde_json::Value::Array(items)) => {
+                    if let Some(item) = last.parse::<usize>().ok().and_then(|i| items.get_mut(i)) {
+                        *item = serde_json::Value::Null;
+                    }
+                }
+                _ => {}
+            },
+            None => *root = serde_json::Value::Null,
+        }
+        return;
+    }
+
+    let Some(value) = value_at_mut(root, segments) else {
+        return;
+    };
+
+    match kind {
+        "map" => {
+            if let serde_json::Value::Array(entries) = value {
+                let map: serde_json::Map<String, serde_json::Value> = entries
+                    .drain(..)
+                    .filter_map(|entry| match entry {
+                        serde_json::Value::Array(mut pair) if pair.len() == 2 => {
+                            let v = pair.pop()?;
+                            let k = match pair.pop()? {
+                                serde_json::Value::String(s) => s,
+                                other => other.to_string(),
+                            };
+                            Some((k, v))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                *value = serde_json::Value::Object(map);
+            }
+        }
+        "bigint" => {
+            if let Some(n) = value.as_str().and_then(|s| s.parse::<i64>().ok()) {
+                *value = json!(n);
+            }
+        }
+        // NaN / Infinity have no JSON representation
+        "number" if value.is_string() => *value = serde_json::Value::Null,
+        _ => {}
+    }
+}
+
+/// Read a tRPC response body and decode its superjson payload
+async fn read_trpc<T: DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+    let envelope: TrpcResponse = response.json().await?;
+    envelope.result.data.decode()
 }
 
 #[derive(Debug, Deserialize)]
@@ -46,6 +489,11 @@ struct CsrfResponse {
 }
 
 #[derive(Debug, Deserialize)]
+struct SessionResponse {
+    expires: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
 #[allow(dead_code)]
 struct BoardResponse {
     id: String,
@@ -62,10 +510,28 @@ struct Section {
     y_offset: i32,
     #[serde(rename = "xOffset")]
     x_offset: i32,
+    /// Display name (category sections only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    /// Collapsed state (category sections only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    collapsed: Option<bool>,
+    /// Fields we don't model (e.g. dynamic section geometry), preserved on save
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
-#[allow(dead_code)]
+impl Section {
+    fn is_category_named(&self, name: &str) -> bool {
+        self.kind == "category"
+            && self
+                .name
+                .as_deref()
+                .is_some_and(|n| n.eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 struct Layout {
     id: String,
     name: String,
@@ -88,14 +554,33 @@ struct CreateAppResponse {
     id: String,
 }
 
+/// media.uploadMedia returns the new media ID(s)
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum UploadedMedia {
+    One(String),
+    Many(Vec<String>),
+}
+
 #[derive(Debug, Deserialize)]
 struct CreateApiKeyResponse {
     #[serde(rename = "apiKey")]
     api_key: String,
 }
 
+/// Outcome of syncing one app to a board
+#[derive(Debug, Clone)]
+pub struct SyncedApp {
+    /// Homarr app ID
+    pub id: String,
+    /// The app was created in Homarr's registry
+    pub created: bool,
+    /// A new tile was placed on the board
+    pub placed: bool,
+}
+
 /// Minimal app data from app.selectable endpoint
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[allow(dead_code)]
 pub struct SelectableApp {
     pub id: String,
@@ -146,6 +631,14 @@ pub struct BoardWithPermission {
     pub group_permissions: Vec<serde_json::Value>,
 }
 
+/// A board looked up or created by [`HomarrClient::ensure_board`]
+#[derive(Debug, Clone)]
+pub struct EnsuredBoard {
+    pub board: BoardWithPermission,
+    /// Whether this call created the board
+    pub created: bool,
+}
+
 impl BoardWithPermission {
     /// Check if this board is writable
     ///
@@ -159,6 +652,26 @@ impl BoardWithPermission {
     }
 }
 
+/// Parse a version such as "v1.12.0", "1.12" or "1.12.0-beta.1" into
+/// (major, minor, patch) for comparison
+pub fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.trim().trim_start_matches('v');
+    let release = version.split(['-', '+']).next()?;
+    let mut parts = release.split('.').map(|p| p.parse::<u64>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().transpose().ok()?.unwrap_or(0);
+    let patch = parts.next().transpose().ok()?.unwrap_or(0);
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((major, minor, patch))
+}
+
+/// Whether `version` is at least `minimum`, `None` if either can't be parsed
+pub fn version_supported(version: &str, minimum: &str) -> Option<bool> {
+    Some(parse_version(version)? >= parse_version(minimum)?)
+}
+
 /// Default icon path (relative URL)
 const DEFAULT_ICON: &str = "/icons/docker.svg";
 
@@ -191,6 +704,27 @@ fn derive_ping_url(app_url: &str) -> Option<String> {
     }
 }
 
+/// URL Homarr pings for an app's status indicator, if any
+///
+/// An explicit `ping_url` wins. `ping = true` pings the app URL itself
+/// (path-only URLs still go through `host.docker.internal`), `ping = false`
+/// turns pinging off. Otherwise the ping URL is derived from the app URL,
+/// except for external apps, which aren't health checked.
+pub fn ping_url_for(app: &AppDefinition) -> Option<String> {
+    match app.ping {
+        Some(false) => None,
+        Some(true) => app.ping_url.clone().or_else(|| {
+            if crate::registry::is_path_only(&app.url) {
+                derive_ping_url(&app.url)
+            } else {
+                Some(app.url.clone())
+            }
+        }),
+        None if app.is_external() => None,
+        None => app.ping_url.clone().or_else(|| derive_ping_url(&app.url)),
+    }
+}
+
 /// Simple hash function for generating unique IDs from URLs
 fn string_hash(s: &str) -> u64 {
     use std::collections::hash_map::DefaultHasher;
@@ -224,1414 +758,5259 @@ pub fn normalize_url(url_str: &str) -> String {
     }
 }
 
-/// Check if a board already has an item for a given app ID.
-/// Used to prevent duplicate board items when the same app is synced multiple times.
-fn board_has_app(items: &[serde_json::Value], app_id: &str) -> bool {
-    items.iter().any(|item| {
-        item.get("options")
-            .and_then(|o| o.get("appId"))
-            .and_then(|a| a.as_str())
-            == Some(app_id)
-    })
-}
-
-/// Transform icon paths to relative URLs for Homarr.
+/// Plan the category sections missing from a board, in configured order.
 ///
-/// Icons are served by Homarr's nginx from /icons/ which maps to /usr/share/pixmaps.
-/// This function transforms:
-/// - `/usr/share/pixmaps/app.png` → `/icons/app.png`
-/// - HTTP/HTTPS URLs → unchanged
-/// - `/icons/*` paths → unchanged
-/// - Everything else → `/icons/docker.svg` (fallback)
-fn transform_icon_url(icon_path: &str) -> String {
-    const PIXMAPS_PREFIX: &str = "/usr/share/pixmaps/";
-
-    if icon_path.is_empty() {
-        return DEFAULT_ICON.to_string();
-    }
-
-    // HTTP/HTTPS URLs pass through unchanged
-    if icon_path.starts_with("http://") || icon_path.starts_with("https://") {
-        return icon_path.to_string();
-    }
-
-    // Already relative /icons/ paths - pass through unchanged
-    if icon_path.starts_with("/icons/") {
-        return icon_path.to_string();
-    }
-
-    // Transform /usr/share/pixmaps/ paths to /icons/
-    if let Some(filename) = icon_path.strip_prefix(PIXMAPS_PREFIX) {
-        if filename.is_empty() {
-            return DEFAULT_ICON.to_string();
+/// Mirrors what Homarr's UI does when adding a category: each category is
+/// followed by an empty section, and new sections are appended below the
+/// existing ones.
+fn plan_missing_sections(existing: &[Section], names: &[String]) -> Vec<Section> {
+    let mut next_y = existing.iter().map(|s| s.y_offset + 1).max().unwrap_or(0);
+    let mut planned = Vec::new();
+
+    for name in names {
+        if existing.iter().any(|s| s.is_category_named(name))
+            || planned.iter().any(|s: &Section| s.is_category_named(name))
+        {
+            continue;
         }
-        return format!("/icons/{}", filename);
+        let id = format!("section-{:x}", string_hash(&name.to_lowercase()));
+        planned.push(Section {
+            id: id.clone(),
+            kind: "category".to_string(),
+            y_offset: next_y,
+            x_offset: 0,
+            name: Some(name.clone()),
+            collapsed: Some(false),
+            extra: serde_json::Map::new(),
+        });
+        planned.push(Section {
+            id: format!("{}-empty", id),
+            kind: "empty".to_string(),
+            y_offset: next_y + 1,
+            x_offset: 0,
+            name: None,
+            collapsed: None,
+            extra: serde_json::Map::new(),
+        });
+        next_y += 2;
     }
 
-    // Unknown format - use fallback
-    DEFAULT_ICON.to_string()
+    planned
 }
 
-impl HomarrClient {
-    /// Create a new Homarr client
-    ///
-    /// # Arguments
-    /// * `base_url` - The Homarr API base URL (e.g., "http://localhost:80")
-    pub fn new(base_url: &str) -> Result<Self> {
-        let jar = Arc::new(Jar::default());
-        let client = Client::builder()
-            .cookie_store(true)
-            .cookie_provider(jar)
-            // Accept self-signed certificates (required for local SSL configurations)
-            .danger_accept_invalid_certs(true)
-            .build()?;
+/// Plan the category section for a compose project or app category, if the
+/// board lacks it
+///
+/// Section IDs are unique across Homarr, so they are derived from the board
+/// ID as well as the section name.
+fn on_demand_section(board_id: &str, sections: &[Section], name: &str) -> Vec<Section> {
+    plan_missing_sections(sections, &[name.to_string()])
+        .into_iter()
+        .map(|s| Section {
+            id: format!("{}-{}", board_id, s.id),
+            ..s
+        })
+        .collect()
+}
 
-        Ok(Self {
-            client,
-            base_url: base_url.trim_end_matches('/').to_string(),
-            api_key: None,
+/// Board settings copied from a template board (its look, not its content)
+const TEMPLATE_SETTINGS: &[&str] = &[
+    "pageTitle",
+    "metaTitle",
+    "logoImageUrl",
+    "faviconImageUrl",
+    "backgroundImageUrl",
+    "backgroundImageAttachment",
+    "backgroundImageRepeat",
+    "backgroundImageSize",
+    "primaryColor",
+    "secondaryColor",
+    "opacity",
+    "customCss",
+    "iconColor",
+    "itemRadius",
+    "disableStatus",
+];
+
+/// Copy a template board's sections for a new board.
+///
+/// Section IDs are unique across Homarr, so copies get new ones derived from
+/// the new board's ID. Dynamic sections are skipped: they only hold items,
+/// which aren't copied.
+fn template_sections(template: &[Section], board_id: &str) -> Vec<Section> {
+    template
+        .iter()
+        .filter(|s| s.kind != "dynamic")
+        .map(|s| Section {
+            id: format!("{}-{:x}", board_id, string_hash(&s.id)),
+            ..s.clone()
         })
-    }
+        .collect()
+}
 
-    /// Set the API key for authentication
-    ///
-    /// When set, all requests will include the `ApiKey: <api_key>` header.
-    pub fn set_api_key(&mut self, api_key: String) {
-        self.api_key = Some(api_key);
-    }
+/// Version of the `board export` file format
+const BOARD_EXPORT_VERSION: u32 = 1;
 
-    /// Make an authenticated GET request
-    async fn get(&self, url: &str) -> reqwest::Result<reqwest::Response> {
-        let mut request = self.client.get(url);
-        if let Some(ref api_key) = self.api_key {
-            request = request.header("ApiKey", api_key);
-        }
-        request.send().await
-    }
+/// A board as written by `board export`
+///
+/// Besides the board's sections, layouts and items, it holds the apps and
+/// integrations the items link to: their IDs differ between Homarr
+/// instances, so an import looks them up by URL instead.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BoardExport {
+    pub version: u32,
+    pub name: String,
+    /// Board ID in the exporting Homarr; an import onto that same board
+    /// keeps section and layout IDs
+    pub board_id: String,
+    #[serde(default)]
+    pub is_public: bool,
+    sections: Vec<Section>,
+    layouts: Vec<Layout>,
+    items: Vec<serde_json::Value>,
+    #[serde(default)]
+    apps: Vec<SelectableApp>,
+    #[serde(default)]
+    integrations: Vec<Integration>,
+}
 
-    /// Make an authenticated POST request with JSON body
-    async fn post_json<T: Serialize + ?Sized>(
-        &self,
-        url: &str,
-        body: &T,
-    ) -> reqwest::Result<reqwest::Response> {
-        let mut request = self.client.post(url).json(body);
-        if let Some(ref api_key) = self.api_key {
-            request = request.header("ApiKey", api_key);
-        }
-        request.send().await
-    }
+/// What `import_board` did
+#[derive(Debug, Default)]
+pub struct BoardImport {
+    /// The board didn't exist and was created
+    pub created_board: bool,
+    /// Names of apps created because Homarr had none with their URL or name
+    pub created_apps: Vec<String>,
+    /// Items restored
+    pub items: usize,
+    /// Items dropped because their app couldn't be found or created
+    pub dropped_items: usize,
+}
 
-    /// Make an authenticated POST request with form data
-    async fn post_form<T: Serialize + ?Sized>(
-        &self,
-        url: &str,
-        form: &T,
-    ) -> reqwest::Result<reqwest::Response> {
-        let mut request = self.client.post(url).form(form);
-        if let Some(ref api_key) = self.api_key {
-            request = request.header("ApiKey", api_key);
+/// A board's exported contents, with IDs rewritten for the board it is
+/// imported onto
+///
+/// Sections and layouts keep their IDs on the board they were exported
+/// from; elsewhere layouts take the IDs of the board's own layouts (matched
+/// by name, then position) and sections get IDs derived from the board's, as
+/// for template copies. App and integration IDs are swapped through the
+/// given maps; items whose app is missing are dropped, as are links to
+/// missing integrations.
+fn import_contents(
+    export: &BoardExport,
+    board_id: &str,
+    board_layouts: &[Layout],
+    app_ids: &HashMap<String, String>,
+    integration_ids: &HashMap<String, String>,
+) -> (Vec<Section>, Vec<Layout>, Vec<serde_json::Value>) {
+    let same_board = export.board_id == board_id;
+    let new_id = |id: &str| format!("{}-{:x}", board_id, string_hash(id));
+
+    let section_ids: HashMap<String, String> = export
+        .sections
+        .iter()
+        .map(|s| {
+            let id = if same_board {
+                s.id.clone()
+            } else {
+                new_id(&s.id)
+            };
+            (s.id.clone(), id)
+        })
+        .collect();
+    let sections = export
+        .sections
+        .iter()
+        .map(|s| {
+            let mut section = Section {
+                id: section_ids[&s.id].clone(),
+                ..s.clone()
+            };
+            // Dynamic sections nest inside another section
+            let parent = section
+                .extra
+                .get("parentSectionId")
+                .and_then(|p| p.as_str())
+                .and_then(|p| section_ids.get(p));
+            if let Some(parent) = parent.cloned() {
+                section
+                    .extra
+                    .insert("parentSectionId".to_string(), json!(parent));
+            }
+            section
+        })
+        .collect();
+
+    let layouts: Vec<Layout> = export
+        .layouts
+        .iter()
+        .enumerate()
+        .map(|(i, l)| {
+            let id = if same_board {
+                l.id.clone()
+            } else {
+                board_layouts
+                    .iter()
+                    .find(|own| own.name == l.name)
+                    .or_else(|| board_layouts.get(i))
+                    .map(|own| own.id.clone())
+                    .unwrap_or_else(|| new_id(&l.id))
+            };
+            Layout { id, ..l.clone() }
+        })
+        .collect();
+    let layout_ids: HashMap<String, String> = export
+        .layouts
+        .iter()
+        .zip(&layouts)
+        .map(|(old, new)| (old.id.clone(), new.id.clone()))
+        .collect();
+
+    let mut items = Vec::new();
+    for item in &export.items {
+        let mut item = item.clone();
+        if let Some(app_id) = item_app_id(&item) {
+            match app_ids.get(app_id) {
+                Some(id) => item["options"]["appId"] = json!(id),
+                None => continue,
+            }
+        }
+        if let Some(linked) = item
+            .get_mut("integrationIds")
+            .and_then(|i| i.as_array_mut())
+        {
+            *linked = linked
+                .iter()
+                .filter_map(|id| integration_ids.get(id.as_str()?))
+                .map(|id| json!(id))
+                .collect();
+        }
+        if let Some(placements) = item.get_mut("layouts").and_then(|l| l.as_array_mut()) {
+            for placement in placements {
+                for (key, ids) in [("layoutId", &layout_ids), ("sectionId", &section_ids)] {
+                    let id = placement
+                        .get(key)
+                        .and_then(|id| id.as_str())
+                        .and_then(|id| ids.get(id))
+                        .cloned();
+                    if let Some(id) = id {
+                        placement[key] = json!(id);
+                    }
+                }
+            }
         }
-        request.send().await
+        items.push(item);
     }
+    (sections, layouts, items)
+}
 
-    /// Get current onboarding step
-    pub async fn get_onboarding_step(&self) -> Result<OnboardingStep> {
-        let url = format!("{}/api/trpc/onboard.currentStep", self.base_url);
-        let response: TrpcResponse<OnboardingStep> = self.get(&url).await?.json().await?;
-        Ok(response.result.data.json)
-    }
+/// Pick the appearance settings of a template board
+fn template_settings(template: &serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+    TEMPLATE_SETTINGS
+        .iter()
+        .filter_map(|key| {
+            let value = template.get(*key)?;
+            (!value.is_null()).then(|| (key.to_string(), value.clone()))
+        })
+        .collect()
+}
 
-    /// Complete the onboarding flow
-    pub async fn complete_onboarding(&self, branding: &BrandingConfig) -> Result<()> {
-        // Step through onboarding until we reach the user step
-        loop {
-            let step = self.get_onboarding_step().await?;
-            tracing::info!("Onboarding step: {}", step.current);
+/// Pick the section a tile should land in.
+///
+/// Uses the category section named `wanted` if the board has one, otherwise
+/// the board's first section (the implicit top-level empty section).
+fn resolve_section_id(sections: &[Section], wanted: Option<&str>) -> String {
+    wanted
+        .and_then(|name| sections.iter().find(|s| s.is_category_named(name)))
+        .or_else(|| sections.first())
+        .map(|s| s.id.clone())
+        .unwrap_or_default()
+}
 
-            match step.current.as_str() {
-                "finish" => break,
-                "start" => {
-                    self.advance_onboarding_step().await?;
-                }
-                "user" => {
-                    self.create_initial_user(branding).await?;
-                }
-                "settings" => {
-                    self.configure_settings(branding).await?;
-                }
-                _ => {
-                    // Skip other steps
-                    self.advance_onboarding_step().await?;
-                }
-            }
-        }
+/// Default size of integration widgets placed next to app tiles
+const WIDGET_SIZE: (i32, i32) = (2, 2);
 
-        Ok(())
-    }
+/// Integration data from integration.all
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[allow(dead_code)]
+struct Integration {
+    id: String,
+    name: String,
+    kind: String,
+    url: String,
+}
 
-    /// Advance to next onboarding step
-    async fn advance_onboarding_step(&self) -> Result<()> {
-        let url = format!("{}/api/trpc/onboard.nextStep", self.base_url);
-        self.post_json(&url, &json!({"json": {}})).await?;
-        Ok(())
-    }
+/// Search engine from searchEngine.getPaginated
+#[derive(Debug, Deserialize, Clone)]
+struct SearchEngineEntry {
+    id: String,
+    name: String,
+    short: String,
+}
 
-    /// Create initial admin user
-    async fn create_initial_user(&self, branding: &BrandingConfig) -> Result<()> {
-        let url = format!("{}/api/trpc/user.initUser", self.base_url);
-        let payload = json!({
-            "json": {
-                "username": branding.credentials.admin_username,
-                "password": branding.credentials.admin_password,
-                "confirmPassword": branding.credentials.admin_password
-            }
-        });
+#[derive(Debug, Deserialize)]
+struct SearchEnginePage {
+    items: Vec<SearchEngineEntry>,
+}
 
-        let response = self.post_json(&url, &payload).await?;
+/// Find a search engine by its short name, then by name (case-insensitive)
+fn find_search_engine<'a>(
+    engines: &'a [SearchEngineEntry],
+    key: &str,
+) -> Option<&'a SearchEngineEntry> {
+    engines
+        .iter()
+        .find(|e| e.short.eq_ignore_ascii_case(key))
+        .or_else(|| engines.iter().find(|e| e.name.eq_ignore_ascii_case(key)))
+}
 
-        if !response.status().is_success() {
-            let text = response.text().await?;
-            return Err(AdapterError::HomarrApi(format!(
-                "Failed to create user: {}",
-                text
-            )));
-        }
+/// Where a tile sits on a board
+#[derive(Debug, Clone, PartialEq)]
+struct TilePlacement {
+    section_id: String,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
 
-        Ok(())
-    }
+/// Find the placement of an app's tile on a board
+fn app_item_placement(items: &[serde_json::Value], app_id: &str) -> Option<TilePlacement> {
+    let item = items.iter().find(|item| {
+        item.get("options")
+            .and_then(|o| o.get("appId"))
+            .and_then(|a| a.as_str())
+            == Some(app_id)
+    })?;
+    let layout = item.get("layouts")?.get(0)?;
+    let get = |key: &str, default: i64| {
+        layout.get(key).and_then(|v| v.as_i64()).unwrap_or(default) as i32
+    };
+    Some(TilePlacement {
+        section_id: layout.get("sectionId")?.as_str()?.to_string(),
+        x: get("xOffset", 0),
+        y: get("yOffset", 0),
+        width: get("width", 1),
+        height: get("height", 1),
+    })
+}
 
-    /// Configure server settings
-    async fn configure_settings(&self, branding: &BrandingConfig) -> Result<()> {
-        let url = format!("{}/api/trpc/serverSettings.initSettings", self.base_url);
-        let payload = json!({
-            "json": {
-                "analytics": {
-                    "enableGeneral": branding.settings.analytics.enable_general,
-                    "enableWidgetData": branding.settings.analytics.enable_widget_data,
-                    "enableIntegrationData": branding.settings.analytics.enable_integration_data,
-                    "enableUserData": branding.settings.analytics.enable_user_data
-                },
-                "crawlingAndIndexing": {
-                    "noIndex": branding.settings.crawling.no_index,
-                    "noFollow": branding.settings.crawling.no_follow,
-                    "noTranslate": branding.settings.crawling.no_translate,
-                    "noSiteLinksSearchBox": branding.settings.crawling.no_sitelinks_search_box
-                }
-            }
-        });
+/// Check if any layout of `item` overlaps an item already on the board in
+/// the same layout and section
+fn item_overlaps(items: &[serde_json::Value], item: &serde_json::Value) -> bool {
+    let layouts = |item: &serde_json::Value| {
+        item.get("layouts")
+            .and_then(|l| l.as_array())
+            .cloned()
+            .unwrap_or_default()
+    };
+    let same_area = |a: &serde_json::Value, b: &serde_json::Value| {
+        a.get("layoutId") == b.get("layoutId") && a.get("sectionId") == b.get("sectionId")
+    };
+
+    layouts(item).iter().any(|layout| {
+        let (x, y, w, h) = layout_rect(layout);
+        items
+            .iter()
+            .flat_map(layouts)
+            .filter(|other| same_area(layout, other))
+            .any(|other| {
+                let (ox, oy, ow, oh) = layout_rect(&other);
+                x < ox + ow && ox < x + w && y < oy + oh && oy < y + h
+            })
+    })
+}
 
-        self.post_json(&url, &payload).await?;
-        Ok(())
+/// Board items for the branding template's apps and widgets that aren't on
+/// the board yet
+///
+/// `apps` pairs each template app with its Homarr app ID. An app counts as
+/// placed when any tile shows it; a widget by its item ID, made from its
+/// `id` or else its kind and position (not its place in the list), so
+/// tiles the user moved stay where they are. An item whose position is
+/// taken by a tile already on the board goes to the first free slot of its
+/// section instead.
+fn missing_template_items(
+    items: &[serde_json::Value],
+    sections: &[Section],
+    (layout_id, column_count): (&str, i32),
+    apps: &[(&TemplateApp, String)],
+    widgets: &[TemplateWidget],
+) -> Vec<serde_json::Value> {
+    let item = |id: String, kind: &str, options: serde_json::Value, position: &TilePosition| {
+        json!({
+            "id": id,
+            "kind": kind,
+            "options": options,
+            "layouts": [{
+                "layoutId": layout_id,
+                "sectionId": resolve_section_id(sections, position.section.as_deref()),
+                "width": position.width,
+                "height": position.height,
+                "xOffset": position.x,
+                "yOffset": position.y
+            }],
+            "integrationIds": [],
+            "advancedOptions": {
+                "customCssClasses": []
+            }
+        })
+    };
+
+    let mut missing = Vec::new();
+    for (app, app_id) in apps {
+        if app_item_placement(items, app_id).is_none() {
+            missing.push(item(
+                format!("template-app-{:x}", string_hash(&app.url)),
+                "app",
+                json!({ "appId": app_id }),
+                &app.position,
+            ));
+        }
     }
-
-    /// Login to Homarr and get session (deprecated - use API key instead)
-    #[allow(dead_code)]
-    async fn login(&self, branding: &BrandingConfig) -> Result<()> {
-        // Get CSRF token
-        let csrf_url = format!("{}/api/auth/csrf", self.base_url);
-        let csrf_response: CsrfResponse = self.get(&csrf_url).await?.json().await?;
-
-        // Login
-        let login_url = format!("{}/api/auth/callback/credentials", self.base_url);
-        let params = [
-            ("csrfToken", csrf_response.csrf_token.as_str()),
-            ("name", &branding.credentials.admin_username),
-            ("password", &branding.credentials.admin_password),
-        ];
-
-        let response = self.post_form(&login_url, &params).await?;
-
-        if !response.status().is_success() && response.status().as_u16() != 302 {
-            return Err(AdapterError::HomarrApi("Login failed".to_string()));
+    for widget in widgets {
+        let id = match &widget.id {
+            Some(id) => format!("template-widget-{}-{}", widget.kind, id),
+            None => {
+                let position = &widget.position;
+                let at = format!("{:?}/{}/{}", position.section, position.x, position.y);
+                format!("template-widget-{}-{:x}", widget.kind, string_hash(&at))
+            }
+        };
+        if !board_has_item(items, &id) {
+            missing.push(item(
+                id,
+                &widget.kind,
+                serde_json::Value::Object(widget.options.clone()),
+                &widget.position,
+            ));
         }
+    }
 
-        Ok(())
+    // Move items off tiles already there (or placed just before them)
+    let mut taken = items.to_vec();
+    for new in &mut missing {
+        if item_overlaps(&taken, new) {
+            let layout = &new["layouts"][0];
+            let grid = Grid {
+                layout_id,
+                section_id: layout["sectionId"].as_str().unwrap_or_default(),
+                column_count,
+            };
+            let (_, _, width, height) = layout_rect(layout);
+            let (x, y) = layout::first_free_slot(&taken, grid, (width, height));
+            tracing::warn!(
+                "Position of template item '{}' is taken, placing it at ({}, {})",
+                new["id"].as_str().unwrap_or_default(),
+                x,
+                y
+            );
+            new["layouts"][0]["xOffset"] = json!(x);
+            new["layouts"][0]["yOffset"] = json!(y);
+        }
+        taken.push(new.clone());
     }
+    missing
+}
 
-    /// Set up default board
-    ///
-    /// Requires API key to be set via `set_api_key()` before calling.
-    pub async fn setup_default_board(&self, branding: &BrandingConfig) -> Result<()> {
-        // Check if board already exists
-        let board = self.get_board_by_name(&branding.board.name).await;
+/// Check if a board has an item with the given ID
+fn board_has_item(items: &[serde_json::Value], item_id: &str) -> bool {
+    items
+        .iter()
+        .any(|item| item.get("id").and_then(|i| i.as_str()) == Some(item_id))
+}
 
-        let board_id = if let Ok(board) = board {
-            tracing::info!("Board '{}' already exists", branding.board.name);
-            board.id
-        } else {
-            // Create the board
-            tracing::info!("Creating board '{}'", branding.board.name);
-            self.create_board(branding).await?
-        };
+/// Place a tile directly right of `anchor`, or else directly below it,
+/// when that space is inside the grid and free.
+fn place_adjacent(
+    items: &[serde_json::Value],
+    grid: Grid,
+    anchor: &TilePlacement,
+    (width, height): (i32, i32),
+) -> Option<(i32, i32)> {
+    let occupied = layout::occupied(items, grid);
+
+    let is_free = |x: i32, y: i32| {
+        x + width <= grid.column_count
+            && occupied.iter().all(|&(ox, oy, ow, oh)| {
+                x >= ox + ow || ox >= x + width || y >= oy + oh || oy >= y + height
+            })
+    };
+
+    [
+        (anchor.x + anchor.width, anchor.y),
+        (anchor.x, anchor.y + anchor.height),
+    ]
+    .into_iter()
+    .find(|&(x, y)| is_free(x, y))
+}
 
-        // Apply board branding settings (page title, logo, colors, etc.)
-        self.save_board_branding_settings(&board_id, branding)
-            .await?;
+/// Find the integration backing an app: same host and port, else same name
+fn find_integration_for<'a>(
+    integrations: &'a [Integration],
+    app: &AppDefinition,
+) -> Option<&'a Integration> {
+    let host_port = |url: &str| {
+        url::Url::parse(url)
+            .ok()
+            .and_then(|u| Some((u.host_str()?.to_lowercase(), u.port_or_known_default())))
+    };
+    let app_host = host_port(&app.url);
+
+    integrations
+        .iter()
+        .find(|i| app_host.is_some() && host_port(&i.url) == app_host)
+        .or_else(|| {
+            integrations
+                .iter()
+                .find(|i| i.name.eq_ignore_ascii_case(&app.name))
+        })
+}
+
+/// Check if a board already has an item for a given app ID.
+/// Used to prevent duplicate board items when the same app is synced multiple times.
+pub(crate) fn board_has_app(items: &[serde_json::Value], app_id: &str) -> bool {
+    items.iter().any(|item| {
+        item.get("options")
+            .and_then(|o| o.get("appId"))
+            .and_then(|a| a.as_str())
+            == Some(app_id)
+    })
+}
 
-        // Set as home board (both user and server-wide)
-        self.set_user_home_board(&board_id).await?;
-        self.set_server_home_board(&board_id).await?;
+/// Adapter-owned apps that are copies of one app
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateApps {
+    pub name: String,
+    /// App ID that is kept
+    pub keep: String,
+    /// App IDs merged into `keep` and deleted
+    pub remove: Vec<String>,
+}
 
-        // Set color scheme
-        self.set_color_scheme(&branding.theme.default_color_scheme)
-            .await?;
+/// Result of `HomarrClient::dedupe`
+#[derive(Debug, Default)]
+pub struct DedupeReport {
+    pub apps: Vec<DuplicateApps>,
+    /// Tiles removed per board name
+    pub tiles_removed: Vec<(String, usize)>,
+}
 
-        Ok(())
+/// Group Homarr apps by the adapter-owned app they belong to
+///
+/// `owned` lists the URLs of the apps the adapter manages. An app belongs to
+/// one by normalized URL only: a user's app that merely shares a name with
+/// an adapter app is left out, like every other app.
+fn group_owned_apps<'a>(
+    apps: &'a [SelectableApp],
+    owned: &[String],
+) -> Vec<Vec<&'a SelectableApp>> {
+    let mut groups: Vec<Vec<&SelectableApp>> = vec![Vec::new(); owned.len()];
+    for app in apps {
+        let owner = app
+            .href
+            .as_deref()
+            .map(normalize_url)
+            .and_then(|href| owned.iter().position(|url| normalize_url(url) == href));
+        if let Some(index) = owner {
+            groups[index].push(app);
+        }
     }
+    groups.retain(|group| !group.is_empty());
+    groups
+}
 
-    /// Save board branding settings (page title, meta title, logo, favicon, colors)
-    async fn save_board_branding_settings(
-        &self,
-        board_id: &str,
-        branding: &BrandingConfig,
-    ) -> Result<()> {
-        let url = format!("{}/api/trpc/board.savePartialBoardSettings", self.base_url);
-
-        // Build the settings payload with only non-null values
-        let mut settings = serde_json::Map::new();
-        settings.insert("id".to_string(), json!(board_id));
+/// Top-left-most position of an item, as (y, x)
+fn item_position(item: &serde_json::Value) -> (i32, i32) {
+    let (x, y, _, _) = item
+        .get("layouts")
+        .and_then(|l| l.get(0))
+        .map(layout_rect)
+        .unwrap_or((i32::MAX, i32::MAX, 1, 1));
+    (y, x)
+}
 
-        // Add page title if configured
-        if let Some(ref page_title) = branding.identity.page_title {
-            settings.insert("pageTitle".to_string(), json!(page_title));
-        }
+fn item_app_id(item: &serde_json::Value) -> Option<&str> {
+    item.get("options")?.get("appId")?.as_str()
+}
 
-        // Add meta title if configured
-        if let Some(ref meta_title) = branding.identity.meta_title {
-            settings.insert("metaTitle".to_string(), json!(meta_title));
+/// Pick the app to keep in each group of copies
+///
+/// The kept app is the one with the best-positioned tile (highest, then
+/// leftmost, on any board); apps without tiles come last. Returns the
+/// duplicate sets and, for every grouped app, the ID its tiles should use.
+fn plan_duplicates(
+    groups: &[Vec<&SelectableApp>],
+    boards: &[Vec<serde_json::Value>],
+) -> (Vec<DuplicateApps>, HashMap<String, String>) {
+    let best_position = |app_id: &str| {
+        boards
+            .iter()
+            .flatten()
+            .filter(|item| item_app_id(item) == Some(app_id))
+            .map(item_position)
+            .min()
+            .unwrap_or((i32::MAX, i32::MAX))
+    };
+
+    let mut duplicates = Vec::new();
+    let mut keep_for = HashMap::new();
+    for group in groups {
+        let Some(keep) = group.iter().min_by_key(|app| best_position(&app.id)) else {
+            continue;
+        };
+        for app in group {
+            keep_for.insert(app.id.clone(), keep.id.clone());
         }
-
-        // Add logo URL if configured
-        if let Some(ref logo_url) = branding.identity.logo_image_url {
-            settings.insert("logoImageUrl".to_string(), json!(logo_url));
+        if group.len() > 1 {
+            duplicates.push(DuplicateApps {
+                name: keep.name.clone(),
+                keep: keep.id.clone(),
+                remove: group
+                    .iter()
+                    .filter(|app| app.id != keep.id)
+                    .map(|app| app.id.clone())
+                    .collect(),
+            });
         }
+    }
+    (duplicates, keep_for)
+}
 
-        // Add favicon URL if configured
-        if let Some(ref favicon_url) = branding.identity.favicon_image_url {
-            settings.insert("faviconImageUrl".to_string(), json!(favicon_url));
+/// Merge a board's tiles for grouped apps into one tile per app
+///
+/// Tiles of a duplicate app are pointed at the kept app. Of several tiles
+/// for one app the best-positioned stays; the others are dropped together
+/// with their widgets. Returns the number of tiles dropped.
+fn merge_duplicate_tiles(
+    items: &mut Vec<serde_json::Value>,
+    keep_for: &HashMap<String, String>,
+) -> usize {
+    for item in items.iter_mut() {
+        let Some(keep) = item_app_id(item).and_then(|id| keep_for.get(id)) else {
+            continue;
+        };
+        let keep = keep.clone();
+        if let Some(options) = item.get_mut("options") {
+            options["appId"] = json!(keep);
         }
+    }
 
-        // Add theme settings
-        settings.insert(
-            "primaryColor".to_string(),
-            json!(branding.theme.primary_color),
-        );
-        settings.insert(
-            "secondaryColor".to_string(),
-            json!(branding.theme.secondary_color),
-        );
-        settings.insert("opacity".to_string(), json!(branding.theme.opacity));
-        settings.insert("itemRadius".to_string(), json!(branding.theme.item_radius));
+    let mut best: HashMap<&str, (usize, (i32, i32))> = HashMap::new();
+    for (index, item) in items.iter().enumerate() {
+        let Some(app_id) = item_app_id(item).filter(|id| keep_for.contains_key(*id)) else {
+            continue;
+        };
+        let position = item_position(item);
+        best.entry(app_id)
+            .and_modify(|current| {
+                if position < current.1 {
+                    *current = (index, position);
+                }
+            })
+            .or_insert((index, position));
+    }
 
-        // Add background image URL if configured
-        if let Some(ref bg_url) = branding.theme.background_image_url {
-            settings.insert("backgroundImageUrl".to_string(), json!(bg_url));
-        }
+    let item_id = |item: &serde_json::Value| {
+        item.get("id")
+            .and_then(|i| i.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let dropped: Vec<String> = items
+        .iter()
+        .enumerate()
+        .filter(|(index, item)| {
+            item_app_id(item)
+                .and_then(|id| best.get(id))
+                .is_some_and(|(keep, _)| keep != index)
+        })
+        .map(|(_, item)| item_id(item))
+        .collect();
+    if dropped.is_empty() {
+        return 0;
+    }
 
-        // Add custom CSS if configured
-        if let Some(ref custom_css) = branding.theme.custom_css {
-            settings.insert("customCss".to_string(), json!(custom_css));
-        }
+    items.retain(|item| {
+        let id = item_id(item);
+        !dropped
+            .iter()
+            .any(|tile| *tile == id || format!("{}-widget", tile) == id)
+    });
+    dropped.len()
+}
 
-        let payload = json!({ "json": settings });
+/// Name of Homarr's built-in group every user belongs to
+const EVERYONE_GROUP: &str = "everyone";
 
-        tracing::info!("Applying board branding settings");
-        let response = self.post_json(&url, &payload).await?;
+/// Group from group.selectable
+#[derive(Debug, Deserialize)]
+struct Group {
+    id: String,
+    name: String,
+}
 
-        if !response.status().is_success() {
-            let text = response.text().await?;
-            tracing::warn!("Failed to save board branding settings: {}", text);
-            // Don't fail the whole setup if branding settings fail
-        }
+/// A group's permission on a board, as board.getBoardPermissions returns it
+#[derive(Debug, Deserialize)]
+struct GroupBoardPermission {
+    group: Group,
+    permission: String,
+}
 
-        Ok(())
+/// Group permissions to save for a board, with one group's permission set
+/// (or removed for `None`) and the others kept
+fn with_group_permission(
+    existing: &[GroupBoardPermission],
+    group_id: &str,
+    permission: EveryonePermission,
+) -> Vec<serde_json::Value> {
+    let mut permissions: Vec<serde_json::Value> = existing
+        .iter()
+        .filter(|p| p.group.id != group_id)
+        .map(|p| json!({ "principalId": p.group.id, "permission": p.permission }))
+        .collect();
+    let permission = match permission {
+        EveryonePermission::None => None,
+        EveryonePermission::View => Some("view"),
+        EveryonePermission::Modify => Some("modify"),
+    };
+    if let Some(permission) = permission {
+        permissions.push(json!({ "principalId": group_id, "permission": permission }));
     }
+    permissions
+}
 
-    /// Get board by name
-    async fn get_board_by_name(&self, name: &str) -> Result<BoardResponse> {
-        let url = format!(
-            "{}/api/trpc/board.getBoardByName?input={}",
-            self.base_url,
-            urlencoding::encode(&format!("{{\"json\":{{\"name\":\"{}\"}}}}", name))
-        );
-
-        let response = self.get(&url).await?;
+/// Outcome of probing one Homarr endpoint the adapter depends on
+#[derive(Debug)]
+pub struct EndpointCheck {
+    pub endpoint: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
 
-        if !response.status().is_success() {
-            return Err(AdapterError::HomarrApi("Board not found".to_string()));
+/// Mutations probed by `api-check` with deliberately invalid input.
+///
+/// A BAD_REQUEST proves the procedure exists without changing anything.
+/// Mutations without an input schema (`onboard.nextStep`, `apiKeys.create`)
+/// would actually run, and onboarding-only ones reject calls once onboarding
+/// is done, so those are left out. `media.uploadMedia` expects form data,
+/// so the JSON probe is rejected the same way.
+const PROBED_MUTATIONS: &[&str] = &[
+    "app.create",
+    "app.update",
+    "app.delete",
+    "apiKeys.delete",
+    "board.changeBoardVisibility",
+    "board.createBoard",
+    "board.saveBoard",
+    "board.saveGroupBoardPermissions",
+    "board.saveLayouts",
+    "board.savePartialBoardSettings",
+    "board.setHomeBoard",
+    "media.uploadMedia",
+    "searchEngine.create",
+    "searchEngine.update",
+    "serverSettings.saveSettings",
+    "user.changeColorScheme",
+];
+
+/// Interpret the status of a mutation called with invalid input
+fn classify_mutation_probe(status: reqwest::StatusCode) -> (bool, String) {
+    match status.as_u16() {
+        400 => (true, "available (probe input rejected)".to_string()),
+        401 | 403 => (false, format!("not authorized ({})", status)),
+        404 => (false, "procedure not found".to_string()),
+        code if status.is_success() => {
+            (true, format!("available (accepted probe input, {})", code))
         }
-
-        let trpc_response: TrpcResponse<BoardResponse> = response.json().await?;
-        Ok(trpc_response.result.data.json)
+        _ => (false, format!("unexpected status {}", status)),
     }
+}
 
-    /// Create a new board
-    async fn create_board(&self, branding: &BrandingConfig) -> Result<String> {
-        let url = format!("{}/api/trpc/board.createBoard", self.base_url);
-        let payload = json!({
-            "json": {
-                "name": branding.board.name,
-                "columnCount": branding.board.column_count,
-                "isPublic": branding.board.is_public
+/// Keep only each item's layout entry for the given board layout.
+fn items_in_layout(items: &[serde_json::Value], layout_id: &str) -> Vec<serde_json::Value> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let layouts: Vec<serde_json::Value> = item
+                .get("layouts")
+                .and_then(|l| l.as_array())?
+                .iter()
+                .filter(|layout| layout.get("layoutId").and_then(|l| l.as_str()) == Some(layout_id))
+                .cloned()
+                .collect();
+            if layouts.is_empty() {
+                return None;
             }
-        });
+            let mut item = item.clone();
+            item["layouts"] = serde_json::Value::Array(layouts);
+            Some(item)
+        })
+        .collect()
+}
 
-        let response = self.post_json(&url, &payload).await?;
-        let trpc_response: TrpcResponse<CreateBoardResponse> = response.json().await?;
+/// A tile drawn on a board preview grid
+#[derive(Debug, Clone)]
+pub struct PreviewTile {
+    pub label: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    /// Tile will be added by the next sync
+    pub planned: bool,
+}
 
-        Ok(trpc_response.result.data.json.board_id)
+/// Keys used to mark tiles on the preview grid, in tile order
+const PREVIEW_KEYS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Render tiles as an ASCII grid followed by a legend.
+///
+/// Each cell shows the key of the tile covering it, `.` when empty, or `!`
+/// when tiles overlap. Tiles beyond the column count are clipped.
+pub fn render_preview_grid(column_count: i32, tiles: &[PreviewTile]) -> String {
+    let columns = column_count.max(1) as usize;
+    let rows = tiles
+        .iter()
+        .map(|t| (t.y + t.height).max(0) as usize)
+        .max()
+        .unwrap_or(0);
+    let mut grid = vec![vec!['.'; columns]; rows];
+    let mut legend = String::new();
+    let mut overlap = false;
+
+    for (i, tile) in tiles.iter().enumerate() {
+        let key = PREVIEW_KEYS.get(i).map(|&b| b as char).unwrap_or('#');
+        for y in tile.y.max(0)..(tile.y + tile.height) {
+            for x in tile.x.max(0)..(tile.x + tile.width) {
+                if let Some(cell) = grid
+                    .get_mut(y as usize)
+                    .and_then(|row| row.get_mut(x as usize))
+                {
+                    if *cell == '.' {
+                        *cell = key;
+                    } else {
+                        *cell = '!';
+                        overlap = true;
+                    }
+                }
+            }
+        }
+        legend.push_str(&format!(
+            "  {} {} ({}x{} at {},{}){}\n",
+            key,
+            tile.label,
+            tile.width,
+            tile.height,
+            tile.x,
+            tile.y,
+            if tile.planned { " [new]" } else { "" }
+        ));
+    }
+    if overlap {
+        legend.push_str("  ! overlapping tiles\n");
     }
 
-    /// Set user's home board
-    ///
-    /// This sets the home board for the current user (stored in users table).
-    async fn set_user_home_board(&self, board_id: &str) -> Result<()> {
-        let url = format!("{}/api/trpc/board.setHomeBoard", self.base_url);
-        let payload = json!({"json": {"id": board_id}});
-        self.post_json(&url, &payload).await?;
-        Ok(())
+    let border = format!("+{}+\n", "-".repeat(columns * 2));
+    let mut out = border.clone();
+    for row in &grid {
+        out.push('|');
+        for cell in row {
+            out.push(' ');
+            out.push(*cell);
+        }
+        out.push_str("|\n");
     }
+    out.push_str(&border);
+    out.push_str(&legend);
+    out
+}
 
-    /// Set server-wide home board
-    ///
-    /// This sets the default home board for the server (stored in serverSettings).
-    /// This is the board shown to users who haven't set their own home board.
-    async fn set_server_home_board(&self, board_id: &str) -> Result<()> {
-        let url = format!("{}/api/trpc/serverSettings.saveSettings", self.base_url);
-        let payload = json!({
-            "json": {
-                "settingsKey": "board",
-                "value": {
-                    "homeBoardId": board_id,
-                    "mobileHomeBoardId": board_id,
-                    "enableStatusByDefault": true,
-                    "forceDisableStatus": false
-                }
-            }
-        });
+/// Local path of a `file://` icon reference
+pub fn file_icon_path(icon: &str) -> Option<&Path> {
+    icon.strip_prefix("file://")
+        .filter(|p| p.starts_with('/'))
+        .map(Path::new)
+}
 
-        let response = self.post_json(&url, &payload).await?;
+/// Icon URL for an app as configured, before any refresh overrides
+fn configured_icon_url(app: &AppDefinition) -> String {
+    transform_icon_url(app.icon_url.as_deref().unwrap_or(DEFAULT_ICON))
+}
 
-        if !response.status().is_success() {
-            let text = response.text().await.unwrap_or_default();
-            tracing::warn!("Failed to set server home board: {}", text);
-            // Don't fail the whole setup if this fails
-        } else {
-            tracing::info!("Server home board set to '{}'", board_id);
-        }
+/// Transform icon paths to relative URLs for Homarr.
+///
+/// Icons are served by Homarr's nginx from /icons/ which maps to /usr/share/pixmaps.
+/// This function transforms:
+/// - `/usr/share/pixmaps/app.png` → `/icons/app.png`
+/// - HTTP/HTTPS URLs → unchanged
+/// - `/icons/*` paths → unchanged
+/// - Everything else → `/icons/docker.svg` (fallback)
+fn transform_icon_url(icon_path: &str) -> String {
+    const PIXMAPS_PREFIX: &str = "/usr/share/pixmaps/";
 
-        Ok(())
+    if icon_path.is_empty() {
+        return DEFAULT_ICON.to_string();
     }
 
-    /// Set color scheme
-    async fn set_color_scheme(&self, scheme: &str) -> Result<()> {
-        let url = format!("{}/api/trpc/user.changeColorScheme", self.base_url);
-        let payload = json!({"json": {"colorScheme": scheme}});
-        self.post_json(&url, &payload).await?;
-        Ok(())
+    // HTTP/HTTPS URLs pass through unchanged
+    if icon_path.starts_with("http://") || icon_path.starts_with("https://") {
+        return icon_path.to_string();
     }
 
-    /// Check if the client is authenticated (has API key set)
+    // Already relative /icons/ paths - pass through unchanged
+    if icon_path.starts_with("/icons/") {
+        return icon_path.to_string();
+    }
+
+    // Transform /usr/share/pixmaps/ paths to /icons/
+    if let Some(filename) = icon_path.strip_prefix(PIXMAPS_PREFIX) {
+        if filename.is_empty() {
+            return DEFAULT_ICON.to_string();
+        }
+        return format!("/icons/{}", filename);
+    }
+
+    // Unknown format - use fallback
+    DEFAULT_ICON.to_string()
+}
+
+impl HomarrClient {
+    /// Create a new Homarr client
+    ///
+    /// # Arguments
+    /// * `base_url` - The Homarr API base URL (e.g., "http://localhost:80")
     #[allow(dead_code)]
-    pub fn is_authenticated(&self) -> bool {
-        self.api_key.is_some()
+    pub fn new(base_url: &str) -> Result<Self> {
+        Self::with_pool_options(base_url, &PoolOptions::default())
     }
 
-    /// Create a new API key
+    /// Create a new Homarr client with connection pool tuning
     ///
-    /// Requires authentication (API key must be set).
-    /// Returns the new API key in format "{id}.{token}".
-    pub async fn create_api_key(&self) -> Result<String> {
-        let url = format!("{}/api/trpc/apiKeys.create", self.base_url);
-        let payload = json!({"json": {}});
+    /// Long-lived clients (watch mode) keep idle connections open between sync
+    /// cycles, avoiding a fresh TCP/TLS handshake against remote instances.
+    pub fn with_pool_options(base_url: &str, pool: &PoolOptions) -> Result<Self> {
+        let jar = Arc::new(Jar::default());
+        let client = Client::builder()
+            .cookie_store(true)
+            .cookie_provider(jar)
+            .pool_idle_timeout(pool.idle_timeout)
+            .pool_max_idle_per_host(pool.max_idle_per_host)
+            .tcp_keepalive(pool.tcp_keepalive)
+            // Accept self-signed certificates (required for local SSL configurations)
+            .danger_accept_invalid_certs(true)
+            .build()?;
 
-        let response = self.post_json(&url, &payload).await?;
+        Ok(Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: None,
+            default_section: None,
+            compose_sections: false,
+            category_sections: false,
+            section_ids: Mutex::new(HashMap::new()),
+            section_limits: SectionLimits::default(),
+            icon_overrides: HashMap::new(),
+            uploaded_icons: HashMap::new(),
+            board_writes: Mutex::new(BoardWrites::default()),
+            policies: RequestPolicies::default(),
+            refuse_mutations: None,
+            session: tokio::sync::Mutex::new(None),
+            boards: Mutex::new(HashMap::new()),
+            rate_limited: AtomicU32::new(0),
+            integrations: Mutex::new(None),
+        })
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            return Err(AdapterError::HomarrApi(format!(
-                "Failed to create API key ({}): {}",
-                status, text
-            )));
-        }
+    /// Forget what was listed in the previous sync cycle, so the next one
+    /// lists it again
+    pub fn start_cycle(&self) {
+        *self.integrations.lock().unwrap() = None;
+    }
 
-        let trpc_response: TrpcResponse<CreateApiKeyResponse> = response.json().await?;
-        Ok(trpc_response.result.data.json.api_key)
+    /// Number of rate-limited (429) responses since the last call
+    pub fn take_rate_limited(&self) -> u32 {
+        self.rate_limited.swap(0, Ordering::Relaxed)
     }
 
-    /// Delete an API key by ID
+    /// Set timeouts and retries for reads and mutations
+    pub fn set_request_policies(&mut self, policies: RequestPolicies) {
+        self.policies = policies;
+    }
+
+    /// Set the API key for authentication
     ///
-    /// Requires authentication (API key must be set).
-    pub async fn delete_api_key(&self, api_key_id: &str) -> Result<()> {
-        let url = format!("{}/api/trpc/apiKeys.delete", self.base_url);
-        let payload = json!({"json": {"apiKeyId": api_key_id}});
+    /// When set, all requests will include the `ApiKey: <api_key>` header.
+    pub fn set_api_key(&mut self, api_key: String) {
+        self.api_key = Some(api_key);
+    }
 
-        let response = self.post_json(&url, &payload).await?;
+    /// Set the section used for tiles that don't name one
+    pub fn set_default_section(&mut self, section: Option<String>) {
+        self.default_section = section;
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            return Err(AdapterError::HomarrApi(format!(
-                "Failed to delete API key '{}' ({}): {}",
-                api_key_id, status, text
-            )));
-        }
+    /// Group container tiles without a section into one category section per
+    /// compose project
+    pub fn set_compose_sections(&mut self, enabled: bool) {
+        self.compose_sections = enabled;
+    }
 
-        Ok(())
+    /// Place tiles without a section in a category section named after
+    /// their `category`
+    pub fn set_category_sections(&mut self, enabled: bool) {
+        self.category_sections = enabled;
     }
 
-    /// Rotate from a bootstrap API key to a new permanent key
-    ///
-    /// This method:
-    /// 1. Authenticates with the bootstrap key
-    /// 2. Creates a new random API key
-    /// 3. Deletes the bootstrap key
-    /// 4. Returns the new API key
-    ///
-    /// If the rotation fails partway through, the bootstrap key may still be valid
-    /// and the operation can be retried.
-    pub async fn rotate_api_key(&mut self, bootstrap_key: &str) -> Result<String> {
-        // Extract the bootstrap key ID (format: "{id}.{token}")
-        let bootstrap_id = bootstrap_key
-            .split('.')
-            .next()
-            .ok_or_else(|| AdapterError::HomarrApi("Invalid bootstrap key format".to_string()))?;
+    /// Limit the rows of a section, and choose what happens to new tiles
+    /// when a section is full
+    pub fn set_section_limits(&mut self, limits: SectionLimits) {
+        self.section_limits = limits;
+    }
 
-        tracing::info!("Rotating bootstrap API key to permanent key...");
+    /// Set the IDs of sections created on demand (from state)
+    pub fn set_section_ids(&mut self, ids: HashMap<String, HashMap<String, String>>) {
+        self.section_ids = Mutex::new(ids);
+    }
 
-        // Authenticate with bootstrap key
-        self.set_api_key(bootstrap_key.to_string());
+    /// IDs of sections created on demand, including ones created this sync
+    pub fn section_ids(&self) -> HashMap<String, HashMap<String, String>> {
+        self.section_ids.lock().unwrap().clone()
+    }
 
-        // Create new API key
-        let new_key = self.create_api_key().await?;
-        tracing::info!("Created new permanent API key");
+    /// Section an app's tile should land in, by name
+    ///
+    /// An explicit section wins, then the compose project (if grouping by
+    /// project), then the category (if grouping by category), then the
+    /// branding default.
+    fn wanted_section<'a>(&'a self, app: &'a AppDefinition) -> Option<&'a str> {
+        app.effective_layout()
+            .section
+            .as_deref()
+            .or_else(|| self.created_section(app))
+            .or(self.default_section.as_deref())
+    }
 
-        // Switch to new key for further operations
-        self.set_api_key(new_key.clone());
+    /// Section created on demand for an app without an explicit section:
+    /// its compose project (if grouping by project), else its category
+    fn created_section<'a>(&self, app: &'a AppDefinition) -> Option<&'a str> {
+        self.compose_section(app).or_else(|| {
+            app.category
+                .as_deref()
+                .filter(|category| self.category_sections && !category.is_empty())
+                .filter(|_| app.effective_layout().section.is_none())
+        })
+    }
 
-        // Delete the bootstrap key
-        match self.delete_api_key(bootstrap_id).await {
-            Ok(()) => {
-                tracing::info!("Deleted bootstrap API key");
+    /// ID of the section created on demand for `name` on a board, if it is
+    /// still there (it may have been renamed in Homarr since)
+    fn known_section_id(&self, board_id: &str, sections: &[Section], name: &str) -> Option<String> {
+        let ids = self.section_ids.lock().unwrap();
+        ids.get(board_id)?
+            .get(&name.to_lowercase())
+            .filter(|id| sections.iter().any(|s| &s.id == *id))
+            .cloned()
+    }
+
+    /// Add the compose project and category sections `apps` need and a
+    /// board lacks to its sections, returning the names added
+    ///
+    /// They are added together in name order rather than as each tile is
+    /// placed, so boards get the same section order whatever order their
+    /// apps sync in.
+    fn add_created_sections(
+        &self,
+        board_id: &str,
+        sections: &mut Vec<Section>,
+        apps: &[&AppDefinition],
+    ) -> Vec<String> {
+        let mut names: Vec<&str> = apps
+            .iter()
+            .filter_map(|app| self.created_section(app))
+            .collect();
+        // Case-insensitive, with a fixed pick among spellings of one name
+        names.sort_by_key(|name| (name.to_lowercase(), *name));
+        names.dedup_by_key(|name| name.to_lowercase());
+
+        let mut added = Vec::new();
+        for name in names {
+            if self.known_section_id(board_id, sections, name).is_some() {
+                continue;
             }
-            Err(e) => {
-                // Log but don't fail - the new key is already working
-                tracing::warn!("Failed to delete bootstrap key (non-fatal): {}", e);
+            let created = on_demand_section(board_id, sections, name);
+            if !created.is_empty() {
+                sections.extend(created);
+                added.push(name.to_string());
             }
         }
+        added
+    }
 
-        Ok(new_key)
+    /// Apps without a tile among a board's `items`
+    fn apps_missing_from<'a>(
+        items: &[serde_json::Value],
+        apps: &[&'a AppDefinition],
+        existing_apps: &[SelectableApp],
+    ) -> Vec<&'a AppDefinition> {
+        apps.iter()
+            .filter(|app| {
+                Self::find_app_by_url(existing_apps, &app.url)
+                    .or_else(|| Self::find_app_by_name(existing_apps, &app.name))
+                    .is_none_or(|existing| !board_has_app(items, &existing.id))
+            })
+            .copied()
+            .collect()
     }
 
-    /// Get all apps (minimal data for matching)
+    /// Set the column count of a board's base layout, if it differs
     ///
-    /// Returns all apps from Homarr for deduplication checks.
-    /// Callers can cache this result to avoid repeated API calls.
-    pub async fn get_all_apps(&self) -> Result<Vec<SelectableApp>> {
-        let url = format!("{}/api/trpc/app.selectable", self.base_url);
-        let response = self.get(&url).await?;
+    /// Tiles of the apps in `managed_app_ids` that no longer fit a narrower
+    /// grid are moved back in, staged with the board's other edits; other
+    /// items are left where the user put them. Returns whether the layout
+    /// changed.
+    pub async fn reconcile_column_count(
+        &self,
+        board_name: &str,
+        column_count: i32,
+        managed_app_ids: &HashSet<String>,
+    ) -> Result<bool> {
+        let staged = self.staged_board(board_name);
+        let was_staged = staged.is_some();
+        let mut board = match staged {
+            Some(staged) => staged,
+            None => self.read_board(board_name).await?,
+        };
+        let Some(layout) = board.board.layouts.first_mut() else {
+            return Ok(false);
+        };
+        if layout.column_count == column_count {
+            return Ok(false);
+        }
+        tracing::info!(
+            "Changing board '{}' from {} to {} columns",
+            board_name,
+            layout.column_count,
+            column_count
+        );
+        layout.column_count = column_count;
+        let layout_id = layout.id.clone();
 
+        let url = format!("{}/api/trpc/board.saveLayouts", self.base_url);
+        let payload = SuperJson::new(json!({
+            "id": board.board.id,
+            "layouts": board.board.layouts,
+        }));
+        let response = self.post_json(&url, &payload).await?;
         if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
             return Err(AdapterError::HomarrApi(format!(
-                "Failed to fetch apps ({}): {}",
-                status, text
+                "Failed to save layouts of board '{}': {}",
+                board_name,
+                response.text().await.unwrap_or_default()
             )));
         }
 
-        let trpc_response: TrpcResponse<Vec<SelectableApp>> = response.json().await?;
-        Ok(trpc_response.result.data.json)
+        let managed = |item: &serde_json::Value| {
+            item.get("options")
+                .and_then(|o| o.get("appId"))
+                .and_then(|a| a.as_str())
+                .is_some_and(|id| managed_app_ids.contains(id))
+        };
+        let moved = layout::repack(&mut board.items, &layout_id, column_count, managed);
+        if moved > 0 {
+            tracing::info!(
+                "Moved {} tile(s) on board '{}' into the grid",
+                moved,
+                board_name
+            );
+        }
+        // Boards read from Homarr later in the sync have the new layout already
+        if moved > 0 || was_staged {
+            self.save_items(board_name, board);
+        }
+        Ok(true)
     }
 
-    /// Get all boards with permission info
+    /// Create the compose project and category sections that the tiles of
+    /// `apps` still to be placed on a board need, in name order, staged with
+    /// the board's other edits
+    pub async fn ensure_created_sections(
+        &self,
+        board_name: &str,
+        apps: &[&AppDefinition],
+        existing_apps: &[SelectableApp],
+    ) -> Result<()> {
+        let mut board = self.get_board(board_name).await?;
+        let missing = Self::apps_missing_from(&board.items, apps, existing_apps);
+        let added = self.add_created_sections(&board.board.id, &mut board.board.sections, &missing);
+        if added.is_empty() {
+            return Ok(());
+        }
+
+        {
+            let mut ids = self.section_ids.lock().unwrap();
+            let board_ids = ids.entry(board.board.id.clone()).or_default();
+            for name in &added {
+                let id = resolve_section_id(&board.board.sections, Some(name));
+                board_ids.insert(name.to_lowercase(), id);
+            }
+        }
+        tracing::info!(
+            "Creating section(s) {} on board '{}'",
+            added.join(", "),
+            board_name
+        );
+        self.save_items(board_name, board);
+        Ok(())
+    }
+
+    /// Section and position for a new tile on `grid`
     ///
-    /// Returns all boards accessible to the authenticated user, with their
-    /// permission levels. Used for multi-board sync to discover which boards
-    /// the adapter can sync apps to.
-    pub async fn get_all_boards(&self) -> Result<Vec<BoardWithPermission>> {
-        let url = format!("{}/api/trpc/board.getAllBoards", self.base_url);
-        let response = self.get(&url).await?;
+    /// A tile that doesn't fit a full section goes below the row limit
+    /// (`grow`) or into the overflow section, which is added to `sections`
+    /// when missing and itself grows as needed (`overflow`).
+    fn place_new_tile(
+        &self,
+        board_id: &str,
+        sections: &mut Vec<Section>,
+        items: &[serde_json::Value],
+        grid: Grid,
+        size: (i32, i32),
+    ) -> (String, (i32, i32)) {
+        let limits = &self.section_limits;
+        let section_id = grid.section_id;
+        if let Some(slot) = layout::free_slot_within(items, grid, size, limits.max_rows) {
+            return (section_id.to_string(), slot);
+        }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let text = response.text().await.unwrap_or_default();
-            return Err(AdapterError::HomarrApi(format!(
-                "Failed to fetch boards ({}): {}",
-                status, text
-            )));
+        let overflow = limits.overflow_section.as_str();
+        let in_overflow = sections
+            .iter()
+            .any(|s| s.id == section_id && s.is_category_named(overflow));
+        if limits.when_full == BoardFull::Grow || in_overflow || overflow.is_empty() {
+            tracing::debug!("Section {} is full, adding rows", section_id);
+            let slot = layout::first_free_slot(items, grid, size);
+            return (section_id.to_string(), slot);
         }
 
-        let trpc_response: TrpcResponse<Vec<BoardWithPermission>> = response.json().await?;
-        Ok(trpc_response.result.data.json)
+        let overflow_id = match self.known_section_id(board_id, sections, overflow) {
+            Some(id) => id,
+            None => {
+                let created = on_demand_section(board_id, sections, overflow);
+                sections.extend(created);
+                resolve_section_id(sections, Some(overflow))
+            }
+        };
+        tracing::debug!(
+            "Section {} is full, placing tile in '{}'",
+            section_id,
+            overflow
+        );
+        let grid = Grid {
+            section_id: &overflow_id,
+            ..grid
+        };
+        let slot = layout::first_free_slot(items, grid, size);
+        (overflow_id, slot)
     }
 
-    /// Get all writable boards (modify or full permission)
-    ///
-    /// Convenience method that filters `get_all_boards()` to only return
-    /// boards where the adapter can add/remove apps.
-    pub async fn get_writable_boards(&self) -> Result<Vec<BoardWithPermission>> {
-        let boards = self.get_all_boards().await?;
-        Ok(boards.into_iter().filter(|b| b.is_writable()).collect())
+    /// Compose project section for an app without an explicit section
+    fn compose_section<'a>(&self, app: &'a AppDefinition) -> Option<&'a str> {
+        if !self.compose_sections || app.effective_layout().section.is_some() {
+            return None;
+        }
+        app.compose_project()
     }
 
-    /// Find an existing app by URL in a pre-fetched list.
-    ///
-    /// Uses URL normalization to handle minor differences like trailing slashes.
-    /// Returns the matching app if found.
-    fn find_app_by_url<'a>(apps: &'a [SelectableApp], url: &str) -> Option<&'a SelectableApp> {
-        let normalized_url = normalize_url(url);
-        apps.iter().find(|app| {
-            app.href
-                .as_ref()
-                .map(|href| normalize_url(href) == normalized_url)
-                .unwrap_or(false)
-        })
+    /// Set replacement icons (from the last icon refresh)
+    pub fn set_icon_overrides(&mut self, overrides: HashMap<String, String>) {
+        self.icon_overrides = overrides;
     }
 
-    /// Find an existing app by name in a pre-fetched list.
-    ///
-    /// Used as fallback when URL matching fails (e.g., URL changed in package update).
-    /// Case-insensitive comparison.
-    fn find_app_by_name<'a>(apps: &'a [SelectableApp], name: &str) -> Option<&'a SelectableApp> {
-        let name_lower = name.to_lowercase();
-        apps.iter()
-            .find(|app| app.name.to_lowercase() == name_lower)
+    /// Set media URLs of uploaded `file://` icons
+    pub fn set_uploaded_icons(&mut self, uploaded: HashMap<String, String>) {
+        self.uploaded_icons = uploaded;
     }
 
-    /// Add a registry app to Homarr (or update if already exists)
-    ///
-    /// Registry apps can have explicit layout positioning and may not be Docker containers.
-    ///
-    /// Deduplication strategy:
-    /// 1. First, try to find an existing app by normalized URL
-    /// 2. If not found, fall back to matching by app name (handles URL changes in package updates)
-    /// 3. If found, update the existing app and ensure it's on the board
-    /// 4. If not found at all, create a new app
-    pub async fn add_registry_app(
-        &self,
-        app: &AppDefinition,
-        board_name: &str,
-        existing_apps: Option<&[SelectableApp]>,
-    ) -> Result<String> {
-        // Get or fetch the list of existing apps for deduplication
-        let apps_for_search: Vec<SelectableApp>;
-        let apps_ref = match existing_apps {
-            Some(apps) => apps,
-            None => match self.get_all_apps().await {
-                Ok(apps) => {
-                    apps_for_search = apps;
-                    &apps_for_search
-                }
+    /// Set the minimum time between saves of the same board
+    pub fn set_board_write_interval(&mut self, interval: Duration) {
+        self.board_writes.get_mut().unwrap().min_interval = interval;
+    }
+
+    /// Time until staged board edits can be saved, if there are any
+    pub fn next_board_flush(&self) -> Option<Duration> {
+        self.board_writes.lock().unwrap().next_due(Instant::now())
+    }
+
+    /// Board edits staged so far, to go back to with `restore_board_saves`
+    pub fn staged_boards(&self) -> HashMap<String, Board> {
+        self.board_writes.lock().unwrap().pending.clone()
+    }
+
+    /// Drop the board edits staged since `snapshot` was taken, keeping the
+    /// ones staged before it that are still waiting to be saved
+    pub fn restore_board_saves(&self, snapshot: HashMap<String, Board>) {
+        self.board_writes.lock().unwrap().restore(snapshot);
+    }
+
+    /// Save staged board edits, skipping boards saved too recently
+    ///
+    /// Skipped boards stay staged and are merged into their next save, as
+    /// are boards whose save failed. Edits to a board that was changed in
+    /// Homarr since they were planned are dropped rather than overwrite the
+    /// change; the result is then `AdapterError::BoardChanged`, so the
+    /// caller can plan again.
+    pub async fn flush_board_saves(&self) -> Result<()> {
+        let due = self.board_writes.lock().unwrap().take_due(Instant::now());
+
+        let mut result = Ok(());
+        let mut changed = None;
+        for (board_name, pending) in due {
+            match self.write_board(&board_name, pending).await {
+                Ok(()) => {}
+                Err(AdapterError::BoardChanged(name)) => changed = Some(name),
                 Err(e) => {
-                    tracing::warn!(
-                        "Failed to fetch existing apps for deduplication: {}. \
-                             Proceeding with create.",
-                        e
-                    );
-                    // Can't deduplicate without existing apps, will create new
-                    apps_for_search = vec![];
-                    &apps_for_search
+                    if result.is_ok() {
+                        result = Err(e);
+                    }
                 }
-            },
-        };
+            }
+        }
+        match changed {
+            Some(board_name) => Err(AdapterError::BoardChanged(board_name)),
+            None => result,
+        }
+    }
 
-        // Try to find existing app: first by URL, then by name as fallback
-        let existing = Self::find_app_by_url(apps_ref, &app.url)
-            .or_else(|| {
-                // URL didn't match - try by name (handles URL changes in package updates)
-                let found_by_name = Self::find_app_by_name(apps_ref, &app.name);
-                if found_by_name.is_some() {
-                    tracing::info!(
-                        "App '{}' not found by URL, but found by name - will update existing app",
-                        app.name
-                    );
-                }
-                found_by_name
-            })
-            .cloned();
+    /// Save a board right away, for boards the caller needs in Homarr
+    /// before it goes on (new boards and sections)
+    ///
+    /// `board` replaces the board's staged edits, so it should build on
+    /// them (`get_board`).
+    async fn save_board_now(&self, board_name: &str, board: Board) -> Result<()> {
+        self.board_writes.lock().unwrap().pending.remove(board_name);
+        self.write_board(board_name, board).await
+    }
 
-        if let Some(existing_app) = existing {
-            // App already exists - update it (including URL) and ensure it's on the board
-            self.update_registry_app(&existing_app.id, app).await?;
-            self.add_registry_app_to_board(&existing_app.id, app, board_name)
-                .await?;
-            return Ok(existing_app.id);
+    /// Write one staged board, unless it was changed in Homarr since
+    ///
+    /// A board that can't be read or saved is staged again for the next
+    /// flush.
+    async fn write_board(&self, board_name: &str, pending: Board) -> Result<()> {
+        // Staged, but nothing changed since the board was read
+        if board_version(&pending.board.sections, &pending.items) == pending.version {
+            tracing::debug!("Board '{}' unchanged, not saving it", board_name);
+            return Ok(());
         }
 
-        // Create new app in Homarr
-        let url = format!("{}/api/trpc/app.create", self.base_url);
-        let icon_url = transform_icon_url(app.icon_url.as_deref().unwrap_or(DEFAULT_ICON));
+        match self.read_board(board_name).await {
+            Ok(current) if current.version != pending.version => {
+                tracing::warn!(
+                    "Board '{}' was edited in Homarr since the sync planned its changes, \
+                     not saving them",
+                    board_name
+                );
+                return Err(AdapterError::BoardChanged(board_name.to_string()));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                self.board_writes
+                    .lock()
+                    .unwrap()
+                    .restage(board_name.to_string(), pending);
+                return Err(e);
+            }
+        }
 
-        // Use explicit ping_url if provided, otherwise derive from URL
-        // For external apps, don't set a ping URL (no health checks)
-        let ping_url = if app.is_external() {
-            None
-        } else {
-            app.ping_url.clone().or_else(|| derive_ping_url(&app.url))
+        let url = format!("{}/api/trpc/board.saveBoard", self.base_url);
+        let payload = SuperJson::new(json!({
+            "id": pending.board.id,
+            "sections": pending.board.sections,
+            "items": pending.items,
+            "integrations": []
+        }));
+        let saved = match self.post_json(&url, &payload).await {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response) => Err(AdapterError::HomarrApi(format!(
+                "Failed to save board '{}': {}",
+                board_name,
+                response.text().await.unwrap_or_default()
+            ))),
+            Err(e) => Err(e),
         };
 
-        let payload = json!({
-            "json": {
-                "name": app.name,
-                "description": app.description.clone().unwrap_or_default(),
-                "iconUrl": icon_url,
-                "href": app.url,
-                "pingUrl": ping_url
+        let mut writes = self.board_writes.lock().unwrap();
+        match saved {
+            Ok(()) => {
+                tracing::debug!("Saved board '{}'", board_name);
+                writes
+                    .last_write
+                    .insert(board_name.to_string(), Instant::now());
+                Ok(())
             }
-        });
+            Err(e) => {
+                writes.restage(board_name.to_string(), pending);
+                Err(e)
+            }
+        }
+    }
 
-        let response = self.post_json(&url, &payload).await?;
+    /// Icon URL to send to Homarr for an app
+    pub fn icon_for(&self, app: &AppDefinition) -> String {
+        if let Some(icon) = self.icon_overrides.get(&app.url) {
+            return icon.clone();
+        }
+        match app
+            .icon_url
+            .as_deref()
+            .and_then(|i| self.uploaded_icons.get(i))
+        {
+            Some(media_url) => media_url.clone(),
+            None => configured_icon_url(app),
+        }
+    }
+
+    /// Upload a local file to Homarr's media store, returning its URL
+    pub async fn upload_media(&self, path: &Path) -> Result<String> {
+        let bytes = tokio::fs::read(path).await?;
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "icon".to_string());
+
+        let url = format!("{}/api/trpc/media.uploadMedia", self.base_url);
+        let response = self
+            .send(false, || {
+                let part =
+                    reqwest::multipart::Part::bytes(bytes.clone()).file_name(file_name.clone());
+                let form = reqwest::multipart::Form::new().part("file", part);
+                self.client.post(&url).multipart(form)
+            })
+            .await?;
 
         if !response.status().is_success() {
-            let text = response.text().await?;
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
             return Err(AdapterError::HomarrApi(format!(
-                "Failed to create registry app '{}': {}",
-                app.name, text
+                "Failed to upload {:?} ({}): {}",
+                path, status, text
             )));
         }
 
-        let app_response: TrpcResponse<CreateAppResponse> = response.json().await?;
-        let app_id = app_response.result.data.json.app_id;
+        let id = match read_trpc::<UploadedMedia>(response).await? {
+            UploadedMedia::One(id) => id,
+            UploadedMedia::Many(ids) => ids.into_iter().next().ok_or_else(|| {
+                AdapterError::HomarrApi("Media upload returned no ID".to_string())
+            })?,
+        };
+        Ok(format!("/api/user-medias/{}", id))
+    }
 
-        // Add to board with layout preferences
-        self.add_registry_app_to_board(&app_id, app, board_name)
-            .await?;
+    /// Check whether an icon URL resolves to something displayable.
+    ///
+    /// `/icons/*` paths are served from /usr/share/pixmaps, so they are
+    /// checked on disk; HTTP(S) icons are fetched (without credentials).
+    pub async fn icon_resolves(&self, icon_url: &str) -> bool {
+        if let Some(filename) = icon_url.strip_prefix("/icons/") {
+            return Path::new("/usr/share/pixmaps").join(filename).is_file();
+        }
+        match self
+            .client
+            .get(icon_url)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+        {
+            Ok(response) => response.status().is_success(),
+            Err(e) => {
+                tracing::debug!("Icon {} unreachable: {}", icon_url, e);
+                false
+            }
+        }
+    }
 
-        tracing::info!(
-            "Added registry app '{}' (href={}) to Homarr (app_id: {})",
-            app.name,
-            app.url,
-            app_id
-        );
-        Ok(app_id)
+    /// Re-resolve an app's icon.
+    ///
+    /// Returns the replacement icon to use when the configured icon is broken
+    /// and the fallback icon works, or `None` to use the configured icon.
+    pub async fn resolve_icon_override(&self, app: &AppDefinition) -> Option<String> {
+        let configured = configured_icon_url(app);
+        if configured == DEFAULT_ICON || self.icon_resolves(&configured).await {
+            return None;
+        }
+        if self.icon_resolves(DEFAULT_ICON).await {
+            tracing::info!(
+                "Icon {} for app '{}' no longer resolves, using fallback",
+                configured,
+                app.name
+            );
+            Some(DEFAULT_ICON.to_string())
+        } else {
+            None
+        }
     }
 
-    /// Update an existing app with registry app data
-    async fn update_registry_app(&self, app_id: &str, app: &AppDefinition) -> Result<()> {
-        let url = format!("{}/api/trpc/app.update", self.base_url);
-        let icon_url = transform_icon_url(app.icon_url.as_deref().unwrap_or(DEFAULT_ICON));
+    /// Build the URL for a GET-style tRPC query with a superjson input
+    ///
+    /// The input is serialized with serde_json and percent-encoded as a whole,
+    /// so names containing quotes, spaces or non-ASCII characters survive intact.
+    fn query_url(&self, procedure: &str, input: serde_json::Value) -> Result<String> {
+        let encoded = serde_json::to_string(&SuperJson::new(input))?;
+        Ok(format!(
+            "{}/api/trpc/{}?input={}",
+            self.base_url,
+            procedure,
+            urlencoding::encode(&encoded)
+        ))
+    }
 
-        let ping_url = if app.is_external() {
-            None
+    /// Send an authenticated request under the read or mutation policy
+    ///
+    /// `build` is called once per attempt, as request bodies are consumed.
+    async fn send(
+        &self,
+        idempotent: bool,
+        build: impl Fn() -> RequestBuilder,
+    ) -> Result<reqwest::Response> {
+        if let (false, Some(reason)) = (idempotent, &self.refuse_mutations) {
+            return Err(AdapterError::UnsupportedHomarr(reason.clone()));
+        }
+        let policy = if idempotent {
+            &self.policies.read
         } else {
-            app.ping_url.clone().or_else(|| derive_ping_url(&app.url))
+            &self.policies.mutation
         };
+        self.refresh_expiring_session().await;
 
-        let payload = json!({
-            "json": {
-                "id": app_id,
-                "name": app.name,
-                "description": app.description.clone().unwrap_or_default(),
-                "iconUrl": icon_url,
-                "href": app.url,
-                "pingUrl": ping_url
+        let mut attempt = 0;
+        let mut throttled = 0;
+        let mut signed_in_again = false;
+        loop {
+            let mut request = build().timeout(policy.timeout);
+            if let Some(ref api_key) = self.api_key {
+                request = request.header("ApiKey", api_key);
+            }
+            let generation = self.session_generation().await;
+            let result = request.send().await;
+
+            // An expired session is renewed and the request retried once
+            let unauthorized =
+                matches!(result, Ok(ref r) if r.status() == StatusCode::UNAUTHORIZED);
+            if unauthorized && !signed_in_again {
+                if let Some(generation) = generation {
+                    signed_in_again = true;
+                    if self.sign_in_again(generation).await {
+                        continue;
+                    }
+                }
             }
-        });
 
-        let response = self.post_json(&url, &payload).await?;
+            // Homarr (or a proxy in front) didn't handle a rate-limited
+            // request, so even mutations are safe to send again
+            if let Ok(ref response) = result {
+                if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                    self.rate_limited.fetch_add(1, Ordering::Relaxed);
+                    throttled += 1;
+                    let wait = response
+                        .headers()
+                        .get(reqwest::header::RETRY_AFTER)
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| parse_retry_after(v, chrono::Utc::now()))
+                        .unwrap_or_else(|| policy.retry_delay(throttled));
+                    if throttled > RATE_LIMIT_RETRIES || wait > MAX_RATE_LIMIT_WAIT {
+                        return Err(AdapterError::RateLimited(wait.as_secs().max(1)));
+                    }
+                    tracing::info!("Homarr is rate limiting requests, waiting {:?}", wait);
+                    tokio::time::sleep(wait).await;
+                    continue;
+                }
+            }
 
-        if !response.status().is_success() {
-            let text = response.text().await?;
-            return Err(AdapterError::HomarrApi(format!(
-                "Failed to update registry app '{}': {}",
-                app.name, text
-            )));
+            let outcome = AttemptOutcome::of(&result);
+            if attempt >= policy.retries || !outcome.retryable(idempotent, policy) {
+                return Ok(result?);
+            }
+            attempt += 1;
+            let delay = policy.retry_delay(attempt);
+            tracing::debug!(
+                "Homarr request failed ({:?}), retry {}/{} in {:?}",
+                outcome,
+                attempt,
+                policy.retries,
+                delay
+            );
+            tokio::time::sleep(delay).await;
         }
+    }
 
-        tracing::info!(
-            "Updated existing registry app '{}' (href={}) (app_id: {})",
-            app.name,
-            app.url,
-            app_id
-        );
+    /// Generation of the current session, if signed in with credentials
+    async fn session_generation(&self) -> Option<u64> {
+        self.session.lock().await.as_ref().map(|s| s.generation)
+    }
+
+    /// Sign in again before the session lapses, so long-running daemons
+    /// don't hit an expired session mid-sync
+    async fn refresh_expiring_session(&self) {
+        let mut session = self.session.lock().await;
+        let Some(session) = session.as_mut() else {
+            return;
+        };
+        if !session.expires_within(SESSION_REFRESH_MARGIN, chrono::Utc::now()) {
+            return;
+        }
+        tracing::info!("Homarr session is about to expire, signing in again");
+        if let Err(e) = self.renew_session(session).await {
+            tracing::warn!("Failed to renew Homarr session: {}", e);
+        }
+    }
+
+    /// Sign in again after Homarr rejected the session with `generation`,
+    /// returning whether a request is worth retrying
+    async fn sign_in_again(&self, generation: u64) -> bool {
+        let mut session = self.session.lock().await;
+        let Some(session) = session.as_mut() else {
+            return false;
+        };
+        // Another request already signed in again
+        if session.generation != generation {
+            return true;
+        }
+        tracing::info!("Homarr session was rejected, signing in again");
+        match self.renew_session(session).await {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("Failed to sign in to Homarr again: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Sign in with a session's credentials, updating its expiry
+    async fn renew_session(&self, session: &mut Session) -> Result<()> {
+        session.expires = self.sign_in(&session.username, &session.password).await?;
+        session.generation += 1;
         Ok(())
     }
 
-    /// Delete an app from Homarr's global registry
-    pub async fn delete_app(&self, app_id: &str) -> Result<()> {
-        let url = format!("{}/api/trpc/app.delete", self.base_url);
-        let payload = json!({
-            "json": {
-                "id": app_id
+    /// Sign in with credentials, returning when the session expires
+    ///
+    /// Bypasses `send`, which itself signs in again on expired sessions.
+    async fn sign_in(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        let timeout = self.policies.mutation.timeout;
+
+        // Get CSRF token
+        let csrf_url = format!("{}/api/auth/csrf", self.base_url);
+        let csrf_response: CsrfResponse = self
+            .client
+            .get(&csrf_url)
+            .timeout(timeout)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        // Login
+        let login_url = format!("{}/api/auth/callback/credentials", self.base_url);
+        let params = [
+            ("csrfToken", csrf_response.csrf_token.as_str()),
+            ("name", username),
+            ("password", password),
+        ];
+        let response = self
+            .client
+            .post(&login_url)
+            .timeout(timeout)
+            .form(&params)
+            .send()
+            .await?;
+        if !response.status().is_success() && response.status().as_u16() != 302 {
+            return Err(AdapterError::HomarrApi("Login failed".to_string()));
+        }
+
+        // The expiry only enables proactive renewal; without it, sessions
+        // are renewed when Homarr rejects them
+        let session_url = format!("{}/api/auth/session", self.base_url);
+        let expires = match self.client.get(&session_url).timeout(timeout).send().await {
+            Ok(response) => response
+                .json::<SessionResponse>()
+                .await
+                .ok()
+                .and_then(|s| s.expires),
+            Err(_) => None,
+        };
+        Ok(expires)
+    }
+
+    /// Whether the client is signed in with credentials
+    pub async fn has_session(&self) -> bool {
+        self.session.lock().await.is_some()
+    }
+
+    /// Refuse every further mutation with `reason`, leaving reads working
+    pub fn refuse_mutations(&mut self, reason: String) {
+        self.refuse_mutations = Some(reason);
+    }
+
+    /// Why mutations are refused, if they are
+    pub fn mutations_refused(&self) -> Option<&str> {
+        self.refuse_mutations.as_deref()
+    }
+
+    /// Make an authenticated GET request (a read)
+    async fn get(&self, url: &str) -> Result<reqwest::Response> {
+        self.send(true, || self.client.get(url)).await
+    }
+
+    /// Make an authenticated POST request with a superjson body (a mutation)
+    async fn post_json(&self, url: &str, body: &SuperJson) -> Result<reqwest::Response> {
+        self.send(false, || self.client.post(url).json(body)).await
+    }
+
+    /// Whether Homarr is up with its database migrated: its health endpoint
+    /// answers OK or, on versions without one, the onboarding query succeeds
+    pub async fn is_ready(&self) -> bool {
+        let url = format!("{}/api/health/live", self.base_url);
+        let timeout = self.policies.read.timeout;
+        match self.client.get(&url).timeout(timeout).send().await {
+            Ok(response) if response.status().is_success() => true,
+            Ok(response) if response.status() == StatusCode::NOT_FOUND => {
+                self.get_onboarding_step().await.is_ok()
             }
-        });
+            _ => false,
+        }
+    }
+
+    /// Get current onboarding step
+    pub async fn get_onboarding_step(&self) -> Result<OnboardingStep> {
+        let url = format!("{}/api/trpc/onboard.currentStep", self.base_url);
+        read_trpc(self.get(&url).await?).await
+    }
+
+    /// Complete the onboarding flow
+    pub async fn complete_onboarding(&self, branding: &BrandingConfig) -> Result<()> {
+        // Step through onboarding until we reach the user step
+        loop {
+            let step = self.get_onboarding_step().await?;
+            tracing::info!("Onboarding step: {}", step.current);
+
+            match step.current.as_str() {
+                "finish" => break,
+                "start" => {
+                    self.advance_onboarding_step().await?;
+                }
+                "user" => {
+                    self.create_initial_user(branding).await?;
+                }
+                "settings" => {
+                    self.configure_settings(branding).await?;
+                }
+                _ => {
+                    // Skip other steps
+                    self.advance_onboarding_step().await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Advance to next onboarding step
+    async fn advance_onboarding_step(&self) -> Result<()> {
+        let url = format!("{}/api/trpc/onboard.nextStep", self.base_url);
+        self.post_json(&url, &SuperJson::new(json!({}))).await?;
+        Ok(())
+    }
+
+    /// Create initial admin user
+    async fn create_initial_user(&self, branding: &BrandingConfig) -> Result<()> {
+        let url = format!("{}/api/trpc/user.initUser", self.base_url);
+        let payload = SuperJson::new(json!({
+            "username": branding.credentials.admin_username,
+            "password": branding.credentials.admin_password,
+            "confirmPassword": branding.credentials.admin_password
+        }));
 
         let response = self.post_json(&url, &payload).await?;
 
         if !response.status().is_success() {
             let text = response.text().await?;
             return Err(AdapterError::HomarrApi(format!(
-                "Failed to delete app '{}': {}",
-                app_id, text
+                "Failed to create user: {}",
+                text
             )));
         }
 
-        tracing::info!("Deleted app from Homarr (app_id: {})", app_id);
         Ok(())
     }
 
-    /// Add a registry app to a board with layout preferences
-    async fn add_registry_app_to_board(
-        &self,
-        app_id: &str,
-        app: &AppDefinition,
-        board_name: &str,
-    ) -> Result<()> {
-        let board_items = self.get_board_items(board_name).await.unwrap_or_default();
+    /// Configure server settings
+    async fn configure_settings(&self, branding: &BrandingConfig) -> Result<()> {
+        let url = format!("{}/api/trpc/serverSettings.initSettings", self.base_url);
+        let payload = SuperJson::new(json!({
+            "analytics": {
+                "enableGeneral": branding.settings.analytics.enable_general,
+                "enableWidgetData": branding.settings.analytics.enable_widget_data,
+                "enableIntegrationData": branding.settings.analytics.enable_integration_data,
+                "enableUserData": branding.settings.analytics.enable_user_data
+            },
+            "crawlingAndIndexing": {
+                "noIndex": branding.settings.crawling.no_index,
+                "noFollow": branding.settings.crawling.no_follow,
+                "noTranslate": branding.settings.crawling.no_translate,
+                "noSiteLinksSearchBox": branding.settings.crawling.no_sitelinks_search_box
+            }
+        }));
 
-        // Check if this app is already on the board
-        if board_has_app(&board_items, app_id) {
-            tracing::info!(
-                "Registry app '{}' already on board '{}', skipping",
-                app.name,
-                board_name
+        self.post_json(&url, &payload).await?;
+        Ok(())
+    }
+
+    /// Login to Homarr and get session (deprecated - use API key instead)
+    #[allow(dead_code)]
+    async fn login(&self, branding: &BrandingConfig) -> Result<()> {
+        self.login_with_credentials(
+            &branding.credentials.admin_username,
+            &branding.credentials.admin_password,
+        )
+        .await
+    }
+
+    /// Log in with existing admin credentials, keeping the session cookie
+    ///
+    /// Used for already-onboarded Homarr instances where no API key is
+    /// available. The session is renewed shortly before it expires, and once
+    /// per request if Homarr rejects it anyway.
+    pub async fn login_with_credentials(&self, username: &str, password: &str) -> Result<()> {
+        let mut session = Session {
+            username: username.to_string(),
+            password: password.to_string(),
+            expires: None,
+            generation: 0,
+        };
+        self.renew_session(&mut session).await?;
+        *self.session.lock().await = Some(session);
+        Ok(())
+    }
+
+    /// Whether a board of this name exists (and is visible to the adapter)
+    pub async fn has_board(&self, name: &str) -> Result<bool> {
+        match self.get_board_by_name(name).await {
+            Ok(_) => Ok(true),
+            Err(e) if e.is_unreachable() => Err(e),
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Find or create the default board, returning its ID
+    ///
+    /// Requires API key to be set via `set_api_key()` before calling.
+    pub async fn ensure_default_board(&self, branding: &BrandingConfig) -> Result<String> {
+        // Check if board already exists
+        let board = self.get_board_by_name(&branding.board.name).await;
+
+        let template = branding.board.template.as_deref();
+        let board_id = if let Ok(board) = board {
+            tracing::info!("Board '{}' already exists", branding.board.name);
+            board.id
+        } else if let Some(template) = template {
+            tracing::info!(
+                "Creating board '{}' from template '{}'",
+                branding.board.name,
+                template
+            );
+            self.create_board_from_template(
+                &branding.board.name,
+                template,
+                branding.board.is_public,
+            )
+            .await?
+        } else {
+            // Create the board
+            tracing::info!("Creating board '{}'", branding.board.name);
+            self.create_board(branding).await?
+        };
+
+        Ok(board_id)
+    }
+
+    /// Create the branding sections on the default board and apply its
+    /// branding settings (page title, logo, colors, etc.), concurrently
+    ///
+    /// Boards from a template keep the template's look.
+    pub async fn brand_default_board(
+        &self,
+        branding: &BrandingConfig,
+        board_id: &str,
+    ) -> Result<()> {
+        let sections = async {
+            if branding.board.sections.is_empty() {
+                return Ok(());
+            }
+            self.ensure_board_sections(&branding.board.name, &branding.board.sections)
+                .await
+        };
+        let settings = async {
+            if branding.board.template.is_some() {
+                return Ok(());
+            }
+            self.save_board_branding_settings(board_id, branding).await
+        };
+        let access = async {
+            match branding.board.everyone {
+                Some(access) => self.apply_everyone_access(board_id, access, branding).await,
+                None => Ok(()),
+            }
+        };
+        tokio::try_join!(sections, settings, access)?;
+        Ok(())
+    }
+
+    /// Groups a board permission can be given to
+    async fn list_groups(&self) -> Result<Vec<Group>> {
+        let url = format!("{}/api/trpc/group.selectable", self.base_url);
+        let response = self.get(&url).await?;
+        if !response.status().is_success() {
+            return Err(AdapterError::HomarrApi(format!(
+                "Failed to fetch groups: {}",
+                response.text().await.unwrap_or_default()
+            )));
+        }
+        read_trpc(response).await
+    }
+
+    /// The group permissions set on a board
+    async fn get_board_permissions(&self, board_id: &str) -> Result<Vec<GroupBoardPermission>> {
+        let url = self.query_url("board.getBoardPermissions", json!({ "id": board_id }))?;
+        let response = self.get(&url).await?;
+        if !response.status().is_success() {
+            return Err(AdapterError::HomarrApi(format!(
+                "Failed to fetch board permissions: {}",
+                response.text().await.unwrap_or_default()
+            )));
+        }
+        let current: serde_json::Value = read_trpc(response).await?;
+        Ok(current
+            .get("groups")
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()?
+            .unwrap_or_default())
+    }
+
+    /// Set the "everyone" group's permission and the visibility of a board
+    async fn apply_everyone_access(
+        &self,
+        board_id: &str,
+        access: EveryoneAccess,
+        branding: &BrandingConfig,
+    ) -> Result<()> {
+        let public = access.public.unwrap_or(branding.board.is_public);
+        let url = format!("{}/api/trpc/board.changeBoardVisibility", self.base_url);
+        let payload = SuperJson::new(json!({
+            "id": board_id,
+            "visibility": if public { "public" } else { "private" }
+        }));
+        let response = self.post_json(&url, &payload).await?;
+        if !response.status().is_success() {
+            return Err(AdapterError::HomarrApi(format!(
+                "Failed to change board visibility: {}",
+                response.text().await.unwrap_or_default()
+            )));
+        }
+
+        let groups = self.list_groups().await?;
+        let Some(everyone) = groups.iter().find(|g| g.name == EVERYONE_GROUP) else {
+            tracing::warn!(
+                "Homarr has no '{}' group, board permission not set",
+                EVERYONE_GROUP
             );
             return Ok(());
+        };
+
+        // Saving replaces all group permissions, so keep the other groups'
+        let existing = self.get_board_permissions(board_id).await?;
+
+        let url = format!("{}/api/trpc/board.saveGroupBoardPermissions", self.base_url);
+        let payload = SuperJson::new(json!({
+            "entityId": board_id,
+            "permissions": with_group_permission(&existing, &everyone.id, access.permission)
+        }));
+        let response = self.post_json(&url, &payload).await?;
+        if !response.status().is_success() {
+            return Err(AdapterError::HomarrApi(format!(
+                "Failed to save board permissions: {}",
+                response.text().await.unwrap_or_default()
+            )));
         }
 
-        let board = self.get_board_by_name(board_name).await?;
+        tracing::info!(
+            "Board '{}' is {}, everyone group permission: {:?}",
+            branding.board.name,
+            if public { "public" } else { "private" },
+            access.permission
+        );
+        Ok(())
+    }
+
+    /// Make the board the user and server-wide home board and set the
+    /// color scheme, concurrently
+    pub async fn make_home_board(&self, branding: &BrandingConfig, board_id: &str) -> Result<()> {
+        tokio::try_join!(
+            self.set_user_home_board(board_id),
+            self.set_server_home_board(board_id),
+            self.set_color_scheme(&branding.theme.default_color_scheme),
+        )?;
+        Ok(())
+    }
+
+    /// Ensure the board has a category section for each name, in order
+    ///
+    /// Existing sections (matched by name, case-insensitively) are left alone,
+    /// so this is safe to call on every setup run.
+    async fn ensure_board_sections(&self, board_name: &str, names: &[String]) -> Result<()> {
+        let mut board = self.get_board(board_name).await?;
+        let missing = plan_missing_sections(&board.board.sections, names);
+        if missing.is_empty() {
+            return Ok(());
+        }
 
-        let section_id = board
-            .sections
+        board.board.sections.extend(missing);
+        self.save_board_now(board_name, board).await?;
+
+        tracing::info!(
+            "Created section(s) {} on board '{}'",
+            names.join(", "),
+            board_name
+        );
+        Ok(())
+    }
+
+    /// Place the branding template's apps and widgets on the default board
+    ///
+    /// Template apps are created unless Homarr has them already (by URL or
+    /// name). Tiles already on the board are left where they are, so this
+    /// is safe to run on every setup.
+    pub async fn apply_board_template(&self, branding: &BrandingConfig) -> Result<()> {
+        let template = &branding.board;
+        if template.apps.is_empty() && template.widgets.is_empty() {
+            return Ok(());
+        }
+
+        let existing = if template.apps.is_empty() {
+            Vec::new()
+        } else {
+            self.list_apps().await?
+        };
+        let mut apps = Vec::new();
+        for app in &template.apps {
+            let found = Self::find_app_by_url(&existing, &app.url)
+                .or_else(|| Self::find_app_by_name(&existing, &app.name));
+            let app_id = match found {
+                Some(found) => found.id.clone(),
+                None => {
+                    tracing::info!("Creating template app '{}'", app.name);
+                    self.create_app(&AppDefinition {
+                        name: app.name.clone(),
+                        url: app.url.clone(),
+                        description: app.description.clone(),
+                        icon_url: app.icon_url.clone(),
+                        visible: true,
+                        ..Default::default()
+                    })
+                    .await?
+                }
+            };
+            apps.push((app, app_id));
+        }
+
+        let mut board = self.get_board(&template.name).await?;
+        let (layout_id, column_count) = board
+            .board
+            .layouts
             .first()
-            .map(|s| s.id.clone())
-            .unwrap_or_default();
-        let layout_id = board
+            .map(|l| (l.id.clone(), l.column_count))
+            .unwrap_or_else(|| (String::new(), 12));
+        let missing = missing_template_items(
+            &board.items,
+            &board.board.sections,
+            (&layout_id, column_count),
+            &apps,
+            &template.widgets,
+        );
+        if missing.is_empty() {
+            return Ok(());
+        }
+        tracing::info!(
+            "Placing {} template item(s) on board '{}'",
+            missing.len(),
+            template.name
+        );
+        board.items.extend(missing);
+        self.save_items(&template.name, board);
+        self.flush_board_saves().await
+    }
+
+    /// Save board branding settings (page title, meta title, logo, favicon, colors)
+    async fn save_board_branding_settings(
+        &self,
+        board_id: &str,
+        branding: &BrandingConfig,
+    ) -> Result<()> {
+        let url = format!("{}/api/trpc/board.savePartialBoardSettings", self.base_url);
+
+        // Build the settings payload with only non-null values
+        let mut settings = serde_json::Map::new();
+        settings.insert("id".to_string(), json!(board_id));
+
+        // Add page title if configured
+        if let Some(ref page_title) = branding.identity.page_title {
+            settings.insert("pageTitle".to_string(), json!(page_title));
+        }
+
+        // Add meta title if configured
+        if let Some(ref meta_title) = branding.identity.meta_title {
+            settings.insert("metaTitle".to_string(), json!(meta_title));
+        }
+
+        // Add logo URL if configured
+        if let Some(ref logo_url) = branding.identity.logo_image_url {
+            settings.insert("logoImageUrl".to_string(), json!(logo_url));
+        }
+
+        // Add favicon URL if configured
+        if let Some(ref favicon_url) = branding.identity.favicon_image_url {
+            settings.insert("faviconImageUrl".to_string(), json!(favicon_url));
+        }
+
+        // Add theme settings
+        settings.insert(
+            "primaryColor".to_string(),
+            json!(branding.theme.primary_color),
+        );
+        settings.insert(
+            "secondaryColor".to_string(),
+            json!(branding.theme.secondary_color),
+        );
+        settings.insert("opacity".to_string(), json!(branding.theme.opacity));
+        settings.insert("itemRadius".to_string(), json!(branding.theme.item_radius));
+
+        // Add background image URL and how it is laid out if configured
+        if let Some(ref bg_url) = branding.theme.background_image_url {
+            settings.insert("backgroundImageUrl".to_string(), json!(bg_url));
+        }
+        if let Some(attachment) = branding.theme.background_image_attachment {
+            settings.insert("backgroundImageAttachment".to_string(), json!(attachment));
+        }
+        if let Some(repeat) = branding.theme.background_image_repeat {
+            settings.insert("backgroundImageRepeat".to_string(), json!(repeat));
+        }
+        if let Some(size) = branding.theme.background_image_size {
+            settings.insert("backgroundImageSize".to_string(), json!(size));
+        }
+
+        // Add custom CSS if configured
+        if let Some(ref custom_css) = branding.theme.custom_css {
+            settings.insert("customCss".to_string(), json!(custom_css));
+        }
+
+        let payload = SuperJson::new(serde_json::Value::Object(settings));
+
+        tracing::info!("Applying board branding settings");
+        let response = self.post_json(&url, &payload).await?;
+
+        if !response.status().is_success() {
+            let text = response.text().await?;
+            tracing::warn!("Failed to save board branding settings: {}", text);
+            // Don't fail the whole setup if branding settings fail
+        }
+
+        Ok(())
+    }
+
+    /// Get board by name
+    async fn get_board_by_name(&self, name: &str) -> Result<BoardResponse> {
+        let url = self.query_url("board.getBoardByName", json!({ "name": name }))?;
+
+        let response = self.get(&url).await?;
+
+        if !response.status().is_success() {
+            return Err(AdapterError::HomarrApi("Board not found".to_string()));
+        }
+
+        read_trpc(response).await
+    }
+
+    /// Create a new board
+    async fn create_board(&self, branding: &BrandingConfig) -> Result<String> {
+        self.create_named_board(
+            &branding.board.name,
+            branding.board.column_count,
+            branding.board.is_public,
+        )
+        .await
+    }
+
+    /// Create an empty board
+    async fn create_named_board(
+        &self,
+        name: &str,
+        column_count: u8,
+        is_public: bool,
+    ) -> Result<String> {
+        let url = format!("{}/api/trpc/board.createBoard", self.base_url);
+        let payload = SuperJson::new(json!({
+            "name": name,
+            "columnCount": column_count,
+            "isPublic": is_public
+        }));
+
+        let response = self.post_json(&url, &payload).await?;
+        let created: CreateBoardResponse = read_trpc(response).await?;
+
+        Ok(created.board_id)
+    }
+
+    /// Create a board with the sections and appearance of a template board
+    ///
+    /// Tiles and widgets are not copied; the new board starts empty.
+    pub async fn create_board_from_template(
+        &self,
+        name: &str,
+        template_name: &str,
+        is_public: bool,
+    ) -> Result<String> {
+        let url = self.query_url("board.getBoardByName", json!({ "name": template_name }))?;
+        let response = self.get(&url).await?;
+        if !response.status().is_success() {
+            return Err(AdapterError::HomarrApi(format!(
+                "Template board '{}' not found",
+                template_name
+            )));
+        }
+        let raw: serde_json::Value = read_trpc(response).await?;
+        let template: BoardResponse = serde_json::from_value(raw.clone())?;
+        let column_count = template
             .layouts
             .first()
-            .map(|l| l.id.clone())
-            .unwrap_or_default();
+            .map(|l| l.column_count)
+            .unwrap_or(12);
+
+        let url = format!("{}/api/trpc/board.createBoard", self.base_url);
+        let payload = SuperJson::new(json!({
+            "name": name,
+            "columnCount": column_count,
+            "isPublic": is_public
+        }));
+        let response = self.post_json(&url, &payload).await?;
+        let created: CreateBoardResponse = read_trpc(response).await?;
+        let board_id = created.board_id;
+
+        let mut board = self.read_board(name).await?;
+        board.board.sections = template_sections(&template.sections, &board_id);
+        self.save_board_now(name, board).await.map_err(|e| {
+            AdapterError::HomarrApi(format!(
+                "Failed to copy sections of template '{}': {}",
+                template_name, e
+            ))
+        })?;
+
+        let mut settings = template_settings(&raw);
+        settings.insert("id".to_string(), json!(board_id));
+        let url = format!("{}/api/trpc/board.savePartialBoardSettings", self.base_url);
+        let response = self
+            .post_json(&url, &SuperJson::new(serde_json::Value::Object(settings)))
+            .await?;
+        if !response.status().is_success() {
+            let text = response.text().await?;
+            tracing::warn!(
+                "Failed to copy settings of template '{}': {}",
+                template_name,
+                text
+            );
+        }
+
+        Ok(board_id)
+    }
 
-        // Get layout preferences from registry
-        let layout = app.effective_layout();
-        let width = layout.width as i32;
-        let height = layout.height as i32;
+    /// A board's sections, layouts and items, with the apps and
+    /// integrations they link to
+    pub async fn export_board(&self, name: &str) -> Result<BoardExport> {
+        let url = self.query_url("board.getBoardByName", json!({ "name": name }))?;
+        let response = self.get(&url).await?;
+        if !response.status().is_success() {
+            return Err(AdapterError::HomarrApi(format!(
+                "Board '{}' not found",
+                name
+            )));
+        }
+        let mut raw: serde_json::Value = read_trpc(response).await?;
+        let items = match raw.get_mut("items").map(serde_json::Value::take) {
+            Some(serde_json::Value::Array(items)) => items,
+            _ => vec![],
+        };
+        let is_public = raw.get("isPublic").and_then(|p| p.as_bool()) == Some(true);
+        let board: BoardResponse = serde_json::from_value(raw)?;
 
-        // Use explicit position if provided, otherwise auto-position
-        let (x_offset, y_offset) = match (layout.x_offset, layout.y_offset) {
-            (Some(x), Some(y)) => (x as i32, y as i32),
-            _ => self.find_next_position(&board_items, 12), // 12 columns for new layout
+        let app_ids: HashSet<&str> = items.iter().filter_map(item_app_id).collect();
+        let apps = if app_ids.is_empty() {
+            Vec::new()
+        } else {
+            self.list_apps()
+                .await?
+                .into_iter()
+                .filter(|app| app_ids.contains(app.id.as_str()))
+                .collect()
+        };
+        let integration_ids: HashSet<&str> = items
+            .iter()
+            .filter_map(|item| item.get("integrationIds")?.as_array())
+            .flatten()
+            .filter_map(|id| id.as_str())
+            .collect();
+        let integrations = if integration_ids.is_empty() {
+            Vec::new()
+        } else {
+            self.get_integrations()
+                .await?
+                .into_iter()
+                .filter(|i| integration_ids.contains(i.id.as_str()))
+                .collect()
         };
 
-        // Generate a unique ID for this board item
-        // Use container name if available, otherwise use a hash of the URL
-        let item_id = if let Some(container) = app.container_name() {
-            format!("registry-{}", container)
-        } else {
-            format!("registry-{:x}", string_hash(&app.url))
-        };
+        Ok(BoardExport {
+            version: BOARD_EXPORT_VERSION,
+            name: board.name,
+            board_id: board.id,
+            is_public,
+            sections: board.sections,
+            layouts: board.layouts,
+            items,
+            apps,
+            integrations,
+        })
+    }
+
+    /// Replace a board's sections, layouts and items with an export,
+    /// creating the board if it doesn't exist
+    ///
+    /// Apps the items link to are found by URL, then name, and created if
+    /// missing; integrations are matched by kind and URL.
+    pub async fn import_board(&self, export: &BoardExport, name: &str) -> Result<BoardImport> {
+        if export.version > BOARD_EXPORT_VERSION {
+            return Err(AdapterError::Config(format!(
+                "Board export format {} is newer than this adapter supports ({})",
+                export.version, BOARD_EXPORT_VERSION
+            )));
+        }
+        let mut report = BoardImport::default();
+
+        let existing = self.list_apps().await?;
+        let mut app_ids = HashMap::new();
+        for app in &export.apps {
+            let found = app
+                .href
+                .as_deref()
+                .and_then(|href| Self::find_app_by_url(&existing, href))
+                .or_else(|| Self::find_app_by_name(&existing, &app.name));
+            let id = match (found, app.href.as_deref()) {
+                (Some(found), _) => found.id.clone(),
+                (None, Some(href)) => {
+                    tracing::info!("Creating app '{}'", app.name);
+                    report.created_apps.push(app.name.clone());
+                    self.create_app(&AppDefinition {
+                        name: app.name.clone(),
+                        url: href.to_string(),
+                        icon_url: Some(app.icon_url.clone()).filter(|i| !i.is_empty()),
+                        visible: true,
+                        ..Default::default()
+                    })
+                    .await?
+                }
+                (None, None) => {
+                    tracing::warn!("App '{}' has no URL and isn't in Homarr, skipped", app.name);
+                    continue;
+                }
+            };
+            app_ids.insert(app.id.clone(), id);
+        }
+
+        let mut integration_ids = HashMap::new();
+        if !export.integrations.is_empty() {
+            let available = self.get_integrations().await?;
+            for integration in &export.integrations {
+                match available.iter().find(|i| {
+                    i.kind == integration.kind
+                        && normalize_url(&i.url) == normalize_url(&integration.url)
+                }) {
+                    Some(found) => {
+                        integration_ids.insert(integration.id.clone(), found.id.clone());
+                    }
+                    None => tracing::warn!(
+                        "No {} integration for {} in Homarr, widgets lose their link to '{}'",
+                        integration.kind,
+                        integration.url,
+                        integration.name
+                    ),
+                }
+            }
+        }
+
+        if !self
+            .get_writable_boards()
+            .await?
+            .iter()
+            .any(|board| board.name == name)
+        {
+            let column_count = export
+                .layouts
+                .first()
+                .map(|l| u8::try_from(l.column_count).unwrap_or(12))
+                .unwrap_or(12);
+            tracing::info!("Creating board '{}'", name);
+            self.create_named_board(name, column_count, export.is_public)
+                .await?;
+            report.created_board = true;
+        }
+        let mut board = self.read_board(name).await?;
+        let (sections, layouts, items) = import_contents(
+            export,
+            &board.board.id,
+            &board.board.layouts,
+            &app_ids,
+            &integration_ids,
+        );
+
+        let url = format!("{}/api/trpc/board.saveLayouts", self.base_url);
+        let payload = SuperJson::new(json!({
+            "id": board.board.id,
+            "layouts": layouts,
+        }));
+        let response = self.post_json(&url, &payload).await?;
+        if !response.status().is_success() {
+            return Err(AdapterError::HomarrApi(format!(
+                "Failed to save layouts of board '{}': {}",
+                name,
+                response.text().await.unwrap_or_default()
+            )));
+        }
+
+        report.items = items.len();
+        report.dropped_items = export.items.len() - items.len();
+        board.board.sections = sections;
+        board.board.layouts = layouts;
+        board.items = items;
+        self.save_items(name, board);
+        self.flush_board_saves().await?;
+        Ok(report)
+    }
+
+    /// Set user's home board
+    ///
+    /// This sets the home board for the current user (stored in users table).
+    async fn set_user_home_board(&self, board_id: &str) -> Result<()> {
+        let url = format!("{}/api/trpc/board.setHomeBoard", self.base_url);
+        let payload = SuperJson::new(json!({"id": board_id}));
+        self.post_json(&url, &payload).await?;
+        Ok(())
+    }
+
+    /// Set server-wide home board
+    ///
+    /// This sets the default home board for the server (stored in serverSettings).
+    /// This is the board shown to users who haven't set their own home board.
+    async fn set_server_home_board(&self, board_id: &str) -> Result<()> {
+        let url = format!("{}/api/trpc/serverSettings.saveSettings", self.base_url);
+        let payload = SuperJson::new(json!({
+            "settingsKey": "board",
+            "value": {
+                "homeBoardId": board_id,
+                "mobileHomeBoardId": board_id,
+                "enableStatusByDefault": true,
+                "forceDisableStatus": false
+            }
+        }));
+
+        let response = self.post_json(&url, &payload).await?;
+
+        if !response.status().is_success() {
+            let text = response.text().await.unwrap_or_default();
+            tracing::warn!("Failed to set server home board: {}", text);
+            // Don't fail the whole setup if this fails
+        } else {
+            tracing::info!("Server home board set to '{}'", board_id);
+        }
+
+        Ok(())
+    }
+
+    /// Search engines configured in Homarr
+    async fn list_search_engines(&self) -> Result<Vec<SearchEngineEntry>> {
+        let url = self.query_url(
+            "searchEngine.getPaginated",
+            json!({ "page": 1, "pageSize": 100 }),
+        )?;
+        let response = self.get(&url).await?;
+        if !response.status().is_success() {
+            return Err(AdapterError::HomarrApi(format!(
+                "Failed to fetch search engines: {}",
+                response.text().await.unwrap_or_default()
+            )));
+        }
+        let page: SearchEnginePage = read_trpc(response).await?;
+        Ok(page.items)
+    }
+
+    /// Create the branding's search engines (updating those Homarr already
+    /// has by `short`) and make `default_engine` the server default
+    ///
+    /// Safe to run on every setup.
+    pub async fn configure_search(&self, search: &Search) -> Result<()> {
+        if search.engines.is_empty() && search.default_engine.is_none() {
+            return Ok(());
+        }
+
+        let mut existing = self.list_search_engines().await?;
+        for engine in &search.engines {
+            let mut payload = json!({
+                "name": engine.name,
+                "short": engine.short,
+                "iconUrl": transform_icon_url(&engine.icon_url),
+                "urlTemplate": engine.url_template,
+                "description": engine.description,
+                "type": "generic"
+            });
+            let found = existing
+                .iter()
+                .find(|e| e.short.eq_ignore_ascii_case(&engine.short));
+            let (endpoint, action) = match found {
+                Some(found) => {
+                    payload["id"] = json!(found.id);
+                    ("searchEngine.update", "update")
+                }
+                None => ("searchEngine.create", "create"),
+            };
+            let url = format!("{}/api/trpc/{}", self.base_url, endpoint);
+            let response = self.post_json(&url, &SuperJson::new(payload)).await?;
+            if !response.status().is_success() {
+                return Err(AdapterError::HomarrApi(format!(
+                    "Failed to {} search engine '{}': {}",
+                    action,
+                    engine.name,
+                    response.text().await.unwrap_or_default()
+                )));
+            }
+            tracing::info!("Search engine '{}' ({}) set up", engine.name, engine.short);
+        }
+
+        let Some(default) = &search.default_engine else {
+            return Ok(());
+        };
+        if !search.engines.is_empty() {
+            // IDs of the engines just created
+            existing = self.list_search_engines().await?;
+        }
+        let Some(engine) = find_search_engine(&existing, default) else {
+            tracing::warn!("Default search engine '{}' not found in Homarr", default);
+            return Ok(());
+        };
+        let url = format!("{}/api/trpc/serverSettings.saveSettings", self.base_url);
+        let payload = SuperJson::new(json!({
+            "settingsKey": "search",
+            "value": {
+                "defaultSearchEngineId": engine.id
+            }
+        }));
+        let response = self.post_json(&url, &payload).await?;
+        if !response.status().is_success() {
+            let text = response.text().await.unwrap_or_default();
+            tracing::warn!("Failed to set the default search engine: {}", text);
+            // Don't fail the whole setup if this fails
+        } else {
+            tracing::info!("Default search engine set to '{}'", engine.name);
+        }
+        Ok(())
+    }
+
+    /// Set color scheme
+    async fn set_color_scheme(&self, scheme: &str) -> Result<()> {
+        let url = format!("{}/api/trpc/user.changeColorScheme", self.base_url);
+        let payload = SuperJson::new(json!({"colorScheme": scheme}));
+        self.post_json(&url, &payload).await?;
+        Ok(())
+    }
+
+    /// Check if the client is authenticated (has API key set)
+    pub fn is_authenticated(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    /// Create a new API key
+    ///
+    /// Requires authentication (API key must be set).
+    /// Returns the new API key in format "{id}.{token}".
+    pub async fn create_api_key(&self) -> Result<String> {
+        let url = format!("{}/api/trpc/apiKeys.create", self.base_url);
+        let payload = SuperJson::new(json!({}));
+
+        let response = self.post_json(&url, &payload).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(AdapterError::HomarrApi(format!(
+                "Failed to create API key ({}): {}",
+                status, text
+            )));
+        }
+
+        let created: CreateApiKeyResponse = read_trpc(response).await?;
+        Ok(created.api_key)
+    }
+
+    /// Delete an API key by ID
+    ///
+    /// Requires authentication (API key must be set).
+    pub async fn delete_api_key(&self, api_key_id: &str) -> Result<()> {
+        let url = format!("{}/api/trpc/apiKeys.delete", self.base_url);
+        let payload = SuperJson::new(json!({"apiKeyId": api_key_id}));
+
+        let response = self.post_json(&url, &payload).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(AdapterError::HomarrApi(format!(
+                "Failed to delete API key '{}' ({}): {}",
+                api_key_id, status, text
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Rotate from a bootstrap API key to a new permanent key
+    ///
+    /// This method:
+    /// 1. Authenticates with the bootstrap key
+    /// 2. Creates a new random API key
+    /// 3. Deletes the bootstrap key
+    /// 4. Returns the new API key
+    ///
+    /// If the rotation fails partway through, the bootstrap key may still be valid
+    /// and the operation can be retried.
+    pub async fn rotate_api_key(&mut self, bootstrap_key: &str) -> Result<String> {
+        // Extract the bootstrap key ID (format: "{id}.{token}")
+        let bootstrap_id = bootstrap_key
+            .split('.')
+            .next()
+            .ok_or_else(|| AdapterError::HomarrApi("Invalid bootstrap key format".to_string()))?;
+
+        tracing::info!("Rotating bootstrap API key to permanent key...");
+
+        // Authenticate with bootstrap key
+        self.set_api_key(bootstrap_key.to_string());
+
+        // Create new API key
+        let new_key = self.create_api_key().await?;
+        tracing::info!("Created new permanent API key");
+
+        // Switch to new key for further operations
+        self.set_api_key(new_key.clone());
+
+        // Delete the bootstrap key
+        match self.delete_api_key(bootstrap_id).await {
+            Ok(()) => {
+                tracing::info!("Deleted bootstrap API key");
+            }
+            Err(e) => {
+                // Log but don't fail - the new key is already working
+                tracing::warn!("Failed to delete bootstrap key (non-fatal): {}", e);
+            }
+        }
+
+        Ok(new_key)
+    }
+
+    /// Get all apps (minimal data for matching)
+    ///
+    /// Returns all apps from Homarr for deduplication checks.
+    /// Callers can cache this result to avoid repeated API calls.
+    pub async fn list_apps(&self) -> Result<Vec<SelectableApp>> {
+        let url = format!("{}/api/trpc/app.selectable", self.base_url);
+        let response = self.get(&url).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(AdapterError::HomarrApi(format!(
+                "Failed to fetch apps ({}): {}",
+                status, text
+            )));
+        }
+
+        read_trpc(response).await
+    }
+
+    /// Get all boards with permission info
+    ///
+    /// Returns all boards accessible to the authenticated user, with their
+    /// permission levels. Used for multi-board sync to discover which boards
+    /// the adapter can sync apps to.
+    pub async fn get_all_boards(&self) -> Result<Vec<BoardWithPermission>> {
+        let url = format!("{}/api/trpc/board.getAllBoards", self.base_url);
+        let response = self.get(&url).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(AdapterError::HomarrApi(format!(
+                "Failed to fetch boards ({}): {}",
+                status, text
+            )));
+        }
+
+        let boards: Vec<BoardWithPermission> = read_trpc(response).await?;
+        *self.boards.lock().unwrap() = boards
+            .iter()
+            .map(|board| (board.name.clone(), board.clone()))
+            .collect();
+        Ok(boards)
+    }
+
+    /// Get a board for `homarr.board` routing, creating it if missing
+    ///
+    /// Boards listed or created before are served from the cache; on a miss
+    /// the board list is fetched again, and a failure to fetch it is returned
+    /// rather than taken as the board missing. New boards copy the branding
+    /// template board when there is one, and otherwise get the branding
+    /// column count and sections.
+    pub async fn ensure_board(
+        &self,
+        name: &str,
+        branding: Option<&BrandingConfig>,
+    ) -> Result<EnsuredBoard> {
+        let cached = self.boards.lock().unwrap().get(name).cloned();
+        let existing = match cached {
+            Some(board) => Some(board),
+            None => self
+                .get_all_boards()
+                .await?
+                .into_iter()
+                .find(|board| board.name == name),
+        };
+        if let Some(board) = existing {
+            return Ok(EnsuredBoard {
+                board,
+                created: false,
+            });
+        }
+
+        let is_public = branding.is_some_and(|b| b.board.is_public);
+        let template = branding.and_then(|b| b.board.template.as_deref());
+        tracing::info!("Creating board '{}' for routed apps", name);
+        let id = match template {
+            Some(template) => {
+                self.create_board_from_template(name, template, is_public)
+                    .await?
+            }
+            None => {
+                let column_count = branding.map_or(12, |b| b.board.column_count);
+                let id = self
+                    .create_named_board(name, column_count, is_public)
+                    .await?;
+                if let Some(branding) = branding {
+                    self.ensure_board_sections(name, &branding.board.sections)
+                        .await?;
+                }
+                id
+            }
+        };
+
+        let board = BoardWithPermission {
+            id,
+            name: name.to_string(),
+            is_public,
+            user_permissions: vec![],
+            group_permissions: vec![],
+        };
+        self.boards
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), board.clone());
+        Ok(EnsuredBoard {
+            board,
+            created: true,
+        })
+    }
+
+    /// Get all writable boards (modify or full permission)
+    ///
+    /// Convenience method that filters `get_all_boards()` to only return
+    /// boards where the adapter can add/remove apps.
+    pub async fn get_writable_boards(&self) -> Result<Vec<BoardWithPermission>> {
+        let boards = self.get_all_boards().await?;
+        Ok(boards.into_iter().filter(|b| b.is_writable()).collect())
+    }
+
+    /// Find an existing app by URL in a pre-fetched list.
+    ///
+    /// Uses URL normalization to handle minor differences like trailing slashes.
+    /// Returns the matching app if found.
+    pub fn find_app_by_url<'a>(apps: &'a [SelectableApp], url: &str) -> Option<&'a SelectableApp> {
+        let normalized_url = normalize_url(url);
+        apps.iter().find(|app| {
+            app.href
+                .as_ref()
+                .map(|href| normalize_url(href) == normalized_url)
+                .unwrap_or(false)
+        })
+    }
+
+    /// Find an existing app by name in a pre-fetched list.
+    ///
+    /// Used as fallback when URL matching fails (e.g., URL changed in package update).
+    /// Case-insensitive comparison.
+    pub fn find_app_by_name<'a>(
+        apps: &'a [SelectableApp],
+        name: &str,
+    ) -> Option<&'a SelectableApp> {
+        let name_lower = name.to_lowercase();
+        apps.iter()
+            .find(|app| app.name.to_lowercase() == name_lower)
+    }
+
+    /// Add a registry app to Homarr (or update if already exists)
+    ///
+    /// Registry apps can have explicit layout positioning and may not be Docker containers.
+    ///
+    /// Deduplication strategy:
+    /// 1. First, try to find an existing app by normalized URL
+    /// 2. If not found, fall back to matching by app name (handles URL changes in package updates)
+    /// 3. If found, update the existing app and ensure it's on the board
+    /// 4. If not found at all, create a new app
+    pub async fn add_registry_app(
+        &self,
+        app: &AppDefinition,
+        board_name: &str,
+        existing_apps: Option<&[SelectableApp]>,
+    ) -> Result<SyncedApp> {
+        // Get or fetch the list of existing apps for deduplication
+        let apps_for_search: Vec<SelectableApp>;
+        let apps_ref = match existing_apps {
+            Some(apps) => apps,
+            None => match self.list_apps().await {
+                Ok(apps) => {
+                    apps_for_search = apps;
+                    &apps_for_search
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to fetch existing apps for deduplication: {}. \
+                             Proceeding with create.",
+                        e
+                    );
+                    // Can't deduplicate without existing apps, will create new
+                    apps_for_search = vec![];
+                    &apps_for_search
+                }
+            },
+        };
+
+        // Try to find existing app: first by URL, then by name as fallback
+        let existing = Self::find_app_by_url(apps_ref, &app.url)
+            .or_else(|| {
+                // URL didn't match - try by name (handles URL changes in package updates)
+                let found_by_name = Self::find_app_by_name(apps_ref, &app.name);
+                if found_by_name.is_some() {
+                    tracing::info!(
+                        "App '{}' not found by URL, but found by name - will update existing app",
+                        app.name
+                    );
+                }
+                found_by_name
+            })
+            .cloned();
+
+        if let Some(existing_app) = existing {
+            // App already exists - update it (including URL) and ensure it's on the board
+            self.update_app(&existing_app.id, app).await?;
+            let placed = !app.hide
+                && self
+                    .add_registry_app_to_board(&existing_app.id, app, board_name)
+                    .await?;
+            return Ok(SyncedApp {
+                id: existing_app.id,
+                created: false,
+                placed,
+            });
+        }
+
+        let app_id = self.create_app(app).await?;
+
+        // Add to board with layout preferences
+        let placed = !app.hide
+            && self
+                .add_registry_app_to_board(&app_id, app, board_name)
+                .await?;
+        Ok(SyncedApp {
+            id: app_id,
+            created: true,
+            placed,
+        })
+    }
+
+    /// App fields sent to Homarr on create and update
+    fn app_payload(&self, app: &AppDefinition) -> serde_json::Value {
+        json!({
+            "name": app.name,
+            "description": app.description.clone().unwrap_or_default(),
+            "iconUrl": self.icon_for(app),
+            "href": app.url,
+            "pingUrl": ping_url_for(app)
+        })
+    }
+
+    /// Create an app in Homarr's global registry, returning its ID
+    ///
+    /// The app isn't placed on any board.
+    pub async fn create_app(&self, app: &AppDefinition) -> Result<String> {
+        let url = format!("{}/api/trpc/app.create", self.base_url);
+        let payload = SuperJson::new(self.app_payload(app));
+
+        let response = self.post_json(&url, &payload).await?;
+
+        if !response.status().is_success() {
+            let text = response.text().await?;
+            return Err(AdapterError::HomarrApi(format!(
+                "Failed to create app '{}': {}",
+                app.name, text
+            )));
+        }
+
+        let created: CreateAppResponse = read_trpc(response).await?;
+        tracing::info!(
+            "Added app '{}' (href={}) to Homarr (app_id: {})",
+            app.name,
+            app.url,
+            created.app_id
+        );
+        Ok(created.app_id)
+    }
+
+    /// Update an existing app's name, description, icon, URL and ping URL
+    pub async fn update_app(&self, app_id: &str, app: &AppDefinition) -> Result<()> {
+        let url = format!("{}/api/trpc/app.update", self.base_url);
+        let mut fields = self.app_payload(app);
+        fields["id"] = json!(app_id);
+        let payload = SuperJson::new(fields);
+
+        let response = self.post_json(&url, &payload).await?;
+
+        if !response.status().is_success() {
+            let text = response.text().await?;
+            return Err(AdapterError::HomarrApi(format!(
+                "Failed to update app '{}': {}",
+                app.name, text
+            )));
+        }
+
+        tracing::info!(
+            "Updated existing registry app '{}' (href={}) (app_id: {})",
+            app.name,
+            app.url,
+            app_id
+        );
+        Ok(())
+    }
+
+    /// Delete an app from Homarr's global registry
+    pub async fn delete_app(&self, app_id: &str) -> Result<()> {
+        let url = format!("{}/api/trpc/app.delete", self.base_url);
+        let payload = SuperJson::new(json!({
+            "id": app_id
+        }));
+
+        let response = self.post_json(&url, &payload).await?;
+
+        if !response.status().is_success() {
+            let text = response.text().await?;
+            return Err(AdapterError::HomarrApi(format!(
+                "Failed to delete app '{}': {}",
+                app_id, text
+            )));
+        }
+
+        tracing::info!("Deleted app from Homarr (app_id: {})", app_id);
+        Ok(())
+    }
+
+    /// Merge duplicate tiles of adapter-owned apps
+    ///
+    /// Earlier adapter versions (or manual experiments) can leave several
+    /// Homarr apps, or several tiles, for one app. `owned` lists the URLs of
+    /// the apps the adapter manages; other apps are never touched. On
+    /// every writable board the best-positioned tile is kept and the others
+    /// removed, then the duplicate apps are deleted. With `dry_run` nothing
+    /// is changed.
+    pub async fn dedupe(&self, owned: &[String], dry_run: bool) -> Result<DedupeReport> {
+        let apps = self.list_apps().await?;
+        let groups = group_owned_apps(&apps, owned);
+
+        let mut boards = Vec::new();
+        for board in self.get_writable_boards().await? {
+            let pending = self.get_board(&board.name).await?;
+            boards.push((board.name, pending));
+        }
+        let items: Vec<Vec<serde_json::Value>> =
+            boards.iter().map(|(_, b)| b.items.clone()).collect();
+        let (duplicates, keep_for) = plan_duplicates(&groups, &items);
+
+        let mut report = DedupeReport {
+            apps: duplicates,
+            ..Default::default()
+        };
+        let mut changed = false;
+        for (board_name, mut board) in boards {
+            let before = board.items.clone();
+            let removed = merge_duplicate_tiles(&mut board.items, &keep_for);
+            if removed > 0 {
+                report.tiles_removed.push((board_name.clone(), removed));
+            }
+            if board.items != before && !dry_run {
+                self.save_items(&board_name, board);
+                changed = true;
+            }
+        }
+        if dry_run {
+            return Ok(report);
+        }
+
+        // Tiles must point at the kept apps before the copies go
+        if changed {
+            self.flush_board_saves().await?;
+        }
+        for duplicate in &report.apps {
+            for app_id in &duplicate.remove {
+                self.delete_app(app_id).await?;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Add a registry app to a board with layout preferences, returning
+    /// whether a new tile was placed
+    async fn add_registry_app_to_board(
+        &self,
+        app_id: &str,
+        app: &AppDefinition,
+        board_name: &str,
+    ) -> Result<bool> {
+        // Build on edits still waiting to be saved
+        let Board {
+            mut board,
+            items: board_items,
+            version,
+        } = self.get_board(board_name).await?;
+
+        // Generate a unique ID for this board item
+        // Use container name if available (plus the URL hash, since one
+        // container can declare several tiles), otherwise a hash of the URL
+        let item_id = if let Some(container) = app.container_name() {
+            format!("registry-{}-{:x}", container, string_hash(&app.url))
+        } else {
+            format!("registry-{:x}", string_hash(&app.url))
+        };
+        let widget_id = format!("{}-widget", item_id);
+
+        // Check if this app (and its widget, if any) is already on the board
+        let existing_tile = app_item_placement(&board_items, app_id);
+        let widget_wanted = app.widget.is_some() && !board_has_item(&board_items, &widget_id);
+        if existing_tile.is_some() && !widget_wanted {
+            tracing::info!(
+                "Registry app '{}' already on board '{}', skipping",
+                app.name,
+                board_name
+            );
+            return Ok(false);
+        }
+        let placed = existing_tile.is_none();
+
+        // Compose project and category sections are created on demand,
+        // saved with the tile. Their IDs are remembered, so one renamed in
+        // Homarr keeps its tiles rather than being created again.
+        let known_section = self
+            .created_section(app)
+            .and_then(|name| self.known_section_id(&board.id, &board.sections, name));
+        if let (None, Some(name)) = (&known_section, self.created_section(app)) {
+            let created = on_demand_section(&board.id, &board.sections, name);
+            if !created.is_empty() {
+                tracing::info!("Creating section '{}' on board '{}'", name, board_name);
+                board.sections.extend(created);
+            }
+        }
+
+        let section_id = known_section
+            .unwrap_or_else(|| resolve_section_id(&board.sections, self.wanted_section(app)));
+        if let Some(name) = self.created_section(app) {
+            self.section_ids
+                .lock()
+                .unwrap()
+                .entry(board.id.clone())
+                .or_default()
+                .insert(name.to_lowercase(), section_id.clone());
+        }
+        let (layout_id, column_count) = board
+            .layouts
+            .first()
+            .map(|l| (l.id.clone(), l.column_count))
+            .unwrap_or_else(|| (String::new(), 12));
+
+        let mut items: Vec<serde_json::Value> = board_items;
+
+        let tile = match existing_tile {
+            Some(tile) => tile,
+            None => {
+                // Get layout preferences from registry
+                let layout = app.effective_layout();
+                let width = layout.width as i32;
+                let height = layout.height as i32;
+
+                // Use explicit position if provided, otherwise auto-position
+                let (section_id, (x_offset, y_offset)) = match (layout.x_offset, layout.y_offset) {
+                    (Some(x), Some(y)) => (section_id, (x as i32, y as i32)),
+                    _ => self.place_new_tile(
+                        &board.id,
+                        &mut board.sections,
+                        &items,
+                        Grid {
+                            layout_id: &layout_id,
+                            section_id: &section_id,
+                            column_count,
+                        },
+                        (width, height),
+                    ),
+                };
+                let overflow = &self.section_limits.overflow_section;
+                if board
+                    .sections
+                    .iter()
+                    .any(|s| s.id == section_id && s.is_category_named(overflow))
+                {
+                    tracing::info!(
+                        "Section full on board '{}', placing '{}' in '{}'",
+                        board_name,
+                        app.name,
+                        overflow
+                    );
+                    self.section_ids
+                        .lock()
+                        .unwrap()
+                        .entry(board.id.clone())
+                        .or_default()
+                        .insert(overflow.to_lowercase(), section_id.clone());
+                }
+
+                items.push(json!({
+                    "id": item_id,
+                    "kind": "app",
+                    "options": {
+                        "appId": app_id
+                    },
+                    "layouts": [{
+                        "layoutId": layout_id,
+                        "sectionId": section_id,
+                        "width": width,
+                        "height": height,
+                        "xOffset": x_offset,
+                        "yOffset": y_offset
+                    }],
+                    "integrationIds": [],
+                    "advancedOptions": {
+                        "customCssClasses": app.css_classes
+                    }
+                }));
+
+                tracing::debug!(
+                    "Added registry app '{}' to board at ({}, {}) size {}x{}",
+                    app.name,
+                    x_offset,
+                    y_offset,
+                    width,
+                    height
+                );
+
+                TilePlacement {
+                    section_id,
+                    x: x_offset,
+                    y: y_offset,
+                    width,
+                    height,
+                }
+            }
+        };
+
+        if let (true, Some(kind)) = (widget_wanted, app.widget.as_deref()) {
+            self.add_app_widget(
+                &mut items,
+                app,
+                kind,
+                &widget_id,
+                &layout_id,
+                &tile,
+                column_count,
+            )
+            .await;
+        }
+
+        self.save_items(
+            board_name,
+            Board {
+                board,
+                items,
+                version,
+            },
+        );
+
+        Ok(placed)
+    }
+
+    /// Staged edits to a board, if any
+    fn staged_board(&self, board_name: &str) -> Option<Board> {
+        self.board_writes
+            .lock()
+            .unwrap()
+            .pending
+            .get(board_name)
+            .cloned()
+    }
+
+    /// Save a board's items (and sections)
+    ///
+    /// The write is staged and made by `flush_board_saves`, together with
+    /// other edits to the same board.
+    pub fn save_items(&self, board_name: &str, board: Board) {
+        self.board_writes
+            .lock()
+            .unwrap()
+            .pending
+            .insert(board_name.to_string(), board);
+    }
+
+    /// A board with its items, including staged edits
+    pub async fn get_board(&self, board_name: &str) -> Result<Board> {
+        match self.staged_board(board_name) {
+            Some(staged) => Ok(staged),
+            None => self.read_board(board_name).await,
+        }
+    }
+
+    /// A board with its items as Homarr has it now
+    async fn read_board(&self, board_name: &str) -> Result<Board> {
+        let url = self.query_url("board.getBoardByName", json!({ "name": board_name }))?;
+        let response = self.get(&url).await?;
+        if !response.status().is_success() {
+            return Err(AdapterError::HomarrApi("Board not found".to_string()));
+        }
+
+        let mut json: serde_json::Value = read_trpc(response).await?;
+        let items = match json.get_mut("items").map(serde_json::Value::take) {
+            Some(serde_json::Value::Array(items)) => items,
+            _ => vec![],
+        };
+        Ok(Board::read(serde_json::from_value(json)?, items))
+    }
+
+    /// Take an app's tile (and its widget) off a board
+    ///
+    /// Returns the removed items, with their IDs and positions, for
+    /// `restore_tile`. Empty if the app has no tile on the board.
+    pub async fn hide_tile(
+        &self,
+        app_id: &str,
+        board_name: &str,
+    ) -> Result<Vec<serde_json::Value>> {
+        let mut board = self.get_board(board_name).await?;
+        let item_id = |item: &serde_json::Value| {
+            item.get("id")
+                .and_then(|i| i.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let Some(tile_id) = board
+            .items
+            .iter()
+            .find(|item| board_has_app(std::slice::from_ref(*item), app_id))
+            .map(item_id)
+        else {
+            return Ok(vec![]);
+        };
+        let widget_id = format!("{}-widget", tile_id);
+
+        let (hidden, kept): (Vec<_>, Vec<_>) = board.items.into_iter().partition(|item| {
+            let id = item_id(item);
+            id == tile_id || id == widget_id
+        });
+        board.items = kept;
+        self.save_items(board_name, board);
+
+        tracing::info!("Hid tile '{}' on board '{}'", tile_id, board_name);
+        Ok(hidden)
+    }
+
+    /// Put items taken off by `hide_tile` back where they were
+    ///
+    /// Returns false, leaving the board alone, if something else took their
+    /// spot meanwhile; the next sync then places the tile like a new one.
+    pub async fn restore_tile(
+        &self,
+        board_name: &str,
+        hidden: Vec<serde_json::Value>,
+    ) -> Result<bool> {
+        let mut board = self.get_board(board_name).await?;
+        if hidden.iter().any(|item| item_overlaps(&board.items, item)) {
+            return Ok(false);
+        }
+        board.items.extend(hidden);
+        self.save_items(board_name, board);
+        Ok(true)
+    }
+
+    /// Add an integration widget for an app next to its tile
+    ///
+    /// The widget is linked to the Homarr integration matching the app's URL
+    /// or name; without one the widget is skipped, since it would only show
+    /// an error.
+    #[allow(clippy::too_many_arguments)]
+    async fn add_app_widget(
+        &self,
+        items: &mut Vec<serde_json::Value>,
+        app: &AppDefinition,
+        kind: &str,
+        widget_id: &str,
+        layout_id: &str,
+        tile: &TilePlacement,
+        column_count: i32,
+    ) {
+        let integrations = match self.get_integrations().await {
+            Ok(integrations) => integrations,
+            Err(e) => {
+                tracing::warn!("Failed to fetch integrations for '{}' widget: {}", kind, e);
+                return;
+            }
+        };
+        let Some(integration) = find_integration_for(&integrations, app) else {
+            tracing::warn!(
+                "No Homarr integration found for app '{}', skipping '{}' widget",
+                app.name,
+                kind
+            );
+            return;
+        };
+
+        let (width, height) = WIDGET_SIZE;
+        let grid = Grid {
+            layout_id,
+            section_id: &tile.section_id,
+            column_count,
+        };
+        let (x, y) = place_adjacent(items, grid, tile, (width, height))
+            .unwrap_or_else(|| layout::first_free_slot(items, grid, (width, height)));
+
+        items.push(json!({
+            "id": widget_id,
+            "kind": kind,
+            "options": {},
+            "layouts": [{
+                "layoutId": layout_id,
+                "sectionId": tile.section_id,
+                "width": width,
+                "height": height,
+                "xOffset": x,
+                "yOffset": y
+            }],
+            "integrationIds": [integration.id],
+            "advancedOptions": {
+                "customCssClasses": []
+            }
+        }));
+
+        tracing::info!(
+            "Added '{}' widget for app '{}' (integration '{}') at ({}, {})",
+            kind,
+            app.name,
+            integration.name,
+            x,
+            y
+        );
+    }
+
+    /// Get all configured integrations, listed once per sync cycle
+    async fn get_integrations(&self) -> Result<Vec<Integration>> {
+        if let Some(integrations) = self.integrations.lock().unwrap().clone() {
+            return Ok(integrations);
+        }
+        let url = format!("{}/api/trpc/integration.all", self.base_url);
+        let response = self.get(&url).await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(AdapterError::HomarrApi(format!(
+                "Failed to fetch integrations ({}): {}",
+                status, text
+            )));
+        }
+
+        let integrations: Vec<Integration> = read_trpc(response).await?;
+        *self.integrations.lock().unwrap() = Some(integrations.clone());
+        Ok(integrations)
+    }
+
+    /// Get board items
+    pub(crate) async fn get_board_items(&self, board_name: &str) -> Result<Vec<serde_json::Value>> {
+        let url = self.query_url("board.getBoardByName", json!({ "name": board_name }))?;
+
+        let response = self.get(&url).await?;
+
+        if !response.status().is_success() {
+            return Ok(vec![]);
+        }
+
+        // Parse the full board response to get items
+        let json: serde_json::Value = response.json().await?;
+        let items = json
+            .get("result")
+            .and_then(|r| r.get("data"))
+            .and_then(|d| d.get("json"))
+            .and_then(|j| j.get("items"))
+            .and_then(|i| i.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(items)
+    }
+
+    /// Render the board grid per layout as it would look after the next sync.
+    ///
+    /// `apps` are the apps the sync would place on this board; those already
+    /// on it are drawn where they are, the rest where auto-positioning would
+    /// put them.
+    pub async fn preview_board(
+        &self,
+        board_name: &str,
+        apps: &[&AppDefinition],
+        existing_apps: &[SelectableApp],
+    ) -> Result<String> {
+        let board = self.get_board_by_name(board_name).await?;
+        let items = self.get_board_items(board_name).await?;
+        Ok(self.render_board_preview(&board, &items, apps, existing_apps))
+    }
+
+    fn render_board_preview(
+        &self,
+        board: &BoardResponse,
+        items: &[serde_json::Value],
+        apps: &[&AppDefinition],
+        existing_apps: &[SelectableApp],
+    ) -> String {
+        // Apps not yet on the board, with the section they'd land in
+        let missing = Self::apps_missing_from(items, apps, existing_apps);
+        let mut sections = board.sections.clone();
+        self.add_created_sections(&board.id, &mut sections, &missing);
+        let planned: Vec<(&AppDefinition, String)> = missing
+            .into_iter()
+            .map(|app| {
+                let known = self
+                    .created_section(app)
+                    .and_then(|name| self.known_section_id(&board.id, &board.sections, name));
+                let section = known
+                    .unwrap_or_else(|| resolve_section_id(&sections, self.wanted_section(app)));
+                (app, section)
+            })
+            .collect();
+
+        let mut out = String::new();
+        for layout in &board.layouts {
+            out.push_str(&format!(
+                "Layout '{}' ({} columns)\n",
+                layout.name, layout.column_count
+            ));
+
+            let mut layout_items = items_in_layout(items, &layout.id);
+            let mut tiles: Vec<(String, PreviewTile)> = layout_items
+                .iter()
+                .filter_map(|item| {
+                    let entry = item.get("layouts")?.get(0)?;
+                    let section_id = entry.get("sectionId")?.as_str()?.to_string();
+                    let label = item
+                        .get("options")
+                        .and_then(|o| o.get("appId"))
+                        .and_then(|a| a.as_str())
+                        .and_then(|id| existing_apps.iter().find(|a| a.id == id))
+                        .map(|a| a.name.clone())
+                        .unwrap_or_else(|| {
+                            let kind = item.get("kind").and_then(|k| k.as_str()).unwrap_or("item");
+                            format!("<{}>", kind)
+                        });
+                    let get = |key: &str, default: i64| {
+                        entry.get(key).and_then(|v| v.as_i64()).unwrap_or(default) as i32
+                    };
+                    Some((
+                        section_id,
+                        PreviewTile {
+                            label,
+                            x: get("xOffset", 0),
+                            y: get("yOffset", 0),
+                            width: get("width", 1),
+                            height: get("height", 1),
+                            planned: false,
+                        },
+                    ))
+                })
+                .collect();
+
+            for (app, section_id) in &planned {
+                let app_layout = app.effective_layout();
+                let (width, height) = (app_layout.width as i32, app_layout.height as i32);
+                let (section_id, (x, y)) = match (app_layout.x_offset, app_layout.y_offset) {
+                    (Some(x), Some(y)) => (section_id.clone(), (x as i32, y as i32)),
+                    _ => self.place_new_tile(
+                        &board.id,
+                        &mut sections,
+                        &layout_items,
+                        Grid {
+                            layout_id: &layout.id,
+                            section_id,
+                            column_count: layout.column_count,
+                        },
+                        (width, height),
+                    ),
+                };
+                layout_items.push(json!({
+                    "layouts": [{
+                        "layoutId": layout.id,
+                        "sectionId": section_id,
+                        "width": width,
+                        "height": height,
+                        "xOffset": x,
+                        "yOffset": y
+                    }]
+                }));
+                tiles.push((
+                    section_id.clone(),
+                    PreviewTile {
+                        label: app.name.clone(),
+                        x,
+                        y,
+                        width,
+                        height,
+                        planned: true,
+                    },
+                ));
+            }
+
+            for section in &sections {
+                let section_tiles: Vec<PreviewTile> = tiles
+                    .iter()
+                    .filter(|(id, _)| *id == section.id)
+                    .map(|(_, tile)| tile.clone())
+                    .collect();
+                if section_tiles.is_empty() {
+                    continue;
+                }
+                match section.name {
+                    Some(ref name) => out.push_str(&format!("Section '{}'\n", name)),
+                    None => out.push_str(&format!("Section {} ({})\n", section.id, section.kind)),
+                }
+                out.push_str(&render_preview_grid(layout.column_count, &section_tiles));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Exercise every endpoint the adapter depends on.
+    ///
+    /// Queries are called and their responses decoded; mutations are probed
+    /// with invalid input so nothing is changed.
+    pub async fn check_api(&self) -> Vec<EndpointCheck> {
+        fn check<T>(
+            endpoint: &'static str,
+            result: Result<T>,
+            detail: impl FnOnce(T) -> String,
+        ) -> EndpointCheck {
+            match result {
+                Ok(value) => EndpointCheck {
+                    endpoint,
+                    ok: true,
+                    detail: detail(value),
+                },
+                Err(e) => EndpointCheck {
+                    endpoint,
+                    ok: false,
+                    detail: e.to_string(),
+                },
+            }
+        }
+
+        let mut checks = Vec::new();
+
+        let csrf_url = format!("{}/api/auth/csrf", self.base_url);
+        let csrf: Result<CsrfResponse> = match self.client.get(&csrf_url).send().await {
+            Ok(response) => response.json().await.map_err(AdapterError::from),
+            Err(e) => Err(e.into()),
+        };
+        checks.push(check("auth.csrf", csrf, |_| "ok".to_string()));
+
+        checks.push(check(
+            "onboard.currentStep",
+            self.get_onboarding_step().await,
+            |step| format!("current step: {}", step.current),
+        ));
+
+        checks.push(check("app.selectable", self.list_apps().await, |apps| {
+            format!("{} app(s)", apps.len())
+        }));
+
+        checks.push(check(
+            "integration.all",
+            self.get_integrations().await,
+            |integrations| format!("{} integration(s)", integrations.len()),
+        ));
+
+        checks.push(check(
+            "searchEngine.getPaginated",
+            self.list_search_engines().await,
+            |engines| format!("{} search engine(s)", engines.len()),
+        ));
+
+        checks.push(check(
+            "group.selectable",
+            self.list_groups().await,
+            |groups| format!("{} group(s)", groups.len()),
+        ));
+
+        let boards = self.get_all_boards().await;
+        let first_board = boards
+            .as_ref()
+            .ok()
+            .and_then(|b| b.first())
+            .map(|b| (b.id.clone(), b.name.clone()));
+        checks.push(check("board.getAllBoards", boards, |boards| {
+            format!("{} board(s)", boards.len())
+        }));
+
+        match first_board {
+            Some((id, name)) => {
+                checks.push(check(
+                    "board.getBoardByName",
+                    self.get_board_by_name(&name).await,
+                    |board| {
+                        format!(
+                            "board '{}': {} section(s), {} layout(s)",
+                            board.name,
+                            board.sections.len(),
+                            board.layouts.len()
+                        )
+                    },
+                ));
+                checks.push(check(
+                    "board.getBoardPermissions",
+                    self.get_board_permissions(&id).await,
+                    |permissions| {
+                        format!(
+                            "board '{}': {} group permission(s)",
+                            name,
+                            permissions.len()
+                        )
+                    },
+                ));
+            }
+            None => {
+                for endpoint in ["board.getBoardByName", "board.getBoardPermissions"] {
+                    checks.push(EndpointCheck {
+                        endpoint,
+                        ok: false,
+                        detail: "skipped (no boards to read)".to_string(),
+                    });
+                }
+            }
+        }
+
+        for &procedure in PROBED_MUTATIONS {
+            let url = format!("{}/api/trpc/{}", self.base_url, procedure);
+            let probe = SuperJson::new(json!("api-check"));
+            let (ok, detail) = match self.post_json(&url, &probe).await {
+                Ok(response) => classify_mutation_probe(response.status()),
+                Err(e) => (false, e.to_string()),
+            };
+            checks.push(EndpointCheck {
+                endpoint: procedure,
+                ok,
+                detail,
+            });
+        }
+
+        checks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn create_test_client() -> HomarrClient {
+        HomarrClient::new("http://localhost:7575").unwrap()
+    }
+
+    // HomarrClient creation tests
+    #[test]
+    fn test_client_new_valid_url() {
+        let client = HomarrClient::new("http://localhost:7575");
+        assert!(client.is_ok());
+    }
+
+    #[test]
+    fn test_client_with_pool_options() {
+        let pool = PoolOptions {
+            idle_timeout: Duration::from_secs(5),
+            max_idle_per_host: 1,
+            tcp_keepalive: Duration::from_secs(10),
+        };
+        let client = HomarrClient::with_pool_options("http://localhost:7575/", &pool).unwrap();
+        assert_eq!(client.base_url, "http://localhost:7575");
+    }
+
+    #[test]
+    fn test_retry_only_safe_attempts() {
+        use AttemptOutcome::*;
+        let RequestPolicies { read, mutation } = RequestPolicies::default();
+
+        // Nothing reached the server: always safe to send again
+        assert!(ConnectFailed.retryable(true, &read));
+        assert!(ConnectFailed.retryable(false, &mutation));
+
+        // The server may have applied a mutation that timed out or failed
+        assert!(TimedOut.retryable(true, &read));
+        assert!(!TimedOut.retryable(false, &mutation));
+        assert!(Response(StatusCode::BAD_GATEWAY).retryable(true, &read));
+        assert!(!Response(StatusCode::BAD_GATEWAY).retryable(false, &mutation));
+
+        assert!(!Response(StatusCode::OK).retryable(true, &read));
+        assert!(!Response(StatusCode::UNAUTHORIZED).retryable(true, &read));
+        assert!(!OtherError.retryable(true, &read));
+
+        // Statuses are classified per policy
+        let strict = RequestPolicy {
+            retry_statuses: vec![StatusCode::SERVICE_UNAVAILABLE],
+            ..read
+        };
+        assert!(!Response(StatusCode::BAD_GATEWAY).retryable(true, &strict));
+        assert!(Response(StatusCode::SERVICE_UNAVAILABLE).retryable(true, &strict));
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        let policy = RequestPolicies::default().read;
+        assert_eq!(policy.backoff_delay(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff_delay(2), Duration::from_secs(1));
+        assert_eq!(policy.backoff_delay(3), Duration::from_secs(2));
+        assert_eq!(policy.backoff_delay(100), Duration::from_secs(30));
+
+        // Jitter takes off up to half
+        for _ in 0..20 {
+            let delay = policy.retry_delay(3);
+            assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_secs(2));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mutation_retries_connection_failures() {
+        // Nothing listens on the port, so every attempt fails to connect
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut client = HomarrClient::new(&format!("http://127.0.0.1:{}", port)).unwrap();
+        let defaults = RequestPolicies::default();
+        client.set_request_policies(RequestPolicies {
+            mutation: RequestPolicy {
+                timeout: Duration::from_secs(1),
+                retries: 1,
+                ..defaults.mutation.clone()
+            },
+            ..defaults.clone()
+        });
+
+        let started = Instant::now();
+        let err = client
+            .post_json(
+                &format!("{}/api/trpc/x", client.base_url),
+                &SuperJson::new(json!({})),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AdapterError::Http(ref e) if e.is_connect()));
+        assert!(started.elapsed() >= defaults.mutation.backoff_delay(1) / 2);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = chrono::DateTime::parse_from_rfc3339("2015-10-21T07:28:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn test_long_retry_after_ends_request() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            use std::io::{Read, Write};
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0u8; 4096]);
+            let _ = stream.write_all(
+                b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 120\r\nContent-Length: 0\r\n\r\n",
+            );
+        });
+        let client = HomarrClient::new(&format!("http://127.0.0.1:{}", port)).unwrap();
+
+        let err = client
+            .post_json(
+                &format!("{}/api/trpc/x", client.base_url),
+                &SuperJson::new(json!({})),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AdapterError::RateLimited(120)));
+        assert_eq!(client.take_rate_limited(), 1);
+        assert_eq!(client.take_rate_limited(), 0);
+    }
+
+    #[test]
+    fn test_session_expires_within_margin() {
+        let now = chrono::Utc::now();
+        let session = |expires| Session {
+            username: "admin".to_string(),
+            password: "secret".to_string(),
+            expires,
+            generation: 1,
+        };
+        let margin = SESSION_REFRESH_MARGIN;
+        assert!(!session(None).expires_within(margin, now));
+        assert!(!session(Some(now + chrono::Duration::hours(1))).expires_within(margin, now));
+        assert!(session(Some(now + chrono::Duration::minutes(2))).expires_within(margin, now));
+        assert!(session(Some(now - chrono::Duration::minutes(1))).expires_within(margin, now));
+    }
+
+    #[tokio::test]
+    async fn test_sign_in_again_needs_session() {
+        let client = create_test_client();
+        assert!(!client.has_session().await);
+        assert_eq!(client.session_generation().await, None);
+        assert!(!client.sign_in_again(0).await);
+    }
+
+    #[test]
+    fn test_client_new_strips_trailing_slash() {
+        let client = HomarrClient::new("http://localhost:7575/").unwrap();
+        assert_eq!(client.base_url, "http://localhost:7575");
+    }
+
+    #[test]
+    fn test_client_new_preserves_path() {
+        let client = HomarrClient::new("http://localhost:7575/homarr").unwrap();
+        assert_eq!(client.base_url, "http://localhost:7575/homarr");
+    }
+
+    // transform_icon_url tests
+
+    #[test]
+    fn test_transform_icon_url_pixmaps_path() {
+        // File path in /usr/share/pixmaps should become relative /icons/filename
+        let result = transform_icon_url("/usr/share/pixmaps/app.png");
+        assert_eq!(result, "/icons/app.png");
+    }
+
+    #[test]
+    fn test_transform_icon_url_pixmaps_nested_path() {
+        // Nested paths should preserve directory structure after pixmaps/
+        let result = transform_icon_url("/usr/share/pixmaps/subdir/icon.svg");
+        assert_eq!(result, "/icons/subdir/icon.svg");
+    }
+
+    #[test]
+    fn test_transform_icon_url_http_passthrough() {
+        // HTTP URLs should pass through unchanged
+        let result = transform_icon_url("http://example.com/icon.png");
+        assert_eq!(result, "http://example.com/icon.png");
+    }
+
+    #[test]
+    fn test_transform_icon_url_https_passthrough() {
+        // HTTPS URLs should pass through unchanged
+        let result = transform_icon_url("https://cdn.example.com/icons/docker.svg");
+        assert_eq!(result, "https://cdn.example.com/icons/docker.svg");
+    }
+
+    #[test]
+    fn test_transform_icon_url_empty_string() {
+        // Empty string should return default icon path
+        let result = transform_icon_url("");
+        assert_eq!(result, "/icons/docker.svg");
+    }
+
+    #[test]
+    fn test_transform_icon_url_unrecognized_path() {
+        // Unrecognized file paths should return default icon path
+        let result = transform_icon_url("/some/other/path/icon.png");
+        assert_eq!(result, "/icons/docker.svg");
+    }
+
+    #[test]
+    fn test_transform_icon_url_relative_path() {
+        // Relative paths should return default icon path
+        let result = transform_icon_url("icons/app.png");
+        assert_eq!(result, "/icons/docker.svg");
+    }
+
+    #[test]
+    fn test_transform_icon_url_icons_path_passthrough() {
+        // Already relative /icons/ paths should pass through unchanged
+        let result = transform_icon_url("/icons/existing.svg");
+        assert_eq!(result, "/icons/existing.svg");
+    }
+
+    #[test]
+    fn test_transform_icon_url_pixmaps_trailing_slash_only() {
+        // Edge case: pixmaps path with trailing slash but no filename
+        let result = transform_icon_url("/usr/share/pixmaps/");
+        assert_eq!(result, "/icons/docker.svg");
+    }
+
+    // Tests for board item deduplication (issue #15)
+
+    #[test]
+    fn test_board_has_app_finds_existing() {
+        let items = vec![
+            json!({
+                "id": "discovered-abc123",
+                "kind": "app",
+                "options": {
+                    "appId": "app-xyz-123"
+                }
+            }),
+            json!({
+                "id": "discovered-def456",
+                "kind": "app",
+                "options": {
+                    "appId": "app-other-456"
+                }
+            }),
+        ];
+
+        assert!(board_has_app(&items, "app-xyz-123"));
+        assert!(board_has_app(&items, "app-other-456"));
+        assert!(!board_has_app(&items, "app-nonexistent"));
+    }
+
+    #[test]
+    fn test_board_has_app_handles_empty_board() {
+        let items: Vec<serde_json::Value> = vec![];
+        assert!(!board_has_app(&items, "any-app-id"));
+    }
+
+    #[test]
+    fn test_board_has_app_handles_malformed_items() {
+        let items = vec![
+            json!({"id": "item-without-options"}),
+            json!({"id": "item-with-empty-options", "options": {}}),
+            json!({"id": "item-with-null-appid", "options": {"appId": null}}),
+        ];
+
+        // Should not crash and should return false for all
+        assert!(!board_has_app(&items, "any-app-id"));
+    }
+
+    // Tests for derive_ping_url (auto-derive host.docker.internal URL for health checks)
+
+    #[test]
+    fn test_derive_ping_url_replaces_hostname() {
+        let result = derive_ping_url("http://test.example.local:3000");
+        assert_eq!(
+            result,
+            Some("http://host.docker.internal:3000/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_derive_ping_url_preserves_path() {
+        let result = derive_ping_url("http://test.example.local:8086/api/v2");
+        assert_eq!(
+            result,
+            Some("http://host.docker.internal:8086/api/v2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_derive_ping_url_preserves_https() {
+        let result = derive_ping_url("https://test.example.local:443/app");
+        assert_eq!(result, Some("https://host.docker.internal/app".to_string()));
+    }
+
+    #[test]
+    fn test_derive_ping_url_handles_no_port() {
+        let result = derive_ping_url("http://test.example.local/dashboard");
+        assert_eq!(
+            result,
+            Some("http://host.docker.internal/dashboard".to_string())
+        );
+    }
+
+    #[test]
+    fn test_derive_ping_url_invalid_url_returns_none() {
+        let result = derive_ping_url("not-a-valid-url");
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_ping_url_for() {
+        let mut app = AppDefinition {
+            url: "http://halos.local:3000".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            ping_url_for(&app).as_deref(),
+            Some("http://host.docker.internal:3000/")
+        );
+        app.ping = Some(true);
+        assert_eq!(
+            ping_url_for(&app).as_deref(),
+            Some("http://halos.local:3000")
+        );
+        app.ping = Some(false);
+        assert_eq!(ping_url_for(&app), None);
+
+        // External apps are pinged only when asked to
+        app.app_type.external = true;
+        app.ping = None;
+        assert_eq!(ping_url_for(&app), None);
+        app.ping = Some(true);
+        app.ping_url = Some("https://status.example.com".to_string());
+        assert_eq!(
+            ping_url_for(&app).as_deref(),
+            Some("https://status.example.com")
+        );
+    }
+
+    #[test]
+    fn test_derive_ping_url_path_only() {
+        let result = derive_ping_url("/cockpit/");
+        assert_eq!(
+            result,
+            Some("https://host.docker.internal/cockpit/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_derive_ping_url_path_only_signalk() {
+        let result = derive_ping_url("/signalk-server/@signalk/freeboard-sk/");
+        assert_eq!(
+            result,
+            Some("https://host.docker.internal/signalk-server/@signalk/freeboard-sk/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_derive_ping_url_protocol_relative_returns_none() {
+        // `//host/path` must not be treated as path-only.
+        let result = derive_ping_url("//evil.example.com/path");
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_derive_ping_url_lone_slash_returns_none() {
+        // Lone `/` is rejected by `is_path_only` and falls through to
+        // `Url::parse`, which also rejects it. Both call sites agree.
+        let result = derive_ping_url("/");
+        assert_eq!(result, None);
+    }
+
+    // Tests for BoardPermission enum
 
-        let url = format!("{}/api/trpc/board.saveBoard", self.base_url);
+    #[test]
+    fn test_board_permission_view_not_writable() {
+        assert!(!BoardPermission::View.is_writable());
+    }
 
-        let mut items: Vec<serde_json::Value> = board_items;
-        items.push(json!({
-            "id": item_id,
-            "kind": "app",
-            "options": {
-                "appId": app_id
-            },
-            "layouts": [{
-                "layoutId": layout_id,
-                "sectionId": section_id,
-                "width": width,
-                "height": height,
-                "xOffset": x_offset,
-                "yOffset": y_offset
-            }],
-            "integrationIds": [],
-            "advancedOptions": {
-                "customCssClasses": []
-            }
-        }));
+    #[test]
+    fn test_board_permission_modify_is_writable() {
+        assert!(BoardPermission::Modify.is_writable());
+    }
 
-        let payload = json!({
-            "json": {
-                "id": board.id,
-                "sections": board.sections,
-                "items": items,
-                "integrations": []
-            }
-        });
+    #[test]
+    fn test_board_permission_full_is_writable() {
+        assert!(BoardPermission::Full.is_writable());
+    }
 
-        self.post_json(&url, &payload).await?;
+    // Tests for BoardWithPermission
 
-        tracing::debug!(
-            "Added registry app '{}' to board at ({}, {}) size {}x{}",
-            app.name,
-            x_offset,
-            y_offset,
-            width,
-            height
-        );
+    #[test]
+    fn test_board_with_permission_deserialize() {
+        // Test that BoardWithPermission deserializes from actual Homarr API format
+        let json = r#"{
+            "id": "board-1",
+            "name": "Test Board",
+            "isPublic": true,
+            "userPermissions": [],
+            "groupPermissions": []
+        }"#;
+        let board: BoardWithPermission = serde_json::from_str(json).unwrap();
+        assert_eq!(board.id, "board-1");
+        assert_eq!(board.name, "Test Board");
+        assert!(board.is_public);
+        assert!(board.user_permissions.is_empty());
+        assert!(board.group_permissions.is_empty());
+    }
 
-        Ok(())
+    #[test]
+    fn test_board_with_permission_is_writable() {
+        // For admin users, all boards are writable
+        let json = r#"{
+            "id": "board-1",
+            "name": "Test Board",
+            "isPublic": false,
+            "userPermissions": [],
+            "groupPermissions": []
+        }"#;
+        let board: BoardWithPermission = serde_json::from_str(json).unwrap();
+        assert!(board.is_writable());
     }
 
-    /// Get board items
-    async fn get_board_items(&self, board_name: &str) -> Result<Vec<serde_json::Value>> {
-        let url = format!(
-            "{}/api/trpc/board.getBoardByName?input={}",
-            self.base_url,
-            urlencoding::encode(&format!("{{\"json\":{{\"name\":\"{}\"}}}}", board_name))
-        );
+    #[test]
+    fn test_board_with_permission_defaults() {
+        // Test that missing optional fields use defaults
+        let json = r#"{"id": "board-1", "name": "Test", "isPublic": true}"#;
+        let board: BoardWithPermission = serde_json::from_str(json).unwrap();
+        assert!(board.user_permissions.is_empty());
+        assert!(board.group_permissions.is_empty());
+    }
 
-        let response = self.get(&url).await?;
+    // Tests for URL normalization (issue #41 - duplicate apps)
 
-        if !response.status().is_success() {
-            return Ok(vec![]);
-        }
+    #[test]
+    fn test_normalize_url_removes_trailing_slash() {
+        assert_eq!(
+            normalize_url("http://localhost:3000/app/"),
+            "http://localhost:3000/app"
+        );
+    }
 
-        // Parse the full board response to get items
-        let json: serde_json::Value = response.json().await?;
-        let items = json
-            .get("result")
-            .and_then(|r| r.get("data"))
-            .and_then(|d| d.get("json"))
-            .and_then(|j| j.get("items"))
-            .and_then(|i| i.as_array())
-            .cloned()
-            .unwrap_or_default();
+    #[test]
+    fn test_normalize_url_preserves_root_path() {
+        // Root path "/" should not be stripped
+        assert_eq!(
+            normalize_url("http://localhost:3000/"),
+            "http://localhost:3000/"
+        );
+    }
 
-        Ok(items)
+    #[test]
+    fn test_normalize_url_lowercases_host() {
+        assert_eq!(
+            normalize_url("http://MyHost.LOCAL:3000/App"),
+            "http://myhost.local:3000/App" // Note: path case preserved
+        );
     }
 
-    /// Find next available position on the board (simple left-to-right, top-to-bottom)
-    fn find_next_position(&self, items: &[serde_json::Value], column_count: i32) -> (i32, i32) {
-        let mut max_y = 0;
-        let mut positions_in_max_row: Vec<i32> = vec![];
+    #[test]
+    fn test_normalize_url_removes_default_http_port() {
+        assert_eq!(normalize_url("http://localhost:80/"), "http://localhost/");
+    }
 
-        for item in items {
-            if let Some(layouts) = item.get("layouts").and_then(|l| l.as_array()) {
-                for layout in layouts {
-                    let x = layout.get("xOffset").and_then(|x| x.as_i64()).unwrap_or(0) as i32;
-                    let y = layout.get("yOffset").and_then(|y| y.as_i64()).unwrap_or(0) as i32;
-                    let h = layout.get("height").and_then(|h| h.as_i64()).unwrap_or(1) as i32;
+    #[test]
+    fn test_normalize_url_removes_default_https_port() {
+        assert_eq!(
+            normalize_url("https://localhost:443/app"),
+            "https://localhost/app"
+        );
+    }
 
-                    let item_bottom = y + h;
-                    if item_bottom > max_y {
-                        max_y = item_bottom;
-                        positions_in_max_row.clear();
-                    }
-                    if y + h == max_y {
-                        let w = layout.get("width").and_then(|w| w.as_i64()).unwrap_or(1) as i32;
-                        for col in x..(x + w) {
-                            positions_in_max_row.push(col);
-                        }
-                    }
-                }
-            }
-        }
+    #[test]
+    fn test_normalize_url_preserves_non_default_port() {
+        assert_eq!(
+            normalize_url("http://localhost:3000/"),
+            "http://localhost:3000/"
+        );
+    }
 
-        // Find first empty column in the last row, or start new row
-        for x in 0..column_count {
-            if !positions_in_max_row.contains(&x) {
-                return (x, max_y.saturating_sub(1).max(0));
-            }
-        }
+    #[test]
+    fn test_normalize_url_handles_invalid_url() {
+        // Should return original string for invalid URLs
+        assert_eq!(normalize_url("not-a-url"), "not-a-url");
+    }
 
-        // All columns full, start new row
-        (0, max_y)
+    #[test]
+    fn test_normalize_url_matching_with_trailing_slash_difference() {
+        // This is the key test for issue #41 - URLs that differ only by trailing slash
+        let url_with_slash = normalize_url("http://localhost:3000/app/");
+        let url_without_slash = normalize_url("http://localhost:3000/app");
+        assert_eq!(url_with_slash, url_without_slash);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde_json::json;
+    // Tests for superjson encoding
 
-    fn create_test_client() -> HomarrClient {
-        HomarrClient::new("http://localhost:7575").unwrap()
+    #[test]
+    fn test_superjson_plain_payload_has_no_meta() {
+        let payload = serde_json::to_value(SuperJson::new(json!({"id": "board-1"}))).unwrap();
+        assert_eq!(payload, json!({"json": {"id": "board-1"}}));
     }
 
-    // HomarrClient creation tests
     #[test]
-    fn test_client_new_valid_url() {
-        let client = HomarrClient::new("http://localhost:7575");
-        assert!(client.is_ok());
+    fn test_superjson_split_path_escaped_dot() {
+        assert_eq!(
+            split_superjson_path(r"options.a\.b.c"),
+            vec!["options", "a.b", "c"]
+        );
+        assert!(split_superjson_path("").is_empty());
     }
 
     #[test]
-    fn test_client_new_strips_trailing_slash() {
-        let client = HomarrClient::new("http://localhost:7575/").unwrap();
-        assert_eq!(client.base_url, "http://localhost:7575");
+    fn test_superjson_decode_date_into_chrono() {
+        #[derive(Deserialize)]
+        struct Item {
+            #[serde(rename = "updatedAt")]
+            updated_at: chrono::DateTime<chrono::Utc>,
+        }
+        let raw: SuperJson = serde_json::from_value(json!({
+            "json": {"updatedAt": "2024-05-01T12:30:00.000Z"},
+            "meta": {"values": {"updatedAt": ["Date"]}}
+        }))
+        .unwrap();
+        let item: Item = raw.decode().unwrap();
+        assert_eq!(item.updated_at.to_rfc3339(), "2024-05-01T12:30:00+00:00");
     }
 
     #[test]
-    fn test_client_new_preserves_path() {
-        let client = HomarrClient::new("http://localhost:7575/homarr").unwrap();
-        assert_eq!(client.base_url, "http://localhost:7575/homarr");
+    fn test_superjson_decode_map_and_undefined() {
+        let raw: SuperJson = serde_json::from_value(json!({
+            "json": {
+                "items": [{"id": "a", "counts": [["x", 1], ["y", 2]]}],
+                "removed": null
+            },
+            "meta": {"values": {
+                "items.0.counts": ["map"],
+                "removed": ["undefined"]
+            }}
+        }))
+        .unwrap();
+        let value: serde_json::Value = raw.decode().unwrap();
+        assert_eq!(value["items"][0]["counts"], json!({"x": 1, "y": 2}));
+        assert!(value.get("removed").is_none());
     }
 
-    // find_next_position tests
     #[test]
-    fn test_find_next_position_empty_board() {
-        let client = create_test_client();
-        let items: Vec<serde_json::Value> = vec![];
-        let (x, y) = client.find_next_position(&items, 10);
-        assert_eq!((x, y), (0, 0));
+    fn test_superjson_decode_root_annotation() {
+        let raw: SuperJson = serde_json::from_value(json!({
+            "json": "42",
+            "meta": {"values": ["bigint"]}
+        }))
+        .unwrap();
+        let value: i64 = raw.decode().unwrap();
+        assert_eq!(value, 42);
     }
 
     #[test]
-    fn test_find_next_position_single_item() {
-        let client = create_test_client();
-        let items = vec![json!({
-            "layouts": [{
-                "xOffset": 0,
-                "yOffset": 0,
-                "width": 1,
-                "height": 1
-            }]
-        })];
-        let (x, y) = client.find_next_position(&items, 10);
-        // Should place next to the existing item
-        assert_eq!((x, y), (1, 0));
+    fn test_superjson_decode_without_meta() {
+        let raw: SuperJson =
+            serde_json::from_value(json!({"json": {"current": "finish", "previous": null}}))
+                .unwrap();
+        let step: OnboardingStep = raw.decode().unwrap();
+        assert_eq!(step.current, "finish");
+        assert!(step.previous.is_none());
     }
 
+    // Tests for tRPC query input encoding
+
     #[test]
-    fn test_find_next_position_full_row() {
+    fn test_query_url_encodes_simple_input() {
         let client = create_test_client();
-        // Fill all 10 columns in row 0
-        let items: Vec<serde_json::Value> = (0..10)
-            .map(|i| {
-                json!({
-                    "layouts": [{
-                        "xOffset": i,
-                        "yOffset": 0,
-                        "width": 1,
-                        "height": 1
-                    }]
-                })
-            })
-            .collect();
-        let (x, y) = client.find_next_position(&items, 10);
-        // Should start a new row
-        assert_eq!((x, y), (0, 1));
+        let url = client
+            .query_url("board.getBoardByName", json!({"name": "default"}))
+            .unwrap();
+        assert_eq!(
+            url,
+            "http://localhost:7575/api/trpc/board.getBoardByName?input=%7B%22json%22%3A%7B%22name%22%3A%22default%22%7D%7D"
+        );
     }
 
     #[test]
-    fn test_find_next_position_with_gap() {
+    fn test_query_url_roundtrips_quotes_and_unicode() {
         let client = create_test_client();
-        // Items at positions 0 and 2, leaving gap at 1
-        let items = vec![
-            json!({
-                "layouts": [{
-                    "xOffset": 0,
-                    "yOffset": 0,
-                    "width": 1,
-                    "height": 1
-                }]
-            }),
-            json!({
-                "layouts": [{
-                    "xOffset": 2,
-                    "yOffset": 0,
-                    "width": 1,
-                    "height": 1
-                }]
-            }),
-        ];
-        let (x, y) = client.find_next_position(&items, 10);
-        // Should fill the gap at position 1
-        assert_eq!((x, y), (1, 0));
+        let name = "Kapteenin \"paras\" näkymä & co/?";
+        let url = client
+            .query_url("board.getBoardByName", json!({ "name": name }))
+            .unwrap();
+
+        let encoded = url.split_once("?input=").unwrap().1;
+        assert!(!encoded.contains('"'));
+        assert!(!encoded.contains('&'));
+        assert!(!encoded.contains(' '));
+
+        let decoded = urlencoding::decode(encoded).unwrap();
+        let input: SuperJson = serde_json::from_str(&decoded).unwrap();
+        assert_eq!(input.json["name"], name);
     }
 
-    #[test]
-    fn test_find_next_position_wide_item() {
-        let client = create_test_client();
-        // Wide item taking columns 0-2
-        let items = vec![json!({
-            "layouts": [{
-                "xOffset": 0,
-                "yOffset": 0,
-                "width": 3,
-                "height": 1
-            }]
-        })];
-        let (x, y) = client.find_next_position(&items, 10);
-        // Should place at column 3
-        assert_eq!((x, y), (3, 0));
+    // Tests for named board sections
+
+    fn section(id: &str, kind: &str, y: i32, name: Option<&str>) -> Section {
+        Section {
+            id: id.to_string(),
+            kind: kind.to_string(),
+            y_offset: y,
+            x_offset: 0,
+            name: name.map(String::from),
+            collapsed: None,
+            extra: serde_json::Map::new(),
+        }
     }
 
     #[test]
-    fn test_find_next_position_tall_item() {
-        let client = create_test_client();
-        // Tall item at position 0
-        let items = vec![json!({
-            "layouts": [{
-                "xOffset": 0,
-                "yOffset": 0,
-                "width": 1,
-                "height": 3
-            }]
-        })];
-        let (x, y) = client.find_next_position(&items, 10);
-        // Should place in the same row but different column
-        assert_eq!((x, y), (1, 2));
+    fn test_plan_missing_sections_appends_in_order() {
+        let existing = vec![section("root", "empty", 0, None)];
+        let names = vec!["System".to_string(), "Apps".to_string()];
+        let planned = plan_missing_sections(&existing, &names);
+
+        let kinds: Vec<(&str, i32, Option<&str>)> = planned
+            .iter()
+            .map(|s| (s.kind.as_str(), s.y_offset, s.name.as_deref()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("category", 1, Some("System")),
+                ("empty", 2, None),
+                ("category", 3, Some("Apps")),
+                ("empty", 4, None),
+            ]
+        );
     }
 
     #[test]
-    fn test_find_next_position_multiple_rows() {
-        let client = create_test_client();
-        // Items in multiple rows
-        let items = vec![
-            json!({
-                "layouts": [{
-                    "xOffset": 0,
-                    "yOffset": 0,
-                    "width": 10,
-                    "height": 1
-                }]
-            }),
-            json!({
-                "layouts": [{
-                    "xOffset": 0,
-                    "yOffset": 1,
-                    "width": 5,
-                    "height": 1
-                }]
-            }),
+    fn test_missing_template_items() {
+        let sections = vec![
+            section("root", "empty", 0, None),
+            section("sys", "category", 1, Some("System")),
+        ];
+        let template: crate::branding::BoardContents = toml::from_str(
+            r#"
+            [[apps]]
+            name = "Cockpit"
+            url = "/cockpit/"
+            section = "System"
+            x = 0
+            y = 0
+
+            [[widgets]]
+            kind = "clock"
+            id = "screen"
+            x = 1
+            y = 0
+            width = 2
+            height = 2
+            options = { is24HourFormat = true }
+
+            [[widgets]]
+            kind = "clock"
+            x = 3
+            y = 0
+"#,
+        )
+        .unwrap();
+        let apps = vec![(&template.apps[0], "app-1".to_string())];
+
+        let missing = missing_template_items(&[], &sections, ("lg", 12), &apps, &template.widgets);
+        let ids: Vec<&str> = missing.iter().map(|i| i["id"].as_str().unwrap()).collect();
+        assert_eq!(ids[1], "template-widget-clock-screen");
+        assert!(ids[2].starts_with("template-widget-clock-"));
+
+        // Reordering the widgets doesn't change their IDs
+        let reordered = vec![template.widgets[1].clone(), template.widgets[0].clone()];
+        let again = missing_template_items(&[], &sections, ("lg", 12), &apps, &reordered);
+        assert_eq!(again[1]["id"], missing[2]["id"]);
+        assert_eq!(again[2]["id"], missing[1]["id"]);
+        assert_eq!(missing[0]["options"]["appId"], "app-1");
+        assert_eq!(missing[0]["layouts"][0]["sectionId"], "sys");
+        assert_eq!(missing[1]["layouts"][0]["sectionId"], "root");
+        assert_eq!(missing[1]["layouts"][0]["width"], 2);
+        assert_eq!(missing[1]["options"]["is24HourFormat"], true);
+        assert_eq!(missing[2]["layouts"][0]["width"], 1);
+
+        // Already placed, wherever the user moved them: nothing to add
+        let mut moved = missing.clone();
+        for item in &mut moved {
+            item["layouts"][0]["xOffset"] = json!(7);
+        }
+        assert!(
+            missing_template_items(&moved, &sections, ("lg", 12), &apps, &template.widgets)
+                .is_empty()
+        );
+
+        // A spot taken by the user's own tile: placed next to it instead
+        let own = vec![
+            json!({"id": "own", "layouts": [{"layoutId": "lg", "sectionId": "root",
+            "xOffset": 1, "yOffset": 0, "width": 4, "height": 2}]}),
         ];
-        let (x, y) = client.find_next_position(&items, 10);
-        // Should place after the item in row 1
-        assert_eq!((x, y), (5, 1));
+        let missing = missing_template_items(&own, &sections, ("lg", 12), &[], &template.widgets);
+        assert_eq!(missing[0]["layouts"][0]["xOffset"], 5);
+        assert_eq!(missing[0]["layouts"][0]["yOffset"], 0);
+        assert_eq!(missing[1]["layouts"][0]["xOffset"], 0);
     }
 
     #[test]
-    fn test_find_next_position_small_column_count() {
-        let client = create_test_client();
-        // Fill a 3-column board
-        let items: Vec<serde_json::Value> = (0..3)
-            .map(|i| {
-                json!({
-                    "layouts": [{
-                        "xOffset": i,
-                        "yOffset": 0,
-                        "width": 1,
-                        "height": 1
-                    }]
-                })
-            })
-            .collect();
-        let (x, y) = client.find_next_position(&items, 3);
-        // Should start a new row
-        assert_eq!((x, y), (0, 1));
+    fn test_find_search_engine() {
+        let engine = |id: &str, name: &str, short: &str| SearchEngineEntry {
+            id: id.to_string(),
+            name: name.to_string(),
+            short: short.to_string(),
+        };
+        let engines = vec![
+            engine("1", "Google", "g"),
+            engine("2", "Signal K", "sk"),
+            engine("3", "SK", "docs"),
+        ];
+        assert_eq!(find_search_engine(&engines, "SK").unwrap().id, "2");
+        assert_eq!(find_search_engine(&engines, "google").unwrap().id, "1");
+        assert!(find_search_engine(&engines, "bing").is_none());
     }
 
     #[test]
-    fn test_find_next_position_items_without_layouts() {
-        let client = create_test_client();
-        // Items missing layouts field
-        let items = vec![json!({"id": "item1"}), json!({"layouts": []})];
-        let (x, y) = client.find_next_position(&items, 10);
-        // Should handle gracefully and start at origin
-        assert_eq!((x, y), (0, 0));
+    fn test_import_contents() {
+        let layout = |id: &str, name: &str| Layout {
+            id: id.to_string(),
+            name: name.to_string(),
+            column_count: 10,
+            breakpoint: 0,
+        };
+        let export: BoardExport = serde_json::from_value(json!({
+            "version": 1,
+            "name": "halos",
+            "board_id": "board-a",
+            "sections": [
+                section("root", "empty", 0, None),
+                section("sys", "category", 1, Some("System")),
+            ],
+            "layouts": [layout("lg-a", "Base")],
+            "items": [
+                {
+                    "id": "registry-1",
+                    "kind": "app",
+                    "options": { "appId": "app-a" },
+                    "layouts": [{ "layoutId": "lg-a", "sectionId": "sys", "xOffset": 0, "yOffset": 0, "width": 1, "height": 1 }],
+                    "integrationIds": []
+                },
+                {
+                    "id": "registry-2",
+                    "kind": "app",
+                    "options": { "appId": "app-gone" },
+                    "layouts": [{ "layoutId": "lg-a", "sectionId": "root", "xOffset": 1, "yOffset": 0, "width": 1, "height": 1 }],
+                    "integrationIds": []
+                },
+                {
+                    "id": "calendar",
+                    "kind": "calendar",
+                    "options": {},
+                    "layouts": [{ "layoutId": "lg-a", "sectionId": "root", "xOffset": 2, "yOffset": 0, "width": 2, "height": 2 }],
+                    "integrationIds": ["int-a", "int-gone"]
+                }
+            ]
+        }))
+        .unwrap();
+        let app_ids = HashMap::from([("app-a".to_string(), "app-b".to_string())]);
+        let integration_ids = HashMap::from([("int-a".to_string(), "int-b".to_string())]);
+
+        // Another board: new section IDs, the board's own layout
+        let (sections, layouts, items) = import_contents(
+            &export,
+            "board-b",
+            &[layout("lg-b", "Base")],
+            &app_ids,
+            &integration_ids,
+        );
+        let sys = format!("board-b-{:x}", string_hash("sys"));
+        assert_eq!(sections[1].id, sys);
+        assert_eq!(layouts[0].id, "lg-b");
+        assert_eq!(layouts[0].column_count, 10);
+        let ids: Vec<&str> = items.iter().map(|i| i["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, ["registry-1", "calendar"]);
+        assert_eq!(items[0]["options"]["appId"], "app-b");
+        assert_eq!(items[0]["layouts"][0]["layoutId"], "lg-b");
+        assert_eq!(items[0]["layouts"][0]["sectionId"], sys.as_str());
+        assert_eq!(items[1]["integrationIds"], json!(["int-b"]));
+
+        // The board it came from: IDs kept
+        let (sections, layouts, _) =
+            import_contents(&export, "board-a", &[], &app_ids, &integration_ids);
+        assert_eq!(sections[1].id, "sys");
+        assert_eq!(layouts[0].id, "lg-a");
     }
 
-    // transform_icon_url tests
+    #[test]
+    fn test_plan_missing_sections_skips_existing() {
+        let existing = vec![
+            section("root", "empty", 0, None),
+            section("sys", "category", 1, Some("system")),
+            section("sys-empty", "empty", 2, None),
+        ];
+        let names = vec!["System".to_string(), "Apps".to_string(), "apps".to_string()];
+        let planned = plan_missing_sections(&existing, &names);
+        assert_eq!(planned.len(), 2);
+        assert_eq!(planned[0].name.as_deref(), Some("Apps"));
+        assert_eq!(planned[0].y_offset, 3);
+    }
 
     #[test]
-    fn test_transform_icon_url_pixmaps_path() {
-        // File path in /usr/share/pixmaps should become relative /icons/filename
-        let result = transform_icon_url("/usr/share/pixmaps/app.png");
-        assert_eq!(result, "/icons/app.png");
+    fn test_resolve_section_id() {
+        let sections = vec![
+            section("root", "empty", 0, None),
+            section("sys", "category", 1, Some("System")),
+            section("apps", "category", 3, Some("Apps")),
+        ];
+        assert_eq!(resolve_section_id(&sections, Some("apps")), "apps");
+        assert_eq!(resolve_section_id(&sections, Some("System")), "sys");
+        assert_eq!(resolve_section_id(&sections, Some("Media")), "root");
+        assert_eq!(resolve_section_id(&sections, None), "root");
+        assert_eq!(resolve_section_id(&[], Some("Apps")), "");
     }
 
     #[test]
-    fn test_transform_icon_url_pixmaps_nested_path() {
-        // Nested paths should preserve directory structure after pixmaps/
-        let result = transform_icon_url("/usr/share/pixmaps/subdir/icon.svg");
-        assert_eq!(result, "/icons/subdir/icon.svg");
+    fn test_compose_sections_grouping() {
+        let mut client = create_test_client();
+        client.set_default_section(Some("Apps".to_string()));
+        let mut app = AppDefinition {
+            name: "Grafana".to_string(),
+            url: "http://localhost:3001".to_string(),
+            ..Default::default()
+        };
+        app.app_type.compose_project = Some("monitoring".to_string());
+        assert_eq!(client.wanted_section(&app), Some("Apps"));
+
+        client.set_compose_sections(true);
+        assert_eq!(client.wanted_section(&app), Some("monitoring"));
+        app.layout.section = Some("System".to_string());
+        assert_eq!(client.wanted_section(&app), Some("System"));
+
+        let existing = vec![section("root", "empty", 0, None)];
+        let planned = on_demand_section("board-1", &existing, "monitoring");
+        assert_eq!(planned.len(), 2);
+        assert!(planned[0].id.starts_with("board-1-section-"));
+        assert!(planned[0].is_category_named("Monitoring"));
+        assert!(on_demand_section("board-1", &planned, "monitoring").is_empty());
     }
 
     #[test]
-    fn test_transform_icon_url_http_passthrough() {
-        // HTTP URLs should pass through unchanged
-        let result = transform_icon_url("http://example.com/icon.png");
-        assert_eq!(result, "http://example.com/icon.png");
+    fn test_category_sections() {
+        let mut client = create_test_client();
+        client.set_default_section(Some("Apps".to_string()));
+        let mut app = AppDefinition {
+            name: "Grafana".to_string(),
+            url: "http://localhost:3001".to_string(),
+            category: Some("Monitoring".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(client.wanted_section(&app), Some("Apps"));
+
+        client.set_category_sections(true);
+        assert_eq!(client.wanted_section(&app), Some("Monitoring"));
+        app.app_type.compose_project = Some("stack".to_string());
+        client.set_compose_sections(true);
+        assert_eq!(client.wanted_section(&app), Some("stack"));
+        app.app_type.compose_project = None;
+
+        // A remembered section is found by ID, even after a rename
+        let board = BoardResponse {
+            id: "board-1".to_string(),
+            name: "halos".to_string(),
+            sections: vec![section("s1", "category", 1, Some("Metrics"))],
+            layouts: vec![],
+        };
+        assert_eq!(
+            client.known_section_id(&board.id, &board.sections, "Monitoring"),
+            None
+        );
+        let ids = HashMap::from([(
+            "board-1".to_string(),
+            HashMap::from([("monitoring".to_string(), "s1".to_string())]),
+        )]);
+        client.set_section_ids(ids);
+        assert_eq!(
+            client
+                .known_section_id(&board.id, &board.sections, "Monitoring")
+                .as_deref(),
+            Some("s1")
+        );
     }
 
     #[test]
-    fn test_transform_icon_url_https_passthrough() {
-        // HTTPS URLs should pass through unchanged
-        let result = transform_icon_url("https://cdn.example.com/icons/docker.svg");
-        assert_eq!(result, "https://cdn.example.com/icons/docker.svg");
+    fn test_created_sections_in_name_order() {
+        let mut client = create_test_client();
+        client.set_category_sections(true);
+        let app = |name: &str, category: &str| AppDefinition {
+            name: name.to_string(),
+            url: format!("http://{}.local", name),
+            category: Some(category.to_string()),
+            ..Default::default()
+        };
+        let apps = [
+            app("grafana", "Monitoring"),
+            app("avnav", "Marine"),
+            app("influx", "monitoring"),
+        ];
+
+        // Same sections, order and IDs whatever order the apps come in
+        let mut boards = Vec::new();
+        for order in [[0, 1, 2], [2, 1, 0]] {
+            let mut sections = vec![section("root", "empty", 0, None)];
+            let apps: Vec<&AppDefinition> = order.iter().map(|&i| &apps[i]).collect();
+            let added = client.add_created_sections("board-1", &mut sections, &apps);
+            assert_eq!(added, vec!["Marine", "Monitoring"]);
+            boards.push(sections);
+        }
+        let layout = |sections: &[Section]| -> Vec<(String, i32, Option<String>)> {
+            sections
+                .iter()
+                .map(|s| (s.id.clone(), s.y_offset, s.name.clone()))
+                .collect()
+        };
+        assert_eq!(layout(&boards[0]), layout(&boards[1]));
+        assert_eq!(boards[0][1].name.as_deref(), Some("Marine"));
+        assert_eq!(boards[0][3].y_offset, 3);
+
+        // Nothing added once they exist
+        let mut sections = boards.remove(0);
+        let apps: Vec<&AppDefinition> = apps.iter().collect();
+        assert!(client
+            .add_created_sections("board-1", &mut sections, &apps)
+            .is_empty());
     }
 
     #[test]
-    fn test_transform_icon_url_empty_string() {
-        // Empty string should return default icon path
-        let result = transform_icon_url("");
-        assert_eq!(result, "/icons/docker.svg");
+    fn test_place_new_tile_when_section_full() {
+        let mut client = create_test_client();
+        let mut sections = vec![section("root", "empty", 0, None)];
+        let items: Vec<serde_json::Value> = (0..4)
+            .map(|x| json!({"layouts": [{"layoutId": "l1", "sectionId": "root", "xOffset": x, "yOffset": 0}]}))
+            .collect();
+        let root = Grid {
+            layout_id: "l1",
+            section_id: "root",
+            column_count: 4,
+        };
+
+        // Without a limit (or with "grow") the section gets another row
+        let placed = client.place_new_tile("b1", &mut sections, &items, root, (1, 1));
+        assert_eq!(placed, ("root".to_string(), (0, 1)));
+        client.set_section_limits(SectionLimits {
+            max_rows: Some(1),
+            when_full: BoardFull::Grow,
+            overflow_section: "More".to_string(),
+        });
+        let placed = client.place_new_tile("b1", &mut sections, &items, root, (1, 1));
+        assert_eq!(placed.1, (0, 1));
+        assert_eq!(sections.len(), 1);
+
+        // With "overflow" the tile goes to the overflow section, created once
+        client.section_limits.when_full = BoardFull::Overflow;
+        let (section_id, slot) = client.place_new_tile("b1", &mut sections, &items, root, (1, 1));
+        assert_eq!(slot, (0, 0));
+        assert!(sections
+            .iter()
+            .any(|s| s.id == section_id && s.is_category_named("More")));
+        client.place_new_tile("b1", &mut sections, &items, root, (1, 1));
+        assert_eq!(sections.len(), 3);
     }
 
     #[test]
-    fn test_transform_icon_url_unrecognized_path() {
-        // Unrecognized file paths should return default icon path
-        let result = transform_icon_url("/some/other/path/icon.png");
-        assert_eq!(result, "/icons/docker.svg");
+    fn test_section_preserves_unknown_fields() {
+        let json = json!({
+            "id": "dyn", "kind": "dynamic", "yOffset": 0, "xOffset": 0,
+            "width": 4, "height": 2, "parentSectionId": "root"
+        });
+        let parsed: Section = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
     }
 
-    #[test]
-    fn test_transform_icon_url_relative_path() {
-        // Relative paths should return default icon path
-        let result = transform_icon_url("icons/app.png");
-        assert_eq!(result, "/icons/docker.svg");
+    // Board preview tests
+    fn preview_tile(label: &str, x: i32, y: i32, width: i32, height: i32) -> PreviewTile {
+        PreviewTile {
+            label: label.to_string(),
+            x,
+            y,
+            width,
+            height,
+            planned: false,
+        }
     }
 
     #[test]
-    fn test_transform_icon_url_icons_path_passthrough() {
-        // Already relative /icons/ paths should pass through unchanged
-        let result = transform_icon_url("/icons/existing.svg");
-        assert_eq!(result, "/icons/existing.svg");
+    fn test_render_preview_grid() {
+        let mut planned = preview_tile("Grafana", 2, 0, 1, 1);
+        planned.planned = true;
+        let tiles = vec![preview_tile("Signal K", 0, 0, 2, 2), planned];
+        let out = render_preview_grid(4, &tiles);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "+--------+");
+        assert_eq!(lines[1], "| A A B .|");
+        assert_eq!(lines[2], "| A A . .|");
+        assert_eq!(lines[3], "+--------+");
+        assert!(lines[4].contains("A Signal K (2x2 at 0,0)"));
+        assert!(lines[5].contains("B Grafana (1x1 at 2,0) [new]"));
     }
 
     #[test]
-    fn test_transform_icon_url_pixmaps_trailing_slash_only() {
-        // Edge case: pixmaps path with trailing slash but no filename
-        let result = transform_icon_url("/usr/share/pixmaps/");
-        assert_eq!(result, "/icons/docker.svg");
+    fn test_render_preview_grid_overlap_and_clipping() {
+        let tiles = vec![
+            preview_tile("One", 0, 0, 1, 1),
+            preview_tile("Two", 0, 0, 1, 1),
+            preview_tile("Wide", 1, 0, 5, 1),
+        ];
+        let out = render_preview_grid(2, &tiles);
+        assert!(out.contains("| ! C|"));
+        assert!(out.contains("! overlapping tiles"));
     }
 
-    // Tests for board item deduplication (issue #15)
-
     #[test]
-    fn test_board_has_app_finds_existing() {
+    fn test_items_in_layout_filters_layout_entries() {
         let items = vec![
-            json!({
-                "id": "discovered-abc123",
-                "kind": "app",
-                "options": {
-                    "appId": "app-xyz-123"
-                }
-            }),
-            json!({
-                "id": "discovered-def456",
-                "kind": "app",
-                "options": {
-                    "appId": "app-other-456"
-                }
-            }),
+            json!({"id": "a", "layouts": [
+                {"layoutId": "l1", "xOffset": 0},
+                {"layoutId": "l2", "xOffset": 3}
+            ]}),
+            json!({"id": "b", "layouts": [{"layoutId": "l2", "xOffset": 1}]}),
         ];
+        let filtered = items_in_layout(&items, "l1");
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0]["layouts"].as_array().unwrap().len(), 1);
+        assert_eq!(filtered[0]["layouts"][0]["xOffset"], 0);
+    }
 
-        assert!(board_has_app(&items, "app-xyz-123"));
-        assert!(board_has_app(&items, "app-other-456"));
-        assert!(!board_has_app(&items, "app-nonexistent"));
+    #[test]
+    fn test_render_board_preview_places_new_apps() {
+        let client = create_test_client();
+        let board: BoardResponse = serde_json::from_value(json!({
+            "id": "board-1",
+            "name": "default",
+            "sections": [{"id": "s1", "kind": "empty", "yOffset": 0, "xOffset": 0}],
+            "layouts": [{"id": "l1", "name": "Base", "columnCount": 4, "breakpoint": 0}]
+        }))
+        .unwrap();
+        let items = vec![json!({
+            "id": "item-1",
+            "kind": "app",
+            "options": {"appId": "app-1"},
+            "layouts": [{"layoutId": "l1", "sectionId": "s1", "xOffset": 0, "yOffset": 0, "width": 1, "height": 1}]
+        })];
+        let existing = vec![SelectableApp {
+            id: "app-1".to_string(),
+            name: "Signal K".to_string(),
+            icon_url: String::new(),
+            href: Some("http://localhost:3000".to_string()),
+        }];
+        let on_board = AppDefinition {
+            name: "Signal K".to_string(),
+            url: "http://localhost:3000".to_string(),
+            ..Default::default()
+        };
+        let new_app = AppDefinition {
+            name: "Grafana".to_string(),
+            url: "http://localhost:3001".to_string(),
+            ..Default::default()
+        };
+
+        let out = client.render_board_preview(&board, &items, &[&on_board, &new_app], &existing);
+        assert!(out.contains("Layout 'Base' (4 columns)"));
+        assert!(out.contains("| A B . .|"));
+        assert!(out.contains("A Signal K"));
+        assert!(out.contains("B Grafana (1x1 at 1,0) [new]"));
+        assert!(!out.contains("Signal K (1x1 at 0,0) [new]"));
     }
 
     #[test]
-    fn test_board_has_app_handles_empty_board() {
-        let items: Vec<serde_json::Value> = vec![];
-        assert!(!board_has_app(&items, "any-app-id"));
+    fn test_app_payload_ping_url() {
+        let client = create_test_client();
+        let mut app = AppDefinition {
+            name: "Grafana".to_string(),
+            url: "http://localhost:3001".to_string(),
+            description: Some("Dashboards".to_string()),
+            ..Default::default()
+        };
+        let payload = client.app_payload(&app);
+        assert_eq!(payload["href"], "http://localhost:3001");
+        assert_eq!(payload["description"], "Dashboards");
+        assert_eq!(payload["pingUrl"], "http://host.docker.internal:3001/");
+
+        app.app_type.external = true;
+        assert!(client.app_payload(&app)["pingUrl"].is_null());
     }
 
     #[test]
-    fn test_board_has_app_handles_malformed_items() {
-        let items = vec![
-            json!({"id": "item-without-options"}),
-            json!({"id": "item-with-empty-options", "options": {}}),
-            json!({"id": "item-with-null-appid", "options": {"appId": null}}),
-        ];
+    fn test_icon_for_prefers_override() {
+        let mut client = create_test_client();
+        let app = AppDefinition {
+            name: "Grafana".to_string(),
+            url: "http://localhost:3001".to_string(),
+            icon_url: Some("/usr/share/pixmaps/grafana.png".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(client.icon_for(&app), "/icons/grafana.png");
 
-        // Should not crash and should return false for all
-        assert!(!board_has_app(&items, "any-app-id"));
+        client.set_icon_overrides(HashMap::from([(app.url.clone(), DEFAULT_ICON.to_string())]));
+        assert_eq!(client.icon_for(&app), DEFAULT_ICON);
     }
 
-    // Tests for derive_ping_url (auto-derive host.docker.internal URL for health checks)
+    #[test]
+    fn test_classify_mutation_probe() {
+        use reqwest::StatusCode;
+        assert!(classify_mutation_probe(StatusCode::BAD_REQUEST).0);
+        assert!(classify_mutation_probe(StatusCode::OK).0);
+        assert!(!classify_mutation_probe(StatusCode::NOT_FOUND).0);
+        assert!(!classify_mutation_probe(StatusCode::UNAUTHORIZED).0);
+        assert!(!classify_mutation_probe(StatusCode::INTERNAL_SERVER_ERROR).0);
+    }
 
     #[test]
-    fn test_derive_ping_url_replaces_hostname() {
-        let result = derive_ping_url("http://test.example.local:3000");
+    fn test_file_icon_path() {
         assert_eq!(
-            result,
-            Some("http://host.docker.internal:3000/".to_string())
+            file_icon_path("file:///srv/icons/app.png"),
+            Some(Path::new("/srv/icons/app.png"))
         );
+        assert_eq!(file_icon_path("file://relative.png"), None);
+        assert_eq!(file_icon_path("/usr/share/pixmaps/app.png"), None);
     }
 
     #[test]
-    fn test_derive_ping_url_preserves_path() {
-        let result = derive_ping_url("http://test.example.local:8086/api/v2");
-        assert_eq!(
-            result,
-            Some("http://host.docker.internal:8086/api/v2".to_string())
-        );
+    fn test_icon_for_uses_uploaded_media() {
+        let mut client = create_test_client();
+        let app = AppDefinition {
+            name: "Custom".to_string(),
+            url: "http://localhost:8080".to_string(),
+            icon_url: Some("file:///srv/icons/custom.png".to_string()),
+            ..Default::default()
+        };
+        // Not uploaded yet: unknown scheme falls back to the default icon
+        assert_eq!(client.icon_for(&app), DEFAULT_ICON);
+
+        client.set_uploaded_icons(HashMap::from([(
+            "file:///srv/icons/custom.png".to_string(),
+            "/api/user-medias/abc".to_string(),
+        )]));
+        assert_eq!(client.icon_for(&app), "/api/user-medias/abc");
     }
 
     #[test]
-    fn test_derive_ping_url_preserves_https() {
-        let result = derive_ping_url("https://test.example.local:443/app");
-        assert_eq!(result, Some("https://host.docker.internal/app".to_string()));
+    fn test_uploaded_media_response_shapes() {
+        let one: UploadedMedia = serde_json::from_value(json!("abc")).unwrap();
+        assert!(matches!(one, UploadedMedia::One(id) if id == "abc"));
+        let many: UploadedMedia = serde_json::from_value(json!(["abc", "def"])).unwrap();
+        assert!(matches!(many, UploadedMedia::Many(ids) if ids.len() == 2));
+    }
+
+    // Integration widget tests
+    fn integration(id: &str, name: &str, url: &str) -> Integration {
+        Integration {
+            id: id.to_string(),
+            name: name.to_string(),
+            kind: "sonarr".to_string(),
+            url: url.to_string(),
+        }
+    }
+
+    fn grid(column_count: i32) -> Grid<'static> {
+        Grid {
+            layout_id: "l1",
+            section_id: "s1",
+            column_count,
+        }
+    }
+
+    fn placed_item(x: i32, y: i32, w: i32, h: i32) -> serde_json::Value {
+        json!({"layouts": [{"layoutId": "l1", "sectionId": "s1", "xOffset": x, "yOffset": y, "width": w, "height": h}]})
     }
 
     #[test]
-    fn test_derive_ping_url_handles_no_port() {
-        let result = derive_ping_url("http://test.example.local/dashboard");
-        assert_eq!(
-            result,
-            Some("http://host.docker.internal/dashboard".to_string())
-        );
+    fn test_find_integration_for_matches_host_then_name() {
+        let integrations = vec![
+            integration("i1", "Radarr", "http://192.168.1.10:7878"),
+            integration("i2", "TV", "http://192.168.1.10:8989/"),
+            integration("i3", "Sonarr", "http://other:8989"),
+        ];
+        let mut app = AppDefinition {
+            name: "Sonarr".to_string(),
+            url: "http://192.168.1.10:8989/calendar".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(find_integration_for(&integrations, &app).unwrap().id, "i2");
+
+        app.url = "/sonarr/".to_string();
+        assert_eq!(find_integration_for(&integrations, &app).unwrap().id, "i3");
+
+        app.name = "Lidarr".to_string();
+        assert!(find_integration_for(&integrations, &app).is_none());
     }
 
     #[test]
-    fn test_derive_ping_url_invalid_url_returns_none() {
-        let result = derive_ping_url("not-a-valid-url");
-        assert_eq!(result, None);
+    fn test_app_item_placement() {
+        let items = vec![json!({
+            "id": "registry-sonarr",
+            "options": {"appId": "app-1"},
+            "layouts": [{"sectionId": "s1", "xOffset": 3, "yOffset": 1, "width": 1, "height": 2}]
+        })];
+        let tile = app_item_placement(&items, "app-1").unwrap();
+        assert_eq!(tile.section_id, "s1");
+        assert_eq!((tile.x, tile.y, tile.width, tile.height), (3, 1, 1, 2));
+        assert!(app_item_placement(&items, "app-2").is_none());
+        assert!(board_has_item(&items, "registry-sonarr"));
+        assert!(!board_has_item(&items, "registry-sonarr-widget"));
     }
 
     #[test]
-    fn test_derive_ping_url_path_only() {
-        let result = derive_ping_url("/cockpit/");
+    fn test_place_adjacent_prefers_right() {
+        let anchor = TilePlacement {
+            section_id: "s1".to_string(),
+            x: 0,
+            y: 0,
+            width: 1,
+            height: 1,
+        };
+        let items = vec![placed_item(0, 0, 1, 1)];
         assert_eq!(
-            result,
-            Some("https://host.docker.internal/cockpit/".to_string())
+            place_adjacent(&items, grid(12), &anchor, (2, 2)),
+            Some((1, 0))
         );
     }
 
     #[test]
-    fn test_derive_ping_url_path_only_signalk() {
-        let result = derive_ping_url("/signalk-server/@signalk/freeboard-sk/");
+    fn test_place_adjacent_falls_back_below() {
+        let anchor = TilePlacement {
+            section_id: "s1".to_string(),
+            x: 0,
+            y: 0,
+            width: 1,
+            height: 1,
+        };
+        // Right side taken
+        let items = vec![placed_item(0, 0, 1, 1), placed_item(1, 0, 1, 1)];
         assert_eq!(
-            result,
-            Some("https://host.docker.internal/signalk-server/@signalk/freeboard-sk/".to_string())
+            place_adjacent(&items, grid(12), &anchor, (2, 2)),
+            Some((0, 1))
         );
+
+        // Right side past the grid edge, below taken
+        let anchor = TilePlacement { x: 3, ..anchor };
+        let items = vec![placed_item(3, 0, 1, 1), placed_item(3, 1, 1, 1)];
+        assert_eq!(place_adjacent(&items, grid(4), &anchor, (2, 2)), None);
     }
 
-    #[test]
-    fn test_derive_ping_url_protocol_relative_returns_none() {
-        // `//host/path` must not be treated as path-only.
-        let result = derive_ping_url("//evil.example.com/path");
-        assert_eq!(result, None);
+    fn staged_test_board(name: &str) -> Board {
+        Board {
+            board: BoardResponse {
+                id: format!("{}-id", name),
+                name: name.to_string(),
+                sections: vec![],
+                layouts: vec![],
+            },
+            items: vec![],
+            version: 0,
+        }
     }
 
     #[test]
-    fn test_derive_ping_url_lone_slash_returns_none() {
-        // Lone `/` is rejected by `is_path_only` and falls through to
-        // `Url::parse`, which also rejects it. Both call sites agree.
-        let result = derive_ping_url("/");
-        assert_eq!(result, None);
+    fn test_board_writes_respect_min_interval() {
+        let now = Instant::now();
+        let mut writes = BoardWrites {
+            min_interval: Duration::from_secs(10),
+            ..Default::default()
+        };
+        assert_eq!(writes.next_due(now), None);
+
+        writes
+            .pending
+            .insert("Home".to_string(), staged_test_board("Home"));
+        writes
+            .pending
+            .insert("Marine".to_string(), staged_test_board("Marine"));
+        writes
+            .last_write
+            .insert("Marine".to_string(), now - Duration::from_secs(4));
+
+        assert_eq!(writes.next_due(now), Some(Duration::ZERO));
+        let due = writes.take_due(now);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].0, "Home");
+
+        // Marine was saved recently and stays staged until its interval passes
+        assert_eq!(writes.next_due(now), Some(Duration::from_secs(6)));
+        assert!(writes.take_due(now).is_empty());
+        let later = now + Duration::from_secs(6);
+        assert_eq!(writes.take_due(later).len(), 1);
+        assert!(writes.pending.is_empty());
     }
 
-    // Tests for BoardPermission enum
-
     #[test]
-    fn test_board_permission_view_not_writable() {
-        assert!(!BoardPermission::View.is_writable());
+    fn test_board_writes_restore() {
+        let mut writes = BoardWrites::default();
+        let mut deferred = staged_test_board("Home");
+        deferred.items.push(placed_item(0, 0, 1, 1));
+        writes.pending.insert("Home".to_string(), deferred);
+        let snapshot = writes.pending.clone();
+
+        // A sync builds on the deferred edits and stages another board
+        let mut replanned = writes.pending["Home"].clone();
+        replanned.items.push(placed_item(1, 0, 1, 1));
+        writes.pending.insert("Home".to_string(), replanned);
+        writes
+            .pending
+            .insert("Marine".to_string(), staged_test_board("Marine"));
+
+        writes.restore(snapshot);
+        assert_eq!(writes.pending.len(), 1);
+        assert_eq!(writes.pending["Home"].items.len(), 1);
     }
 
     #[test]
-    fn test_board_permission_modify_is_writable() {
-        assert!(BoardPermission::Modify.is_writable());
+    fn test_board_writes_restage() {
+        let mut writes = BoardWrites::default();
+        let mut failed = staged_test_board("Home");
+        failed.items.push(placed_item(0, 0, 1, 1));
+
+        writes.restage("Home".to_string(), failed.clone());
+        assert_eq!(writes.pending["Home"].items.len(), 1);
+
+        // Edits staged while the save was under way win
+        let mut newer = failed;
+        newer.items.push(placed_item(1, 0, 1, 1));
+        writes.pending.insert("Home".to_string(), newer);
+        writes.restage("Home".to_string(), staged_test_board("Home"));
+        assert_eq!(writes.pending["Home"].items.len(), 2);
     }
 
     #[test]
-    fn test_board_permission_full_is_writable() {
-        assert!(BoardPermission::Full.is_writable());
+    fn test_board_version_tracks_edits() {
+        let board = staged_test_board("Home").board;
+        let items = vec![placed_item(0, 0, 1, 1)];
+        let read = Board::read(board.clone(), items.clone());
+        assert_eq!(read.version, board_version(&board.sections, &items));
+
+        // Moving a tile or adding a section changes the version
+        let moved = vec![placed_item(1, 0, 1, 1)];
+        assert_ne!(read.version, board_version(&board.sections, &moved));
+        let sections = plan_missing_sections(&[], &["Apps".to_string()]);
+        assert_ne!(read.version, board_version(&sections, &items));
     }
 
-    // Tests for BoardWithPermission
+    #[tokio::test]
+    async fn test_flush_board_saves_with_nothing_staged() {
+        let client = create_test_client();
+        assert_eq!(client.next_board_flush(), None);
+        assert!(client.flush_board_saves().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_flush_board_saves_skips_unchanged_boards() {
+        // Saving would fail: nothing listens on the test client's port
+        let client = create_test_client();
+        let staged = staged_test_board("Home");
+        let unchanged = Board::read(staged.board, vec![placed_item(0, 0, 1, 1)]);
+        client.save_items("Home", unchanged);
+        assert!(client.flush_board_saves().await.is_ok());
+        assert!(client.staged_boards().is_empty());
+    }
 
     #[test]
-    fn test_board_with_permission_deserialize() {
-        // Test that BoardWithPermission deserializes from actual Homarr API format
-        let json = r#"{
-            "id": "board-1",
-            "name": "Test Board",
-            "isPublic": true,
-            "userPermissions": [],
-            "groupPermissions": []
-        }"#;
-        let board: BoardWithPermission = serde_json::from_str(json).unwrap();
-        assert_eq!(board.id, "board-1");
-        assert_eq!(board.name, "Test Board");
-        assert!(board.is_public);
-        assert!(board.user_permissions.is_empty());
-        assert!(board.group_permissions.is_empty());
+    fn test_item_overlaps() {
+        let items = vec![placed_item(0, 0, 2, 2)];
+        assert!(item_overlaps(&items, &placed_item(1, 1, 1, 1)));
+        assert!(!item_overlaps(&items, &placed_item(2, 0, 1, 1)));
+        assert!(!item_overlaps(&items, &placed_item(0, 2, 2, 1)));
+
+        // Same spot in another section is free
+        let other_section = json!({"layouts": [{"sectionId": "s2", "xOffset": 0, "yOffset": 0, "width": 1, "height": 1}]});
+        assert!(!item_overlaps(&items, &other_section));
     }
 
     #[test]
-    fn test_board_with_permission_is_writable() {
-        // For admin users, all boards are writable
-        let json = r#"{
-            "id": "board-1",
-            "name": "Test Board",
-            "isPublic": false,
-            "userPermissions": [],
-            "groupPermissions": []
-        }"#;
-        let board: BoardWithPermission = serde_json::from_str(json).unwrap();
-        assert!(board.is_writable());
+    fn test_template_sections_get_new_ids() {
+        let section = |id: &str, kind: &str, name: Option<&str>| Section {
+            id: id.to_string(),
+            kind: kind.to_string(),
+            y_offset: 0,
+            x_offset: 0,
+            name: name.map(str::to_string),
+            collapsed: None,
+            extra: serde_json::Map::new(),
+        };
+        let template = vec![
+            section("s1", "empty", None),
+            section("s2", "category", Some("Marine")),
+            section("s3", "dynamic", None),
+        ];
+
+        let copied = template_sections(&template, "board-2");
+        assert_eq!(copied.len(), 2);
+        assert!(copied.iter().all(|s| s.id.starts_with("board-2-")));
+        assert_ne!(copied[0].id, copied[1].id);
+        assert!(copied[1].is_category_named("marine"));
     }
 
     #[test]
-    fn test_board_with_permission_defaults() {
-        // Test that missing optional fields use defaults
-        let json = r#"{"id": "board-1", "name": "Test", "isPublic": true}"#;
-        let board: BoardWithPermission = serde_json::from_str(json).unwrap();
-        assert!(board.user_permissions.is_empty());
-        assert!(board.group_permissions.is_empty());
+    fn test_template_settings_copies_appearance_only() {
+        let template = json!({
+            "id": "template-id",
+            "name": "Template",
+            "primaryColor": "#112233",
+            "customCss": null,
+            "opacity": 80,
+            "items": []
+        });
+        let settings = template_settings(&template);
+        assert_eq!(settings.len(), 2);
+        assert_eq!(settings["primaryColor"], "#112233");
+        assert_eq!(settings["opacity"], 80);
     }
 
-    // Tests for URL normalization (issue #41 - duplicate apps)
+    fn selectable(id: &str, name: &str, href: &str) -> SelectableApp {
+        SelectableApp {
+            id: id.to_string(),
+            name: name.to_string(),
+            icon_url: String::new(),
+            href: Some(href.to_string()),
+        }
+    }
 
-    #[test]
-    fn test_normalize_url_removes_trailing_slash() {
-        assert_eq!(
-            normalize_url("http://localhost:3000/app/"),
-            "http://localhost:3000/app"
-        );
+    fn app_tile(id: &str, app_id: &str, x: i32, y: i32) -> serde_json::Value {
+        json!({
+            "id": id,
+            "kind": "app",
+            "options": { "appId": app_id },
+            "layouts": [{ "layoutId": "l1", "sectionId": "s1",
+                          "xOffset": x, "yOffset": y, "width": 1, "height": 1 }]
+        })
     }
 
     #[test]
-    fn test_normalize_url_preserves_root_path() {
-        // Root path "/" should not be stripped
-        assert_eq!(
-            normalize_url("http://localhost:3000/"),
-            "http://localhost:3000/"
-        );
+    fn test_group_owned_apps_by_url() {
+        let apps = vec![
+            selectable("a1", "Grafana", "http://localhost:3001/"),
+            selectable("a2", "grafana", "http://old-host:3001"),
+            selectable("a3", "Grafana", "http://localhost:3001"),
+            selectable("u1", "Notes", "http://notes.local"),
+        ];
+        let owned = vec![
+            "http://localhost:3001".to_string(),
+            "http://localhost:3000".to_string(),
+        ];
+        let groups = group_owned_apps(&apps, &owned);
+        assert_eq!(groups.len(), 1);
+        // The same-name app at another URL is the user's and left alone
+        let ids: Vec<&str> = groups[0].iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(ids, vec!["a1", "a3"]);
     }
 
     #[test]
-    fn test_normalize_url_lowercases_host() {
+    fn test_dedupe_keeps_best_positioned_tile() {
+        let apps = vec![
+            selectable("a1", "Grafana", "http://localhost:3001"),
+            selectable("a2", "Grafana", "http://localhost:3001"),
+        ];
+        let owned = vec!["http://localhost:3001".to_string()];
+        let groups = group_owned_apps(&apps, &owned);
+
+        let mut items = vec![
+            app_tile("t1", "a1", 3, 1),
+            app_tile("t2", "a2", 0, 0),
+            app_tile("t3", "a2", 2, 2),
+            json!({ "id": "t3-widget", "kind": "clock", "options": {} }),
+            app_tile("t4", "other", 5, 5),
+        ];
+        let (duplicates, keep_for) = plan_duplicates(&groups, &[items.clone()]);
         assert_eq!(
-            normalize_url("http://MyHost.LOCAL:3000/App"),
-            "http://myhost.local:3000/App" // Note: path case preserved
+            duplicates,
+            vec![DuplicateApps {
+                name: "Grafana".to_string(),
+                keep: "a2".to_string(),
+                remove: vec!["a1".to_string()],
+            }]
         );
-    }
 
-    #[test]
-    fn test_normalize_url_removes_default_http_port() {
-        assert_eq!(normalize_url("http://localhost:80/"), "http://localhost/");
+        assert_eq!(merge_duplicate_tiles(&mut items, &keep_for), 2);
+        let ids: Vec<&str> = items.iter().filter_map(|i| i["id"].as_str()).collect();
+        assert_eq!(ids, vec!["t2", "t4"]);
+
+        // Already merged: nothing more to do
+        assert_eq!(merge_duplicate_tiles(&mut items, &keep_for), 0);
     }
 
     #[test]
-    fn test_normalize_url_removes_default_https_port() {
+    fn test_with_group_permission_keeps_other_groups() {
+        let existing: Vec<GroupBoardPermission> = serde_json::from_value(json!([
+            { "group": { "id": "g-admins", "name": "admins" }, "permission": "full" },
+            { "group": { "id": "g-everyone", "name": "everyone" }, "permission": "modify" }
+        ]))
+        .unwrap();
+
+        let view = with_group_permission(&existing, "g-everyone", EveryonePermission::View);
         assert_eq!(
-            normalize_url("https://localhost:443/app"),
-            "https://localhost/app"
+            view,
+            vec![
+                json!({ "principalId": "g-admins", "permission": "full" }),
+                json!({ "principalId": "g-everyone", "permission": "view" }),
+            ]
         );
-    }
 
-    #[test]
-    fn test_normalize_url_preserves_non_default_port() {
+        let none = with_group_permission(&existing, "g-everyone", EveryonePermission::None);
         assert_eq!(
-            normalize_url("http://localhost:3000/"),
-            "http://localhost:3000/"
+            none,
+            vec![json!({ "principalId": "g-admins", "permission": "full" })]
         );
     }
 
     #[test]
-    fn test_normalize_url_handles_invalid_url() {
-        // Should return original string for invalid URLs
-        assert_eq!(normalize_url("not-a-url"), "not-a-url");
+    fn test_version_supported() {
+        assert_eq!(parse_version("v1.12.0"), Some((1, 12, 0)));
+        assert_eq!(parse_version("1.3"), Some((1, 3, 0)));
+        assert_eq!(parse_version("1.0.0-beta.4"), Some((1, 0, 0)));
+        assert_eq!(parse_version("latest"), None);
+        assert_eq!(version_supported("1.12.0", "1.2.0"), Some(true));
+        assert_eq!(version_supported("v0.15.10", "1.0.0"), Some(false));
+        assert_eq!(version_supported("1.0.0", "1.0"), Some(true));
+        assert_eq!(version_supported("nightly", "1.0.0"), None);
     }
 
-    #[test]
-    fn test_normalize_url_matching_with_trailing_slash_difference() {
-        // This is the key test for issue #41 - URLs that differ only by trailing slash
-        let url_with_slash = normalize_url("http://localhost:3000/app/");
-        let url_without_slash = normalize_url("http://localhost:3000/app");
-        assert_eq!(url_with_slash, url_without_slash);
+    #[tokio::test]
+    async fn test_refused_mutations_fail_before_sending() {
+        let mut client = HomarrClient::new("http://127.0.0.1:9").unwrap();
+        client.refuse_mutations("Homarr 0.15.0 is older than 1.0.0".to_string());
+        let err = client.delete_app("app-1").await.unwrap_err();
+        assert!(matches!(err, AdapterError::UnsupportedHomarr(_)));
+        assert!(err.to_string().contains("older than 1.0.0"));
     }
 }
@@ -0,0 +1,259 @@
+//! Icons from the dashboard-icons catalog, picked by container image
+//!
+//! A container without a `homarr.icon` label gets the catalog icon for its
+//! image: `lscr.io/linuxserver/sonarr:latest` → `sonarr`. Images whose name
+//! differs from the icon's slug are listed in `IMAGE_SLUGS`; other images
+//! match a known slug after dropping separators and common suffixes
+//! (`-ce`, `-server`, ...), or failing that the one known slug within a
+//! typo or two of the name (`mosquito` → `mosquitto`). Images that match
+//! nothing keep the default icon.
+
+/// Dashboard icons CDN
+const DASHBOARD_ICONS_URL: &str = "https://cdn.jsdelivr.net/gh/homarr-labs/dashboard-icons";
+
+/// Images (repository path, or its last segment) whose slug differs from
+/// their name
+const IMAGE_SLUGS: &[(&str, &str)] = &[
+    ("vaultwarden/server", "vaultwarden"),
+    ("nextcloud/all-in-one", "nextcloud"),
+    ("homeassistant/home-assistant", "home-assistant"),
+    ("home-assistant/home-assistant", "home-assistant"),
+    ("pihole/pihole", "pi-hole"),
+    ("adguard/adguardhome", "adguard-home"),
+    ("jc21/nginx-proxy-manager", "nginx-proxy-manager"),
+    ("louislam/uptime-kuma", "uptime-kuma"),
+    ("eclipse-mosquitto", "mosquitto"),
+    ("portainer-ce", "portainer"),
+    ("portainer-ee", "portainer"),
+    ("qbittorrent-nox", "qbittorrent"),
+    ("nodered", "node-red"),
+    ("code-server", "vscode"),
+    ("openvscode-server", "vscode"),
+    ("signalk-server", "signal-k"),
+    ("postgres", "postgresql"),
+    ("mongo", "mongodb"),
+];
+
+/// Slugs matched by name when an image isn't in `IMAGE_SLUGS`
+const KNOWN_SLUGS: &[&str] = &[
+    "adguard-home",
+    "audiobookshelf",
+    "authelia",
+    "bazarr",
+    "calibre-web",
+    "cockpit",
+    "dozzle",
+    "duplicati",
+    "emby",
+    "filebrowser",
+    "frigate",
+    "gitea",
+    "grafana",
+    "heimdall",
+    "home-assistant",
+    "homarr",
+    "homepage",
+    "immich",
+    "influxdb",
+    "jellyfin",
+    "jellyseerr",
+    "kavita",
+    "lidarr",
+    "mariadb",
+    "mealie",
+    "mosquitto",
+    "mysql",
+    "navidrome",
+    "nextcloud",
+    "nginx",
+    "nginx-proxy-manager",
+    "node-red",
+    "overseerr",
+    "paperless-ngx",
+    "photoprism",
+    "pi-hole",
+    "plex",
+    "portainer",
+    "prometheus",
+    "prowlarr",
+    "qbittorrent",
+    "radarr",
+    "readarr",
+    "redis",
+    "sabnzbd",
+    "signal-k",
+    "sonarr",
+    "syncthing",
+    "tautulli",
+    "telegraf",
+    "traefik",
+    "transmission",
+    "uptime-kuma",
+    "vaultwarden",
+    "watchtower",
+    "wireguard",
+    "zigbee2mqtt",
+];
+
+/// Suffixes dropped from image names before matching slugs
+const IMAGE_SUFFIXES: &[&str] = &["-ce", "-ee", "-server", "-docker", "-app", "-web", "-oss"];
+
+/// Repository path of an image reference, without registry, tag or digest
+/// (`lscr.io/linuxserver/sonarr:4.0` → `linuxserver/sonarr`)
+fn repository(image: &str) -> String {
+    let image = image.split('@').next().unwrap_or_default();
+    // A colon after the last slash starts the tag (one before it is a
+    // registry port)
+    let image = match image.rfind(':') {
+        Some(colon) if !image[colon..].contains('/') => &image[..colon],
+        _ => image,
+    };
+    let mut segments: Vec<&str> = image.split('/').collect();
+    // The first segment is a registry if it looks like a host
+    if segments.len() > 1
+        && (segments[0].contains('.') || segments[0].contains(':') || segments[0] == "localhost")
+    {
+        segments.remove(0);
+    }
+    segments.join("/").to_lowercase()
+}
+
+/// Letters and digits only, for comparing names
+fn squash(name: &str) -> String {
+    name.chars().filter(char::is_ascii_alphanumeric).collect()
+}
+
+/// Number of single-character insertions, deletions and substitutions
+/// turning `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitute = previous[j] + usize::from(ca != cb);
+            current.push(substitute.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Edits a squashed name may be away from a slug and still match: none for
+/// short names, where one letter makes another app
+fn allowed_edits(name: &str) -> usize {
+    match name.len() {
+        0..=4 => 0,
+        5..=9 => 1,
+        _ => 2,
+    }
+}
+
+/// The known slug nearest to a squashed name within [`allowed_edits`], if
+/// exactly one is that near
+fn nearest_slug(name: &str) -> Option<&'static str> {
+    let mut nearest: Vec<(usize, &'static str)> = KNOWN_SLUGS
+        .iter()
+        .map(|slug| (edit_distance(name, &squash(slug)), *slug))
+        .filter(|&(distance, _)| distance <= allowed_edits(name))
+        .collect();
+    nearest.sort();
+    match nearest.as_slice() {
+        [(_, slug)] => Some(slug),
+        [(best, slug), (next, _), ..] if best < next => Some(slug),
+        _ => None,
+    }
+}
+
+/// Dashboard-icons slug for a container image, if one is known
+pub fn slug_for_image(image: &str) -> Option<&'static str> {
+    let repo = repository(image);
+    let name = repo.rsplit('/').next().unwrap_or_default();
+    if name.is_empty() {
+        return None;
+    }
+    if let Some((_, slug)) = IMAGE_SLUGS
+        .iter()
+        .find(|(image, _)| *image == repo)
+        .or_else(|| IMAGE_SLUGS.iter().find(|(image, _)| *image == name))
+    {
+        return Some(slug);
+    }
+
+    let trimmed = IMAGE_SUFFIXES
+        .iter()
+        .find_map(|suffix| name.strip_suffix(suffix))
+        .unwrap_or(name);
+    let candidates = [squash(name), squash(trimmed)];
+    candidates
+        .iter()
+        .find_map(|candidate| {
+            KNOWN_SLUGS
+                .iter()
+                .find(|slug| squash(slug) == *candidate)
+                .copied()
+        })
+        .or_else(|| {
+            candidates
+                .iter()
+                .find_map(|candidate| nearest_slug(candidate))
+        })
+}
+
+/// CDN URL of a catalog icon
+pub fn icon_url(slug: &str, ext: &str) -> String {
+    format!("{}/{}/{}.{}", DASHBOARD_ICONS_URL, ext, slug, ext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repository() {
+        assert_eq!(
+            repository("lscr.io/linuxserver/sonarr:latest"),
+            "linuxserver/sonarr"
+        );
+        assert_eq!(repository("grafana/grafana"), "grafana/grafana");
+        assert_eq!(repository("localhost:5000/tools/app:1.0"), "tools/app");
+        assert_eq!(repository("redis@sha256:abc"), "redis");
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("sonarr", "sonarr"), 0);
+        assert_eq!(edit_distance("mosquito", "mosquitto"), 1);
+        assert_eq!(edit_distance("lidarr", "radarr"), 2);
+        assert_eq!(edit_distance("", "plex"), 4);
+    }
+
+    #[test]
+    fn test_slug_for_image() {
+        for (image, slug) in [
+            ("lscr.io/linuxserver/sonarr:latest", Some("sonarr")),
+            ("grafana/grafana-oss:11.2", Some("grafana")),
+            ("portainer/portainer-ce:2.21", Some("portainer")),
+            ("nodered/node-red:4.0", Some("node-red")),
+            (
+                "ghcr.io/home-assistant/home-assistant:stable",
+                Some("home-assistant"),
+            ),
+            ("vaultwarden/server", Some("vaultwarden")),
+            ("pihole/pihole", Some("pi-hole")),
+            ("ghcr.io/hatlabs/signalk-server:latest", Some("signal-k")),
+            ("influxdb:2", Some("influxdb")),
+            // Near matches: a typo, a missing letter
+            ("example/mosquito", Some("mosquitto")),
+            ("example/jelyfin", Some("jellyfin")),
+            ("example/audiobookshelff-server", Some("audiobookshelf")),
+            // Too short to guess, or as near to two slugs
+            ("example/plx", None),
+            ("example/ladarr", None),
+            ("ghcr.io/hatlabs/halos-custom:1", None),
+            ("", None),
+        ] {
+            assert_eq!(slug_for_image(image), slug, "{}", image);
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! End-to-end tests against a real Homarr (`integration-tests` feature)
+//!
+//! Starts Homarr and a Docker-in-Docker sandbox with testcontainers, runs
+//! setup and sync against them and checks what lands on the board. Needs a
+//! Docker daemon that can run privileged containers:
+//!
+//! ```text
+//! cargo test --features integration-tests integration_tests
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use bollard::container::{Config as ContainerConfig, CreateContainerOptions};
+use bollard::image::CreateImageOptions;
+use bollard::Docker;
+use futures_util::StreamExt;
+use tempfile::TempDir;
+use testcontainers::core::{IntoContainerPort, Mount, WaitFor};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::{ContainerAsync, GenericImage, ImageExt};
+
+use crate::branding::BrandingConfig;
+use crate::config::Config;
+use crate::homarr::HomarrClient;
+
+const HOMARR_IMAGE: (&str, &str) = ("ghcr.io/homarr-labs/homarr", "latest");
+const DIND_IMAGE: (&str, &str) = ("docker", "dind");
+const HOMARR_PORT: u16 = 7575;
+
+const BOARD_NAME: &str = "halos";
+const ADMIN_USERNAME: &str = "admin";
+const ADMIN_PASSWORD: &str = "Integration-Test-1";
+
+/// Branding for the test board (credentials are created during onboarding)
+fn branding_toml() -> String {
+    format!(
+        r##"
+[identity]
+product_name = "HaLOS Test"
+
+[theme]
+default_color_scheme = "dark"
+primary_color = "#0066cc"
+secondary_color = "#ff6600"
+
+[credentials]
+admin_username = "{ADMIN_USERNAME}"
+admin_password = "{ADMIN_PASSWORD}"
+
+[board]
+name = "{BOARD_NAME}"
+display_name = "HaLOS"
+column_count = 12
+is_public = true
+sections = ["System"]
+
+[settings.analytics]
+enable_general = false
+enable_widget_data = false
+enable_integration_data = false
+enable_user_data = false
+
+[settings.crawling]
+no_index = true
+no_follow = true
+no_translate = true
+no_sitelinks_search_box = true
+"##
+    )
+}
+
+/// A running Homarr, its URL and the sandbox files the adapter uses
+struct Harness {
+    _homarr: ContainerAsync<GenericImage>,
+    _dind: ContainerAsync<GenericImage>,
+    dir: TempDir,
+    config: Config,
+    sandbox: Docker,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let dir = TempDir::new().unwrap();
+        let registry_dir = dir.path().join("webapps.d");
+        let run_dir = dir.path().join("dind-run");
+        std::fs::create_dir_all(&registry_dir).unwrap();
+        std::fs::create_dir_all(&run_dir).unwrap();
+        std::fs::write(dir.path().join("branding.toml"), branding_toml()).unwrap();
+
+        let homarr = GenericImage::new(HOMARR_IMAGE.0, HOMARR_IMAGE.1)
+            .with_exposed_port(HOMARR_PORT.tcp())
+            .with_env_var("SECRET_ENCRYPTION_KEY", "0".repeat(64))
+            .start()
+            .await
+            .expect("failed to start Homarr");
+
+        // The sandbox daemon's socket lands in run_dir on the host
+        let dind = GenericImage::new(DIND_IMAGE.0, DIND_IMAGE.1)
+            .with_wait_for(WaitFor::message_on_stderr(
+                "API listen on /var/run/docker.sock",
+            ))
+            .with_privileged(true)
+            .with_env_var("DOCKER_TLS_CERTDIR", "")
+            .with_mount(Mount::bind_mount(run_dir.display().to_string(), "/var/run"))
+            .start()
+            .await
+            .expect("failed to start Docker-in-Docker sandbox");
+
+        let host = homarr.get_host().await.unwrap();
+        let port = homarr.get_host_port_ipv4(HOMARR_PORT.tcp()).await.unwrap();
+        let socket = run_dir.join("docker.sock").display().to_string();
+
+        let config = Config {
+            homarr_url: format!("http://{}:{}", host, port),
+            branding_file: dir.path().join("branding.toml").display().to_string(),
+            state_file: dir.path().join("state.json").display().to_string(),
+            registry_dir: registry_dir.display().to_string(),
+            docker_socket: socket.clone(),
+            signalk_url: None,
+            existing_instance: true,
+            admin_username: Some(ADMIN_USERNAME.to_string()),
+            admin_password: Some(ADMIN_PASSWORD.to_string()),
+            ..Default::default()
+        };
+        let sandbox =
+            Docker::connect_with_socket(&socket, 120, bollard::API_DEFAULT_VERSION).unwrap();
+
+        let harness = Self {
+            _homarr: homarr,
+            _dind: dind,
+            dir,
+            config,
+            sandbox,
+        };
+        harness.wait_for_homarr().await;
+        harness.onboard().await;
+        harness
+    }
+
+    /// Poll Homarr until it answers (first start migrates its database)
+    async fn wait_for_homarr(&self) {
+        let client = reqwest::Client::new();
+        for _ in 0..90 {
+            if let Ok(response) = client.get(&self.config.homarr_url).send().await {
+                if !response.status().is_server_error() {
+                    return;
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+        panic!("Homarr did not come up at {}", self.config.homarr_url);
+    }
+
+    /// Onboard the fresh Homarr, creating the admin user from branding
+    async fn onboard(&self) {
+        let branding = BrandingConfig::load(&self.config.branding_file).unwrap();
+        let client = HomarrClient::new(&self.config.homarr_url).unwrap();
+        client.complete_onboarding(&branding).await.unwrap();
+    }
+
+    fn registry_dir(&self) -> &Path {
+        Path::new(&self.config.registry_dir)
+    }
+
+    /// Run a labelled container in the sandbox
+    async fn run_labelled(&self, name: &str, labels: &[(&str, &str)]) {
+        let mut pull = self.sandbox.create_image(
+            Some(CreateImageOptions {
+                from_image: "busybox",
+                tag: "latest",
+                ..Default::default()
+            }),
+            None,
+            None,
+        );
+        while let Some(progress) = pull.next().await {
+            progress.expect("failed to pull busybox in the sandbox");
+        }
+
+        let labels: HashMap<&str, &str> = labels.iter().copied().collect();
+        self.sandbox
+            .create_container(
+                Some(CreateContainerOptions {
+                    name,
+                    platform: None,
+                }),
+                ContainerConfig {
+                    image: Some("busybox:latest"),
+                    cmd: Some(vec!["sleep", "3600"]),
+                    labels: Some(labels),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        self.sandbox
+            .start_container::<String>(name, None)
+            .await
+            .unwrap();
+    }
+
+    /// Log in as the admin and return the app URLs placed on the board
+    async fn board_app_urls(&self) -> Vec<String> {
+        let client = HomarrClient::new(&self.config.homarr_url).unwrap();
+        client
+            .login_with_credentials(ADMIN_USERNAME, ADMIN_PASSWORD)
+            .await
+            .unwrap();
+
+        let apps = client.list_apps().await.unwrap();
+        let items = client.get_board_items(BOARD_NAME).await.unwrap();
+        items
+            .iter()
+            .filter_map(|item| item.get("options")?.get("appId")?.as_str())
+            .filter_map(|app_id| apps.iter().find(|app| app.id == app_id))
+            .filter_map(|app| app.href.clone())
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn test_setup_and_sync_place_registry_and_container_apps() {
+    let harness = Harness::start().await;
+
+    std::fs::write(
+        harness.registry_dir().join("grafana.toml"),
+        r#"
+name = "Grafana"
+url = "http://grafana.local:3000"
+visible = true
+"#,
+    )
+    .unwrap();
+    harness
+        .run_labelled(
+            "signalk",
+            &[
+                ("homarr.enable", "true"),
+                ("homarr.name", "Signal K"),
+                ("homarr.url", "http://signalk.local:3000"),
+            ],
+        )
+        .await;
+
+    crate::run_setup(&harness.config, None).await.unwrap();
+    crate::run_sync(&harness.config).await.unwrap();
+
+    let urls = harness.board_app_urls().await;
+    assert!(
+        urls.iter()
+            .any(|u| u.starts_with("http://grafana.local:3000")),
+        "registry app missing from board: {:?}",
+        urls
+    );
+    assert!(
+        urls.iter()
+            .any(|u| u.starts_with("http://signalk.local:3000")),
+        "container app missing from board: {:?}",
+        urls
+    );
+
+    // A second sync must not duplicate tiles
+    crate::run_sync(&harness.config).await.unwrap();
+    assert_eq!(harness.board_app_urls().await.len(), urls.len());
+
+    let state = crate::state::State::load(&harness.config.state_file).unwrap();
+    assert!(state.first_boot_completed);
+    assert_eq!(state.discovered_apps.len(), 2);
+    drop(harness.dir);
+}
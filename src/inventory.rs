@@ -0,0 +1,130 @@
+//! `inventory` command: synced apps with when they were first and last seen
+//!
+//! Reads the state file only. An app's last-seen time moves forward on
+//! every sync that still finds it among the desired apps, so apps whose
+//! container or registry file is long gone stand out for cleanup. The same
+//! time holds off `removal_policy` for `removal_grace_days`.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::state::{AppSource, State};
+use crate::table;
+
+/// One synced app from the state file
+#[derive(Debug, Serialize)]
+pub struct InventoryRow {
+    pub name: String,
+    pub url: String,
+    pub source: AppSource,
+    /// Container the app came from, if any
+    pub container: Option<String>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// Whole days since the app was last seen
+    pub days_unseen: i64,
+}
+
+/// Synced apps, least recently seen first, optionally only those not seen
+/// for at least `unseen_days`
+pub fn inventory(state: &State, now: DateTime<Utc>, unseen_days: Option<u32>) -> Vec<InventoryRow> {
+    let mut rows: Vec<InventoryRow> = state
+        .discovered_apps
+        .iter()
+        .map(|(url, app)| InventoryRow {
+            name: app.name.clone(),
+            url: url.clone(),
+            source: app.source,
+            container: Some(app.container_id.clone()).filter(|c| !c.is_empty()),
+            first_seen: app.added_at,
+            last_seen: app.last_seen(),
+            days_unseen: (now - app.last_seen()).num_days().max(0),
+        })
+        .filter(|row| unseen_days.is_none_or(|days| row.days_unseen >= i64::from(days)))
+        .collect();
+    rows.sort_by(|a, b| {
+        a.last_seen
+            .cmp(&b.last_seen)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    rows
+}
+
+/// Render rows as a table
+pub fn render_table(rows: &[InventoryRow]) -> String {
+    let date = |t: &DateTime<Utc>| t.format("%Y-%m-%d %H:%M").to_string();
+    let cells: Vec<[String; 5]> = rows
+        .iter()
+        .map(|row| {
+            let source = match &row.container {
+                Some(container) => format!("{} ({})", row.source.as_str(), container),
+                None => row.source.as_str().to_string(),
+            };
+            let unseen = match row.days_unseen {
+                0 => "today".to_string(),
+                1 => "1 day ago".to_string(),
+                days => format!("{} days ago", days),
+            };
+            [
+                row.name.clone(),
+                source,
+                date(&row.first_seen),
+                format!("{} ({})", date(&row.last_seen), unseen),
+                row.url.clone(),
+            ]
+        })
+        .collect();
+
+    table::render(["APP", "SOURCE", "FIRST SEEN", "LAST SEEN", "URL"], cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::DiscoveredApp;
+    use chrono::Duration;
+
+    #[test]
+    fn test_inventory_unseen_filter() {
+        let now = Utc::now();
+        let mut state = State::default();
+        for (name, url, source, last_seen) in [
+            (
+                "Grafana",
+                "http://grafana.local",
+                AppSource::Container,
+                Some(now),
+            ),
+            (
+                "AvNav",
+                "http://avnav.local",
+                AppSource::Container,
+                Some(now - Duration::days(40)),
+            ),
+            ("Router", "http://192.168.1.1", AppSource::Unknown, None),
+        ] {
+            state.discovered_apps.insert(
+                url.to_string(),
+                DiscoveredApp {
+                    name: name.to_string(),
+                    container_id: String::new(),
+                    added_at: now - Duration::days(60),
+                    last_seen,
+                    source,
+                    pinned: false,
+                },
+            );
+        }
+
+        let rows = inventory(&state, now, None);
+        let names: Vec<&str> = rows.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["Router", "AvNav", "Grafana"]);
+        // Apps recorded before last-seen was tracked count from when added
+        assert_eq!(rows[0].days_unseen, 60);
+
+        let stale = inventory(&state, now, Some(30));
+        assert_eq!(stale.len(), 2);
+        assert!(render_table(&stale).contains("40 days ago"));
+        assert_eq!(inventory(&state, now, Some(50))[0].name, "Router");
+    }
+}
@@ -0,0 +1,151 @@
+//! OS keyring storage for Homarr credentials
+//!
+//! Keeps the admin password and API key out of `branding.toml` and the state
+//! file. Two backends:
+//!
+//! - `secret-service`: the Secret Service keyring (GNOME Keyring, KWallet)
+//!   through libsecret's `secret-tool`, with items identified by the
+//!   attributes `service=homarr-container-adapter name=<secret>`
+//! - `systemd-creds`: read-only credentials passed to the unit with
+//!   `LoadCredential=` or `LoadCredentialEncrypted=`
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::Deserialize;
+
+use crate::error::{AdapterError, Result};
+
+/// Secret holding the permanent Homarr API key
+pub const API_KEY: &str = "api-key";
+
+/// Secret holding the Homarr admin password
+pub const ADMIN_PASSWORD: &str = "admin-password";
+
+/// `service` attribute of Secret Service items
+const SERVICE: &str = "homarr-container-adapter";
+
+/// Where credentials are kept besides config, branding and state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CredentialStore {
+    /// Only config, branding and state files
+    #[default]
+    None,
+    /// Secret Service keyring via `secret-tool`
+    SecretService,
+    /// systemd credentials (read-only)
+    SystemdCreds,
+}
+
+impl CredentialStore {
+    /// Look up a secret, `None` if the store doesn't have it
+    pub fn get(self, name: &str) -> Result<Option<String>> {
+        match self {
+            CredentialStore::None => Ok(None),
+            CredentialStore::SecretService => secret_tool_lookup(name),
+            CredentialStore::SystemdCreds => {
+                let dir = std::env::var_os("CREDENTIALS_DIRECTORY").map(PathBuf::from);
+                read_credential(dir.as_deref(), name)
+            }
+        }
+    }
+
+    /// Whether secrets can be saved to this store
+    pub fn is_writable(self) -> bool {
+        self == CredentialStore::SecretService
+    }
+
+    /// Save a secret (Secret Service only)
+    pub fn store(self, name: &str, secret: &str) -> Result<()> {
+        if !self.is_writable() {
+            return Err(AdapterError::Config(format!(
+                "Credential store {:?} is read-only",
+                self
+            )));
+        }
+
+        let mut child = secret_tool()
+            .args(["store", "--label", &format!("{} {}", SERVICE, name)])
+            .args(["service", SERVICE, "name", name])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(secret.as_bytes())?;
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(AdapterError::Config(format!(
+                "Failed to store '{}' in the keyring: {}",
+                name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn secret_tool() -> Command {
+    Command::new("secret-tool")
+}
+
+/// Look up a secret with `secret-tool`, which exits 1 when there is none
+fn secret_tool_lookup(name: &str) -> Result<Option<String>> {
+    let output = secret_tool()
+        .args(["lookup", "service", SERVICE, "name", name])
+        .output()
+        .map_err(|e| AdapterError::Config(format!("Cannot run secret-tool: {}", e)))?;
+
+    let secret = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || secret.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(secret))
+}
+
+/// Read a systemd credential, `None` outside a unit with credentials or
+/// when this one wasn't passed
+fn read_credential(dir: Option<&Path>, name: &str) -> Result<Option<String>> {
+    let Some(path) = dir.map(|dir| dir.join(name)) else {
+        return Ok(None);
+    };
+    if !path.exists() {
+        return Ok(None);
+    }
+    let secret = std::fs::read_to_string(&path)?.trim().to_string();
+    Ok((!secret.is_empty()).then_some(secret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_credential() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(API_KEY), "abc.token\n").unwrap();
+        std::fs::write(dir.path().join(ADMIN_PASSWORD), "").unwrap();
+
+        assert_eq!(
+            read_credential(Some(dir.path()), API_KEY).unwrap(),
+            Some("abc.token".to_string())
+        );
+        assert_eq!(
+            read_credential(Some(dir.path()), ADMIN_PASSWORD).unwrap(),
+            None
+        );
+        assert_eq!(read_credential(Some(dir.path()), "missing").unwrap(), None);
+        assert_eq!(read_credential(None, API_KEY).unwrap(), None);
+    }
+
+    #[test]
+    fn test_none_store_is_empty_and_read_only() {
+        assert_eq!(CredentialStore::None.get(API_KEY).unwrap(), None);
+        assert!(!CredentialStore::SystemdCreds.is_writable());
+        assert!(CredentialStore::None.store(API_KEY, "secret").is_err());
+    }
+}
@@ -0,0 +1,314 @@
+//! Kubernetes discovery (`kubernetes` feature)
+//!
+//! Builds apps from `homarr.halos.io/*` annotations on running pods and on
+//! ingresses, for k3s-based installs. Annotations mirror the Docker labels:
+//! `homarr.halos.io/enable: "true"`, `homarr.halos.io/url`, ... and
+//! `homarr.halos.io/1.name` for additional tiles. An annotated ingress
+//! without a URL links to its first host and path.
+//!
+//! The API server is queried directly with a service account token. Inside
+//! a pod, the API URL, token and CA certificate default to the in-cluster
+//! ones.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::config::KubernetesConfig;
+use crate::docker;
+use crate::error::{AdapterError, Result};
+use crate::registry::AppDefinition;
+
+/// Prefix of adapter annotations
+const ANNOTATION_PREFIX: &str = "homarr.halos.io/";
+
+/// Service account files mounted into every pod
+const SERVICE_ACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+/// Timeout for API server requests
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// --- Kubernetes API response types (only the fields used) ---
+
+#[derive(Debug, Deserialize)]
+struct List<T> {
+    items: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Metadata {
+    name: String,
+    #[serde(default)]
+    annotations: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Pod {
+    metadata: Metadata,
+    status: Option<PodStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PodStatus {
+    phase: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Ingress {
+    metadata: Metadata,
+    #[serde(default)]
+    spec: IngressSpec,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct IngressSpec {
+    #[serde(default)]
+    rules: Vec<IngressRule>,
+    #[serde(default)]
+    tls: Vec<IngressTls>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IngressRule {
+    host: Option<String>,
+    http: Option<HttpIngress>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HttpIngress {
+    #[serde(default)]
+    paths: Vec<HttpIngressPath>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HttpIngressPath {
+    path: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IngressTls {
+    #[serde(default)]
+    hosts: Vec<String>,
+}
+
+/// Docker-style `homarr.*` labels from `homarr.halos.io/*` annotations
+fn annotation_labels(annotations: &HashMap<String, String>) -> HashMap<String, String> {
+    annotations
+        .iter()
+        .filter_map(|(key, value)| {
+            let field = key.strip_prefix(ANNOTATION_PREFIX)?;
+            Some((format!("homarr.{}", field), value.clone()))
+        })
+        .collect()
+}
+
+/// URL served by an ingress's first host rule, https when TLS covers it
+fn ingress_url(spec: &IngressSpec) -> Option<String> {
+    let rule = spec.rules.iter().find(|rule| rule.host.is_some())?;
+    let host = rule.host.as_deref()?;
+    let path = rule
+        .http
+        .as_ref()
+        .and_then(|http| http.paths.first())
+        .and_then(|p| p.path.as_deref())
+        .unwrap_or("/");
+    let scheme = if spec
+        .tls
+        .iter()
+        .any(|tls| tls.hosts.iter().any(|h| h == host))
+    {
+        "https"
+    } else {
+        "http"
+    };
+    Some(format!("{}://{}{}", scheme, host, path))
+}
+
+/// Apps from annotated objects, given as (name, `homarr.*` labels)
+///
+/// Replicas of a deployment share their pod template's annotations, so
+/// tiles are kept once per URL.
+fn apps_from_objects(objects: Vec<(String, HashMap<String, String>)>) -> Vec<AppDefinition> {
+    let mut seen = HashSet::new();
+    objects
+        .iter()
+        .flat_map(|(name, labels)| docker::apps_from_labels(name, labels))
+        .filter(|app| seen.insert(app.url.clone()))
+        .collect()
+}
+
+/// Connection settings, with in-cluster defaults filled in
+struct ApiServer {
+    url: String,
+    token: String,
+    ca: Option<Vec<u8>>,
+}
+
+impl ApiServer {
+    fn from_config(config: &KubernetesConfig) -> Result<Self> {
+        let in_cluster = Path::new(SERVICE_ACCOUNT_DIR);
+        let url = match &config.api_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => {
+                let host = std::env::var("KUBERNETES_SERVICE_HOST").map_err(|_| {
+                    AdapterError::Config(
+                        "kubernetes.api_url is not set and not running in a cluster".to_string(),
+                    )
+                })?;
+                let port =
+                    std::env::var("KUBERNETES_SERVICE_PORT").unwrap_or_else(|_| "443".to_string());
+                format!("https://{}:{}", host, port)
+            }
+        };
+        let token_file = config
+            .token_file
+            .clone()
+            .unwrap_or_else(|| in_cluster.join("token").display().to_string());
+        let token = std::fs::read_to_string(&token_file)
+            .map_err(|e| {
+                AdapterError::Config(format!(
+                    "Cannot read Kubernetes token from {}: {}",
+                    token_file, e
+                ))
+            })?
+            .trim()
+            .to_string();
+        let ca_file = config.ca_file.clone().or_else(|| {
+            let ca = in_cluster.join("ca.crt");
+            ca.exists().then(|| ca.display().to_string())
+        });
+        let ca = ca_file.map(std::fs::read).transpose()?;
+        Ok(Self { url, token, ca })
+    }
+
+    fn client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder().timeout(REQUEST_TIMEOUT);
+        if let Some(ca) = &self.ca {
+            builder = builder.add_root_certificate(reqwest::Certificate::from_pem(ca)?);
+        }
+        Ok(builder.build()?)
+    }
+
+    /// List a resource, in one namespace or all of them
+    async fn list<T: for<'de> Deserialize<'de>>(
+        &self,
+        client: &reqwest::Client,
+        api: &str,
+        resource: &str,
+        namespace: Option<&str>,
+    ) -> Result<Vec<T>> {
+        let url = match namespace {
+            Some(ns) => format!("{}/{}/namespaces/{}/{}", self.url, api, ns, resource),
+            None => format!("{}/{}/{}", self.url, api, resource),
+        };
+        let response = client.get(&url).bearer_auth(&self.token).send().await?;
+        if !response.status().is_success() {
+            return Err(AdapterError::Kubernetes(format!(
+                "listing {} returned {}",
+                resource,
+                response.status()
+            )));
+        }
+        Ok(response.json::<List<T>>().await?.items)
+    }
+}
+
+/// Discover apps from annotated pods and ingresses
+pub async fn discover_apps(config: &KubernetesConfig) -> Result<Vec<AppDefinition>> {
+    let server = ApiServer::from_config(config)?;
+    let client = server.client()?;
+    let namespace = config.namespace.as_deref();
+
+    let pods: Vec<Pod> = server.list(&client, "api/v1", "pods", namespace).await?;
+    let ingresses: Vec<Ingress> = server
+        .list(&client, "apis/networking.k8s.io/v1", "ingresses", namespace)
+        .await?;
+
+    let mut objects: Vec<(String, HashMap<String, String>)> = pods
+        .into_iter()
+        .filter(|pod| pod.status.as_ref().and_then(|s| s.phase.as_deref()) == Some("Running"))
+        .map(|pod| {
+            let labels = annotation_labels(&pod.metadata.annotations);
+            (pod.metadata.name, labels)
+        })
+        .collect();
+    objects.extend(ingresses.into_iter().map(|ingress| {
+        let mut labels = annotation_labels(&ingress.metadata.annotations);
+        if !labels.contains_key("homarr.url") {
+            if let Some(url) = ingress_url(&ingress.spec) {
+                labels.insert("homarr.url".to_string(), url);
+            }
+        }
+        (ingress.metadata.name, labels)
+    }));
+
+    let apps = apps_from_objects(objects);
+    tracing::debug!("Discovered {} app(s) from Kubernetes", apps.len());
+    Ok(apps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annotations(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_annotation_labels() {
+        let labels = annotation_labels(&annotations(&[
+            ("homarr.halos.io/enable", "true"),
+            ("homarr.halos.io/1.name", "Admin"),
+            ("kubectl.kubernetes.io/restartedAt", "2025-01-01"),
+        ]));
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels["homarr.enable"], "true");
+        assert_eq!(labels["homarr.1.name"], "Admin");
+    }
+
+    #[test]
+    fn test_ingress_url() {
+        let spec: IngressSpec = serde_json::from_value(serde_json::json!({
+            "rules": [{ "host": "grafana.halos.local",
+                        "http": { "paths": [{ "path": "/dashboards" }] } }],
+            "tls": [{ "hosts": ["grafana.halos.local"] }]
+        }))
+        .unwrap();
+        assert_eq!(
+            ingress_url(&spec).as_deref(),
+            Some("https://grafana.halos.local/dashboards")
+        );
+
+        let spec: IngressSpec = serde_json::from_value(serde_json::json!({
+            "rules": [{ "host": "influx.halos.local" }]
+        }))
+        .unwrap();
+        assert_eq!(
+            ingress_url(&spec).as_deref(),
+            Some("http://influx.halos.local/")
+        );
+        assert_eq!(ingress_url(&IngressSpec::default()), None);
+    }
+
+    #[test]
+    fn test_apps_from_objects_collapses_replicas() {
+        let labels = annotation_labels(&annotations(&[
+            ("homarr.halos.io/enable", "true"),
+            ("homarr.halos.io/name", "Grafana"),
+            ("homarr.halos.io/url", "http://grafana.local"),
+        ]));
+        let apps = apps_from_objects(vec![
+            ("grafana-7c9d-abcde".to_string(), labels.clone()),
+            ("grafana-7c9d-fghij".to_string(), labels),
+            ("postgres-0".to_string(), HashMap::new()),
+        ]);
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0].name, "Grafana");
+    }
+}
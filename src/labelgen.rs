@@ -0,0 +1,234 @@
+//! `label-gen` command: starter labels for containers without any
+//!
+//! Guesses `homarr.*` labels for running containers that don't opt in yet:
+//! the name from the compose service (or container name), the URL from the
+//! published web port, and the icon from the dashboard-icons catalog by
+//! image. Output is either compose-style label blocks to paste, or a
+//! `docker-compose.override.yml` for one compose project.
+
+use std::fmt::Write;
+
+use crate::docker::{self, RunningContainer};
+use crate::icons;
+
+/// Labels guessed for one container
+#[derive(Debug)]
+pub struct Guess<'a> {
+    pub container: &'a RunningContainer,
+    /// (label, value) pairs, `homarr.enable` first
+    pub labels: Vec<(&'static str, String)>,
+    /// What couldn't be guessed
+    pub missing: Vec<&'static str>,
+}
+
+impl Guess<'_> {
+    /// Compose service the labels belong to, or the container name
+    fn service(&self) -> &str {
+        self.container
+            .compose_service
+            .as_deref()
+            .unwrap_or(&self.container.name)
+    }
+}
+
+/// Title-cased words of a service name (`node-red` → `Node Red`)
+fn display_name(service: &str) -> String {
+    service
+        .split(['-', '_', '.'])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Guess labels for the running containers that have none, optionally only
+/// those of one compose project
+pub fn guess<'a>(
+    containers: &'a [RunningContainer],
+    project: Option<&str>,
+    host: &str,
+) -> Vec<Guess<'a>> {
+    let mut guesses: Vec<Guess> = containers
+        .iter()
+        .filter(|c| !c.labelled)
+        .filter(|c| project.is_none_or(|p| c.compose_project.as_deref() == Some(p)))
+        .map(|container| {
+            let service = container
+                .compose_service
+                .as_deref()
+                .unwrap_or(&container.name);
+            let mut labels = vec![
+                ("homarr.enable", "true".to_string()),
+                ("homarr.name", display_name(service)),
+            ];
+            let mut missing = Vec::new();
+            match docker::published_port_url(&container.ports, host) {
+                Some(url) => labels.push(("homarr.url", url)),
+                None => missing.push("homarr.url"),
+            }
+            if let Some(slug) = icons::slug_for_image(&container.image) {
+                labels.push(("homarr.icon", icons::icon_url(slug, "png")));
+            }
+            Guess {
+                container,
+                labels,
+                missing,
+            }
+        })
+        .collect();
+    guesses.sort_by(|a, b| a.service().cmp(b.service()));
+    guesses
+}
+
+/// Render guesses as label blocks, one per container
+pub fn render_snippets(guesses: &[Guess]) -> String {
+    let mut out = String::new();
+    if guesses.is_empty() {
+        out.push_str("# Every running container already has homarr.* labels\n");
+        return out;
+    }
+    out.push_str("# Guessed homarr.* labels for running containers without any.\n");
+    out.push_str("# Check each value, then add the block to the service's labels.\n");
+    for guess in guesses {
+        let _ = writeln!(out);
+        write_service(&mut out, guess, 0);
+    }
+    out
+}
+
+/// Render guesses as a compose override file
+///
+/// Compose applies an override to one project, so the guesses must all be
+/// for services of the same project.
+pub fn render_override(guesses: &[Guess]) -> std::result::Result<String, String> {
+    let mut projects: Vec<&str> = guesses
+        .iter()
+        .map(|g| g.container.compose_project.as_deref().unwrap_or_default())
+        .collect();
+    projects.sort_unstable();
+    projects.dedup();
+    match projects.as_slice() {
+        [] => Err("no running container without labels".to_string()),
+        [""] => Err("the containers weren't started by compose".to_string()),
+        [project] => {
+            let mut out = format!(
+                "# docker-compose.override.yml for project '{}': guessed homarr.* labels.\n",
+                project
+            );
+            out.push_str("# Check each value, then run `docker compose up -d`.\n");
+            out.push_str("services:\n");
+            for guess in guesses {
+                write_service(&mut out, guess, 2);
+            }
+            Ok(out)
+        }
+        _ => Err(format!(
+            "containers from several compose projects ({}); pick one with --project",
+            projects
+                .iter()
+                .map(|p| if p.is_empty() { "none" } else { p })
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Write one service's label block, indented by `indent` spaces
+fn write_service(out: &mut String, guess: &Guess, indent: usize) {
+    let pad = " ".repeat(indent);
+    let quote = |value: &str| serde_json::to_string(value).unwrap_or_default();
+    let _ = writeln!(
+        out,
+        "{}# {} ({})",
+        pad, guess.container.name, guess.container.image
+    );
+    for label in &guess.missing {
+        let _ = writeln!(out, "{}# no published port: set {} by hand", pad, label);
+    }
+    let _ = writeln!(out, "{}{}:", pad, guess.service());
+    let _ = writeln!(out, "{}  labels:", pad);
+    for (label, value) in &guess.labels {
+        let _ = writeln!(out, "{}    {}: {}", pad, label, quote(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn container(name: &str, image: &str, ports: &[(u16, u16)]) -> RunningContainer {
+        RunningContainer {
+            name: name.to_string(),
+            image: image.to_string(),
+            ports: ports.to_vec(),
+            compose_project: Some("marine".to_string()),
+            compose_service: Some(name.trim_start_matches("marine-").to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_guess_labels() {
+        let mut labelled = container("marine-avnav", "avnav", &[(8080, 8082)]);
+        labelled.labelled = true;
+        let containers = vec![
+            container("marine-node-red", "nodered/node-red:4.0", &[(1880, 1880)]),
+            container("marine-mosquitto", "eclipse-mosquitto:2", &[]),
+            labelled,
+        ];
+        let guesses = guess(&containers, None, "halos.local");
+        assert_eq!(guesses.len(), 2);
+
+        assert_eq!(guesses[0].service(), "mosquitto");
+        assert_eq!(guesses[0].missing, vec!["homarr.url"]);
+
+        let labels: Vec<(&str, &str)> = guesses[1]
+            .labels
+            .iter()
+            .map(|(k, v)| (*k, v.as_str()))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("homarr.enable", "true"),
+                ("homarr.name", "Node Red"),
+                ("homarr.url", "http://halos.local:1880"),
+                (
+                    "homarr.icon",
+                    "https://cdn.jsdelivr.net/gh/homarr-labs/dashboard-icons/png/node-red.png"
+                ),
+            ]
+        );
+
+        assert!(guess(&containers, Some("other"), "halos.local").is_empty());
+    }
+
+    #[test]
+    fn test_render_override() {
+        let containers = vec![container(
+            "marine-grafana",
+            "grafana/grafana",
+            &[(3000, 3000)],
+        )];
+        let out = render_override(&guess(&containers, None, "halos.local")).unwrap();
+        assert!(out.contains("services:\n"));
+        assert!(out.contains("  grafana:\n    labels:\n      homarr.enable: \"true\"\n"));
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&out).unwrap();
+        assert_eq!(
+            parsed["services"]["grafana"]["labels"]["homarr.url"].as_str(),
+            Some("http://halos.local:3000")
+        );
+
+        let mut other = container("web", "nginx", &[(80, 8080)]);
+        other.compose_project = Some("web".to_string());
+        let mixed = vec![containers[0].clone(), other];
+        assert!(render_override(&guess(&mixed, None, "halos.local")).is_err());
+        assert!(render_override(&guess(&mixed, Some("web"), "halos.local")).is_ok());
+    }
+}
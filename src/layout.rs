@@ -0,0 +1,375 @@
+//! Grid placement of new board tiles
+//!
+//! A Homarr board section is a grid `columnCount` columns wide that grows
+//! downwards. Each item layout covers a rectangle of cells (`xOffset`,
+//! `yOffset`, `width`, `height`). New tiles go in the first free slot,
+//! scanning rows top to bottom and columns left to right, so they fill gaps
+//! before extending the board and never overlap existing tiles.
+
+use serde::{Deserialize, Serialize};
+
+/// What happens to a new tile when its section is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoardFull {
+    /// Add rows to the section beyond the limit
+    #[default]
+    Grow,
+    /// Place the tile in the overflow section instead
+    Overflow,
+}
+
+/// How many rows a section may hold, and what to do beyond that
+#[derive(Debug, Clone, Default)]
+pub struct SectionLimits {
+    /// Rows per section before it counts as full (`None`: never full)
+    pub max_rows: Option<i32>,
+    pub when_full: BoardFull,
+    /// Section for tiles that don't fit their own, created when missing
+    pub overflow_section: String,
+}
+
+/// Grid rectangle (x, y, width, height) of an item layout
+pub fn layout_rect(layout: &serde_json::Value) -> (i32, i32, i32, i32) {
+    let get = |key: &str, default: i64| {
+        layout.get(key).and_then(|v| v.as_i64()).unwrap_or(default) as i32
+    };
+    (
+        get("xOffset", 0),
+        get("yOffset", 0),
+        get("width", 1),
+        get("height", 1),
+    )
+}
+
+/// A section of one board layout: the grid a tile is placed on
+#[derive(Debug, Clone, Copy)]
+pub struct Grid<'a> {
+    pub layout_id: &'a str,
+    pub section_id: &'a str,
+    pub column_count: i32,
+}
+
+impl Grid<'_> {
+    /// Whether an item layout entry lies on this grid
+    fn contains(&self, layout: &serde_json::Value) -> bool {
+        layout.get("layoutId").and_then(|l| l.as_str()) == Some(self.layout_id)
+            && layout.get("sectionId").and_then(|s| s.as_str()) == Some(self.section_id)
+    }
+}
+
+/// Rectangles covered by the layouts of `items` on `grid`
+///
+/// An item's layouts in other board layouts or sections cover other grids.
+pub fn occupied(items: &[serde_json::Value], grid: Grid) -> Vec<(i32, i32, i32, i32)> {
+    items
+        .iter()
+        .filter_map(|item| item.get("layouts")?.as_array())
+        .flatten()
+        .filter(|layout| grid.contains(layout))
+        .map(layout_rect)
+        .collect()
+}
+
+/// Whether two rectangles share a cell
+fn overlaps(a: (i32, i32, i32, i32), b: (i32, i32, i32, i32)) -> bool {
+    let (ax, ay, aw, ah) = a;
+    let (bx, by, bw, bh) = b;
+    ax < bx + bw && bx < ax + aw && ay < by + bh && by < ay + ah
+}
+
+/// First free position on `grid` for a `width` x `height` tile among `items`
+///
+/// Tiles wider than the grid are placed at column 0 (Homarr clips them).
+pub fn first_free_slot(
+    items: &[serde_json::Value],
+    grid: Grid,
+    (width, height): (i32, i32),
+) -> (i32, i32) {
+    let column_count = grid.column_count;
+    let taken = occupied(items, grid);
+    let width = width.clamp(1, column_count.max(1));
+    let height = height.max(1);
+    // The row below every tile is always free, which ends the scan
+    let bottom = taken.iter().map(|&(_, y, _, h)| y + h).max().unwrap_or(0);
+
+    for y in 0..=bottom {
+        for x in 0..=(column_count - width).max(0) {
+            if taken
+                .iter()
+                .all(|&rect| !overlaps((x, y, width, height), rect))
+            {
+                return (x, y);
+            }
+        }
+    }
+    (0, bottom)
+}
+
+/// First free position for a tile that keeps it within `max_rows`, if
+/// there is one
+///
+/// The first free slot is on the topmost row with room, so if the tile
+/// sticks out below the limit there, it does everywhere else too.
+pub fn free_slot_within(
+    items: &[serde_json::Value],
+    grid: Grid,
+    (width, height): (i32, i32),
+    max_rows: Option<i32>,
+) -> Option<(i32, i32)> {
+    let (x, y) = first_free_slot(items, grid, (width, height));
+    max_rows
+        .is_none_or(|rows| y + height.max(1) <= rows)
+        .then_some((x, y))
+}
+
+/// Move tiles that stick out of a grid narrowed to `column_count` back in
+///
+/// Only item layouts in board layout `layout_id`, of items `movable`
+/// accepts, are moved: each is narrowed to the grid if wider, then put in the
+/// first free slot of its section, in reading order. Returns how many moved.
+pub fn repack(
+    items: &mut [serde_json::Value],
+    layout_id: &str,
+    column_count: i32,
+    movable: impl Fn(&serde_json::Value) -> bool,
+) -> usize {
+    let in_layout = |layout: &serde_json::Value| {
+        layout.get("layoutId").and_then(|l| l.as_str()) == Some(layout_id)
+    };
+    let section = |layout: &serde_json::Value| {
+        layout
+            .get("sectionId")
+            .and_then(|s| s.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    // (item, layout entry) of each tile to move, in reading order per section
+    let mut moving: Vec<(usize, usize)> = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        if !movable(item) {
+            continue;
+        }
+        let Some(layouts) = item.get("layouts").and_then(|l| l.as_array()) else {
+            continue;
+        };
+        for (j, layout) in layouts.iter().enumerate() {
+            let (x, _, width, _) = layout_rect(layout);
+            if in_layout(layout) && x + width > column_count {
+                moving.push((i, j));
+            }
+        }
+    }
+    let entry =
+        |items: &[serde_json::Value], (i, j): (usize, usize)| items[i]["layouts"][j].clone();
+    moving.sort_by_key(|&at| {
+        let layout = entry(items, at);
+        let (x, y, _, _) = layout_rect(&layout);
+        (section(&layout), y, x)
+    });
+
+    // Everything else in the layout stays put
+    let mut placed: Vec<serde_json::Value> = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        let Some(layouts) = item.get("layouts").and_then(|l| l.as_array()) else {
+            continue;
+        };
+        for (j, layout) in layouts.iter().enumerate() {
+            if in_layout(layout) && !moving.contains(&(i, j)) {
+                placed.push(layout.clone());
+            }
+        }
+    }
+
+    for &at in &moving {
+        let layout = entry(items, at);
+        let (_, _, width, height) = layout_rect(&layout);
+        let width = width.clamp(1, column_count.max(1));
+        let neighbours: Vec<serde_json::Value> = placed
+            .iter()
+            .map(|other| serde_json::json!({ "layouts": [other] }))
+            .collect();
+        let grid = Grid {
+            layout_id,
+            section_id: &section(&layout),
+            column_count,
+        };
+        let (x, y) = first_free_slot(&neighbours, grid, (width, height));
+
+        let (i, j) = at;
+        let target = &mut items[i]["layouts"][j];
+        target["xOffset"] = x.into();
+        target["yOffset"] = y.into();
+        target["width"] = width.into();
+        placed.push(target.clone());
+    }
+    moving.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tile(x: i32, y: i32, width: i32, height: i32) -> serde_json::Value {
+        json!({
+            "layouts": [{
+                "layoutId": "l1",
+                "sectionId": "s1",
+                "xOffset": x,
+                "yOffset": y,
+                "width": width,
+                "height": height
+            }]
+        })
+    }
+
+    fn grid(column_count: i32) -> Grid<'static> {
+        Grid {
+            layout_id: "l1",
+            section_id: "s1",
+            column_count,
+        }
+    }
+
+    #[test]
+    fn test_first_free_slot_ignores_other_grids() {
+        // The item's entries in another section and another layout
+        let item = json!({
+            "layouts": [
+                {"layoutId": "l1", "sectionId": "s2", "xOffset": 0, "yOffset": 0, "width": 2, "height": 1},
+                {"layoutId": "l2", "sectionId": "s1", "xOffset": 0, "yOffset": 0, "width": 2, "height": 1}
+            ]
+        });
+        assert_eq!(first_free_slot(&[item], grid(4), (1, 1)), (0, 0));
+    }
+
+    #[test]
+    fn test_repack_narrowed_grid() {
+        let entry = |section: &str, x: i32, y: i32, width: i32| {
+            json!({
+                "layouts": [{
+                    "layoutId": "l1",
+                    "sectionId": section,
+                    "xOffset": x,
+                    "yOffset": y,
+                    "width": width,
+                    "height": 1
+                }]
+            })
+        };
+        let mut items = vec![
+            entry("s1", 0, 0, 2),
+            entry("s1", 4, 0, 1),
+            entry("s1", 2, 1, 8),
+            entry("s2", 5, 0, 1),
+            // Not the adapter's: left alone
+            json!({"pinned": true, "layouts": [{"layoutId": "l1", "sectionId": "s1", "xOffset": 5, "yOffset": 0}]}),
+        ];
+        let moved = repack(&mut items, "l1", 4, |item| item.get("pinned").is_none());
+        assert_eq!(moved, 3);
+
+        let rect = |item: &serde_json::Value| layout_rect(&item["layouts"][0]);
+        assert_eq!(rect(&items[0]), (0, 0, 2, 1));
+        // First free slot in its section, in reading order
+        assert_eq!(rect(&items[1]), (2, 0, 1, 1));
+        // Narrowed to the grid, below the tiles above
+        assert_eq!(rect(&items[2]), (0, 1, 4, 1));
+        assert_eq!(rect(&items[3]), (0, 0, 1, 1));
+        assert_eq!(rect(&items[4]), (5, 0, 1, 1));
+
+        // Nothing sticks out: nothing moves
+        assert_eq!(repack(&mut items[..4], "l1", 4, |_| true), 0);
+    }
+
+    #[test]
+    fn test_first_free_slot_empty_board() {
+        assert_eq!(first_free_slot(&[], grid(10), (1, 1)), (0, 0));
+    }
+
+    #[test]
+    fn test_first_free_slot_single_item() {
+        // Next to the existing item
+        let items = vec![tile(0, 0, 1, 1)];
+        assert_eq!(first_free_slot(&items, grid(10), (1, 1)), (1, 0));
+    }
+
+    #[test]
+    fn test_first_free_slot_full_row() {
+        let items: Vec<serde_json::Value> = (0..10).map(|x| tile(x, 0, 1, 1)).collect();
+        assert_eq!(first_free_slot(&items, grid(10), (1, 1)), (0, 1));
+    }
+
+    #[test]
+    fn test_first_free_slot_with_gap() {
+        // Fills the gap at column 1
+        let items = vec![tile(0, 0, 1, 1), tile(2, 0, 1, 1)];
+        assert_eq!(first_free_slot(&items, grid(10), (1, 1)), (1, 0));
+    }
+
+    #[test]
+    fn test_first_free_slot_gap_too_narrow() {
+        // A 2-wide tile doesn't fit the 1-wide gap
+        let items = vec![tile(0, 0, 1, 1), tile(2, 0, 8, 1)];
+        assert_eq!(first_free_slot(&items, grid(10), (2, 1)), (0, 1));
+    }
+
+    #[test]
+    fn test_first_free_slot_wide_item() {
+        let items = vec![tile(0, 0, 3, 1)];
+        assert_eq!(first_free_slot(&items, grid(10), (1, 1)), (3, 0));
+    }
+
+    #[test]
+    fn test_first_free_slot_tall_item() {
+        // Beside the tall item, in its top row
+        let items = vec![tile(0, 0, 1, 3)];
+        assert_eq!(first_free_slot(&items, grid(10), (1, 1)), (1, 0));
+    }
+
+    #[test]
+    fn test_first_free_slot_multiple_rows() {
+        let items = vec![tile(0, 0, 10, 1), tile(0, 1, 5, 1)];
+        assert_eq!(first_free_slot(&items, grid(10), (1, 1)), (5, 1));
+    }
+
+    #[test]
+    fn test_first_free_slot_respects_column_count() {
+        // A 2-wide tile doesn't fit the last column of a 3-column grid
+        let items = vec![tile(0, 0, 2, 1)];
+        assert_eq!(first_free_slot(&items, grid(3), (2, 1)), (0, 1));
+
+        let items: Vec<serde_json::Value> = (0..3).map(|x| tile(x, 0, 1, 1)).collect();
+        assert_eq!(first_free_slot(&items, grid(3), (1, 1)), (0, 1));
+    }
+
+    #[test]
+    fn test_first_free_slot_tall_tile_avoids_overlap() {
+        // A 1x2 tile can't go in column 1: row 1 there is taken
+        let items = vec![tile(0, 0, 1, 1), tile(1, 1, 1, 1)];
+        assert_eq!(first_free_slot(&items, grid(2), (1, 2)), (0, 1));
+    }
+
+    #[test]
+    fn test_first_free_slot_items_without_layouts() {
+        let items = vec![json!({"id": "item1"}), json!({"layouts": []})];
+        assert_eq!(first_free_slot(&items, grid(10), (1, 1)), (0, 0));
+    }
+
+    #[test]
+    fn test_free_slot_within_row_limit() {
+        let items: Vec<serde_json::Value> = (0..4).map(|x| tile(x, 0, 1, 1)).collect();
+        assert_eq!(
+            free_slot_within(&items, grid(4), (1, 1), None),
+            Some((0, 1))
+        );
+        assert_eq!(
+            free_slot_within(&items, grid(4), (1, 1), Some(2)),
+            Some((0, 1))
+        );
+        assert_eq!(free_slot_within(&items, grid(4), (1, 1), Some(1)), None);
+        // Fits the second row, but a 2-high tile would need a third
+        assert_eq!(free_slot_within(&items, grid(4), (1, 2), Some(2)), None);
+    }
+}
@@ -5,13 +5,37 @@
 //! - App registry: Syncs apps from /etc/halos/webapps.d/ to Homarr dashboard
 //! - Watch mode: Daemon that monitors Docker events and syncs on changes
 
+mod alerts;
 mod branding;
+mod changelog;
 mod config;
+mod diff;
+mod docker;
 mod error;
+mod explain;
+mod export;
+mod grpc;
+mod health;
 mod homarr;
+mod icons;
+#[cfg(all(test, feature = "integration-tests"))]
+mod integration_tests;
+mod inventory;
+mod keyring;
+#[cfg(feature = "kubernetes")]
+mod kubernetes;
+mod labelgen;
+mod layout;
+mod plan;
 mod registry;
+mod selftest;
+mod setup;
 mod signalk;
+mod signing;
 mod state;
+mod state_sqlite;
+mod systemd;
+mod table;
 
 use std::collections::HashMap;
 use std::time::Duration;
@@ -19,10 +43,11 @@ use std::time::Duration;
 use bollard::container::ListContainersOptions;
 use bollard::system::EventsOptions;
 use bollard::Docker;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use futures_util::StreamExt;
 use tokio::time::{interval, sleep};
 use tracing::{debug, error, info, warn, Level};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::FmtSubscriber;
 
 use crate::config::Config;
@@ -45,6 +70,14 @@ struct Cli {
     #[arg(short, long)]
     debug: bool,
 
+    /// Config profile to apply (a `[profiles.<name>]` table in the config)
+    #[arg(long, env = "HOMARR_ADAPTER_PROFILE")]
+    profile: Option<String>,
+
+    /// Directory for the state file, overriding `state_file` in the config
+    #[arg(long, env = "HOMARR_ADAPTER_STATE_DIR")]
+    state_dir: Option<std::path::PathBuf>,
+
     /// Reset state before running command
     ///
     /// Clears all persistent state including API key, sync history, and
@@ -59,32 +92,349 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Run a sync cycle (load registry and sync to Homarr)
-    Sync,
+    Sync {
+        /// Re-validate app icons now, replacing broken ones with a fallback
+        #[arg(long)]
+        refresh_icons: bool,
+
+        /// Print the apps and tiles the sync would create, update or remove,
+        /// without changing Homarr or the state file
+        #[arg(long)]
+        dry_run: bool,
+    },
 
     /// Run first-boot setup only
-    Setup,
+    Setup {
+        /// Print what each setup step would do, without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
 
     /// Check adapter status
     Status,
 
     /// Watch for Docker events and sync continuously (daemon mode)
     Watch,
+
+    /// Exit 0 if the adapter is healthy, 1 otherwise (for container HEALTHCHECK)
+    ///
+    /// Healthy means first-boot setup has completed and the last successful
+    /// sync is recent.
+    Healthcheck {
+        /// Maximum age of the last successful sync in seconds
+        /// (default: three sync intervals)
+        #[arg(long)]
+        max_age: Option<u64>,
+    },
+
+    /// Probe every Homarr API endpoint the adapter uses and report results
+    ///
+    /// Read-only: queries are called, mutations are sent invalid input so
+    /// nothing changes. Run before upgrading Homarr to check compatibility.
+    /// Exits 1 if any check fails.
+    ApiCheck,
+
+    /// Check that the installed adapter works, for image builds
+    ///
+    /// Parses the shipped example config, the branding file, the app
+    /// registry and built-in label fixtures without Docker or Homarr.
+    /// Exits 1 on any failure.
+    SelfTest {
+        /// Also probe the configured Homarr's API (as `api-check`)
+        #[arg(long)]
+        api: bool,
+    },
+
+    /// Show where each tile will land on the board grid, without applying
+    ///
+    /// Renders every layout of each writable board as ASCII art, marking
+    /// tiles the next sync would add.
+    Preview {
+        /// Only preview this board
+        #[arg(long)]
+        board: Option<String>,
+    },
+
+    /// Show how a container's labels map to dashboard tiles
+    ///
+    /// Prints the matched labels, each resolved tile (name, URL, icon,
+    /// category, section, size) and the boards it would be placed on, or
+    /// why a sync would skip it.
+    Explain {
+        /// Container name or ID
+        container: String,
+    },
+
+    /// List the apps found from container labels, without touching Homarr
+    ///
+    /// Runs only the Docker scan of a sync (label parsing, replica counts,
+    /// description templates) and prints the resulting apps, so other
+    /// tooling can reuse the adapter's labels.
+    Discover {
+        /// Output format (json writes logs to stderr to keep stdout parseable)
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Compare what runs in Docker, what exists in Homarr and what the state
+    /// file records
+    ///
+    /// One row per app from a labelled container or synced before, marked
+    /// `=` in sync, `+` missing from Homarr, `-` gone from Docker or Homarr,
+    /// `~` URL differs or not recorded. Read-only.
+    Diff {
+        /// Output format (json writes logs to stderr to keep stdout parseable)
+        #[arg(long, alias = "output", value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Suggest `homarr.*` labels for apps created by hand in Homarr
+    ///
+    /// Matches Homarr apps the adapter doesn't manage to running containers
+    /// by published port or host name and prints label blocks to add to
+    /// their compose files. Read-only.
+    ExportLabels {
+        /// Write the suggestions to this file instead of stdout
+        #[arg(long)]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// Guess `homarr.*` labels for running containers without any
+    ///
+    /// Names come from the compose service or container name, URLs from the
+    /// published web port and icons from the container image. Prints label
+    /// blocks, or a compose override file for one project. Read-only.
+    LabelGen {
+        /// Only containers of this compose project
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Label blocks, or a docker-compose.override.yml
+        #[arg(long, value_enum, default_value_t = LabelFormat::Snippet)]
+        format: LabelFormat,
+
+        /// Write the labels to this file instead of stdout
+        #[arg(long)]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// List synced apps with when they were first and last seen
+    ///
+    /// Reads the state file only. Apps a sync no longer finds keep their
+    /// last-seen time, so `--unseen-days` shows candidates for cleanup.
+    Inventory {
+        /// Only apps not seen for at least this many days
+        #[arg(long, value_name = "DAYS")]
+        unseen_days: Option<u32>,
+
+        /// Output format (json writes logs to stderr to keep stdout parseable)
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+
+    /// Check the signatures of the state file and changelogs
+    ///
+    /// Needs `signing_key_file` or `signing_key_credential`. Fails if a
+    /// file was changed outside the adapter.
+    Verify,
+
+    /// Merge duplicate tiles of adapter-managed apps
+    ///
+    /// Finds Homarr apps that copy one adapter-managed app (same URL, or
+    /// same name), keeps the best-positioned tile on each board, removes the
+    /// other tiles and deletes the copies. Apps the adapter doesn't manage
+    /// are left alone.
+    Dedupe {
+        /// Only report what would be merged
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Back up a board to a file, or restore one
+    ///
+    /// The file holds the board's sections, layouts and tiles, plus the apps
+    /// and integrations the tiles link to, so it can also be imported on
+    /// another device.
+    Board {
+        #[command(subcommand)]
+        command: BoardCommand,
+    },
+
+    /// Add or remove individual apps without editing the registry
+    Apps {
+        #[command(subcommand)]
+        command: AppsCommand,
+    },
+
+    /// Inspect or fix the adapter's state without editing it by hand
+    State {
+        #[command(subcommand)]
+        command: StateCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum BoardCommand {
+    /// Write a board to a JSON file
+    Export {
+        /// File to write
+        #[arg(long)]
+        file: std::path::PathBuf,
+
+        /// Board to export (default: the branding board)
+        #[arg(long)]
+        board: Option<String>,
+    },
+
+    /// Replace a board's contents with a file written by `board export`,
+    /// creating the board if needed
+    Import {
+        /// File to read
+        #[arg(long)]
+        file: std::path::PathBuf,
+
+        /// Board to restore into (default: the exported board's name)
+        #[arg(long)]
+        board: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AppsCommand {
+    /// Add a one-off app and sync it to Homarr
+    Add {
+        /// Display name
+        #[arg(long)]
+        name: String,
+
+        /// App URL (absolute, or path-only such as /cockpit/)
+        #[arg(long)]
+        url: String,
+
+        /// Icon URL (/icons/*, http(s)://, or /usr/share/pixmaps/*)
+        #[arg(long)]
+        icon: Option<String>,
+
+        /// Category for grouping
+        #[arg(long)]
+        category: Option<String>,
+
+        /// App description
+        #[arg(long)]
+        description: Option<String>,
+    },
+
+    /// Remove an adapter-managed app from Homarr by name
+    ///
+    /// Manually added apps are forgotten. Registry and Signal K apps are
+    /// marked as removed so the next sync doesn't re-add them.
+    Remove {
+        /// Display name of the app
+        name: String,
+    },
+
+    /// Pin an app: keep it in Homarr, but stop updating, moving, hiding or
+    /// removing its tiles (like the `homarr.managed=false` label)
+    Pin {
+        /// Display name of the app
+        name: String,
+    },
+
+    /// Manage a pinned app again from the next sync on
+    Unpin {
+        /// Display name of the app
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum StateCommand {
+    /// Print the state as JSON, with the API key redacted
+    Show,
+
+    /// Forget everything recorded about an app (removals, pin, hidden
+    /// tiles), so the next sync handles it like a new one
+    Forget {
+        /// Display name or URL of the app
+        app: String,
+    },
+
+    /// Let the next sync add an app removed from boards (or deleted in
+    /// Homarr) again
+    Unremove {
+        /// Display name or URL of the app
+        app: String,
+    },
+
+    /// Delete the state, like --reset-state
+    ///
+    /// First-boot setup runs again on the next sync unless
+    /// --keep-credentials is given.
+    Reset {
+        /// Keep the API key, the record of first-boot setup, queued
+        /// changes and the IDs of sections the adapter created
+        #[arg(long)]
+        keep_credentials: bool,
+    },
+}
+
+/// How a command prints its results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// How `label-gen` prints its guesses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LabelFormat {
+    /// Label blocks per container, to paste into compose files
+    Snippet,
+    /// A compose override file adding the labels to each service
+    Override,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    // Set up logging
+    // Set up logging; machine-readable output keeps stdout to itself
     let level = if cli.debug { Level::DEBUG } else { Level::INFO };
+    let json_output = matches!(
+        cli.command,
+        Commands::Discover {
+            format: OutputFormat::Json
+        } | Commands::Diff {
+            format: OutputFormat::Json
+        } | Commands::Inventory {
+            format: OutputFormat::Json,
+            ..
+        } | Commands::State {
+            command: StateCommand::Show
+        }
+    );
+    let writer = if json_output {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
     let subscriber = FmtSubscriber::builder()
         .with_max_level(level)
         .with_target(false)
+        .with_writer(writer)
         .finish();
     tracing::subscriber::set_global_default(subscriber)?;
 
     // Load config
-    let config = Config::load(&cli.config)?;
+    let source = ConfigSource {
+        path: cli.config.clone(),
+        profile: cli.profile.clone(),
+        state_dir: cli.state_dir.clone(),
+    };
+    let config = source.load()?;
+    state::init_encryption(&config)?;
+    state::init_backend(&config)?;
+    signing::init(&config)?;
 
     // Handle --reset-state flag
     if cli.reset_state {
@@ -92,47 +442,428 @@ async fn main() -> Result<()> {
     }
 
     match cli.command {
-        Commands::Sync => {
+        Commands::Sync { dry_run: true, .. } => {
+            run_sync_dry_run(&config).await?;
+        }
+        Commands::Sync { refresh_icons, .. } => {
             info!("Running sync cycle");
-            run_sync(&config).await?;
+            let mut clients = HomarrClients::new(&config)?;
+            sync_all(&config, &mut clients, refresh_icons, None).await?;
         }
-        Commands::Setup => {
+        Commands::Setup { dry_run: true } => {
+            print_setup_plan(&config).await?;
+        }
+        Commands::Setup { dry_run: false } => {
             info!("Running first-boot setup");
-            run_setup(&config).await?;
+            let _lock = lock_state(&config).await?;
+            run_setup(&config, None).await?;
         }
         Commands::Status => {
             check_status(&config).await?;
         }
         Commands::Watch => {
             info!("Starting watch mode (daemon)");
-            run_watch(&config).await?;
+            run_watch(&config, &source).await?;
+        }
+        Commands::Healthcheck { max_age } => {
+            if !check_health(&config, max_age) {
+                std::process::exit(1);
+            }
+        }
+        Commands::ApiCheck => {
+            if !run_api_check(&config).await {
+                std::process::exit(1);
+            }
+        }
+        Commands::SelfTest { api } => {
+            let passed = run_self_test(&config);
+            if !passed || (api && !run_api_check(&config).await) {
+                std::process::exit(1);
+            }
+        }
+        Commands::Preview { board } => {
+            run_preview(&config, board.as_deref()).await?;
+        }
+        Commands::Explain { container } => {
+            run_explain(&config, &container).await?;
+        }
+        Commands::Discover { format } => {
+            run_discover(&config, format).await?;
+        }
+        Commands::Diff { format } => {
+            run_diff(&config, format).await?;
+        }
+        Commands::ExportLabels { output } => {
+            run_export_labels(&config, output.as_deref()).await?;
+        }
+        Commands::LabelGen {
+            project,
+            format,
+            output,
+        } => {
+            run_label_gen(&config, project.as_deref(), format, output.as_deref()).await?;
+        }
+        Commands::Inventory {
+            unseen_days,
+            format,
+        } => {
+            run_inventory(&config, unseen_days, format)?;
+        }
+        Commands::Verify => {
+            run_verify(&config)?;
+        }
+        Commands::Dedupe { dry_run } => {
+            run_dedupe(&config, dry_run).await?;
+        }
+        Commands::Board { command } => {
+            run_board(&config, command).await?;
+        }
+        Commands::Apps { command } => match command {
+            AppsCommand::Add {
+                name,
+                url,
+                icon,
+                category,
+                description,
+            } => {
+                let app = registry::AppDefinition {
+                    name,
+                    url,
+                    description,
+                    icon_url: icon,
+                    category,
+                    visible: true,
+                    ..Default::default()
+                };
+                run_apps_add(&config, app).await?;
+            }
+            AppsCommand::Remove { name } => {
+                run_apps_remove(&config, &name).await?;
+            }
+            AppsCommand::Pin { name } => {
+                run_apps_pin(&config, &name, true).await?;
+            }
+            AppsCommand::Unpin { name } => {
+                run_apps_pin(&config, &name, false).await?;
+            }
+        },
+        Commands::State { command } => {
+            run_state(&config, command).await?;
         }
     }
 
     Ok(())
 }
 
+/// Where the config came from, so watch mode can load it again on SIGHUP
+struct ConfigSource {
+    path: String,
+    profile: Option<String>,
+    state_dir: Option<std::path::PathBuf>,
+}
+
+impl ConfigSource {
+    /// Load and validate the config, with command-line overrides applied
+    fn load(&self) -> Result<Config> {
+        let mut config = Config::load(&self.path, self.profile.as_deref())?;
+        if let Some(dir) = &self.state_dir {
+            config.set_state_dir(dir);
+        }
+        Ok(config)
+    }
+}
+
+/// Create a Homarr client with the configured connection pool and request
+/// policy settings
+fn homarr_client(config: &Config) -> Result<homarr::HomarrClient> {
+    let mut client =
+        homarr::HomarrClient::with_pool_options(&config.homarr_url, &config.pool_options())?;
+    client.set_request_policies(config.request_policies());
+    client.set_compose_sections(config.compose_sections);
+    client.set_category_sections(config.category_sections);
+    client.set_section_limits(config.section_limits());
+    Ok(client)
+}
+
+/// Clients of the main Homarr and of each `[[homarr]]` target, in config
+/// order
+///
+/// Watch mode keeps them for the lifetime of the daemon, like a single
+/// client. Targets' board edits aren't deferred, so only the main client
+/// has board saves to flush.
+struct HomarrClients {
+    main: homarr::HomarrClient,
+    targets: Vec<homarr::HomarrClient>,
+}
+
+impl HomarrClients {
+    fn new(config: &Config) -> Result<Self> {
+        Ok(Self {
+            main: homarr_client(config)?,
+            targets: config
+                .homarr
+                .iter()
+                .map(|target| homarr_client(&config.for_target(target)))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
 async fn run_sync(config: &Config) -> Result<()> {
+    let mut clients = HomarrClients::new(config)?;
+    sync_all(config, &mut clients, false, None).await
+}
+
+/// Sync the main Homarr and every `[[homarr]]` target
+///
+/// Targets sync concurrently (up to `max_concurrent_targets`), each with
+/// its own client from `clients` and its own state file, so one unreachable instance doesn't hold
+/// up the others. Each instance's result is logged and, in watch mode,
+/// published as a sync event naming it. Fails when any instance failed:
+/// with the main instance's error if it was one of them, otherwise naming
+/// the failed targets.
+async fn sync_all(
+    config: &Config,
+    clients: &mut HomarrClients,
+    refresh_icons: bool,
+    hub: Option<&grpc::ControlHub>,
+) -> Result<()> {
+    use tracing::Instrument;
+
+    let HomarrClients {
+        main: client,
+        targets: target_clients,
+    } = clients;
+    if config.homarr.is_empty() {
+        return run_sync_with_client(config, client, refresh_icons, hub).await;
+    }
+
+    let primary = async {
+        let started = std::time::Instant::now();
+        let result = run_sync_with_client(config, client, refresh_icons, hub).await;
+        ("main".to_string(), result, started.elapsed())
+    };
+    let targets = futures_util::stream::iter(config.homarr.iter().zip(target_clients).map(
+        |(target, client)| {
+            let target_config = config.for_target(target);
+            let span = tracing::info_span!("target", name = %target.name);
+            async move {
+                let started = std::time::Instant::now();
+                let result =
+                    run_sync_with_client(&target_config, client, refresh_icons, None).await;
+                (target.name.clone(), result, started.elapsed())
+            }
+            .instrument(span)
+        },
+    ))
+    .buffer_unordered(config.max_concurrent_targets)
+    .collect::<Vec<_>>();
+    let (primary, mut outcomes) = tokio::join!(primary, targets);
+    outcomes.insert(0, primary);
+
+    for (name, result, elapsed) in &outcomes {
+        use grpc::proto::EventKind;
+
+        let (kind, message) = match result {
+            Ok(()) => {
+                info!("Target '{}': synced in {:.1?}", name, elapsed);
+                (
+                    EventKind::SyncSucceeded,
+                    format!("synced in {:.1?}", elapsed),
+                )
+            }
+            Err(e) => {
+                warn!(
+                    "Target '{}': sync failed after {:.1?}: {}",
+                    name, elapsed, e
+                );
+                (EventKind::SyncFailed, e.to_string())
+            }
+        };
+        if let Some(hub) = hub {
+            hub.publish_for_target(kind, name, message);
+        }
+    }
+    let primary = outcomes.remove(0).1;
+    let failed: Vec<&str> = outcomes
+        .iter()
+        .filter(|(_, result, _)| result.is_err())
+        .map(|(name, _, _)| name.as_str())
+        .collect();
+    match primary {
+        Err(e) => Err(e),
+        Ok(()) if failed.is_empty() => Ok(()),
+        Ok(()) => Err(AdapterError::TargetsFailed(failed.join(", "))),
+    }
+}
+
+/// How long a sync waits for another one to finish
+const SYNC_LOCK_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Take the state's sync lock, for anything that loads state, changes it
+/// and saves it back (never while already holding it)
+async fn lock_state(config: &Config) -> Result<state::SyncLock> {
+    state::SyncLock::acquire(std::path::Path::new(&config.state_file), SYNC_LOCK_TIMEOUT).await
+}
+
+/// Run a sync cycle with an existing client
+///
+/// Watch mode keeps one client for the lifetime of the daemon so pooled
+/// connections (and their TLS sessions) are reused across cycles.
+///
+/// `refresh_icons` re-validates icons regardless of the refresh interval.
+/// Holds the state's sync lock throughout.
+async fn run_sync_with_client(
+    config: &Config,
+    client: &mut homarr::HomarrClient,
+    refresh_icons: bool,
+    hub: Option<&grpc::ControlHub>,
+) -> Result<()> {
+    let _lock = lock_state(config).await?;
+    let mut result = sync_cycle(config, client, refresh_icons, hub).await;
+    // Plan again against a board edited in Homarr during the sync, once
+    if let Err(AdapterError::BoardChanged(ref board)) = result {
+        info!("Board '{}' changed during the sync, syncing again", board);
+        result = sync_cycle(config, client, refresh_icons, hub).await;
+    }
+    record_sync_outcome(config, &result, client.take_rate_limited()).await;
+    result
+}
+
+/// Track the consecutive failure streak and alert when it hits the threshold
+///
+/// Rate limiting pauses syncs instead of counting as a failure.
+async fn record_sync_outcome(config: &Config, result: &Result<()>, rate_limited: u32) {
+    let mut state = match state::State::load(&config.state_file) {
+        Ok(state) => state,
+        Err(e) => {
+            warn!("Failed to load state to record sync outcome: {}", e);
+            return;
+        }
+    };
+
+    let failures = match result {
+        Ok(()) => {
+            let ended = state.record_sync_success();
+            if ended > 0 {
+                info!("Sync recovered after {} failure(s)", ended);
+            }
+            0
+        }
+        Err(AdapterError::RateLimited(secs)) => {
+            let now = chrono::Utc::now();
+            if state.rate_limit_pause(now).is_none() {
+                let until = now + chrono::Duration::seconds(*secs as i64);
+                warn!(
+                    "Homarr is rate limiting requests, pausing syncs until {}",
+                    until.with_timezone(&chrono::Local).format("%H:%M:%S")
+                );
+                state.rate_limited_until = Some(until);
+            }
+            0
+        }
+        Err(_) => state.record_sync_failure(),
+    };
+    state.rate_limited_responses += u64::from(rate_limited);
+
+    if let Err(e) = state.save(&config.state_file) {
+        warn!("Failed to save sync failure count: {}", e);
+    }
+
+    // Each channel decides whether the streak has reached its threshold
+    if let Err(e) = result {
+        if failures > 0 {
+            let error = e.to_string();
+            let alert = alerts::Alert::SyncFailing {
+                failures,
+                error: &error,
+                last_success: state.last_sync,
+            };
+            if let Err(e) = alerts::send(config, &alert).await {
+                warn!("Failed to send sync failure alert: {}", e);
+            }
+        }
+    }
+}
+
+async fn sync_cycle(
+    config: &Config,
+    client: &mut homarr::HomarrClient,
+    refresh_icons: bool,
+    hub: Option<&grpc::ControlHub>,
+) -> Result<()> {
+    if let Some(until) = config.maintenance_until(chrono::Local::now()) {
+        info!(
+            "Homarr maintenance window until {}, skipping sync",
+            until.format("%H:%M")
+        );
+        return Ok(());
+    }
+    let started = std::time::Instant::now();
+
     // Check if first-boot setup is needed
     let mut state = state::State::load(&config.state_file)?;
 
+    if let Some(until) = state.rate_limit_pause(chrono::Utc::now()) {
+        let secs = (until - chrono::Utc::now()).num_seconds().max(1) as u64;
+        return Err(AdapterError::RateLimited(secs));
+    }
+
     if !state.first_boot_completed {
         info!("First boot detected, running setup");
-        run_setup(config).await?;
+        run_setup(config, hub).await?;
         // Reload state after setup (it saved first_boot_completed = true)
         state = state::State::load(&config.state_file)?;
     }
 
-    // Create client and set up authentication
-    let mut client = homarr::HomarrClient::new(&config.homarr_url)?;
-    ensure_authenticated(&mut client, config, &mut state).await?;
+    // Set up authentication
+    ensure_authenticated(client, config, &mut state).await?;
+    if let Some(reason) = client.mutations_refused() {
+        state.save(&config.state_file)?;
+        return Err(AdapterError::UnsupportedHomarr(reason.to_string()));
+    }
+    client.start_cycle();
+    let mut timings = state::SyncTimings::default();
+    let mut phase = std::time::Instant::now();
+    // Board edits deferred by earlier syncs, already recorded in state
+    let staged_before = client.staged_boards();
+
+    // Apps without an explicit section go to the branding default section
+    let branding = match branding::BrandingConfig::load(&config.branding_file) {
+        Ok(branding) => {
+            client.set_default_section(branding.board.default_section.clone());
+            Some(branding)
+        }
+        Err(e) => {
+            debug!("Branding not loaded, using first board section: {}", e);
+            None
+        }
+    };
 
     // Discover writable boards
-    let writable_boards = client.get_writable_boards().await.unwrap_or_else(|e| {
-        warn!("Failed to fetch writable boards: {}", e);
-        vec![]
-    });
-
+    let mut writable_boards = match client.get_writable_boards().await {
+        // A `[[homarr]]` target with a board only gets tiles on that board
+        Ok(boards) => match &config.target_board {
+            Some(only) if !boards.iter().any(|board| &board.name == only) => {
+                return Err(AdapterError::Config(format!(
+                    "Target board '{}' is not among the writable boards of {}",
+                    only, config.homarr_url
+                )));
+            }
+            Some(only) => boards
+                .into_iter()
+                .filter(|board| &board.name == only)
+                .collect(),
+            None => boards,
+        },
+        Err(e) => {
+            warn!(
+                "Failed to fetch writable boards: {}",
+                rate_limit_stops(client, &staged_before, e)?
+            );
+            vec![]
+        }
+    };
     if writable_boards.is_empty() {
         warn!("No writable boards found, skipping sync");
         return Ok(());
@@ -149,10 +880,35 @@ async fn run_sync(config: &Config) -> Result<()> {
     );
 
     // Pre-fetch existing apps for efficient deduplication
-    let existing_apps = client.get_all_apps().await.unwrap_or_else(|e| {
-        warn!("Failed to fetch existing apps: {}", e);
-        vec![]
-    });
+    let (existing_apps, apps_listed) = match client.list_apps().await {
+        Ok(apps) => (apps, true),
+        Err(e) => {
+            warn!(
+                "Failed to fetch existing apps: {}",
+                rate_limit_stops(client, &staged_before, e)?
+            );
+            (vec![], false)
+        }
+    };
+
+    // What this sync changes in Homarr, for the per-boot change log
+    let mut changes = changelog::Changelog::default();
+
+    // Changes queued while Homarr was unreachable go first, in order
+    if let Err(e) = flush_pending_changes(
+        client,
+        &mut state,
+        &mut changes,
+        &existing_apps,
+        &writable_boards,
+    )
+    .await
+    {
+        return Err(rate_limit_stops(client, &staged_before, e)?);
+    }
+
+    timings.planning_ms += lap(&mut phase);
+    systemd::status("Syncing: discovering apps");
 
     // Load registry apps
     info!("Loading apps from registry: {}", config.registry_dir);
@@ -175,6 +931,13 @@ async fn run_sync(config: &Config) -> Result<()> {
     };
     let signalk_apps = signalk_result.as_deref().unwrap_or(&[]);
 
+    // Discover apps from container labels
+    let (container_apps, containers_complete) = discover_container_apps(config).await;
+    state.docker_permission_denied =
+        !containers_complete && docker::socket_permission_denied(config).is_some();
+    timings.discovery_ms += lap(&mut phase);
+    systemd::status("Syncing: planning");
+
     // Clean up stale Signal K webapps (only when SK was reachable)
     if signalk_result.is_some() {
         let current_sk_urls: std::collections::HashSet<&str> =
@@ -196,19 +959,42 @@ async fn run_sync(config: &Config) -> Result<()> {
                 .map(|a| a.name.clone())
                 .unwrap_or_else(|| "unknown".to_string());
 
-            // Try to find and delete the app in Homarr
-            if let Ok(apps) = client.get_all_apps().await {
-                if let Some(existing) = apps.iter().find(|a| {
-                    a.href
-                        .as_ref()
-                        .map(|h| homarr::normalize_url(h) == homarr::normalize_url(url))
-                        .unwrap_or(false)
-                }) {
-                    match client.delete_app(&existing.id).await {
-                        Ok(_) => info!("Removed stale Signal K webapp '{}' from Homarr", app_name),
-                        Err(e) => warn!("Failed to remove stale webapp '{}': {}", app_name, e),
+            // Try to find and delete the app in Homarr; if Homarr can't be
+            // reached, queue the deletion so it isn't forgotten
+            let queued = state::PendingChange::Delete {
+                url: url.clone(),
+                name: app_name.clone(),
+                remove_from_boards: false,
+            };
+            match client.list_apps().await {
+                Ok(apps) => {
+                    if let Some(existing) = apps.iter().find(|a| {
+                        a.href
+                            .as_ref()
+                            .map(|h| homarr::normalize_url(h) == homarr::normalize_url(url))
+                            .unwrap_or(false)
+                    }) {
+                        match client.delete_app(&existing.id).await {
+                            Ok(_) => {
+                                info!("Removed stale Signal K webapp '{}' from Homarr", app_name);
+                                changes.record(
+                                    format!("Deleted app '{}'", app_name),
+                                    "no longer served by Signal K",
+                                );
+                            }
+                            Err(e) if e.is_unreachable() => state.queue_change(queued),
+                            Err(e) => warn!(
+                                "Failed to remove stale webapp '{}': {}",
+                                app_name,
+                                rate_limit_stops(client, &staged_before, e)?
+                            ),
+                        }
                     }
                 }
+                Err(e) if e.is_unreachable() => state.queue_change(queued),
+                Err(e) => {
+                    rate_limit_stops(client, &staged_before, e)?;
+                }
             }
 
             state.discovered_apps.remove(url);
@@ -234,27 +1020,228 @@ async fn run_sync(config: &Config) -> Result<()> {
         );
     }
 
-    let all_visible_apps: Vec<&registry::AppDefinition> = visible_registry
-        .into_iter()
-        .chain(signalk_apps.iter())
+    // Manually added apps (`apps add`) and `[[apps]]` are always visible
+    let manual_apps = state.manual_apps.clone();
+    let config_apps: Vec<registry::AppDefinition> =
+        config.apps.iter().map(config::StaticApp::app).collect();
+
+    let registry_count = visible_registry.len();
+    let mut resolved_apps = registry::resolve_duplicate_names(
+        visible_registry
+            .into_iter()
+            .chain(container_apps.iter())
+            .chain(signalk_apps.iter())
+            .chain(manual_apps.iter())
+            .chain(config_apps.iter())
+            .collect(),
+        config.duplicate_names,
+    )?;
+    if config.require_https {
+        resolved_apps = registry::enforce_https(
+            resolved_apps,
+            config.https_upgrade,
+            &config.https_exceptions,
+        );
+    }
+
+    // Tiles outside their visible_hours are hidden like `hide = true`
+    let now = chrono::Local::now();
+    for app in resolved_apps.iter_mut() {
+        if !app.in_visible_hours(now.time()) {
+            app.hide = true;
+        }
+    }
+    state.next_visibility_change =
+        registry::next_visibility_change(&resolved_apps, now).map(|t| t.to_utc());
+
+    // `apps pin` pins an app like `homarr.managed=false`
+    for app in resolved_apps.iter_mut() {
+        let url = homarr::normalize_url(&app.url);
+        if state
+            .pinned_apps
+            .iter()
+            .any(|pinned| homarr::normalize_url(pinned) == url)
+        {
+            app.pinned = true;
+        }
+    }
+
+    let all_visible_apps: Vec<&registry::AppDefinition> = resolved_apps.iter().collect();
+
+    // Apps of containers that are gone, unless discovery came back short or
+    // Homarr's apps couldn't be listed (every app would look deleted)
+    if containers_complete && apps_listed {
+        let current: std::collections::HashSet<&str> = registry_apps
+            .iter()
+            .map(|e| e.app.url.as_str())
+            .chain(resolved_apps.iter().map(|app| app.url.as_str()))
+            .chain(container_apps.iter().map(|app| app.url.as_str()))
+            .collect();
+        if let Err(e) = apply_removal_policy(
+            client,
+            config,
+            &mut state,
+            &mut changes,
+            &current,
+            &existing_apps,
+            &writable_boards,
+        )
+        .await
+        {
+            return Err(rate_limit_stops(client, &staged_before, e)?);
+        }
+    }
+
+    let icon_interval = chrono::Duration::seconds(config.icon_refresh_interval as i64);
+    if refresh_icons
+        || (config.icon_refresh_interval > 0 && state.icons_due(icon_interval, chrono::Utc::now()))
+    {
+        refresh_icon_overrides(client, &mut state, &all_visible_apps).await;
+    }
+    client.set_icon_overrides(state.icon_overrides.clone());
+    client.set_section_ids(state.section_ids.clone());
+    upload_file_icons(client, &mut state, &all_visible_apps).await;
+
+    // Apps deleted in the Homarr UI stay deleted
+    if apps_listed {
+        record_user_deletions(
+            &mut state,
+            &mut changes,
+            &all_visible_apps,
+            &existing_apps,
+            &writable_boards,
+        );
+    }
+
+    // Where an app comes from, for the inventory and the change log
+    let source = |app: &registry::AppDefinition| match app.container_name() {
+        Some(_) => state::AppSource::Container,
+        None if signalk::is_signalk_webapp_url(&app.url) => state::AppSource::SignalK,
+        None if manual_apps.iter().any(|m| m.url == app.url) => state::AppSource::Manual,
+        None if config_apps.iter().any(|c| c.url == app.url) => state::AppSource::Config,
+        None => state::AppSource::Registry,
+    };
+    let origin = |app: &registry::AppDefinition| match (source(app), app.container_name()) {
+        (_, Some(container)) => format!("from container '{}'", container),
+        (state::AppSource::SignalK, _) => "Signal K webapp".to_string(),
+        (state::AppSource::Manual, _) => "added with `apps add`".to_string(),
+        (state::AppSource::Config, _) => "listed in the adapter config".to_string(),
+        _ => "in the app registry".to_string(),
+    };
+
+    // Boards named by `homarr.board` are created on first use
+    let routed: std::collections::BTreeSet<&str> = all_visible_apps
+        .iter()
+        .filter_map(|app| app.board.as_deref())
         .collect();
+    for name in routed {
+        if writable_boards.iter().any(|board| board.name == name)
+            || config
+                .target_board
+                .as_ref()
+                .is_some_and(|only| only != name)
+        {
+            continue;
+        }
+        match client.ensure_board(name, branding.as_ref()).await {
+            Ok(ensured) if !ensured.board.is_writable() => {
+                warn!(
+                    "Board '{}' named by homarr.board is read-only, skipping",
+                    name
+                )
+            }
+            Ok(ensured) => {
+                if ensured.created {
+                    changes.record(format!("Created board '{}'", name), "named by homarr.board");
+                }
+                writable_boards.push(ensured.board);
+            }
+            Err(e) => warn!(
+                "Failed to get or create board '{}': {}",
+                name,
+                rate_limit_stops(client, &staged_before, e)?
+            ),
+        }
+    }
+
+    // Column counts in branding apply to existing boards too; only tiles of
+    // the apps synced here are moved to fit a narrower grid
+    if let Some(branding) = &branding {
+        let managed: std::collections::HashSet<String> = all_visible_apps
+            .iter()
+            .filter(|app| !app.pinned)
+            .filter_map(|app| homarr::HomarrClient::find_app_by_url(&existing_apps, &app.url))
+            .map(|app| app.id.clone())
+            .collect();
+        for board in &writable_boards {
+            let Some(columns) = branding.board.columns_for(&board.name) else {
+                continue;
+            };
+            match client
+                .reconcile_column_count(&board.name, columns.into(), &managed)
+                .await
+            {
+                Ok(true) => changes.record(
+                    format!("Set board '{}' to {} columns", board.name, columns),
+                    "column count in branding",
+                ),
+                Ok(false) => {}
+                Err(e) => warn!(
+                    "Failed to set the column count of board '{}': {}",
+                    board.name,
+                    rate_limit_stops(client, &staged_before, e)?
+                ),
+            }
+        }
+    }
+
+    // Compose project and category sections, created together in name order
+    for board in &writable_boards {
+        let board_apps: Vec<&registry::AppDefinition> = all_visible_apps
+            .iter()
+            .filter(|app| {
+                let pinned = app.pinned
+                    && homarr::HomarrClient::find_app_by_url(&existing_apps, &app.url).is_some();
+                app.targets_board(&board.name)
+                    && !pinned
+                    && !state.is_removed_from_board(&board.id, &app.url)
+            })
+            .copied()
+            .collect();
+        if let Err(e) = client
+            .ensure_created_sections(&board.name, &board_apps, &existing_apps)
+            .await
+        {
+            warn!(
+                "Failed to create sections on board '{}': {}",
+                board.name,
+                rate_limit_stops(client, &staged_before, e)?
+            );
+        }
+    }
+
+    timings.planning_ms += lap(&mut phase);
+    systemd::status("Syncing: placing apps");
 
-    // Sync each visible app to each writable board
+    // Sync each visible app to each writable board it targets
     let mut synced_count = 0;
     for app in &all_visible_apps {
-        // Track app in discovered_apps (once per app, not per board)
-        let container_id = app.container_name().unwrap_or("").to_string();
-        state.discovered_apps.insert(
-            app.url.clone(),
-            state::DiscoveredApp {
-                name: app.name.clone(),
-                container_id,
-                added_at: chrono::Utc::now(),
-            },
-        );
+        // Only apps that made it into Homarr count as synced, so one that
+        // failed to be created isn't later taken for deleted by the user
+        let mut in_homarr =
+            homarr::HomarrClient::find_app_by_url(&existing_apps, &app.url).is_some();
+
+        // Pinned apps are created once, then left to the user
+        let pinned = app.pinned && in_homarr;
+        if pinned {
+            debug!("App '{}' is pinned, leaving it as it is", app.name);
+        }
 
         // Sync to each writable board
-        for board in &writable_boards {
+        for board in writable_boards
+            .iter()
+            .filter(|board| !pinned && app.targets_board(&board.name))
+        {
             // Check if app was removed from this specific board
             if state.is_removed_from_board(&board.id, &app.url) {
                 debug!(
@@ -264,125 +1251,1691 @@ async fn run_sync(config: &Config) -> Result<()> {
                 continue;
             }
 
+            // Put an unhidden tile back where it was, unless the app is gone
+            if !app.hide
+                && homarr::HomarrClient::find_app_by_url(&existing_apps, &app.url).is_some()
+            {
+                if let Some(items) = state.take_hidden_tile(&board.id, &app.url) {
+                    match client.restore_tile(&board.name, items).await {
+                        Ok(true) => {
+                            info!("Restored tile '{}' on board '{}'", app.name, board.name);
+                            changes.record(
+                                format!("Restored tile '{}' on board '{}'", app.name, board.name),
+                                "no longer hidden",
+                            );
+                        }
+                        Ok(false) => info!(
+                            "Position of tile '{}' on board '{}' was taken, placing it anew",
+                            app.name, board.name
+                        ),
+                        Err(e) => warn!(
+                            "Failed to restore tile '{}': {}",
+                            app.name,
+                            rate_limit_stops(client, &staged_before, e)?
+                        ),
+                    }
+                }
+            }
+
             match client
                 .add_registry_app(app, &board.name, Some(&existing_apps))
                 .await
             {
-                Ok(_) => {
+                Ok(synced) if app.hide => match client.hide_tile(&synced.id, &board.name).await {
+                    Ok(items) => {
+                        in_homarr = true;
+                        if !items.is_empty() {
+                            state.hide_tile(&board.id, &app.url, items);
+                            changes.record(
+                                format!("Hid tile '{}' on board '{}'", app.name, board.name),
+                                if app.in_visible_hours(now.time()) {
+                                    "homarr.hide is set"
+                                } else {
+                                    "outside its visible_hours"
+                                },
+                            );
+                        }
+                        synced_count += 1;
+                    }
+                    Err(e) => warn!(
+                        "Failed to hide tile '{}': {}",
+                        app.name,
+                        rate_limit_stops(client, &staged_before, e)?
+                    ),
+                },
+                Ok(synced) => {
+                    in_homarr = true;
+                    if synced.created {
+                        changes.record(format!("Created app '{}'", app.name), origin(app));
+                        changes.added_apps.push(app.name.clone());
+                    }
+                    if synced.placed {
+                        changes.record(
+                            format!("Placed tile '{}' on board '{}'", app.name, board.name),
+                            origin(app),
+                        );
+                    }
                     synced_count += 1;
                 }
+                Err(e) if e.is_unreachable() => {
+                    warn!(
+                        "Homarr unreachable adding app '{}' to board '{}', queued",
+                        app.name, board.name
+                    );
+                    state.queue_change(state::PendingChange::Upsert {
+                        board: board.name.clone(),
+                        app: Box::new((*app).clone()),
+                    });
+                }
                 Err(e) => {
                     warn!(
                         "Failed to add app '{}' to board '{}': {}",
-                        app.name, board.name, e
+                        app.name,
+                        board.name,
+                        rate_limit_stops(client, &staged_before, e)?
                     );
                 }
             }
         }
+
+        // Track app in discovered_apps (once per app, not per board)
+        if in_homarr {
+            let container_id = app.container_name().unwrap_or("").to_string();
+            let now = chrono::Utc::now();
+            state.record_discovered_app(
+                &app.url,
+                state::DiscoveredApp {
+                    name: app.name.clone(),
+                    container_id,
+                    added_at: now,
+                    last_seen: Some(now),
+                    source: source(app),
+                    pinned: app.pinned,
+                },
+            );
+        }
+    }
+
+    timings.apps_ms += lap(&mut phase);
+    systemd::status("Syncing: saving boards");
+
+    // One save per board for all tiles placed above. Until the boards hold
+    // them, none of this sync's hidden tiles, removals or change log lines
+    // are kept: the next attempt plans them again from what Homarr has.
+    let flushed = client.flush_board_saves().await;
+    timings.board_save_ms += lap(&mut phase);
+    if let Err(e) = flushed {
+        client.restore_board_saves(staged_before);
+        if !matches!(e, AdapterError::BoardChanged(_)) {
+            warn!("Failed to save board changes: {}", e);
+        }
+        return Err(e);
+    }
+    state.section_ids = client.section_ids();
+    // Writing the change log takes its lines; the notification lists them
+    let changed = changes.entries().to_vec();
+    if let Err(e) = changes.write(&config.changelog_file()) {
+        warn!("Failed to write change log: {}", e);
+    }
+    if !changes.added_apps.is_empty() || !changes.removed_apps.is_empty() {
+        let alert = alerts::Alert::AppsChanged {
+            added: &changes.added_apps,
+            removed: &changes.removed_apps,
+            summary: alerts::SyncSummary {
+                apps: all_visible_apps.len(),
+                boards: writable_boards.len(),
+                synced: synced_count,
+                changes: &changed,
+            },
+        };
+        if let Err(e) = alerts::send(config, &alert).await {
+            warn!("Failed to send app change notification: {}", e);
+        }
     }
 
+    timings.total_ms = started.elapsed().as_millis() as u64;
+    debug!("Sync timing: {}", timings.summary());
+    state.last_sync_timings = Some(timings);
     state.update_sync_time();
     state.save(&config.state_file)?;
 
     info!(
-        "Sync complete: {} visible app(s) ({} registry, {} Signal K), {} app-board combinations synced",
+        "Sync complete: {} visible app(s) ({} registry, {} container, {} Signal K, {} manual, {} config), {} app-board combinations synced",
         all_visible_apps.len(),
-        all_visible_apps.len() - signalk_apps.len(),
+        registry_count,
+        container_apps.len(),
         signalk_apps.len(),
+        manual_apps.len(),
+        config_apps.len(),
         synced_count
     );
     Ok(())
 }
 
-/// Ensure the Homarr client is authenticated with a valid API key.
+/// Stop the sync cycle at a rate limit, returning other errors to be
+/// logged
 ///
-/// If a permanent API key is stored in state, use it.
-/// Otherwise, rotate from the bootstrap API key to a new permanent key.
-async fn ensure_authenticated(
+/// The client has already waited out its retries, so carrying on would
+/// only earn more 429s. Board edits staged by the cycle are dropped, as for
+/// a failed board save.
+fn rate_limit_stops(
+    client: &homarr::HomarrClient,
+    staged_before: &std::collections::HashMap<String, homarr::Board>,
+    e: AdapterError,
+) -> Result<AdapterError> {
+    if matches!(e, AdapterError::RateLimited(_)) {
+        client.restore_board_saves(staged_before.clone());
+        return Err(e);
+    }
+    Ok(e)
+}
+
+/// Milliseconds since `mark`, moving `mark` to now
+fn lap(mark: &mut std::time::Instant) -> u64 {
+    let now = std::time::Instant::now();
+    let elapsed = now.duration_since(*mark).as_millis() as u64;
+    *mark = now;
+    elapsed
+}
+
+/// Latest last-seen time of an app `removal_policy` applies to
+fn removal_cutoff(config: &Config) -> chrono::DateTime<chrono::Utc> {
+    chrono::Utc::now() - chrono::Duration::days(config.removal_grace_days.into())
+}
+
+/// Hide or delete the apps of containers that disappeared since earlier
+/// syncs, per `removal_policy` once `removal_grace_days` have passed, and
+/// forget those that are back
+///
+/// Hidden tiles of a returning container are restored by the placement
+/// loop; deleted apps are simply created again. Stops at a rate limit.
+async fn apply_removal_policy(
+    client: &homarr::HomarrClient,
+    config: &Config,
+    state: &mut state::State,
+    changes: &mut changelog::Changelog,
+    current: &std::collections::HashSet<&str>,
+    existing_apps: &[homarr::SelectableApp],
+    writable_boards: &[homarr::BoardWithPermission],
+) -> Result<()> {
+    let policy = config.removal_policy;
+    for app in state.take_returned_apps(current) {
+        info!(
+            "Container '{}' of app '{}' is back",
+            app.container, app.name
+        );
+    }
+    if policy == state::RemovalPolicy::Keep {
+        return Ok(());
+    }
+
+    for (url, name, container) in state.vanished_container_apps(current, removal_cutoff(config)) {
+        let Some(existing) = homarr::HomarrClient::find_app_by_url(existing_apps, &url) else {
+            // Already deleted in Homarr, nothing left to hide or remove
+            state.discovered_apps.remove(&url);
+            continue;
+        };
+        let done = match policy {
+            state::RemovalPolicy::Keep => unreachable!(),
+            state::RemovalPolicy::Hide => {
+                let mut hidden = true;
+                for board in writable_boards {
+                    match client.hide_tile(&existing.id, &board.name).await {
+                        Ok(items) if !items.is_empty() => state.hide_tile(&board.id, &url, items),
+                        Ok(_) => {}
+                        Err(e @ AdapterError::RateLimited(_)) => return Err(e),
+                        Err(e) => {
+                            warn!("Failed to hide tile '{}': {}", name, e);
+                            hidden = false;
+                        }
+                    }
+                }
+                hidden
+            }
+            state::RemovalPolicy::Remove => match client.delete_app(&existing.id).await {
+                Ok(_) => {
+                    state.discovered_apps.remove(&url);
+                    true
+                }
+                Err(e) if e.is_unreachable() => {
+                    state.queue_change(state::PendingChange::Delete {
+                        url: url.clone(),
+                        name: name.clone(),
+                        remove_from_boards: false,
+                    });
+                    state.discovered_apps.remove(&url);
+                    true
+                }
+                Err(e @ AdapterError::RateLimited(_)) => return Err(e),
+                Err(e) => {
+                    warn!("Failed to remove app '{}': {}", name, e);
+                    false
+                }
+            },
+        };
+        // Retried on the next sync if not done
+        if done {
+            info!(
+                "Container '{}' is gone, app '{}' {}",
+                container,
+                name,
+                policy.outcome()
+            );
+            changes.record(
+                format!("App '{}' {}", name, policy.outcome()),
+                format!("container '{}' is gone", container),
+            );
+            changes
+                .removed_apps
+                .push(format!("{} ({})", name, policy.outcome()));
+            state.vanished_apps.insert(
+                url,
+                state::VanishedApp {
+                    name,
+                    container,
+                    action: policy,
+                    at: chrono::Utc::now(),
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Mark apps the user deleted in Homarr as removed from every board
+///
+/// An app synced before (in `discovered_apps`) that is still wanted but no
+/// longer in Homarr, by URL or by name, was deleted in the Homarr UI;
+/// creating it again every cycle would undo that. `existing_apps` must be
+/// a successful listing, or every app would look deleted.
+fn record_user_deletions(
+    state: &mut state::State,
+    changes: &mut changelog::Changelog,
+    apps: &[&registry::AppDefinition],
+    existing_apps: &[homarr::SelectableApp],
+    writable_boards: &[homarr::BoardWithPermission],
+) {
+    for app in apps {
+        let synced_before = state.discovered_apps.contains_key(&app.url);
+        let in_homarr = homarr::HomarrClient::find_app_by_url(existing_apps, &app.url)
+            .or_else(|| homarr::HomarrClient::find_app_by_name(existing_apps, &app.name))
+            .is_some();
+        let already_removed = writable_boards
+            .iter()
+            .all(|board| state.is_removed_from_board(&board.id, &app.url));
+        if !synced_before || in_homarr || already_removed {
+            continue;
+        }
+
+        info!(
+            "App '{}' was deleted in Homarr, no longer adding it to any board",
+            app.name
+        );
+        for board in writable_boards {
+            state.mark_removed_from_board(&board.id, &app.url);
+        }
+        changes.record(
+            format!("Stopped syncing app '{}'", app.name),
+            "deleted in Homarr by a user",
+        );
+    }
+}
+
+/// Apply changes queued while Homarr was unreachable, oldest first
+///
+/// Stops at the first change that fails for lack of connectivity again,
+/// keeping it and the rest queued, and returns a rate limit with the
+/// change still queued. Changes failing for other reasons are dropped, as
+/// they would have been without the queue.
+async fn flush_pending_changes(
+    client: &homarr::HomarrClient,
+    state: &mut state::State,
+    changes: &mut changelog::Changelog,
+    existing_apps: &[homarr::SelectableApp],
+    writable_boards: &[homarr::BoardWithPermission],
+) -> Result<()> {
+    if state.pending_changes.is_empty() {
+        return Ok(());
+    }
+    info!(
+        "Applying {} change(s) queued while Homarr was unreachable",
+        state.pending_changes.len()
+    );
+
+    while let Some(change) = state.pending_changes.first().cloned() {
+        let result = match change {
+            state::PendingChange::Upsert { ref board, ref app } => client
+                .add_registry_app(app, board, Some(existing_apps))
+                .await
+                .map(|_| ()),
+            state::PendingChange::Delete {
+                ref url,
+                remove_from_boards,
+                ..
+            } => {
+                if remove_from_boards {
+                    for board in writable_boards {
+                        state.mark_removed_from_board(&board.id, url);
+                    }
+                }
+                match homarr::HomarrClient::find_app_by_url(existing_apps, url) {
+                    Some(existing) => client.delete_app(&existing.id).await,
+                    None => Ok(()),
+                }
+            }
+        };
+
+        match result {
+            Err(e) if e.is_unreachable() => {
+                warn!("Homarr still unreachable, keeping queued changes");
+                return Ok(());
+            }
+            Err(e @ AdapterError::RateLimited(_)) => return Err(e),
+            Err(e) => warn!("Dropping queued change to {}: {}", change.url(), e),
+            Ok(()) => {
+                debug!("Applied queued change to {}", change.url());
+                changes.record(
+                    format!("Applied queued change to {}", change.url()),
+                    "made while Homarr was unreachable",
+                );
+            }
+        }
+        state.pending_changes.remove(0);
+    }
+    Ok(())
+}
+
+/// Re-validate app icons, recording fallbacks for those that broke
+async fn refresh_icon_overrides(
+    client: &homarr::HomarrClient,
+    state: &mut state::State,
+    apps: &[&registry::AppDefinition],
+) {
+    info!("Re-validating icons for {} app(s)", apps.len());
+    let mut overrides = HashMap::new();
+    for app in apps {
+        if let Some(icon) = client.resolve_icon_override(app).await {
+            overrides.insert(app.url.clone(), icon);
+        }
+    }
+    if !overrides.is_empty() {
+        info!("{} app(s) using a fallback icon", overrides.len());
+    }
+    state.icon_overrides = overrides;
+    state.icons_checked_at = Some(chrono::Utc::now());
+}
+
+/// Upload new or changed `file://` icons to Homarr's media store
+///
+/// Uploads are remembered in state by file size and modification time, so
+/// each file is uploaded once until it changes. Icons that fail to upload
+/// fall back to the default icon.
+async fn upload_file_icons(
+    client: &mut homarr::HomarrClient,
+    state: &mut state::State,
+    apps: &[&registry::AppDefinition],
+) {
+    for icon in apps.iter().filter_map(|app| app.icon_url.as_deref()) {
+        if let Some(path) = homarr::file_icon_path(icon) {
+            upload_file(client, state, icon, path).await;
+        }
+    }
+
+    client.set_uploaded_icons(
+        state
+            .uploaded_icons
+            .iter()
+            .map(|(icon, uploaded)| (icon.clone(), uploaded.media_url.clone()))
+            .collect(),
+    );
+}
+
+/// Upload a local file to Homarr's media store, unless it was uploaded
+/// under `key` before and hasn't changed since, and return its media URL
+pub(crate) async fn upload_file(
+    client: &homarr::HomarrClient,
+    state: &mut state::State,
+    key: &str,
+    path: &std::path::Path,
+) -> Option<String> {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            warn!("File {:?} unavailable: {}", path, e);
+            return None;
+        }
+    };
+    let size = metadata.len();
+    let modified: chrono::DateTime<chrono::Utc> = match metadata.modified() {
+        Ok(time) => time.into(),
+        Err(_) => chrono::Utc::now(),
+    };
+
+    if let Some(uploaded) = state
+        .uploaded_icons
+        .get(key)
+        .filter(|u| u.size == size && u.modified == modified)
+    {
+        return Some(uploaded.media_url.clone());
+    }
+
+    match client.upload_media(path).await {
+        Ok(media_url) => {
+            info!("Uploaded {:?} to Homarr ({})", path, media_url);
+            state.uploaded_icons.insert(
+                key.to_string(),
+                state::UploadedIcon {
+                    media_url: media_url.clone(),
+                    size,
+                    modified,
+                },
+            );
+            Some(media_url)
+        }
+        Err(e) => {
+            warn!("Failed to upload {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Ensure the Homarr client is authenticated with a valid API key.
+///
+/// If a permanent API key is stored in state, use it.
+/// Otherwise, rotate from the bootstrap API key to a new permanent key.
+async fn ensure_authenticated(
     client: &mut homarr::HomarrClient,
     config: &Config,
     state: &mut state::State,
 ) -> Result<()> {
     use std::fs;
 
-    // Check if we already have a permanent API key
-    if let Some(ref api_key) = state.api_key {
-        info!("Using stored API key for authentication");
-        client.set_api_key(api_key.clone());
-        return Ok(());
-    }
+    check_homarr_version(client, config, state).await;
+
+    // Already-onboarded Homarr: use the operator's credentials, swapping a
+    // password login for an API key on first use
+    if config.existing_instance {
+        authenticate_existing(client, config, state).await?;
+        if !client.is_authenticated() {
+            create_existing_instance_key(client, config, state).await;
+        }
+        return Ok(());
+    }
+
+    // Check the keyring for a permanent API key
+    let store = config.credential_store;
+    if let Some(api_key) = store.get(keyring::API_KEY)? {
+        info!("Using API key from the keyring for authentication");
+        client.set_api_key(api_key);
+        if state.api_key.take().is_some() {
+            state.save(&config.state_file)?;
+        }
+        return Ok(());
+    }
+
+    // Check if we already have a permanent API key
+    if let Some(ref api_key) = state.api_key {
+        info!("Using stored API key for authentication");
+        client.set_api_key(api_key.clone());
+        if store.is_writable() {
+            store.store(keyring::API_KEY, api_key)?;
+            state.api_key = None;
+            state.save(&config.state_file)?;
+            info!("Moved API key from state to the keyring");
+        }
+        return Ok(());
+    }
+
+    // No permanent key - need to rotate from bootstrap key
+    info!("No permanent API key found, rotating from bootstrap key");
+
+    // Read bootstrap key from file
+    let bootstrap_key = fs::read_to_string(&config.bootstrap_api_key_file)
+        .map_err(|e| {
+            AdapterError::Config(format!(
+                "Failed to read bootstrap API key from {}: {}",
+                config.bootstrap_api_key_file, e
+            ))
+        })?
+        .trim()
+        .to_string();
+
+    if bootstrap_key.is_empty() {
+        return Err(AdapterError::Config(
+            "Bootstrap API key file is empty".to_string(),
+        ));
+    }
+
+    // Rotate to permanent key
+    let permanent_key = client.rotate_api_key(&bootstrap_key).await?;
+
+    // Save the permanent key to the keyring, or else to state
+    if store.is_writable() {
+        store.store(keyring::API_KEY, &permanent_key)?;
+        info!("API key rotation complete, permanent key saved to the keyring");
+    } else {
+        state.api_key = Some(permanent_key.clone());
+        state.save(&config.state_file)?;
+        info!("API key rotation complete, permanent key saved to state");
+    }
+    Ok(())
+}
+
+/// Record the running Homarr's version and refuse to change a Homarr older
+/// than `min_homarr_version`
+///
+/// The version comes from the Homarr container; without Docker access or a
+/// versioned image the minimum isn't enforced.
+async fn check_homarr_version(
+    client: &mut homarr::HomarrClient,
+    config: &Config,
+    state: &mut state::State,
+) {
+    let version = match docker::connect(config) {
+        Ok(docker) => docker::homarr_version(&docker).await,
+        Err(_) => None,
+    };
+    state.homarr_version = version.clone();
+
+    let Some(minimum) = config.min_homarr_version.as_deref() else {
+        return;
+    };
+    match version
+        .as_deref()
+        .and_then(|v| homarr::version_supported(v, minimum).map(|ok| (v, ok)))
+    {
+        Some((_, true)) => {}
+        Some((version, false)) => {
+            let reason = format!(
+                "Homarr {} is older than the minimum supported {}",
+                version, minimum
+            );
+            error!("{}; not making any changes to Homarr", reason);
+            client.refuse_mutations(reason);
+        }
+        None => debug!(
+            "Homarr version unknown, min_homarr_version {} not enforced",
+            minimum
+        ),
+    }
+}
+
+/// Create an API key for an existing instance after a password login
+///
+/// Later syncs authenticate with the key, so the admin password can be
+/// removed from the config. Failures are logged and the session is used;
+/// a key that can't be saved is deleted again, so failed saves don't leave
+/// a new unused key in Homarr on every sync.
+async fn create_existing_instance_key(
+    client: &mut homarr::HomarrClient,
+    config: &Config,
+    state: &mut state::State,
+) {
+    let api_key = match client.create_api_key().await {
+        Ok(api_key) => api_key,
+        Err(e) => {
+            warn!(
+                "Failed to create an API key, keeping the login session: {}",
+                e
+            );
+            return;
+        }
+    };
+    let store = config.credential_store;
+    let saved = if store.is_writable() {
+        store.store(keyring::API_KEY, &api_key)
+    } else {
+        state.api_key = Some(api_key.clone());
+        state.save(&config.state_file)
+    };
+    match saved {
+        Ok(()) => {
+            info!("Created an API key for Homarr; admin_password is no longer needed");
+            client.set_api_key(api_key);
+        }
+        Err(e) => {
+            warn!("Failed to save the new API key: {}", e);
+            state.api_key = None;
+            let id = api_key.split('.').next().unwrap_or_default();
+            if let Err(e) = client.delete_api_key(id).await {
+                warn!("Failed to delete the unsaved API key '{}': {}", id, e);
+            }
+        }
+    }
+}
+
+/// Authenticate without changing anything, for dry runs
+///
+/// Unlike `ensure_authenticated`, the bootstrap key is used as it is rather
+/// than rotated, and neither state nor keyring is written. Returns which
+/// credentials were used.
+async fn authenticate_read_only(
+    client: &mut homarr::HomarrClient,
+    config: &Config,
+    state: &state::State,
+) -> Result<&'static str> {
+    if config.existing_instance {
+        authenticate_existing(client, config, state).await?;
+        return Ok("existing instance's credentials");
+    }
+    if let Some(api_key) = config.credential_store.get(keyring::API_KEY)? {
+        client.set_api_key(api_key);
+        return Ok("API key from the keyring");
+    }
+    if let Some(ref api_key) = state.api_key {
+        client.set_api_key(api_key.clone());
+        return Ok("stored API key");
+    }
+    let bootstrap_key = std::fs::read_to_string(&config.bootstrap_api_key_file)
+        .map_err(|e| {
+            AdapterError::Config(format!(
+                "Failed to read bootstrap API key from {}: {}",
+                config.bootstrap_api_key_file, e
+            ))
+        })?
+        .trim()
+        .to_string();
+    if bootstrap_key.is_empty() {
+        return Err(AdapterError::Config(
+            "Bootstrap API key file is empty".to_string(),
+        ));
+    }
+    client.set_api_key(bootstrap_key);
+    Ok("bootstrap API key (rotated to a permanent key)")
+}
+
+/// Authenticate against an already-onboarded Homarr with configured credentials
+///
+/// An API key from the config, the keyring or state is preferred over a
+/// password login. A session from an earlier cycle is kept; the client
+/// renews it itself.
+async fn authenticate_existing(
+    client: &mut homarr::HomarrClient,
+    config: &Config,
+    state: &state::State,
+) -> Result<()> {
+    if client.has_session().await {
+        return Ok(());
+    }
+
+    // The keyring is only asked when the config has no value
+    let store = config.credential_store;
+    let mut api_key = config.api_key.clone();
+    if api_key.is_none() {
+        api_key = store.get(keyring::API_KEY)?;
+    }
+    if let Some(api_key) = api_key.or_else(|| state.api_key.clone()) {
+        info!("Using configured API key for existing Homarr instance");
+        client.set_api_key(api_key);
+        return Ok(());
+    }
+
+    let mut password = config.admin_password.clone();
+    if password.is_none() {
+        password = store.get(keyring::ADMIN_PASSWORD)?;
+    }
+    match (&config.admin_username, &password) {
+        (Some(username), Some(password)) => {
+            info!("Logging in to existing Homarr instance as '{}'", username);
+            client.login_with_credentials(username, password).await
+        }
+        _ => Err(AdapterError::Config(
+            "existing_instance requires api_key or admin_username and admin_password".to_string(),
+        )),
+    }
+}
+
+async fn run_setup(config: &Config, hub: Option<&grpc::ControlHub>) -> Result<()> {
+    let first_boot = !state::State::load(&config.state_file)
+        .map(|state| state.first_boot_completed)
+        .unwrap_or(false);
+    let result = setup::run(config, hub).await;
+
+    match result {
+        Ok(()) if first_boot => {
+            if let Err(e) = alerts::send(config, &alerts::Alert::SetupCompleted).await {
+                warn!("Failed to send setup notification: {}", e);
+            }
+        }
+        Ok(()) | Err(AdapterError::Cancelled) => {}
+        Err(ref e) => {
+            let error = e.to_string();
+            let alert = alerts::Alert::SetupFailed { error: &error };
+            if let Err(e) = alerts::send(config, &alert).await {
+                warn!("Failed to send setup failure alert: {}", e);
+            }
+        }
+    }
+
+    result
+}
+
+/// Add a one-off app to state and sync it through the normal sync cycle
+async fn run_apps_add(config: &Config, app: registry::AppDefinition) -> Result<()> {
+    if app.name.is_empty() {
+        return Err(AdapterError::Config("App name is empty".to_string()));
+    }
+    registry::validate_app_url(&app.url)
+        .map_err(|e| AdapterError::Config(format!("Invalid URL '{}': {}", app.url, e)))?;
+
+    {
+        let _lock = lock_state(config).await?;
+        let mut state = state::State::load(&config.state_file)?;
+        info!("Adding app '{}' ({})", app.name, app.url);
+        state.upsert_manual_app(app);
+        state.save(&config.state_file)?;
+    }
+
+    if let Some(until) = config.maintenance_until(chrono::Local::now()) {
+        info!(
+            "Homarr maintenance window until {}, the app is added by the next sync",
+            until.format("%H:%M")
+        );
+        return Ok(());
+    }
+    run_sync(config).await
+}
+
+/// Discover apps from `homarr.*` container labels, and whether every
+/// source answered
+///
+/// Docker being unavailable is not fatal: registry and Signal K apps still
+/// sync, so this logs and returns no apps. Incomplete results must not be
+/// taken to mean containers are gone.
+async fn discover_container_apps(config: &Config) -> (Vec<registry::AppDefinition>, bool) {
+    let mut apps = match docker::connect(config) {
+        Ok(docker) => docker::discover_apps(
+            &docker,
+            config.replica_count,
+            config.description_template.as_deref(),
+            &config.port_url_host(),
+            &config.label_schemes,
+            config.catalog_icons,
+        )
+        .await
+        .map_err(|e| match docker::socket_permission_denied(config) {
+            Some(socket) => error!("{}", AdapterError::DockerPermissionDenied(socket)),
+            None => warn!("Failed to discover container apps: {}", e),
+        })
+        .ok(),
+        Err(e) => {
+            debug!("Docker unavailable, skipping container discovery: {}", e);
+            None
+        }
+    };
+    if config.docker_desktop {
+        apps.iter_mut()
+            .flatten()
+            .for_each(docker::adapt_for_desktop);
+    }
+    let kubernetes_apps = discover_kubernetes_apps(config).await;
+    let complete = apps.is_some() && kubernetes_apps.is_some();
+    let mut apps = apps.unwrap_or_default();
+    apps.extend(kubernetes_apps.unwrap_or_default());
+    if let Some(locale) = config.locale.as_deref() {
+        apps.iter_mut().for_each(|app| app.localize(locale));
+    }
+    (apps, complete)
+}
+
+/// Apps from annotated Kubernetes pods and ingresses, if configured
+/// (None if the API server couldn't be queried)
+#[cfg(feature = "kubernetes")]
+async fn discover_kubernetes_apps(config: &Config) -> Option<Vec<registry::AppDefinition>> {
+    let Some(kubernetes) = &config.kubernetes else {
+        return Some(vec![]);
+    };
+    kubernetes::discover_apps(kubernetes)
+        .await
+        .map_err(|e| warn!("Failed to discover Kubernetes apps: {}", e))
+        .ok()
+}
+
+#[cfg(not(feature = "kubernetes"))]
+async fn discover_kubernetes_apps(config: &Config) -> Option<Vec<registry::AppDefinition>> {
+    if config.kubernetes.is_some() {
+        warn!("[kubernetes] is configured but this build lacks the kubernetes feature");
+    }
+    Some(vec![])
+}
+
+/// Load the apps a sync would place on the dashboard, without touching Homarr
+///
+/// Same sources as a sync cycle: visible registry apps, labelled containers,
+/// Signal K webapps and manually added apps, with duplicate names resolved.
+async fn load_desired_apps(
+    config: &Config,
+    state: &state::State,
+) -> Result<Vec<registry::AppDefinition>> {
+    Ok(discover_desired_apps(config, state).await?.apps)
+}
+
+/// Apps a sync would place, and which discovery sources answered
+struct DesiredApps {
+    apps: Vec<registry::AppDefinition>,
+    /// Every container source answered (see `discover_container_apps`)
+    containers_complete: bool,
+    /// Signal K webapps, if Signal K is configured and answered
+    signalk_apps: Option<Vec<registry::AppDefinition>>,
+}
+
+/// `load_desired_apps`, also reporting which sources answered
+async fn discover_desired_apps(config: &Config, state: &state::State) -> Result<DesiredApps> {
+    let registry_apps = registry::load_all_apps(&config.registry_dir).unwrap_or_else(|e| {
+        warn!("Failed to load registry apps: {}", e);
+        vec![]
+    });
+    let (container_apps, containers_complete) = discover_container_apps(config).await;
+    let signalk_result = match config.signalk_url.as_deref() {
+        Some(url) if !url.is_empty() => signalk::discover_webapps(url).await,
+        _ => None,
+    };
+    let signalk_apps = signalk_result.as_deref().unwrap_or(&[]);
+    let config_apps: Vec<registry::AppDefinition> =
+        config.apps.iter().map(config::StaticApp::app).collect();
+    let apps = registry::resolve_duplicate_names(
+        registry_apps
+            .iter()
+            .map(|e| &e.app)
+            .filter(|app| app.is_visible())
+            .chain(container_apps.iter())
+            .chain(signalk_apps.iter())
+            .chain(state.manual_apps.iter())
+            .chain(config_apps.iter())
+            .collect(),
+        config.duplicate_names,
+    )?;
+    let apps = if config.require_https {
+        registry::enforce_https(apps, config.https_upgrade, &config.https_exceptions)
+    } else {
+        apps
+    };
+    Ok(DesiredApps {
+        apps,
+        containers_complete,
+        signalk_apps: signalk_result,
+    })
+}
+
+/// Print what a sync would change, without changing Homarr or the state file
+async fn run_sync_dry_run(config: &Config) -> Result<()> {
+    let state = state::State::load(&config.state_file)?;
+    if !state.first_boot_completed {
+        println!("First-boot setup would run first:");
+        if let Err(e) = print_setup_plan(config).await {
+            println!("  (can't plan setup: {})", e);
+        }
+        println!();
+    }
+
+    let mut client = homarr_client(config)?;
+    authenticate_read_only(&mut client, config, &state).await?;
+    if let Ok(branding) = branding::BrandingConfig::load(&config.branding_file) {
+        client.set_default_section(branding.board.default_section);
+    }
+    client.set_icon_overrides(state.icon_overrides.clone());
+
+    let desired = discover_desired_apps(config, &state).await?;
+    let mut apps = desired.apps;
+    let now = chrono::Local::now();
+    for app in apps.iter_mut() {
+        if !app.in_visible_hours(now.time()) {
+            app.hide = true;
+        }
+    }
+    let existing_apps = client.list_apps().await?;
+    let boards = client.get_writable_boards().await?;
+
+    let mut plan = plan::SyncPlan {
+        queued: state
+            .pending_changes
+            .iter()
+            .map(|change| match change {
+                state::PendingChange::Upsert { board, app } => {
+                    format!("add {} to board '{}'", app.name, board)
+                }
+                state::PendingChange::Delete { name, .. } => format!("delete {}", name),
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    let mut stopped = std::collections::HashSet::new();
+    for app in &apps {
+        // Left alone on every board it targets, so not even updated
+        let mut targeted = boards
+            .iter()
+            .filter(|board| app.targets_board(&board.name))
+            .peekable();
+        if targeted.peek().is_some()
+            && targeted.all(|board| state.is_removed_from_board(&board.id, &app.url))
+        {
+            continue;
+        }
+        let existing = homarr::HomarrClient::find_app_by_url(&existing_apps, &app.url)
+            .or_else(|| homarr::HomarrClient::find_app_by_name(&existing_apps, &app.name));
+        match existing {
+            Some(existing) => {
+                let changes = plan::app_changes(app, existing, &client.icon_for(app));
+                if !changes.is_empty() {
+                    plan.update.push((app.name.clone(), changes));
+                }
+            }
+            // Synced before but gone: deleted in the Homarr UI
+            None if state.discovered_apps.contains_key(&app.url) => {
+                plan.remove.push((
+                    app.name.clone(),
+                    "deleted in Homarr by a user, no longer synced".to_string(),
+                ));
+                stopped.insert(app.url.clone());
+            }
+            None => plan.create.push(format!("{} ({})", app.name, app.url)),
+        }
+    }
+
+    let current: std::collections::HashSet<&str> =
+        apps.iter().map(|app| app.url.as_str()).collect();
+    if let Some(signalk_apps) = &desired.signalk_apps {
+        let served: std::collections::HashSet<&str> =
+            signalk_apps.iter().map(|app| app.url.as_str()).collect();
+        for (url, app) in &state.discovered_apps {
+            if signalk::is_signalk_webapp_url(url) && !served.contains(url.as_str()) {
+                plan.remove
+                    .push((app.name.clone(), "no longer served by Signal K".to_string()));
+            }
+        }
+    }
+    if desired.containers_complete && config.removal_policy != state::RemovalPolicy::Keep {
+        for (_, name, container) in state.vanished_container_apps(&current, removal_cutoff(config))
+        {
+            plan.remove.push((
+                name,
+                format!(
+                    "container '{}' is gone, would be {}",
+                    container,
+                    config.removal_policy.outcome()
+                ),
+            ));
+        }
+    }
+
+    let board_apps: Vec<&registry::AppDefinition> = apps
+        .iter()
+        .filter(|app| !stopped.contains(&app.url))
+        .collect();
+    for board in &boards {
+        let items = client.get_board_items(&board.name).await?;
+        let apps: Vec<&registry::AppDefinition> = board_apps
+            .iter()
+            .copied()
+            .filter(|app| app.targets_board(&board.name))
+            .collect();
+        plan.boards.push(plan::plan_board(
+            &board.name,
+            &items,
+            &apps,
+            &existing_apps,
+            |url| state.is_removed_from_board(&board.id, url),
+        ));
+    }
+    let routed: std::collections::BTreeSet<&str> = board_apps
+        .iter()
+        .filter_map(|app| app.board.as_deref())
+        .collect();
+    for name in routed
+        .into_iter()
+        .filter(|name| boards.iter().all(|b| b.name != *name))
+    {
+        let apps: Vec<&registry::AppDefinition> = board_apps
+            .iter()
+            .copied()
+            .filter(|app| app.targets_board(name))
+            .collect();
+        plan.boards.push(plan::BoardPlan {
+            new: true,
+            ..plan::plan_board(name, &[], &apps, &existing_apps, |_| false)
+        });
+    }
+
+    print!("{}", plan);
+    Ok(())
+}
+
+/// Print the drift between Docker, Homarr and the state file
+async fn run_diff(config: &Config, format: OutputFormat) -> Result<()> {
+    let state = state::State::load(&config.state_file)?;
+    let (container_apps, complete) = discover_container_apps(config).await;
+    if !complete {
+        warn!("Container discovery incomplete, running apps may be shown as gone");
+    }
+
+    let mut client = homarr_client(config)?;
+    authenticate_read_only(&mut client, config, &state).await?;
+    let homarr_apps = client.list_apps().await?;
+
+    let rows = diff::diff(&container_apps, &homarr_apps, &state);
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        OutputFormat::Text => print!("{}", diff::render_table(&rows)),
+    }
+    Ok(())
+}
+
+/// Print or write label suggestions for hand-made Homarr apps
+async fn run_export_labels(config: &Config, output: Option<&std::path::Path>) -> Result<()> {
+    let state = state::State::load(&config.state_file)?;
+    let docker = docker::connect(config)?;
+    let containers = docker::running_containers(&docker, &config.label_schemes).await?;
+
+    let mut client = homarr_client(config)?;
+    authenticate_read_only(&mut client, config, &state).await?;
+    let homarr_apps = client.list_apps().await?;
+
+    let suggestions = export::suggest(&homarr_apps, &state, &containers);
+    let text = export::render(&suggestions);
+    match output {
+        Some(path) => {
+            std::fs::write(path, text)?;
+            info!("Wrote label suggestions to {}", path.display());
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+/// Print or write guessed labels for containers without any
+async fn run_label_gen(
+    config: &Config,
+    project: Option<&str>,
+    format: LabelFormat,
+    output: Option<&std::path::Path>,
+) -> Result<()> {
+    let docker = docker::connect(config)?;
+    let containers = docker::running_containers(&docker, &config.label_schemes).await?;
+    let guesses = labelgen::guess(&containers, project, &config.port_url_host());
+
+    let text = match format {
+        LabelFormat::Snippet => labelgen::render_snippets(&guesses),
+        LabelFormat::Override => labelgen::render_override(&guesses)
+            .map_err(|e| AdapterError::Config(format!("Cannot write an override file: {}", e)))?,
+    };
+    match output {
+        Some(path) => {
+            std::fs::write(path, text)?;
+            info!(
+                "Wrote labels for {} container(s) to {}",
+                guesses.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+fn run_inventory(config: &Config, unseen_days: Option<u32>, format: OutputFormat) -> Result<()> {
+    let state = state::State::load(&config.state_file)?;
+    let rows = inventory::inventory(&state, chrono::Utc::now(), unseen_days);
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        OutputFormat::Text if rows.is_empty() => println!("No apps to report"),
+        OutputFormat::Text => print!("{}", inventory::render_table(&rows)),
+    }
+    Ok(())
+}
+
+/// `state` subcommands
+///
+/// Changes wait for a running sync to finish, so they aren't overwritten.
+async fn run_state(config: &Config, command: StateCommand) -> Result<()> {
+    let path = std::path::Path::new(&config.state_file);
+    let find = |state: &state::State, app: &str| {
+        let urls = state.app_urls(app);
+        if urls.is_empty() {
+            return Err(AdapterError::Config(format!(
+                "No app named '{}' or with that URL in the state",
+                app
+            )));
+        }
+        Ok(urls)
+    };
+    match command {
+        StateCommand::Show => {
+            let mut document = state::State::load(path)?.document()?;
+            if let Some(key) = document.get_mut("api_key").filter(|key| !key.is_null()) {
+                *key = serde_json::Value::String("<redacted>".to_string());
+            }
+            println!("{}", serde_json::to_string_pretty(&document)?);
+            Ok(())
+        }
+        StateCommand::Forget { app } => {
+            update_state(path, |state| {
+                for url in find(state, &app)? {
+                    state.forget_app(&url);
+                    println!("Forgot {}", url);
+                }
+                Ok(())
+            })
+            .await
+        }
+        StateCommand::Unremove { app } => {
+            update_state(path, |state| {
+                for url in find(state, &app)? {
+                    match state.unremove_app(&url) {
+                        0 => println!("{} was not removed from any board", url),
+                        boards => println!(
+                        "{} will be added again by the next sync (was removed from {} board(s))",
+                        url, boards
+                    ),
+                    }
+                }
+                Ok(())
+            })
+            .await
+        }
+        StateCommand::Reset {
+            keep_credentials: true,
+        } => {
+            update_state(path, |state| {
+                // Queued changes and created sections describe Homarr, not
+                // the synced apps, and are still needed against it
+                *state = state::State {
+                    first_boot_completed: state.first_boot_completed,
+                    api_key: state.api_key.take(),
+                    homarr_version: state.homarr_version.take(),
+                    pending_changes: std::mem::take(&mut state.pending_changes),
+                    section_ids: std::mem::take(&mut state.section_ids),
+                    ..Default::default()
+                };
+                println!(
+                    "State reset, keeping the API key, first-boot setup, queued changes and created sections"
+                );
+                Ok(())
+            })
+            .await
+        }
+        StateCommand::Reset {
+            keep_credentials: false,
+        } => {
+            let _lock = state::SyncLock::acquire(path, SYNC_LOCK_TIMEOUT).await?;
+            // Without reading it first: the state may be beyond repair
+            reset_state(config)
+        }
+    }
+}
+
+/// Load the state under the sync lock, change it and save it
+async fn update_state(
+    path: &std::path::Path,
+    change: impl FnOnce(&mut state::State) -> Result<()>,
+) -> Result<()> {
+    let _lock = state::SyncLock::acquire(path, SYNC_LOCK_TIMEOUT).await?;
+    let mut state = state::State::load(path)?;
+    change(&mut state)?;
+    state.save(path)
+}
+
+/// Check and print the signatures of the state file and changelogs
+fn run_verify(config: &Config) -> Result<()> {
+    if !signing::enabled() {
+        return Err(AdapterError::Config(
+            "No signing key configured (signing_key_file or signing_key_credential)".to_string(),
+        ));
+    }
+
+    let state_path = std::path::PathBuf::from(&config.state_file);
+    let changelog = config.changelog_file();
+    let mut previous = changelog.clone().into_os_string();
+    previous.push(".1");
+
+    // Deleting a file or its signatures must not pass for a clean check, so
+    // only the previous boot's log (absent until a reboot) may be missing
+    let mut tampered = 0;
+    for (path, is_log, required) in [
+        (state_path, false, true),
+        (changelog, true, true),
+        (previous.into(), true, false),
+    ] {
+        let contents = match std::fs::read(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !required => continue,
+            Err(e) => {
+                tampered += 1;
+                println!("{}: MISSING ({})", path.display(), e);
+                continue;
+            }
+        };
+        let verdict = if is_log {
+            signing::check_log(&String::from_utf8_lossy(&contents))
+        } else {
+            signing::check_file(&path, &contents)
+        };
+        match verdict {
+            Some(signing::Verdict::Valid) => println!("{}: valid", path.display()),
+            Some(signing::Verdict::Unsigned) => {
+                tampered += 1;
+                println!(
+                    "{}: NOT SIGNED (signatures removed, or written before signing was enabled)",
+                    path.display()
+                );
+            }
+            Some(signing::Verdict::Tampered(detail)) => {
+                tampered += 1;
+                println!(
+                    "{}: CHANGED OUTSIDE THE ADAPTER ({})",
+                    path.display(),
+                    detail
+                );
+            }
+            None => {}
+        }
+    }
+
+    if tampered > 0 {
+        return Err(AdapterError::State(format!(
+            "{} file(s) failed signature checks",
+            tampered
+        )));
+    }
+    Ok(())
+}
+
+/// Print what each first-boot setup step would do
+async fn print_setup_plan(config: &Config) -> Result<()> {
+    for (stage, action) in setup::plan(config).await? {
+        println!("  {}: {}", stage.description(), action);
+    }
+    Ok(())
+}
+
+/// Print the planned board grid for each writable board
+async fn run_preview(config: &Config, only_board: Option<&str>) -> Result<()> {
+    // Authenticating may save a new API key
+    let _lock = lock_state(config).await?;
+    let mut state = state::State::load(&config.state_file)?;
+    if !state.first_boot_completed {
+        return Err(AdapterError::Config(
+            "First-boot setup has not completed; run setup first".to_string(),
+        ));
+    }
+
+    let mut client = homarr_client(config)?;
+    ensure_authenticated(&mut client, config, &mut state).await?;
+    if let Ok(branding) = branding::BrandingConfig::load(&config.branding_file) {
+        client.set_default_section(branding.board.default_section);
+    }
+
+    let resolved_apps = load_desired_apps(config, &state).await?;
+    let apps: Vec<&registry::AppDefinition> = resolved_apps.iter().collect();
+
+    let existing_apps = client.list_apps().await?;
+    let boards = client.get_writable_boards().await?;
+    for board in boards
+        .iter()
+        .filter(|b| only_board.is_none_or(|name| b.name == name))
+    {
+        let board_apps: Vec<&registry::AppDefinition> = apps
+            .iter()
+            .copied()
+            .filter(|app| !state.is_removed_from_board(&board.id, &app.url))
+            .collect();
+        println!("Board '{}'", board.name);
+        print!(
+            "{}",
+            client
+                .preview_board(&board.name, &board_apps, &existing_apps)
+                .await?
+        );
+    }
+
+    Ok(())
+}
+
+/// Print how the adapter interprets one container
+///
+/// Docker is required; Homarr is optional and only used to list the boards
+/// tiles would land on.
+async fn run_explain(config: &Config, name_or_id: &str) -> Result<()> {
+    let docker = docker::connect(config)?;
+    let container = docker::inspect(&docker, name_or_id).await?;
+    let (service, replicas) = docker::service_replicas(&docker, &container.labels).await?;
+
+    let _lock = lock_state(config).await?;
+    let mut state = state::State::load(&config.state_file)?;
+    let desired = load_desired_apps(config, &state).await?;
+    let branding = branding::BrandingConfig::load(&config.branding_file).ok();
+
+    let mut client = homarr_client(config)?;
+    client.set_icon_overrides(state.icon_overrides.clone());
+    client.set_uploaded_icons(
+        state
+            .uploaded_icons
+            .iter()
+            .map(|(icon, uploaded)| (icon.clone(), uploaded.media_url.clone()))
+            .collect(),
+    );
+
+    let boards = if state.first_boot_completed {
+        match ensure_authenticated(&mut client, config, &mut state).await {
+            Ok(()) => client
+                .get_writable_boards()
+                .await
+                .map(|boards| boards.into_iter().map(|b| (b.name, b.id)).collect())
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    } else {
+        Err("first-boot setup has not completed".to_string())
+    };
 
-    // No permanent key - need to rotate from bootstrap key
-    info!("No permanent API key found, rotating from bootstrap key");
+    let icon = |app: &registry::AppDefinition| client.icon_for(app);
+    let ctx = explain::Context {
+        config,
+        state: &state,
+        service,
+        replicas,
+        desired: &desired,
+        default_section: branding
+            .as_ref()
+            .and_then(|b| b.board.default_section.as_deref()),
+        boards,
+        icon: &icon,
+        now: chrono::Local::now().time(),
+    };
+    print!("{}", explain::explain(&container, &ctx));
+    Ok(())
+}
 
-    // Read bootstrap key from file
-    let bootstrap_key = fs::read_to_string(&config.bootstrap_api_key_file)
-        .map_err(|e| {
-            AdapterError::Config(format!(
-                "Failed to read bootstrap API key from {}: {}",
-                config.bootstrap_api_key_file, e
-            ))
-        })?
-        .trim()
-        .to_string();
+/// Print the apps found from container labels
+///
+/// Unlike a sync, a Docker failure is an error here rather than an empty
+/// list, so callers can tell "no apps" from "no Docker".
+async fn run_discover(config: &Config, format: OutputFormat) -> Result<()> {
+    let docker = docker::connect(config)?;
+    let mut apps = docker::discover_apps(
+        &docker,
+        config.replica_count,
+        config.description_template.as_deref(),
+        &config.port_url_host(),
+        &config.label_schemes,
+        config.catalog_icons,
+    )
+    .await?;
+    if config.docker_desktop {
+        apps.iter_mut().for_each(docker::adapt_for_desktop);
+    }
 
-    if bootstrap_key.is_empty() {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&apps)?),
+        OutputFormat::Text => {
+            for app in &apps {
+                println!(
+                    "{}\t{}\t{}",
+                    app.container_name().unwrap_or("-"),
+                    app.name,
+                    app.url
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Merge duplicate tiles of adapter-managed apps in Homarr
+async fn run_dedupe(config: &Config, dry_run: bool) -> Result<()> {
+    let _lock = lock_state(config).await?;
+    let mut state = state::State::load(&config.state_file)?;
+    if !state.first_boot_completed {
         return Err(AdapterError::Config(
-            "Bootstrap API key file is empty".to_string(),
+            "First-boot setup has not completed; run setup first".to_string(),
         ));
     }
+    if let Some(until) = config.maintenance_until(chrono::Local::now()) {
+        if !dry_run {
+            return Err(AdapterError::Config(format!(
+                "Homarr maintenance window until {}, not modifying Homarr",
+                until.format("%H:%M")
+            )));
+        }
+    }
 
-    // Rotate to permanent key
-    let permanent_key = client.rotate_api_key(&bootstrap_key).await?;
+    let mut client = homarr_client(config)?;
+    ensure_authenticated(&mut client, config, &mut state).await?;
 
-    // Save the permanent key to state
-    state.api_key = Some(permanent_key.clone());
-    state.save(&config.state_file)?;
+    let owned: Vec<String> = state
+        .discovered_apps
+        .keys()
+        .cloned()
+        .chain(state.manual_apps.iter().map(|app| app.url.clone()))
+        // Pinned apps are the user's to tidy up
+        .filter(|url| !state.is_pinned(url))
+        .collect();
+    let report = client.dedupe(&owned, dry_run).await?;
+
+    if report.apps.is_empty() && report.tiles_removed.is_empty() {
+        println!("No duplicate tiles found");
+        return Ok(());
+    }
+    let verb = if dry_run { "Would merge" } else { "Merged" };
+    for duplicate in &report.apps {
+        println!(
+            "{} {} duplicate app(s) of '{}' into {}",
+            verb,
+            duplicate.remove.len(),
+            duplicate.name,
+            duplicate.keep
+        );
+    }
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    for (board, tiles) in &report.tiles_removed {
+        println!(
+            "{} {} duplicate tile(s) from board '{}'",
+            verb, tiles, board
+        );
+    }
+    Ok(())
+}
 
-    info!("API key rotation complete, permanent key saved to state");
+/// Export a board to a file, or import one
+async fn run_board(config: &Config, command: BoardCommand) -> Result<()> {
+    // Before loading state: authenticating may save it, and an import must
+    // not run while a sync edits the same board
+    let _lock = lock_state(config).await?;
+    let mut state = state::State::load(&config.state_file)?;
+    let mut client = homarr_client(config)?;
+    match command {
+        BoardCommand::Export { file, board } => {
+            let name = match board {
+                Some(name) => name,
+                None => {
+                    branding::BrandingConfig::load(&config.branding_file)?
+                        .board
+                        .name
+                }
+            };
+            ensure_authenticated(&mut client, config, &mut state).await?;
+            let export = client.export_board(&name).await?;
+            std::fs::write(&file, serde_json::to_string_pretty(&export)?)?;
+            println!("Exported board '{}' to {}", name, file.display());
+        }
+        BoardCommand::Import { file, board } => {
+            let export: homarr::BoardExport =
+                serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+            let name = board.unwrap_or_else(|| export.name.clone());
+            if let Some(until) = config.maintenance_until(chrono::Local::now()) {
+                return Err(AdapterError::Config(format!(
+                    "Homarr maintenance window until {}, not modifying Homarr",
+                    until.format("%H:%M")
+                )));
+            }
+            ensure_authenticated(&mut client, config, &mut state).await?;
+            let report = client.import_board(&export, &name).await?;
+            if report.created_board {
+                println!("Created board '{}'", name);
+            }
+            for app in &report.created_apps {
+                println!("Created app '{}'", app);
+            }
+            println!("Restored {} item(s) on board '{}'", report.items, name);
+            if report.dropped_items > 0 {
+                println!(
+                    "Skipped {} item(s) whose app couldn't be found or created",
+                    report.dropped_items
+                );
+            }
+        }
+    }
     Ok(())
 }
 
-async fn run_setup(config: &Config) -> Result<()> {
-    // Load branding config
-    let branding = branding::BrandingConfig::load(&config.branding_file)?;
+/// Remove an adapter-managed app from Homarr and record it in state
+async fn run_apps_remove(config: &Config, name: &str) -> Result<()> {
+    let _lock = lock_state(config).await?;
+    let mut state = state::State::load(&config.state_file)?;
 
-    // Create Homarr client
-    let mut client = homarr::HomarrClient::new(&config.homarr_url)?;
+    let manual = state.remove_manual_app(name);
+    let url = match manual {
+        Some(ref app) => app.url.clone(),
+        None => state
+            .discovered_apps
+            .iter()
+            .find(|(_, app)| app.name.eq_ignore_ascii_case(name))
+            .map(|(url, _)| url.clone())
+            .ok_or_else(|| {
+                AdapterError::Config(format!("No adapter-managed app named '{}'", name))
+            })?,
+    };
 
-    // Load state
-    let mut state = state::State::load(&config.state_file).unwrap_or_default();
+    if let Some(until) = config.maintenance_until(chrono::Local::now()) {
+        queue_removal(&mut state, &url, name, manual.is_none());
+        state.save(&config.state_file)?;
+        info!(
+            "Homarr maintenance window until {}, removal of '{}' queued for the next sync",
+            until.format("%H:%M"),
+            name
+        );
+        return Ok(());
+    }
 
-    // Ensure we have a valid API key (rotate from bootstrap if needed)
+    let mut client = homarr_client(config)?;
     ensure_authenticated(&mut client, config, &mut state).await?;
 
-    // Check onboarding status (should already be complete from seed database)
-    let step = client.get_onboarding_step().await?;
-    info!("Current onboarding step: {:?}", step);
-
-    if step.current != "finish" {
-        info!("Completing onboarding");
-        client.complete_onboarding(&branding).await?;
+    let deleted = match client.list_apps().await {
+        Ok(apps) => match homarr::HomarrClient::find_app_by_url(&apps, &url) {
+            Some(existing) => client.delete_app(&existing.id).await,
+            None => {
+                warn!("App '{}' ({}) not found in Homarr", name, url);
+                Ok(())
+            }
+        },
+        Err(e) => Err(e),
+    };
+    match deleted {
+        Ok(()) => {
+            let mut changes = changelog::Changelog::default();
+            changes.record(format!("Deleted app '{}'", name), "`apps remove`");
+            if let Err(e) = changes.write(&config.changelog_file()) {
+                warn!("Failed to write change log: {}", e);
+            }
+        }
+        Err(e) if e.is_unreachable() => {
+            queue_removal(&mut state, &url, name, manual.is_none());
+            state.save(&config.state_file)?;
+            warn!(
+                "Homarr unreachable, removal of '{}' queued for the next sync",
+                name
+            );
+            return Ok(());
+        }
+        Err(e) => return Err(e),
     }
 
-    // Set up default board
-    info!("Setting up default board");
-    client.setup_default_board(&branding).await?;
+    // Registry and Signal K apps would be re-added on the next sync unless
+    // they are marked as removed from every board
+    if manual.is_none() {
+        for board in client.get_writable_boards().await? {
+            state.mark_removed_from_board(&board.id, &url);
+        }
+    }
 
-    // Mark first boot complete
-    state.first_boot_completed = true;
+    state.discovered_apps.remove(&url);
+    state.pinned_apps.remove(&url);
     state.save(&config.state_file)?;
 
-    info!("First-boot setup complete");
+    info!("Removed app '{}' ({})", name, url);
     Ok(())
 }
 
+/// Pin or unpin an adapter-managed app by name
+async fn run_apps_pin(config: &Config, name: &str, pin: bool) -> Result<()> {
+    let _lock = lock_state(config).await?;
+    let mut state = state::State::load(&config.state_file)?;
+
+    let url = state
+        .manual_apps
+        .iter()
+        .find(|app| app.name.eq_ignore_ascii_case(name))
+        .map(|app| app.url.clone())
+        .or_else(|| {
+            state
+                .discovered_apps
+                .iter()
+                .find(|(_, app)| app.name.eq_ignore_ascii_case(name))
+                .map(|(url, _)| url.clone())
+        })
+        .ok_or_else(|| AdapterError::Config(format!("No adapter-managed app named '{}'", name)))?;
+
+    if pin {
+        state.pinned_apps.insert(url.clone());
+        info!("Pinned app '{}' ({})", name, url);
+    } else {
+        state.pinned_apps.remove(&url);
+        // A label pin is recorded again by the next sync
+        if let Some(app) = state.discovered_apps.get_mut(&url) {
+            app.pinned = false;
+        }
+        info!("Unpinned app '{}' ({})", name, url);
+    }
+    state.save(&config.state_file)
+}
+
+/// Queue an app's deletion (and removal marking) for the next sync
+fn queue_removal(state: &mut state::State, url: &str, name: &str, remove_from_boards: bool) {
+    state.queue_change(state::PendingChange::Delete {
+        url: url.to_string(),
+        name: name.to_string(),
+        remove_from_boards,
+    });
+    state.discovered_apps.remove(url);
+}
+
 async fn check_status(config: &Config) -> Result<()> {
     let state = state::State::load(&config.state_file).unwrap_or_default();
 
     if state.first_boot_completed {
         println!("Status: First-boot setup completed");
         println!("Last sync: {:?}", state.last_sync);
+        if let Some(timings) = &state.last_sync_timings {
+            println!("Last sync timing: {}", timings.summary());
+        }
+        if state.docker_permission_denied {
+            println!(
+                "Docker: permission denied on the socket, container discovery paused \
+                 (is the adapter's user in the docker group?)"
+            );
+        }
+        if let Some(until) = state.rate_limit_pause(chrono::Utc::now()) {
+            println!(
+                "Homarr: rate limiting requests, syncs paused until {}",
+                until.with_timezone(&chrono::Local).format("%H:%M:%S")
+            );
+        }
+        if state.rate_limited_responses > 0 {
+            println!(
+                "Rate-limited Homarr responses: {}",
+                state.rate_limited_responses
+            );
+        }
         println!("Registered apps: {}", state.discovered_apps.len());
         for (url, app) in &state.discovered_apps {
             let container_info = if app.container_id.is_empty() {
@@ -395,13 +2948,208 @@ async fn check_status(config: &Config) -> Result<()> {
             };
             println!("  - {} ({}) [{}]", app.name, url, container_info);
         }
+        if !state.container_stops.is_empty() {
+            let mut stops: Vec<_> = state.container_stops.iter().collect();
+            stops.sort_by_key(|(_, stop)| std::cmp::Reverse(stop.at));
+            println!("Stopped containers:");
+            for (name, stop) in stops {
+                println!(
+                    "  - {}: {} at {}",
+                    name,
+                    stop.reason(),
+                    stop.at.format("%Y-%m-%d %H:%M:%S UTC")
+                );
+            }
+        }
+        if !state.vanished_apps.is_empty() {
+            let mut vanished: Vec<_> = state.vanished_apps.values().collect();
+            vanished.sort_by_key(|app| std::cmp::Reverse(app.at));
+            println!("Apps of vanished containers:");
+            for app in vanished {
+                println!(
+                    "  - {} (container {}): {} since {}",
+                    app.name,
+                    app.container,
+                    app.action.outcome(),
+                    app.at.format("%Y-%m-%d %H:%M:%S UTC")
+                );
+            }
+        }
+        if let Some(version) = &state.homarr_version {
+            let unsupported = config
+                .min_homarr_version
+                .as_deref()
+                .filter(|min| homarr::version_supported(version, min) == Some(false));
+            match unsupported {
+                Some(minimum) => println!(
+                    "Homarr version: {} (UNSUPPORTED, minimum {}: changes refused)",
+                    version, minimum
+                ),
+                None => println!("Homarr version: {}", version),
+            }
+        }
+        if !state.pending_changes.is_empty() {
+            println!(
+                "Queued changes (Homarr was unreachable): {}",
+                state.pending_changes.len()
+            );
+        }
     } else {
         println!("Status: First-boot setup pending");
     }
 
+    if !config.homarr.is_empty() {
+        println!("Targets:");
+    }
+    for target in &config.homarr {
+        let state = match state::State::load(&config.for_target(target).state_file) {
+            Ok(state) => state,
+            Err(e) => {
+                println!(
+                    "  - {} ({}): state unreadable: {}",
+                    target.name, target.url, e
+                );
+                continue;
+            }
+        };
+        let last_sync = state
+            .last_sync
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| "never".to_string());
+        match state.consecutive_sync_failures {
+            0 => println!(
+                "  - {} ({}): last sync {}",
+                target.name, target.url, last_sync
+            ),
+            failures => println!(
+                "  - {} ({}): last sync {}, {} failed in a row since",
+                target.name, target.url, last_sync, failures
+            ),
+        }
+    }
+
     Ok(())
 }
 
+/// Probe Homarr API compatibility, printing one line per endpoint
+///
+/// Uses the stored API key, falling back to the bootstrap key without
+/// rotating it, so the check itself never changes adapter state.
+async fn run_api_check(config: &Config) -> bool {
+    let mut client = match homarr_client(config) {
+        Ok(client) => client,
+        Err(e) => {
+            println!("FAIL  cannot create client: {}", e);
+            return false;
+        }
+    };
+
+    let state = state::State::load(&config.state_file).unwrap_or_default();
+    let api_key = config
+        .api_key
+        .clone()
+        .or_else(|| {
+            config
+                .credential_store
+                .get(keyring::API_KEY)
+                .unwrap_or_else(|e| {
+                    println!("Cannot read the keyring: {}", e);
+                    None
+                })
+        })
+        .or(state.api_key)
+        .or_else(|| {
+            std::fs::read_to_string(&config.bootstrap_api_key_file)
+                .ok()
+                .map(|key| key.trim().to_string())
+                .filter(|key| !key.is_empty())
+        });
+    match api_key {
+        Some(key) => client.set_api_key(key),
+        None => println!("No API key available, checking unauthenticated"),
+    }
+
+    println!("Checking Homarr API at {}", config.homarr_url);
+    let checks = client.check_api().await;
+    for check in &checks {
+        println!(
+            "{}  {:<32} {}",
+            if check.ok { "ok  " } else { "FAIL" },
+            check.endpoint,
+            check.detail
+        );
+    }
+
+    let failed = checks.iter().filter(|c| !c.ok).count();
+    if failed == 0 {
+        println!("All {} checks passed", checks.len());
+    } else {
+        println!("{} of {} checks failed", failed, checks.len());
+    }
+    failed == 0
+}
+
+/// Run the offline self-test, printing one line per check
+fn run_self_test(config: &Config) -> bool {
+    let checks = selftest::run(config);
+    for check in &checks {
+        println!(
+            "{}  {:<32} {}",
+            if check.ok { "ok  " } else { "FAIL" },
+            check.name,
+            check.detail
+        );
+    }
+    let failed = checks.iter().filter(|c| !c.ok).count();
+    if failed == 0 {
+        println!("All {} checks passed", checks.len());
+    } else {
+        println!("{} of {} checks failed", failed, checks.len());
+    }
+    failed == 0
+}
+
+/// Check adapter health from state freshness, printing the verdict
+fn check_health(config: &Config, max_age: Option<u64>) -> bool {
+    let max_age = max_age.unwrap_or(config.sync_interval.saturating_mul(3));
+
+    let state = match state::State::load(&config.state_file) {
+        Ok(state) => state,
+        Err(e) => {
+            println!("unhealthy: cannot read state: {}", e);
+            return false;
+        }
+    };
+
+    if !state.first_boot_completed {
+        println!("unhealthy: first-boot setup has not completed");
+        return false;
+    }
+
+    // Syncs are skipped on purpose during maintenance
+    if let Some(until) = config.maintenance_until(chrono::Local::now()) {
+        println!(
+            "healthy (maintenance window until {})",
+            until.format("%H:%M")
+        );
+        return true;
+    }
+
+    let max_age = chrono::Duration::seconds(i64::try_from(max_age).unwrap_or(i64::MAX));
+    if !state.is_sync_fresh(max_age, chrono::Utc::now()) {
+        println!(
+            "unhealthy: no successful sync within {}s (last: {:?}, {} consecutive failure(s))",
+            max_age.num_seconds(),
+            state.last_sync,
+            state.consecutive_sync_failures
+        );
+        return false;
+    }
+
+    println!("healthy");
+    true
+}
+
 /// Reset adapter state to initial values
 ///
 /// Removes the state file, clearing:
@@ -410,19 +3158,20 @@ async fn check_status(config: &Config) -> Result<()> {
 /// - Authelia sync flag
 /// - Discovered apps tracking
 /// - Removed apps tracking
+/// - Manually added apps
 /// - Last sync timestamp
 fn reset_state(config: &Config) -> Result<()> {
     use std::path::Path;
 
-    let state_path = Path::new(&config.state_file);
+    let state_path = state::state_file(Path::new(&config.state_file));
 
     if state_path.exists() {
-        std::fs::remove_file(state_path)?;
-        info!("State file removed: {}", config.state_file);
+        std::fs::remove_file(&state_path)?;
+        info!("State file removed: {}", state_path.display());
     } else {
         info!(
             "State file does not exist, nothing to reset: {}",
-            config.state_file
+            state_path.display()
         );
     }
 
@@ -430,7 +3179,19 @@ fn reset_state(config: &Config) -> Result<()> {
 }
 
 /// Watch mode: monitor Docker events and sync on changes
-async fn run_watch(config: &Config) -> Result<()> {
+async fn run_watch(config: &Config, source: &ConfigSource) -> Result<()> {
+    systemd::ready();
+    systemd::status("Starting");
+
+    // The watch loop sends watchdog keepalives; until it runs, a background
+    // task does
+    let startup_keepalive = tokio::spawn(async {
+        let mut watchdog = systemd::Watchdog::new();
+        loop {
+            watchdog.tick().await;
+        }
+    });
+
     // Wait for startup delay to let Homarr start
     if config.startup_delay > 0 {
         info!(
@@ -441,31 +3202,53 @@ async fn run_watch(config: &Config) -> Result<()> {
     }
 
     // Connect to Docker
-    let docker = Docker::connect_with_socket(
-        &config.docker_socket,
-        120, // timeout in seconds
-        bollard::API_DEFAULT_VERSION,
-    )?;
+    let docker = docker::connect(config)?;
+
+    // One client per instance for the lifetime of the daemon, reusing pooled
+    // connections
+    let mut clients = HomarrClients::new(config)?;
+    clients
+        .main
+        .set_board_write_interval(Duration::from_secs(config.board_save_interval));
+
+    // Control interface; requested syncs queue until the watch loop runs them
+    let mut hub = grpc::ControlHub::new();
+    if let Some(ref listen) = config.grpc_listen {
+        hub.serve(config, listen)?;
+    }
+    if let Some(ref listen) = config.health_listen {
+        health::serve(config, listen, docker.clone())?;
+    }
 
     // Verify Docker connection
     match docker.ping().await {
         Ok(_) => info!("Connected to Docker daemon"),
-        Err(e) => {
-            error!("Failed to connect to Docker: {}", e);
-            return Err(e.into());
-        }
+        Err(e) => match docker::socket_permission_denied(config) {
+            Some(socket) => {
+                error!("{}", AdapterError::DockerPermissionDenied(socket));
+                wait_for_docker(config, &docker, &mut clients, &mut hub).await?;
+            }
+            None => {
+                error!("Failed to connect to Docker: {}", e);
+                return Err(e.into());
+            }
+        },
     }
 
     // Run initial sync with retry
     loop {
-        match run_sync(config).await {
+        match watch_sync(config, &mut clients, &hub, "initial", false).await {
             Ok(_) => {
                 info!("Initial sync completed successfully");
                 break;
             }
+            Err(AdapterError::Cancelled) => return Err(AdapterError::Cancelled),
             Err(e) => {
                 warn!("Initial sync failed: {}. Retrying in 10 seconds...", e);
-                sleep(Duration::from_secs(10)).await;
+                tokio::select! {
+                    _ = sleep(Duration::from_secs(10)) => {}
+                    _ = tokio::signal::ctrl_c() => return Err(AdapterError::Cancelled),
+                }
             }
         }
     }
@@ -475,11 +3258,111 @@ async fn run_watch(config: &Config) -> Result<()> {
         "Watching for Docker events, periodic sync every {} seconds",
         config.sync_interval
     );
-    watch_loop(config, &docker).await
+    startup_keepalive.abort();
+    let mut config = config.clone();
+    watch_loop(&mut config, source, &docker, &mut clients, &mut hub).await
+}
+
+/// First and longest delay between Docker socket retries
+const DOCKER_RETRY_MIN: Duration = Duration::from_secs(10);
+const DOCKER_RETRY_MAX: Duration = Duration::from_secs(300);
+
+/// Retry the Docker socket with backoff until permission is granted
+///
+/// Registry and Signal K apps keep syncing meanwhile and the control
+/// interface keeps answering, so a missing group membership doesn't wedge
+/// first boot.
+async fn wait_for_docker(
+    config: &Config,
+    docker: &Docker,
+    clients: &mut HomarrClients,
+    hub: &mut grpc::ControlHub,
+) -> Result<()> {
+    if let Err(e) = watch_sync(config, clients, hub, "initial", false).await {
+        warn!("Sync without Docker failed: {}", e);
+    }
+
+    let mut delay = DOCKER_RETRY_MIN;
+    loop {
+        info!("Retrying the Docker socket in {} seconds", delay.as_secs());
+        tokio::select! {
+            _ = sleep(delay) => {}
+            Some(request) = hub.sync_requests.recv() => {
+                info!("Sync requested over gRPC");
+                let result = watch_sync(config, clients, hub, "grpc", request.refresh_icons).await;
+                let _ = request.reply.send(result.map_err(|e| e.to_string()));
+                continue;
+            }
+            _ = tokio::signal::ctrl_c() => return Err(AdapterError::Cancelled),
+        }
+
+        match docker.ping().await {
+            Ok(_) => {
+                info!("Connected to Docker daemon");
+                return Ok(());
+            }
+            Err(e) => debug!("Docker still unavailable: {}", e),
+        }
+        delay = (delay * 2).min(DOCKER_RETRY_MAX);
+        if let Err(e) = watch_sync(config, clients, hub, "periodic", false).await {
+            warn!("Sync without Docker failed: {}", e);
+        }
+    }
+}
+
+/// Run a sync from the watch daemon, publishing its progress as events
+async fn watch_sync(
+    config: &Config,
+    clients: &mut HomarrClients,
+    hub: &grpc::ControlHub,
+    trigger: &str,
+    refresh_icons: bool,
+) -> Result<()> {
+    use grpc::proto::EventKind;
+
+    hub.publish(EventKind::SyncStarted, trigger);
+    systemd::status(&format!("Syncing ({})", trigger));
+    let result = sync_all(config, clients, refresh_icons, Some(hub)).await;
+    match result {
+        Ok(()) => {
+            hub.publish(EventKind::SyncSucceeded, trigger);
+            systemd::status(&format!(
+                "Watching, last sync {}",
+                chrono::Local::now().format("%H:%M:%S")
+            ));
+        }
+        Err(AdapterError::RateLimited(secs)) => {
+            hub.publish(
+                EventKind::SyncFailed,
+                format!("rate limited for {} s", secs),
+            );
+            systemd::status(&format!(
+                "Watching, syncs paused: Homarr rate limiting for {} s",
+                secs
+            ));
+        }
+        Err(ref e) => {
+            hub.publish(EventKind::SyncFailed, e.to_string());
+            systemd::status(&format!("Watching, last sync failed: {}", e));
+        }
+    }
+    result
 }
 
+/// Time for a container to fully start or stop, and for related events to
+/// arrive, before syncing
+const EVENT_SETTLE: Duration = Duration::from_secs(2);
+
 /// Main watch loop that handles Docker events and periodic syncs
-async fn watch_loop(config: &Config, docker: &Docker) -> Result<()> {
+///
+/// SIGHUP reloads `config` from `source`.
+async fn watch_loop(
+    config: &mut Config,
+    source: &ConfigSource,
+    docker: &Docker,
+    clients: &mut HomarrClients,
+    hub: &mut grpc::ControlHub,
+) -> Result<()> {
     let mut sync_timer = interval(Duration::from_secs(config.sync_interval));
     // Skip the first immediate tick
     sync_timer.tick().await;
@@ -487,9 +3370,33 @@ async fn watch_loop(config: &Config, docker: &Docker) -> Result<()> {
     // Set up Docker event stream with filter for container events
     let mut filters = HashMap::new();
     filters.insert("type", vec!["container"]);
-    filters.insert("event", vec!["start", "stop", "die", "destroy"]);
+    filters.insert("event", docker::watched_events(config.container_runtime));
+
+    // Sync due after a burst of labelled container events settles
+    let mut event_sync: Option<tokio::time::Instant> = None;
+
+    // Keepalives stop if the loop hangs, and systemd restarts the daemon
+    let mut watchdog = systemd::Watchdog::new();
+
+    let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
 
     loop {
+        // Board edits held back by board_save_interval
+        let board_flush = clients.main.next_board_flush();
+
+        // Next visible_hours boundary, recorded by the last sync
+        let schedule_change = state::State::load(&config.state_file)
+            .ok()
+            .and_then(|s| s.next_visibility_change)
+            .and_then(|at| (at - chrono::Utc::now()).to_std().ok());
+
+        // End of the current maintenance window; board saves wait for it
+        let now = chrono::Local::now();
+        let maintenance_end = config
+            .maintenance_until(now)
+            .and_then(|until| (until - now).to_std().ok());
+        let board_flush = board_flush.filter(|_| maintenance_end.is_none());
+
         // Create a fresh event stream for this iteration
         let options = EventsOptions {
             since: None,
@@ -503,22 +3410,45 @@ async fn watch_loop(config: &Config, docker: &Docker) -> Result<()> {
             Some(event_result) = events.next() => {
                 match event_result {
                     Ok(event) => {
-                        let action = event.action.as_deref().unwrap_or("unknown");
+                        let action = docker::event_action(event.action.as_deref().unwrap_or("unknown"));
                         let actor = event.actor.as_ref();
-                        let container_name = actor
-                            .and_then(|a| a.attributes.as_ref())
-                            .and_then(|attrs| attrs.get("name"))
+                        let mut attributes = actor
+                            .and_then(|a| a.attributes.clone())
+                            .unwrap_or_default();
+                        docker::translate_labels(&mut attributes, &config.label_schemes);
+                        let container_name = attributes
+                            .get("name")
                             .map(|s| s.as_str())
                             .unwrap_or("unknown");
 
                         info!("Docker event: {} container '{}'", action, container_name);
-
-                        // Brief delay to let container fully start/stop
-                        sleep(Duration::from_secs(2)).await;
-
-                        // Trigger sync
-                        if let Err(e) = run_sync(config).await {
-                            warn!("Sync failed after Docker event: {}", e);
+                        let detail = watchdog
+                            .keep_alive(track_container_stop(
+                                config,
+                                docker,
+                                action,
+                                container_name,
+                                &attributes,
+                            ))
+                            .await;
+                        hub.publish(
+                            grpc::proto::EventKind::Container,
+                            match detail {
+                                Some(reason) => format!("{} {}: {}", action, container_name, reason),
+                                None => format!("{} {}", action, container_name),
+                            },
+                        );
+
+                        // Only labelled containers change the dashboard; the
+                        // periodic sync picks up anything else. Events within
+                        // EVENT_SETTLE of the first (e.g. `compose up`)
+                        // share one sync.
+                        if docker::is_labelled(&attributes) {
+                            event_sync.get_or_insert_with(|| {
+                                tokio::time::Instant::now() + EVENT_SETTLE
+                            });
+                        } else {
+                            debug!("Container '{}' has no homarr labels, not syncing", container_name);
                         }
                     }
                     Err(e) => {
@@ -528,15 +3458,178 @@ async fn watch_loop(config: &Config, docker: &Docker) -> Result<()> {
                 }
             }
 
+            // Labelled containers started or stopped
+            _ = tokio::time::sleep_until(event_sync.unwrap_or_else(tokio::time::Instant::now)),
+                if event_sync.is_some() => {
+                event_sync = None;
+                if let Err(e) = watchdog
+                    .keep_alive(watch_sync(config, clients, hub, "docker-event", false))
+                    .await {
+                    warn!("Sync failed after Docker event: {}", e);
+                }
+            }
+
             // Periodic sync timer
             _ = sync_timer.tick() => {
                 debug!("Periodic sync triggered");
-                if let Err(e) = run_sync(config).await {
+                if let Err(e) = watchdog
+                    .keep_alive(watch_sync(config, clients, hub, "periodic", false))
+                    .await {
                     warn!("Periodic sync failed: {}", e);
                 }
             }
+
+            // A scheduled tile is due to appear or disappear
+            _ = sleep(schedule_change.unwrap_or_default()), if schedule_change.is_some() => {
+                info!("Tile schedule changed, syncing");
+                if let Err(e) = watchdog
+                    .keep_alive(watch_sync(config, clients, hub, "schedule", false))
+                    .await {
+                    warn!("Scheduled sync failed: {}", e);
+                }
+            }
+
+            // Catch up on everything skipped during maintenance
+            _ = sleep(maintenance_end.unwrap_or_default()), if maintenance_end.is_some() => {
+                info!("Homarr maintenance window ended, syncing");
+                if let Err(e) = watchdog
+                    .keep_alive(watch_sync(config, clients, hub, "maintenance-end", false))
+                    .await {
+                    warn!("Sync after maintenance window failed: {}", e);
+                }
+            }
+
+            // Save board edits that were held back
+            _ = sleep(board_flush.unwrap_or_default()), if board_flush.is_some() => {
+                if let Err(e) = watchdog.keep_alive(clients.main.flush_board_saves()).await {
+                    warn!("Failed to save board changes: {}", e);
+                }
+            }
+
+            // Sync requested over the control interface
+            Some(request) = hub.sync_requests.recv() => {
+                info!("Sync requested over gRPC");
+                let result = watchdog
+                    .keep_alive(watch_sync(config, clients, hub, "grpc", request.refresh_icons))
+                    .await;
+                let _ = request.reply.send(result.map_err(|e| e.to_string()));
+            }
+
+            _ = watchdog.tick() => {}
+
+            // Config or branding edited
+            _ = hangup.recv() => {
+                info!("SIGHUP received, reloading {}", source.path);
+                systemd::notify("RELOADING=1");
+                match reload_config(source, config, clients).await {
+                    Ok(()) => {
+                        sync_timer = interval(Duration::from_secs(config.sync_interval));
+                        sync_timer.tick().await;
+                        info!("Config reloaded, changes apply from the next sync");
+                    }
+                    Err(e) => error!("Config reload failed, keeping the current config: {}", e),
+                }
+                systemd::notify("READY=1");
+            }
+
+            // Interrupted (setup may have taken over Ctrl-C from the default
+            // handler, so stop here rather than ignore it)
+            _ = tokio::signal::ctrl_c() => {
+                info!("Interrupted, saving pending board edits and stopping");
+                systemd::notify("STOPPING=1");
+                if let Err(e) = clients.main.flush_board_saves().await {
+                    warn!("Failed to save board changes: {}", e);
+                }
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Load the config again for the watch loop, replacing `config` and
+/// `clients` only if it is valid
+///
+/// The branding file is read by every sync, so it is only checked here.
+async fn reload_config(
+    source: &ConfigSource,
+    config: &mut Config,
+    clients: &mut HomarrClients,
+) -> Result<()> {
+    let new = source.load()?;
+    if let Err(e) = branding::BrandingConfig::load(&new.branding_file) {
+        error!("Branding file {} is invalid: {}", new.branding_file, e);
+    }
+    for setting in config.changed_startup_settings(&new) {
+        warn!("'{}' changed, restart the adapter to apply it", setting);
+    }
+
+    // Homarr URLs and request settings live in the clients; edits the main
+    // client has staged go out first
+    let mut fresh = HomarrClients::new(&new)?;
+    fresh
+        .main
+        .set_board_write_interval(Duration::from_secs(new.board_save_interval));
+    if let Err(e) = clients.main.flush_board_saves().await {
+        warn!("Failed to save board changes: {}", e);
+    }
+    *clients = fresh;
+    *config = new;
+    Ok(())
+}
+
+/// Record why a labelled container died, or forget it once it starts again
+///
+/// Returns the stop reason for `die` events so it can go out with the event.
+async fn track_container_stop(
+    config: &Config,
+    docker: &Docker,
+    action: &str,
+    container_name: &str,
+    attributes: &HashMap<String, String>,
+) -> Option<String> {
+    if !docker::is_labelled(attributes) {
+        return None;
+    }
+
+    let stop = match action {
+        "die" => Some(docker::stop_details(docker, container_name, attributes).await),
+        "start" => None,
+        _ => return None,
+    };
+    let reason = stop.as_ref().map(|stop| stop.reason());
+    if let (Some(stop), Some(reason)) = (&stop, &reason) {
+        if stop.oom_killed || !matches!(stop.exit_code, Some(0 | 143)) {
+            warn!("Container '{}' {}", container_name, reason);
+        } else {
+            info!("Container '{}' {}", container_name, reason);
+        }
+    }
+
+    if let Err(e) = save_container_stop(config, container_name, stop).await {
+        warn!("Failed to save container stop: {}", e);
+    }
+    reason
+}
+
+/// Record a container's stop in state, or forget it when `stop` is `None`
+async fn save_container_stop(
+    config: &Config,
+    container_name: &str,
+    stop: Option<state::ContainerStop>,
+) -> Result<()> {
+    let _lock = lock_state(config).await?;
+    let mut state = state::State::load(&config.state_file)?;
+    let changed = match stop {
+        Some(stop) => {
+            state.record_container_stop(container_name, stop);
+            true
         }
+        None => state.clear_container_stop(container_name),
+    };
+    if changed {
+        state.save(&config.state_file)?;
     }
+    Ok(())
 }
 
 /// Get the current list of running containers (for debugging)
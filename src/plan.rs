@@ -0,0 +1,247 @@
+//! Dry-run plans (`sync --dry-run`)
+//!
+//! What a sync would change in Homarr, worked out from the same discovery
+//! and Homarr reads as a real sync but without any writes. Board plans
+//! follow `add_registry_app`: apps are matched by URL, then by name, and
+//! apps marked removed from a board are left off it.
+
+use std::fmt;
+
+use crate::homarr::{self, HomarrClient, SelectableApp};
+use crate::registry::AppDefinition;
+
+/// Changes a sync would make
+#[derive(Debug, Default)]
+pub struct SyncPlan {
+    /// Queued changes from while Homarr was unreachable, applied first
+    pub queued: Vec<String>,
+    /// Apps to create in Homarr's registry, as "name (url)"
+    pub create: Vec<String>,
+    /// Existing apps whose fields would change, with the changed fields
+    pub update: Vec<(String, Vec<&'static str>)>,
+    /// Apps that would be deleted, hidden or no longer synced, with why
+    pub remove: Vec<(String, String)>,
+    /// Tile changes per writable board
+    pub boards: Vec<BoardPlan>,
+}
+
+/// Tile changes a sync would make on one board
+#[derive(Debug, Default, PartialEq)]
+pub struct BoardPlan {
+    pub name: String,
+    /// Board named by `homarr.board` that would be created
+    pub new: bool,
+    /// Apps whose tiles would be placed
+    pub place: Vec<String>,
+    /// Apps whose tiles would be taken off (`homarr.hide`, `visible_hours`)
+    pub hide: Vec<String>,
+    /// Apps left off because they were removed from this board
+    pub skipped: Vec<String>,
+}
+
+impl BoardPlan {
+    fn is_empty(&self) -> bool {
+        !self.new && self.place.is_empty() && self.hide.is_empty()
+    }
+}
+
+/// Fields of an existing Homarr app that a sync would overwrite
+pub fn app_changes(app: &AppDefinition, existing: &SelectableApp, icon: &str) -> Vec<&'static str> {
+    let mut changes = Vec::new();
+    if existing.name != app.name {
+        changes.push("name");
+    }
+    let href = existing.href.as_deref().map(homarr::normalize_url);
+    if href.as_deref() != Some(homarr::normalize_url(&app.url).as_str()) {
+        changes.push("url");
+    }
+    if existing.icon_url != icon {
+        changes.push("icon");
+    }
+    changes
+}
+
+/// Tile changes on a board with `items`, for the apps a sync would place
+pub fn plan_board(
+    name: &str,
+    items: &[serde_json::Value],
+    apps: &[&AppDefinition],
+    existing_apps: &[SelectableApp],
+    is_removed: impl Fn(&str) -> bool,
+) -> BoardPlan {
+    let mut plan = BoardPlan {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    for app in apps {
+        if is_removed(&app.url) {
+            plan.skipped.push(app.name.clone());
+            continue;
+        }
+        let on_board = HomarrClient::find_app_by_url(existing_apps, &app.url)
+            .or_else(|| HomarrClient::find_app_by_name(existing_apps, &app.name))
+            .is_some_and(|existing| homarr::board_has_app(items, &existing.id));
+        match (app.hide, on_board) {
+            (true, true) => plan.hide.push(app.name.clone()),
+            (false, false) => plan.place.push(app.name.clone()),
+            _ => {}
+        }
+    }
+    plan
+}
+
+impl SyncPlan {
+    /// Whether the sync would change nothing
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+            && self.create.is_empty()
+            && self.update.is_empty()
+            && self.remove.is_empty()
+            && self.boards.iter().all(BoardPlan::is_empty)
+    }
+}
+
+impl fmt::Display for SyncPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No changes: Homarr matches the discovered apps");
+        }
+        if !self.queued.is_empty() {
+            writeln!(f, "Queued changes applied first:")?;
+            for change in &self.queued {
+                writeln!(f, "  ~ {}", change)?;
+            }
+        }
+        if !self.create.is_empty() {
+            writeln!(f, "Apps to create:")?;
+            for app in &self.create {
+                writeln!(f, "  + {}", app)?;
+            }
+        }
+        if !self.update.is_empty() {
+            writeln!(f, "Apps to update:")?;
+            for (app, fields) in &self.update {
+                writeln!(f, "  ~ {} ({})", app, fields.join(", "))?;
+            }
+        }
+        if !self.remove.is_empty() {
+            writeln!(f, "Apps to remove:")?;
+            for (app, reason) in &self.remove {
+                writeln!(f, "  - {} ({})", app, reason)?;
+            }
+        }
+        for board in self.boards.iter().filter(|b| !b.is_empty()) {
+            if board.new {
+                writeln!(f, "Board '{}' (created):", board.name)?;
+            } else {
+                writeln!(f, "Board '{}':", board.name)?;
+            }
+            for app in &board.place {
+                writeln!(f, "  + place {}", app)?;
+            }
+            for app in &board.hide {
+                writeln!(f, "  - hide {}", app)?;
+            }
+            if !board.skipped.is_empty() {
+                writeln!(
+                    f,
+                    "  (left off, removed from this board: {})",
+                    board.skipped.join(", ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn app(name: &str, url: &str) -> AppDefinition {
+        AppDefinition {
+            name: name.to_string(),
+            url: url.to_string(),
+            visible: true,
+            ..Default::default()
+        }
+    }
+
+    fn existing(id: &str, name: &str, href: &str) -> SelectableApp {
+        SelectableApp {
+            id: id.to_string(),
+            name: name.to_string(),
+            icon_url: "/icons/docker.svg".to_string(),
+            href: Some(href.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_app_changes() {
+        let grafana = app("Grafana", "http://grafana.local/");
+        let current = existing("a1", "Grafana", "http://grafana.local");
+        assert!(app_changes(&grafana, &current, "/icons/docker.svg").is_empty());
+
+        let renamed = existing("a1", "Old Grafana", "http://grafana.local:3000");
+        assert_eq!(
+            app_changes(&grafana, &renamed, "/icons/grafana.svg"),
+            vec!["name", "url", "icon"]
+        );
+    }
+
+    #[test]
+    fn test_plan_board() {
+        let grafana = app("Grafana", "http://grafana.local");
+        let mut influx = app("InfluxDB", "http://influx.local");
+        influx.hide = true;
+        let signalk = app("Signal K", "http://signalk.local");
+        let chart = app("Chart Plotter", "http://chart.local");
+        let existing_apps = vec![
+            existing("a1", "Grafana", "http://grafana.local"),
+            existing("a2", "InfluxDB", "http://influx.local"),
+        ];
+        let items = vec![json!({ "kind": "app", "options": { "appId": "a2" } })];
+
+        let plan = plan_board(
+            "halos",
+            &items,
+            &[&grafana, &influx, &signalk, &chart],
+            &existing_apps,
+            |url| url == "http://chart.local",
+        );
+        assert_eq!(plan.place, vec!["Grafana", "Signal K"]);
+        assert_eq!(plan.hide, vec!["InfluxDB"]);
+        assert_eq!(plan.skipped, vec!["Chart Plotter"]);
+    }
+
+    #[test]
+    fn test_new_board_display() {
+        let plan = SyncPlan {
+            boards: vec![BoardPlan {
+                name: "media".to_string(),
+                new: true,
+                place: vec!["Jellyfin".to_string()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let text = plan.to_string();
+        assert!(text.contains("Board 'media' (created):"));
+        assert!(text.contains("+ place Jellyfin"));
+    }
+
+    #[test]
+    fn test_empty_plan_display() {
+        let plan = SyncPlan {
+            boards: vec![BoardPlan {
+                name: "halos".to_string(),
+                skipped: vec!["Grafana".to_string()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert!(plan.is_empty());
+        assert!(plan.to_string().starts_with("No changes"));
+    }
+}
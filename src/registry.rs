@@ -23,8 +23,9 @@
 //!
 //! Example: `url = "https://cockpit.{{domain}}"`
 
-use serde::Deserialize;
-use std::collections::HashMap;
+use chrono::{DateTime, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 use url::Url;
@@ -36,7 +37,7 @@ use crate::error::{AdapterError, Result};
 pub const DEFAULT_REGISTRY_DIR: &str = "/etc/halos/webapps.d";
 
 /// App definition from a registry file
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct AppDefinition {
     /// Display name for the app
@@ -48,7 +49,8 @@ pub struct AppDefinition {
     /// Optional description
     pub description: Option<String>,
 
-    /// Icon URL (can be /icons/*, http(s)://, or /usr/share/pixmaps/*)
+    /// Icon URL (can be /icons/*, http(s)://, /usr/share/pixmaps/*, or
+    /// file:///path to upload a local file to Homarr's media store)
     pub icon_url: Option<String>,
 
     /// Category for grouping (e.g., "Marine", "System")
@@ -65,13 +67,54 @@ pub struct AppDefinition {
     /// Optional override for ping URL (health checks)
     pub ping_url: Option<String>,
 
+    /// Homarr's status ping: `true` pings the app URL itself (or `ping_url`),
+    /// `false` turns it off; unset derives a ping URL Homarr can reach,
+    /// except for external apps
+    #[serde(default)]
+    pub ping: Option<bool>,
+
     /// Board layout configuration (includes priority)
     #[serde(default)]
     pub layout: LayoutConfig,
+
+    /// CSS classes added to the board item (Homarr `customCssClasses`)
+    #[serde(default)]
+    pub css_classes: Vec<String>,
+
+    /// Integration widget kind (e.g. "calendar") placed next to the app tile,
+    /// linked to the Homarr integration matching the app's URL or name
+    #[serde(default)]
+    pub widget: Option<String>,
+
+    /// Temporarily take the tile off boards, keeping the app in Homarr; the
+    /// tile returns to the same position when this is cleared
+    #[serde(default)]
+    pub hide: bool,
+
+    /// Daily local-time windows when the tile is shown, e.g. "08:00-22:00"
+    /// (comma-separated for several; a window may wrap past midnight).
+    /// Outside them the tile is hidden as with `hide`
+    #[serde(default)]
+    pub visible_hours: Option<String>,
+
+    /// Create the app and its tiles once, then leave them to the user: later
+    /// syncs don't update, move, hide or remove them
+    #[serde(default)]
+    pub pinned: bool,
+
+    /// Board to place the tile on, created if missing; unset places it on
+    /// every writable board
+    #[serde(default)]
+    pub board: Option<String>,
+
+    /// Display names by locale (e.g. "de", "pt-br"), used instead of `name`
+    /// when the configured `locale` matches
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub localized_names: BTreeMap<String, String>,
 }
 
 /// App type - determines how health checks work
-#[derive(Debug, Clone, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AppType {
     /// Docker container name (enables container health tracking)
     pub container_name: Option<String>,
@@ -79,10 +122,14 @@ pub struct AppType {
     /// External link flag (no health checks)
     #[serde(default)]
     pub external: bool,
+
+    /// Compose project of the container (`com.docker.compose.project`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compose_project: Option<String>,
 }
 
 /// Board layout configuration
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LayoutConfig {
     /// Priority for placement order (lower = placed first, default: 50)
     /// Ranges: 00-19 system, 20-39 primary, 40-59 default, 60-79 utility, 80-99 external
@@ -104,6 +151,11 @@ pub struct LayoutConfig {
     /// Explicit row position
     /// If omitted, auto-positioned based on priority
     pub y_offset: Option<u8>,
+
+    /// Named board section to place the tile in (see branding `[board] sections`)
+    /// If omitted, the branding default section is used
+    #[serde(default)]
+    pub section: Option<String>,
 }
 
 fn default_priority() -> u8 {
@@ -122,6 +174,7 @@ impl Default for LayoutConfig {
             height: 1,
             x_offset: None,
             y_offset: None,
+            section: None,
         }
     }
 }
@@ -154,11 +207,49 @@ impl AppDefinition {
         self.visible
     }
 
+    /// Check if the tile is scheduled to be shown at a local time of day
+    ///
+    /// Always true without `visible_hours` (or with an invalid one).
+    pub fn in_visible_hours(&self, time: NaiveTime) -> bool {
+        match self.visible_hours.as_deref().map(parse_visible_hours) {
+            Some(Ok(windows)) => windows
+                .iter()
+                .any(|&(start, end)| window_contains(start, end, time)),
+            _ => true,
+        }
+    }
+
+    /// Use the display name for `locale`, falling back from a regional
+    /// locale ("de-AT") to its language ("de") and then to `name`
+    pub fn localize(&mut self, locale: &str) {
+        let locale = locale.to_lowercase().replace('_', "-");
+        let language = locale.split('-').next().unwrap_or_default();
+        if let Some(name) = self
+            .localized_names
+            .get(&locale)
+            .or_else(|| self.localized_names.get(language))
+        {
+            self.name = name.clone();
+        }
+    }
+
+    /// Whether the tile belongs on the board named `board_name`
+    pub fn targets_board(&self, board_name: &str) -> bool {
+        self.board
+            .as_deref()
+            .is_none_or(|board| board == board_name)
+    }
+
     /// Get the container name if this is a container app
     pub fn container_name(&self) -> Option<&str> {
         self.app_type.container_name.as_deref()
     }
 
+    /// Get the compose project of the app's container, if any
+    pub fn compose_project(&self) -> Option<&str> {
+        self.app_type.compose_project.as_deref()
+    }
+
     /// Get the layout configuration
     pub fn effective_layout(&self) -> &LayoutConfig {
         &self.layout
@@ -183,7 +274,7 @@ fn get_hostname() -> String {
 }
 
 /// Get the mDNS domain (hostname.local)
-fn get_domain() -> String {
+pub(crate) fn get_domain() -> String {
     format!("{}.local", get_hostname())
 }
 
@@ -288,20 +379,132 @@ fn load_app_file<P: AsRef<Path>>(path: P) -> Result<AppDefinition> {
         AdapterError::Config(format!("Invalid URL '{}' in {:?}: {}", app.url, path, e))
     })?;
 
+    if let Some(ref hours) = app.visible_hours {
+        parse_visible_hours(hours)
+            .map_err(|e| AdapterError::Config(format!("{} in {:?}", e, path)))?;
+    }
+
     Ok(app)
 }
 
+/// Parse `visible_hours`: comma-separated "HH:MM-HH:MM" windows
+pub fn parse_visible_hours(spec: &str) -> Result<Vec<(NaiveTime, NaiveTime)>> {
+    let invalid = || AdapterError::Config(format!("Invalid visible_hours '{}'", spec));
+    spec.split(',')
+        .map(|window| {
+            let (start, end) = window.trim().split_once('-').ok_or_else(invalid)?;
+            let time =
+                |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M").map_err(|_| invalid());
+            Ok((time(start)?, time(end)?))
+        })
+        .collect()
+}
+
+/// Check if a time of day falls in a window, which may wrap past midnight
+fn window_contains(start: NaiveTime, end: NaiveTime, time: NaiveTime) -> bool {
+    if start <= end {
+        start <= time && time < end
+    } else {
+        time >= start || time < end
+    }
+}
+
+/// The next time of day `boundary` comes around after `now`
+fn next_occurrence(boundary: NaiveTime, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let today = now.date_naive();
+    [today, today.succ_opt()?]
+        .into_iter()
+        .filter_map(|day| {
+            Local
+                .from_local_datetime(&day.and_time(boundary))
+                .earliest()
+        })
+        .find(|at| *at > now)
+}
+
+/// The next time any app's scheduled visibility changes
+pub fn next_visibility_change(
+    apps: &[AppDefinition],
+    now: DateTime<Local>,
+) -> Option<DateTime<Local>> {
+    apps.iter()
+        .filter_map(|app| parse_visible_hours(app.visible_hours.as_deref()?).ok())
+        .flatten()
+        .flat_map(|(start, end)| [start, end])
+        .filter_map(|boundary| next_occurrence(boundary, now))
+        .min()
+}
+
+/// When the window `now` falls in closes, or `None` outside all windows
+pub fn window_closes(
+    windows: &[(NaiveTime, NaiveTime)],
+    now: DateTime<Local>,
+) -> Option<DateTime<Local>> {
+    windows
+        .iter()
+        .filter(|&&(start, end)| window_contains(start, end, now.time()))
+        .filter_map(|&(_, end)| next_occurrence(end, now))
+        .min()
+}
+
 /// Validate that an app URL is either an absolute URL or a path-only URL.
 ///
 /// Path-only URLs must start with `/` followed by a non-`/` character, so a
 /// lone `/` and protocol-relative `//host/...` forms are rejected.
-fn validate_app_url(url: &str) -> std::result::Result<(), String> {
+pub(crate) fn validate_app_url(url: &str) -> std::result::Result<(), String> {
     if is_path_only(url) {
         return Ok(());
     }
     Url::parse(url).map(|_| ()).map_err(|e| e.to_string())
 }
 
+/// Validate a discovered app URL and return it in canonical form.
+///
+/// Absolute URLs must be `http` or `https` with a host. Parsing lowercases
+/// the host, converts internationalized hostnames to punycode and drops
+/// default ports; trailing slashes are trimmed from the path, so
+/// `http://Grafana.local:80/` becomes `http://grafana.local`. Path-only URLs
+/// are returned unchanged.
+pub(crate) fn normalize_app_url(url: &str) -> std::result::Result<String, String> {
+    let url = url.trim();
+    if is_path_only(url) {
+        return Ok(url.to_string());
+    }
+    let mut parsed = Url::parse(url).map_err(|e| e.to_string())?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("unsupported scheme '{}'", parsed.scheme()));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err("no host".to_string());
+    }
+    let path = parsed.path().trim_end_matches('/').to_string();
+    parsed.set_path(&path);
+    let mut normalized = parsed.to_string();
+    if path.is_empty() && parsed.query().is_none() && parsed.fragment().is_none() {
+        normalized.pop();
+    }
+    Ok(normalized)
+}
+
+/// Resolve an icon given relative to its app (`static/logo.png`) against
+/// the app URL. Absolute URLs, absolute paths and icons of path-only apps
+/// are returned unchanged.
+pub(crate) fn resolve_icon_url(icon: &str, app_url: &str) -> String {
+    if icon.is_empty() || icon.starts_with('/') || Url::parse(icon).is_ok() {
+        return icon.to_string();
+    }
+    // Join against the app's directory: `http://host/app` serves `app/...`
+    let base = if app_url.ends_with('/') {
+        app_url.to_string()
+    } else {
+        format!("{}/", app_url)
+    };
+    Url::parse(&base)
+        .and_then(|base| base.join(icon))
+        .map(String::from)
+        .unwrap_or_else(|_| icon.to_string())
+}
+
 /// True iff `url` starts with `/` and the second character (if any) is not `/`.
 ///
 /// Shared with `homarr::derive_ping_url` so both call sites agree on what
@@ -311,6 +514,143 @@ pub(crate) fn is_path_only(url: &str) -> bool {
     chars.next() == Some('/') && chars.next().is_some_and(|c| c != '/')
 }
 
+/// How to handle several apps declaring the same display name
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicateNameStrategy {
+    /// Suffix later duplicates with their container name (or URL host)
+    #[default]
+    Suffix,
+    /// Keep the first app with a name and skip the rest
+    KeepFirst,
+    /// Fail the sync, listing the conflicting apps
+    Strict,
+}
+
+/// Qualifier used to tell a duplicate apart: container name, else URL host
+fn duplicate_qualifier(app: &AppDefinition) -> String {
+    if let Some(container) = app.container_name() {
+        return container.to_string();
+    }
+    Url::parse(&app.url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_string()))
+        .unwrap_or_else(|| app.url.clone())
+}
+
+/// Enforce HTTPS-only app URLs.
+///
+/// `http://` URLs are rewritten to `https://` when `upgrade` is set, otherwise
+/// the app is dropped. Path-only URLs are served by Traefik over HTTPS and
+/// pass as they are, as do apps whose container or display name is listed in
+/// `exceptions`.
+pub fn enforce_https(
+    apps: Vec<AppDefinition>,
+    upgrade: bool,
+    exceptions: &[String],
+) -> Vec<AppDefinition> {
+    apps.into_iter()
+        .filter_map(|mut app| {
+            let exempt = exceptions.iter().any(|e| {
+                app.container_name() == Some(e.as_str()) || app.name.eq_ignore_ascii_case(e)
+            });
+            let Ok(mut url) = Url::parse(&app.url) else {
+                return Some(app);
+            };
+            if exempt || url.scheme() != "http" {
+                return Some(app);
+            }
+
+            if upgrade && url.set_scheme("https").is_ok() {
+                tracing::debug!("Upgraded '{}' to {}", app.name, url);
+                app.url = url.to_string();
+                Some(app)
+            } else {
+                tracing::warn!(
+                    "Skipping app '{}': {} is not HTTPS (require_https)",
+                    app.name,
+                    app.url
+                );
+                None
+            }
+        })
+        .collect()
+}
+
+/// Resolve apps that share a display name but point at different URLs.
+///
+/// Homarr matches existing apps by name when the URL changes, so same-named
+/// apps would otherwise overwrite each other on every sync. Names compare
+/// case-insensitively; apps repeating the same URL are not conflicts.
+pub fn resolve_duplicate_names(
+    apps: Vec<&AppDefinition>,
+    strategy: DuplicateNameStrategy,
+) -> Result<Vec<AppDefinition>> {
+    // Lowercased name -> URL of the app that claimed it
+    let mut taken: HashMap<String, String> = HashMap::new();
+    let mut conflicts = Vec::new();
+    let mut resolved = Vec::with_capacity(apps.len());
+
+    for app in apps {
+        let key = app.name.to_lowercase();
+        let first_url = match taken.get(&key) {
+            None => {
+                taken.insert(key, app.url.clone());
+                resolved.push(app.clone());
+                continue;
+            }
+            Some(url) if *url == app.url => {
+                resolved.push(app.clone());
+                continue;
+            }
+            Some(url) => url.clone(),
+        };
+
+        match strategy {
+            DuplicateNameStrategy::Strict => {
+                conflicts.push(format!("'{}' ({} and {})", app.name, first_url, app.url));
+            }
+            DuplicateNameStrategy::KeepFirst => {
+                tracing::warn!(
+                    "Duplicate app name '{}': keeping {}, skipping {}",
+                    app.name,
+                    first_url,
+                    app.url
+                );
+            }
+            DuplicateNameStrategy::Suffix => {
+                let base = format!("{} ({})", app.name, duplicate_qualifier(app));
+                let mut name = base.clone();
+                let mut n = 2;
+                while taken.contains_key(&name.to_lowercase()) {
+                    name = format!("{} {}", base, n);
+                    n += 1;
+                }
+                tracing::warn!(
+                    "Duplicate app name '{}' ({}), renamed to '{}'",
+                    app.name,
+                    app.url,
+                    name
+                );
+                taken.insert(name.to_lowercase(), app.url.clone());
+                resolved.push(AppDefinition {
+                    name,
+                    ..app.clone()
+                });
+            }
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(AdapterError::Config(format!(
+            "Duplicate app names: {}",
+            conflicts.join(", ")
+        )));
+    }
+
+    Ok(resolved)
+}
+
 /// Get apps as a HashMap keyed by URL (for deduplication)
 #[allow(dead_code)]
 pub fn apps_by_url(entries: &[RegistryEntry]) -> HashMap<String, &RegistryEntry> {
@@ -349,6 +689,7 @@ url = "http://localhost:8080"
         assert!(!entries[0].app.is_container());
         assert!(!entries[0].app.is_external());
         assert!(!entries[0].app.is_visible()); // default: false
+        assert!(entries[0].app.css_classes.is_empty());
     }
 
     #[test]
@@ -393,6 +734,23 @@ y_offset = 0
         assert_eq!(layout.y_offset, Some(0));
     }
 
+    #[test]
+    fn test_load_app_css_classes() {
+        let dir = TempDir::new().unwrap();
+        create_test_app_file(
+            dir.path(),
+            "styled",
+            r#"
+name = "Styled App"
+url = "http://localhost:8080"
+css_classes = ["accent", "large-title"]
+"#,
+        );
+
+        let entries = load_all_apps(dir.path()).unwrap();
+        assert_eq!(entries[0].app.css_classes, vec!["accent", "large-title"]);
+    }
+
     #[test]
     fn test_load_external_app() {
         let dir = TempDir::new().unwrap();
@@ -696,6 +1054,51 @@ url = "/signalk-server/@signalk/freeboard-sk/"
         assert!(validate_app_url("/signalk-server/@signalk/freeboard-sk/").is_ok());
     }
 
+    #[test]
+    fn test_normalize_app_url() {
+        let normalize = |url| normalize_app_url(url).unwrap();
+        assert_eq!(
+            normalize("http://Grafana.local:80/"),
+            "http://grafana.local"
+        );
+        assert_eq!(normalize(" https://host:443/app// "), "https://host/app");
+        assert_eq!(
+            normalize("http://host:3000/d?x=1"),
+            "http://host:3000/d?x=1"
+        );
+        assert_eq!(
+            normalize("http://bücher.example/"),
+            "http://xn--bcher-kva.example"
+        );
+        assert_eq!(normalize("/cockpit/"), "/cockpit/");
+
+        assert!(normalize_app_url("not-a-url").is_err());
+        assert!(normalize_app_url("javascript:alert(1)").is_err());
+        assert!(normalize_app_url("file:///etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_resolve_icon_url() {
+        let resolve = resolve_icon_url;
+        assert_eq!(
+            resolve("static/logo.png", "http://host:3000"),
+            "http://host:3000/static/logo.png"
+        );
+        assert_eq!(
+            resolve("./logo.png", "http://host/grafana"),
+            "http://host/grafana/logo.png"
+        );
+        assert_eq!(
+            resolve("/icons/grafana.svg", "http://host"),
+            "/icons/grafana.svg"
+        );
+        assert_eq!(
+            resolve("https://cdn/x.png", "http://host"),
+            "https://cdn/x.png"
+        );
+        assert_eq!(resolve("logo.png", "/cockpit/"), "logo.png");
+    }
+
     #[test]
     fn test_load_app_with_template_url() {
         let dir = TempDir::new().unwrap();
@@ -715,4 +1118,217 @@ url = "https://app.{{domain}}"
         assert_eq!(entries[0].app.name, "Templated App");
         assert_eq!(entries[0].app.url, format!("https://app.{}", domain));
     }
+
+    fn named_app(name: &str, url: &str, container: Option<&str>) -> AppDefinition {
+        let mut app = AppDefinition {
+            name: name.to_string(),
+            url: url.to_string(),
+            ..Default::default()
+        };
+        app.app_type.container_name = container.map(|c| c.to_string());
+        app
+    }
+
+    #[test]
+    fn test_resolve_duplicate_names_suffix() {
+        let a = named_app("Grafana", "http://localhost:3001", Some("grafana"));
+        let b = named_app("grafana", "http://localhost:3002", Some("grafana-2"));
+        let c = named_app("Grafana", "https://grafana.example.com", None);
+        let resolved =
+            resolve_duplicate_names(vec![&a, &b, &c], DuplicateNameStrategy::Suffix).unwrap();
+        let names: Vec<&str> = resolved.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Grafana",
+                "grafana (grafana-2)",
+                "Grafana (grafana.example.com)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_duplicate_names_suffix_collision() {
+        let a = named_app("Web", "http://localhost:1", Some("web"));
+        let b = named_app("Web", "http://localhost:2", Some("web"));
+        let c = named_app("Web", "http://localhost:3", Some("web"));
+        let resolved =
+            resolve_duplicate_names(vec![&a, &b, &c], DuplicateNameStrategy::Suffix).unwrap();
+        assert_eq!(resolved[1].name, "Web (web)");
+        assert_eq!(resolved[2].name, "Web (web) 2");
+    }
+
+    #[test]
+    fn test_resolve_duplicate_names_keep_first() {
+        let a = named_app("Grafana", "http://localhost:3001", None);
+        let b = named_app("Grafana", "http://localhost:3002", None);
+        let resolved =
+            resolve_duplicate_names(vec![&a, &b], DuplicateNameStrategy::KeepFirst).unwrap();
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].url, "http://localhost:3001");
+    }
+
+    #[test]
+    fn test_resolve_duplicate_names_strict() {
+        let a = named_app("Grafana", "http://localhost:3001", None);
+        let b = named_app("Grafana", "http://localhost:3002", None);
+        let err = resolve_duplicate_names(vec![&a, &b], DuplicateNameStrategy::Strict).unwrap_err();
+        assert!(err.to_string().contains("'Grafana'"));
+    }
+
+    #[test]
+    fn test_resolve_duplicate_names_same_url_is_not_conflict() {
+        let a = named_app("Grafana", "http://localhost:3001", None);
+        let b = named_app("Grafana", "http://localhost:3001", None);
+        let resolved =
+            resolve_duplicate_names(vec![&a, &b], DuplicateNameStrategy::Strict).unwrap();
+        assert_eq!(resolved.len(), 2);
+    }
+
+    #[test]
+    fn test_load_app_widget() {
+        let dir = TempDir::new().unwrap();
+        create_test_app_file(
+            dir.path(),
+            "sonarr",
+            r#"
+name = "Sonarr"
+url = "http://localhost:8989"
+widget = "calendar"
+"#,
+        );
+
+        let apps = load_all_apps(dir.path()).unwrap();
+        assert_eq!(apps[0].app.widget.as_deref(), Some("calendar"));
+    }
+
+    fn time(hm: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(hm, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_parse_visible_hours() {
+        assert_eq!(
+            parse_visible_hours("08:00-12:00, 14:30-22:00").unwrap(),
+            vec![
+                (time("08:00"), time("12:00")),
+                (time("14:30"), time("22:00"))
+            ]
+        );
+        assert!(parse_visible_hours("08:00").is_err());
+        assert!(parse_visible_hours("8am-10pm").is_err());
+        assert!(parse_visible_hours("25:00-26:00").is_err());
+    }
+
+    #[test]
+    fn test_in_visible_hours() {
+        let mut app = AppDefinition::default();
+        assert!(app.in_visible_hours(time("03:00")));
+
+        app.visible_hours = Some("08:00-22:00".to_string());
+        assert!(app.in_visible_hours(time("08:00")));
+        assert!(app.in_visible_hours(time("21:59")));
+        assert!(!app.in_visible_hours(time("22:00")));
+        assert!(!app.in_visible_hours(time("03:00")));
+
+        // Wrapping past midnight
+        app.visible_hours = Some("22:00-06:00".to_string());
+        assert!(app.in_visible_hours(time("23:30")));
+        assert!(app.in_visible_hours(time("05:00")));
+        assert!(!app.in_visible_hours(time("12:00")));
+
+        // Invalid schedules never hide the tile
+        app.visible_hours = Some("whenever".to_string());
+        assert!(app.in_visible_hours(time("12:00")));
+    }
+
+    #[test]
+    fn test_next_visibility_change() {
+        let now = Local
+            .from_local_datetime(
+                &chrono::NaiveDate::from_ymd_opt(2025, 6, 1)
+                    .unwrap()
+                    .and_time(time("12:00")),
+            )
+            .unwrap();
+        let app = |hours: Option<&str>| AppDefinition {
+            visible_hours: hours.map(str::to_string),
+            ..Default::default()
+        };
+        assert_eq!(next_visibility_change(&[app(None)], now), None);
+
+        let apps = [app(Some("08:00-22:00")), app(Some("13:00-14:00"))];
+        let next = next_visibility_change(&apps, now).unwrap();
+        assert_eq!(next.time(), time("13:00"));
+        assert_eq!(next.date_naive(), now.date_naive());
+
+        // Boundaries already passed today roll over to tomorrow
+        let next = next_visibility_change(&[app(Some("06:00-10:00"))], now).unwrap();
+        assert_eq!(next.time(), time("06:00"));
+        assert_eq!(next.date_naive(), now.date_naive().succ_opt().unwrap());
+    }
+
+    #[test]
+    fn test_window_closes() {
+        let at = |hhmm: &str| {
+            Local
+                .from_local_datetime(
+                    &chrono::NaiveDate::from_ymd_opt(2025, 6, 1)
+                        .unwrap()
+                        .and_time(time(hhmm)),
+                )
+                .unwrap()
+        };
+        let windows = parse_visible_hours("02:00-04:00, 23:30-00:30").unwrap();
+
+        assert_eq!(window_closes(&windows, at("12:00")), None);
+        assert_eq!(window_closes(&windows, at("03:00")), Some(at("04:00")));
+        assert_eq!(window_closes(&windows, at("04:00")), None);
+
+        // A window wrapping past midnight closes tomorrow
+        let closes = window_closes(&windows, at("23:45")).unwrap();
+        assert_eq!(closes.time(), time("00:30"));
+        assert_eq!(
+            closes.date_naive(),
+            at("23:45").date_naive().succ_opt().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_enforce_https() {
+        let app = |name: &str, url: &str, container: Option<&str>| AppDefinition {
+            name: name.to_string(),
+            url: url.to_string(),
+            app_type: AppType {
+                container_name: container.map(str::to_string),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let apps = vec![
+            app("Secure", "https://secure.local", None),
+            app("Cockpit", "/cockpit/", None),
+            app("Grafana", "http://grafana.local:3000/d", Some("grafana")),
+            app("Legacy", "http://legacy.local", Some("legacy")),
+            app("Plotter", "http://plotter.local", None),
+        ];
+        let exceptions = vec!["legacy".to_string(), "plotter".to_string()];
+
+        let rejected = enforce_https(apps.clone(), false, &exceptions);
+        let names: Vec<&str> = rejected.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["Secure", "Cockpit", "Legacy", "Plotter"]);
+
+        let upgraded = enforce_https(apps, true, &[]);
+        let urls: Vec<&str> = upgraded.iter().map(|a| a.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://secure.local",
+                "/cockpit/",
+                "https://grafana.local:3000/d",
+                "https://legacy.local/",
+                "https://plotter.local/"
+            ]
+        );
+    }
 }
@@ -0,0 +1,205 @@
+//! `self-test` command: catch packaging breakage during image builds
+//!
+//! Needs neither Docker nor Homarr. Parses the example config shipped in the
+//! package, the installed branding file and app registry, and built-in label
+//! fixtures, checking the tiles they produce. With `--api` it also probes
+//! the configured Homarr's endpoints (as `api-check` does), for builds that
+//! start one.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::branding::BrandingConfig;
+use crate::config::Config;
+use crate::docker::{self, LabelScheme};
+use crate::registry;
+
+/// Example config shipped in the package
+const EXAMPLE_CONFIG: &str = include_str!("../etc/config.toml");
+
+/// Minimal branding file, parsed to check the branding parser itself
+const BRANDING_FIXTURE: &str = r##"
+[identity]
+product_name = "HaLOS"
+
+[theme]
+default_color_scheme = "dark"
+primary_color = "#0066cc"
+secondary_color = "#ff6600"
+
+[credentials]
+admin_username = "admin"
+admin_password = "self-test"
+
+[board]
+name = "halos"
+display_name = "HaLOS"
+column_count = 12
+is_public = true
+
+[settings.analytics]
+enable_general = false
+enable_widget_data = false
+enable_integration_data = false
+enable_user_data = false
+
+[settings.crawling]
+no_index = true
+no_follow = true
+no_translate = true
+no_sitelinks_search_box = true
+"##;
+
+/// Label or tile pairs: (label, value) or (name, URL)
+type Pairs = &'static [(&'static str, &'static str)];
+
+/// Container labels and the (name, URL) of each tile they should produce
+const LABEL_FIXTURES: &[(&str, Pairs, Pairs)] = &[
+    (
+        "grafana",
+        &[
+            ("homarr.enable", "true"),
+            ("homarr.name", "Grafana"),
+            ("homarr.url", "HTTP://Halos.local:3000/"),
+        ],
+        &[("Grafana", "http://halos.local:3000")],
+    ),
+    (
+        "proxy",
+        &[
+            ("homarr.enable", "true"),
+            ("homarr.icon", "https://example.com/proxy.png"),
+            ("homarr.url", "http://halos.local:81"),
+            ("homarr.1.name", "Proxy Admin"),
+            ("homarr.1.url", "http://halos.local:81/admin"),
+        ],
+        &[
+            ("proxy", "http://halos.local:81"),
+            ("Proxy Admin", "http://halos.local:81/admin"),
+        ],
+    ),
+    (
+        "db",
+        &[("homarr.enable", "true"), ("homarr.url", "ftp://db")],
+        &[],
+    ),
+    (
+        "jellyfin",
+        &[
+            ("homepage.name", "Jellyfin"),
+            ("homepage.href", "http://halos.local:8096"),
+        ],
+        &[("Jellyfin", "http://halos.local:8096")],
+    ),
+];
+
+/// Outcome of one self-test step
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, result: Result<String, String>) -> Self {
+        let (ok, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(detail) => (false, detail),
+        };
+        Self { name, ok, detail }
+    }
+}
+
+/// Run the offline checks against the loaded config
+pub fn run(config: &Config) -> Vec<Check> {
+    vec![
+        Check::new("example config", check_example_config()),
+        Check::new("branding parser", check_branding_fixture()),
+        Check::new("branding file", check_branding_file(&config.branding_file)),
+        Check::new("label parsing", check_label_fixtures()),
+        Check::new("app registry", check_registry(&config.registry_dir)),
+    ]
+}
+
+fn check_example_config() -> Result<String, String> {
+    toml::from_str::<Config>(EXAMPLE_CONFIG)
+        .map(|_| "etc/config.toml parses".to_string())
+        .map_err(|e| e.to_string())
+}
+
+fn check_branding_fixture() -> Result<String, String> {
+    let branding: BrandingConfig = toml::from_str(BRANDING_FIXTURE).map_err(|e| e.to_string())?;
+    match branding.board.name.as_str() {
+        "halos" => Ok("built-in branding parses".to_string()),
+        other => Err(format!("board name parsed as '{}'", other)),
+    }
+}
+
+/// The installed branding file, if there is one (packages built without it
+/// install it at deploy time)
+fn check_branding_file(path: &str) -> Result<String, String> {
+    if !Path::new(path).exists() {
+        return Ok(format!("{} not installed, skipped", path));
+    }
+    BrandingConfig::load(path)
+        .map(|branding| format!("{} (board '{}')", path, branding.board.name))
+        .map_err(|e| e.to_string())
+}
+
+fn check_label_fixtures() -> Result<String, String> {
+    for (container, labels, expected) in LABEL_FIXTURES {
+        let mut labels: HashMap<String, String> = labels
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        docker::translate_labels(&mut labels, &[LabelScheme::Homepage]);
+        let tiles: Vec<(String, String)> = docker::apps_from_labels(container, &labels)
+            .into_iter()
+            .map(|app| (app.name, app.url))
+            .collect();
+        let expected: Vec<(String, String)> = expected
+            .iter()
+            .map(|(name, url)| (name.to_string(), url.to_string()))
+            .collect();
+        if tiles != expected {
+            return Err(format!(
+                "container '{}' gave {:?}, expected {:?}",
+                container, tiles, expected
+            ));
+        }
+    }
+    Ok(format!("{} fixtures", LABEL_FIXTURES.len()))
+}
+
+fn check_registry(dir: &str) -> Result<String, String> {
+    registry::load_all_apps(dir)
+        .map(|apps| format!("{} app(s) in {}", apps.len(), dir))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_test_passes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = Config {
+            branding_file: dir.path().join("branding.toml").display().to_string(),
+            registry_dir: dir.path().join("webapps.d").display().to_string(),
+            ..Config::default()
+        };
+        for check in run(&config) {
+            assert!(check.ok, "{}: {}", check.name, check.detail);
+        }
+
+        std::fs::write(&config.branding_file, "[identity]\n").unwrap();
+        let failed: Vec<&str> = run(&config)
+            .iter()
+            .filter(|c| !c.ok)
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(failed, vec!["branding file"]);
+    }
+}
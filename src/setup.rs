@@ -0,0 +1,435 @@
+//! First-boot setup pipeline
+//!
+//! Setup runs as a fixed sequence of idempotent stages. A stage that fails
+//! for a transient reason (Homarr unreachable or erroring) is retried on its
+//! own without repeating the stages before it. Progress is logged and
+//! published on the control interface's event stream, and Ctrl-C cancels
+//! the pipeline, leaving first-boot setup incomplete. Before the first
+//! stage, setup waits for Homarr to come up, as it usually starts alongside
+//! the adapter and migrates its database first.
+
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+use crate::branding::BrandingConfig;
+use crate::config::Config;
+use crate::error::{AdapterError, Result};
+use crate::grpc::{self, proto::EventKind};
+use crate::homarr::HomarrClient;
+use crate::state::State;
+use crate::{authenticate_read_only, ensure_authenticated, homarr_client, keyring, upload_file};
+
+/// Attempts per stage before setup gives up
+const STAGE_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry of a stage, doubled for each further retry
+const STAGE_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// How often to check whether Homarr is ready
+const READY_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// One step of first-boot setup, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Use the stored API key, or rotate the bootstrap key
+    Authenticate,
+    /// Finish Homarr's onboarding wizard (skipped for existing instances)
+    Onboarding,
+    /// Find or create the default board
+    Board,
+    /// Sections, board branding settings and search engines
+    Branding,
+    /// Apps and widgets from the branding's board template
+    Template,
+    /// Home board and color scheme
+    HomeBoard,
+    /// Record setup as complete
+    Finish,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 7] = [
+        Stage::Authenticate,
+        Stage::Onboarding,
+        Stage::Board,
+        Stage::Branding,
+        Stage::Template,
+        Stage::HomeBoard,
+        Stage::Finish,
+    ];
+
+    /// What the stage does, for progress messages
+    pub fn description(self) -> &'static str {
+        match self {
+            Stage::Authenticate => "Authenticating with Homarr",
+            Stage::Onboarding => "Completing onboarding",
+            Stage::Board => "Creating the dashboard board",
+            Stage::Branding => "Applying sections, branding and search engines",
+            Stage::Template => "Placing template apps and widgets",
+            Stage::HomeBoard => "Setting the home board and color scheme",
+            Stage::Finish => "Saving setup state",
+        }
+    }
+}
+
+/// Progress message for a stage, e.g. "step 3/7: Creating the dashboard board"
+fn progress_message(stage: Stage) -> String {
+    let step = Stage::ALL.iter().position(|s| *s == stage).unwrap_or(0) + 1;
+    format!(
+        "step {}/{}: {}",
+        step,
+        Stage::ALL.len(),
+        stage.description()
+    )
+}
+
+/// Whether a failed stage is worth retrying
+///
+/// Configuration problems won't fix themselves; Homarr not answering or
+/// rejecting a request during its own startup may.
+fn is_transient(error: &AdapterError) -> bool {
+    matches!(error, AdapterError::Http(_) | AdapterError::HomarrApi(_))
+}
+
+/// What the stages share
+struct Pipeline<'a> {
+    config: &'a Config,
+    branding: BrandingConfig,
+    client: HomarrClient,
+    state: State,
+    board_id: Option<String>,
+}
+
+impl Pipeline<'_> {
+    async fn run_stage(&mut self, stage: Stage) -> Result<()> {
+        match stage {
+            Stage::Authenticate => {
+                ensure_authenticated(&mut self.client, self.config, &mut self.state).await
+            }
+            Stage::Onboarding => {
+                if self.config.existing_instance {
+                    info!("Existing Homarr instance, skipping onboarding");
+                    return Ok(());
+                }
+                // Should already be complete from the seed database
+                let step = self.client.get_onboarding_step().await?;
+                info!("Current onboarding step: {:?}", step);
+                if step.current != "finish" {
+                    info!("Completing onboarding");
+                    self.client.complete_onboarding(&self.branding).await?;
+                }
+                Ok(())
+            }
+            Stage::Board => {
+                let board_id = self.client.ensure_default_board(&self.branding).await?;
+                self.board_id = Some(board_id);
+                Ok(())
+            }
+            Stage::Branding => {
+                self.upload_branding_images().await;
+                tokio::try_join!(
+                    self.client
+                        .brand_default_board(&self.branding, self.board_id()),
+                    self.client.configure_search(&self.branding.search),
+                )?;
+                Ok(())
+            }
+            Stage::Template => self.client.apply_board_template(&self.branding).await,
+            Stage::HomeBoard => {
+                self.client
+                    .make_home_board(&self.branding, self.board_id())
+                    .await
+            }
+            Stage::Finish => {
+                self.state.first_boot_completed = true;
+                self.state.save(&self.config.state_file)
+            }
+        }
+    }
+
+    /// Upload the branding's local logo, favicon and background image, to
+    /// be set as the board's in place of the configured URLs
+    ///
+    /// A failed upload leaves the configured URL (or Homarr's own) in place.
+    async fn upload_branding_images(&mut self) {
+        let theme = &mut self.branding.theme;
+        let identity = &mut self.branding.identity;
+        for (path, url) in [
+            (&theme.logo_path, &mut identity.logo_image_url),
+            (&theme.favicon_path, &mut identity.favicon_image_url),
+            (
+                &theme.background_image_path,
+                &mut theme.background_image_url,
+            ),
+        ] {
+            let Some(path) = path else {
+                continue;
+            };
+            let key = format!("file://{}", path.display());
+            if let Some(media_url) = upload_file(&self.client, &mut self.state, &key, path).await {
+                *url = Some(media_url);
+            }
+        }
+    }
+
+    fn board_id(&self) -> &str {
+        self.board_id.as_deref().unwrap_or_default()
+    }
+
+    /// Run every stage in order, retrying transient failures per stage
+    async fn run(&mut self, hub: Option<&grpc::ControlHub>) -> Result<()> {
+        for stage in Stage::ALL {
+            let message = progress_message(stage);
+            info!("Setting up dashboard: {}", message);
+            if let Some(hub) = hub {
+                hub.publish(EventKind::SetupProgress, message);
+            }
+
+            let mut attempt = 1;
+            loop {
+                match self.run_stage(stage).await {
+                    Ok(()) => break,
+                    Err(e) if attempt < STAGE_ATTEMPTS && is_transient(&e) => {
+                        let delay = STAGE_RETRY_DELAY * 2u32.pow(attempt - 1);
+                        warn!(
+                            "{} failed ({}), retrying in {}s",
+                            stage.description(),
+                            e,
+                            delay.as_secs()
+                        );
+                        tokio::time::sleep(delay).await;
+                        attempt += 1;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Run first-boot setup, publishing progress to `hub` when given
+pub async fn run(config: &Config, hub: Option<&grpc::ControlHub>) -> Result<()> {
+    // Load branding config; the keyring is only asked for the admin
+    // password when branding has none
+    let mut branding = BrandingConfig::load(&config.branding_file)?;
+    if branding.credentials.admin_password.is_empty() {
+        if let Some(password) = config.credential_store.get(keyring::ADMIN_PASSWORD)? {
+            branding.credentials.admin_password = password;
+        }
+    }
+    if branding.credentials.admin_password.is_empty() && !config.existing_instance {
+        return Err(AdapterError::Config(format!(
+            "No admin password for '{}': set admin_password or admin_password_file \
+             in branding's [credentials], or store it in the keyring",
+            branding.credentials.admin_username
+        )));
+    }
+
+    let mut pipeline = Pipeline {
+        config,
+        branding,
+        client: homarr_client(config)?,
+        state: State::load(&config.state_file).unwrap_or_default(),
+        board_id: None,
+    };
+
+    let ready_timeout = Duration::from_secs(config.homarr_ready_timeout);
+    tokio::select! {
+        result = async {
+            wait_for_homarr(&pipeline.client, ready_timeout, hub).await?;
+            pipeline.run(hub).await
+        } => result?,
+        _ = tokio::signal::ctrl_c() => {
+            warn!("First-boot setup interrupted");
+            return Err(AdapterError::Cancelled);
+        }
+    }
+
+    info!("First-boot setup complete");
+    Ok(())
+}
+
+/// Wait for Homarr to be ready, for up to `timeout` (zero: don't wait)
+async fn wait_for_homarr(
+    client: &HomarrClient,
+    timeout: Duration,
+    hub: Option<&grpc::ControlHub>,
+) -> Result<()> {
+    if timeout.is_zero() {
+        return Ok(());
+    }
+    let started = Instant::now();
+    let mut waiting = false;
+    loop {
+        if client.is_ready().await {
+            if waiting {
+                info!("Homarr is ready after {}s", started.elapsed().as_secs());
+            }
+            return Ok(());
+        }
+        if started.elapsed() >= timeout {
+            return Err(AdapterError::HomarrNotReady(timeout.as_secs()));
+        }
+        if !waiting {
+            let message = format!("waiting for Homarr to start (up to {}s)", timeout.as_secs());
+            info!("Setting up dashboard: {}", message);
+            if let Some(hub) = hub {
+                hub.publish(EventKind::SetupProgress, message);
+            }
+            waiting = true;
+        }
+        tokio::time::sleep(READY_POLL_INTERVAL.min(timeout)).await;
+    }
+}
+
+/// What each stage would do, found out without changing anything
+/// (`setup --dry-run`)
+pub async fn plan(config: &Config) -> Result<Vec<(Stage, String)>> {
+    let branding = BrandingConfig::load(&config.branding_file)?;
+    let state = State::load(&config.state_file).unwrap_or_default();
+    let mut client = homarr_client(config)?;
+    let board = &branding.board.name;
+
+    let authenticated = authenticate_read_only(&mut client, config, &state).await;
+    let authenticate = match &authenticated {
+        Ok(how) => format!("use the {}", how),
+        Err(e) => format!("would fail: {}", e),
+    };
+    let onboarding = if config.existing_instance {
+        "skip (existing instance)".to_string()
+    } else {
+        match client.get_onboarding_step().await {
+            Ok(step) if step.current == "finish" => "nothing to do, already complete".to_string(),
+            Ok(step) => format!("complete the wizard (now at '{}')", step.current),
+            Err(e) => format!("unknown, Homarr not answering: {}", e),
+        }
+    };
+    let exists = match authenticated {
+        Ok(_) => client.has_board(board).await,
+        Err(e) => Err(e),
+    };
+    let board_stage = match (exists, branding.board.template.as_deref()) {
+        (Err(e), _) => format!("find or create board '{}', unknown which: {}", board, e),
+        (Ok(true), _) => format!("nothing to do, board '{}' exists", board),
+        (Ok(false), Some(template)) => {
+            format!("create board '{}' from template '{}'", board, template)
+        }
+        (Ok(false), None) => format!("create board '{}'", board),
+    };
+
+    Ok(vec![
+        (Stage::Authenticate, authenticate),
+        (Stage::Onboarding, onboarding),
+        (Stage::Board, board_stage),
+        (
+            Stage::Branding,
+            format!(
+                "ensure sections [{}]{} and apply board branding{}",
+                branding.board.sections.join(", "),
+                if [
+                    &branding.theme.logo_path,
+                    &branding.theme.favicon_path,
+                    &branding.theme.background_image_path,
+                ]
+                .iter()
+                .all(|path| path.is_none())
+                {
+                    ""
+                } else {
+                    ", upload branding images"
+                },
+                match branding.search.engines.len() {
+                    0 => String::new(),
+                    n => format!(", set up {} search engine(s)", n),
+                }
+            ),
+        ),
+        (
+            Stage::Template,
+            if branding.board.apps.is_empty() && branding.board.widgets.is_empty() {
+                "nothing to do, no template apps or widgets".to_string()
+            } else {
+                format!(
+                    "place {} app(s) and {} widget(s) not yet on the board",
+                    branding.board.apps.len(),
+                    branding.board.widgets.len()
+                )
+            },
+        ),
+        (
+            Stage::HomeBoard,
+            format!(
+                "make '{}' the home board, color scheme '{}'",
+                board, branding.theme.default_color_scheme
+            ),
+        ),
+        (
+            Stage::Finish,
+            format!("record setup as complete in {}", config.state_file),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_message_numbers_stages() {
+        assert_eq!(
+            progress_message(Stage::Authenticate),
+            "step 1/7: Authenticating with Homarr"
+        );
+        assert_eq!(
+            progress_message(Stage::Board),
+            "step 3/7: Creating the dashboard board"
+        );
+        assert_eq!(
+            progress_message(Stage::Finish),
+            "step 7/7: Saving setup state"
+        );
+    }
+
+    /// Serve one canned HTTP response per connection
+    fn serve(response: &'static str) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            use std::io::{Read, Write};
+            for mut stream in listener.incoming().flatten() {
+                let _ = stream.read(&mut [0u8; 4096]);
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        format!("http://127.0.0.1:{}", port)
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_homarr() {
+        let ready = serve("HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+        let client = HomarrClient::new(&ready).unwrap();
+        wait_for_homarr(&client, Duration::from_secs(1), None)
+            .await
+            .unwrap();
+
+        let starting = serve("HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n");
+        let client = HomarrClient::new(&starting).unwrap();
+        assert!(matches!(
+            wait_for_homarr(&client, Duration::from_millis(100), None).await,
+            Err(AdapterError::HomarrNotReady(_))
+        ));
+        // No waiting configured
+        wait_for_homarr(&client, Duration::ZERO, None)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_only_homarr_failures_are_retried() {
+        assert!(is_transient(&AdapterError::HomarrApi("busy".to_string())));
+        assert!(!is_transient(&AdapterError::Config("no key".to_string())));
+        assert!(!is_transient(&AdapterError::Cancelled));
+    }
+}
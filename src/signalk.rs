@@ -199,6 +199,7 @@ async fn discover_webapps_inner(
             app_type: AppType {
                 container_name: None,
                 external: false,
+                compose_project: None,
             },
             ping_url: Some(build_ping_url(&location)),
             layout: LayoutConfig {
@@ -207,7 +208,9 @@ async fn discover_webapps_inner(
                 height: 1,
                 x_offset: None,
                 y_offset: None,
+                section: None,
             },
+            ..Default::default()
         });
     }
 
@@ -366,6 +369,7 @@ mod tests {
                 app_type: AppType {
                     container_name: None,
                     external: false,
+                    compose_project: None,
                 },
                 ping_url: Some(build_ping_url(&location)),
                 layout: LayoutConfig {
@@ -374,7 +378,9 @@ mod tests {
                     height: 1,
                     x_offset: None,
                     y_offset: None,
+                    section: None,
                 },
+                ..Default::default()
             });
         }
 
@@ -0,0 +1,229 @@
+//! Tamper evidence for the state file and the changelog
+//!
+//! With a signing key configured (`signing_key_file`, or the systemd
+//! credential named by `signing_key_credential`), each state save writes an
+//! HMAC-SHA256 of the file to `<state file>.sig`, and each changelog line
+//! ends with an HMAC chained over the lines before it, so edited, inserted or
+//! reordered lines are caught. Signatures are checked when the files are
+//! read and by the `verify` command. A mismatch is reported but doesn't stop
+//! the adapter: the point is that tampering shows, not that it is prevented.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::state;
+
+/// Separates a changelog entry from its signature
+const LINE_MAC: &str = "\thmac=";
+
+/// Key for signatures, set once at startup when signing is configured
+static SIGNING_KEY: OnceLock<Vec<u8>> = OnceLock::new();
+
+/// Outcome of checking a file's signatures
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Valid,
+    /// Written before signing was enabled (signed from the next write on)
+    Unsigned,
+    /// Changed outside the adapter; says where
+    Tampered(String),
+}
+
+/// Enable signing if a key file or systemd credential is configured
+pub fn init(config: &Config) -> Result<()> {
+    let Some(path) = state::key_path(
+        config.signing_key_file.as_deref(),
+        config.signing_key_credential.as_deref(),
+        "signing_key_credential",
+    )?
+    else {
+        return Ok(());
+    };
+
+    let key = state::read_key(&path)?;
+    let _ = SIGNING_KEY.set(key.to_vec());
+    tracing::debug!("State and changelog signing enabled");
+    Ok(())
+}
+
+/// Whether a signing key is configured
+pub fn enabled() -> bool {
+    SIGNING_KEY.get().is_some()
+}
+
+/// Hex HMAC-SHA256 of `parts` in order
+fn mac(key: &[u8], parts: &[&[u8]]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Where a file's detached signature is kept
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut sig = path.as_os_str().to_owned();
+    sig.push(".sig");
+    PathBuf::from(sig)
+}
+
+/// Write the detached signature of a file just saved with `contents`
+pub fn write_signature(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(key) = SIGNING_KEY.get() {
+        crate::state::write_atomic(&signature_path(path), mac(key, &[contents]).as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Check a file read as `contents` against its detached signature
+pub fn check_file(path: &Path, contents: &[u8]) -> Option<Verdict> {
+    let key = SIGNING_KEY.get()?;
+    Some(check_file_with(key, path, contents))
+}
+
+fn check_file_with(key: &[u8], path: &Path, contents: &[u8]) -> Verdict {
+    match fs::read_to_string(signature_path(path)) {
+        Err(_) => Verdict::Unsigned,
+        Ok(sig) if sig.trim() == mac(key, &[contents]) => Verdict::Valid,
+        Ok(_) => Verdict::Tampered("contents don't match the signature".to_string()),
+    }
+}
+
+/// Sign changelog entries appended after `previous`, the log's last line
+///
+/// Lines are returned unchanged when signing is off.
+pub fn sign_lines(previous: &str, entries: &[String]) -> Vec<String> {
+    match SIGNING_KEY.get() {
+        Some(key) => sign_lines_with(key, previous, entries),
+        None => entries.to_vec(),
+    }
+}
+
+/// What the next line's signature is chained to: the previous line's
+/// signature, or the whole line if it has none (the header, or lines from
+/// before signing was enabled)
+fn chain_link(line: &str) -> &str {
+    line.rsplit_once(LINE_MAC)
+        .map_or(line, |(_, signature)| signature)
+}
+
+fn sign_lines_with(key: &[u8], previous: &str, entries: &[String]) -> Vec<String> {
+    let mut link = chain_link(previous).to_string();
+    entries
+        .iter()
+        .map(|entry| {
+            link = mac(key, &[link.as_bytes(), b"\n", entry.as_bytes()]);
+            format!("{}{}{}", entry, LINE_MAC, link)
+        })
+        .collect()
+}
+
+/// Check the chained line signatures of a changelog
+pub fn check_log(log: &str) -> Option<Verdict> {
+    let key = SIGNING_KEY.get()?;
+    Some(check_log_with(key, log))
+}
+
+fn check_log_with(key: &[u8], log: &str) -> Verdict {
+    let mut lines = log.lines().enumerate();
+    let Some((_, header)) = lines.next() else {
+        return Verdict::Unsigned;
+    };
+    let mut link = chain_link(header).to_string();
+    let mut signed = false;
+    let mut entries = false;
+    for (index, line) in lines {
+        entries = true;
+        match line.rsplit_once(LINE_MAC) {
+            Some((entry, signature)) => {
+                if mac(key, &[link.as_bytes(), b"\n", entry.as_bytes()]) != signature {
+                    return Verdict::Tampered(format!("line {} doesn't match", index + 1));
+                }
+                signed = true;
+                link = signature.to_string();
+            }
+            // Once signing is on, every line is signed
+            None if signed => {
+                return Verdict::Tampered(format!("line {} is not signed", index + 1));
+            }
+            None => link = line.to_string(),
+        }
+    }
+    // A log without entries (a boot without changes) has nothing to sign
+    if signed || !entries {
+        Verdict::Valid
+    } else {
+        Verdict::Unsigned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const KEY: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn test_file_signature() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("state.json");
+        assert_eq!(check_file_with(KEY, &path, b"{}"), Verdict::Unsigned);
+
+        fs::write(signature_path(&path), mac(KEY, &[b"{}"])).unwrap();
+        assert_eq!(check_file_with(KEY, &path, b"{}"), Verdict::Valid);
+        assert!(matches!(
+            check_file_with(KEY, &path, b"{\"api_key\": \"x\"}"),
+            Verdict::Tampered(_)
+        ));
+        assert!(matches!(
+            check_file_with(b"another key", &path, b"{}"),
+            Verdict::Tampered(_)
+        ));
+    }
+
+    #[test]
+    fn test_log_signature_chain() {
+        let header = "# changes, boot a";
+        let unsigned = "2026-10-15 10:00:00 placed Grafana (new app)".to_string();
+        let first = sign_lines_with(KEY, &unsigned, &["hid InfluxDB (stopped)".to_string()]);
+        let more = sign_lines_with(
+            KEY,
+            &first[0],
+            &["placed AvNav (new app)".to_string(), "x".to_string()],
+        );
+        let log = [vec![header.to_string(), unsigned], first, more].concat();
+        let text = log.join("\n");
+        assert_eq!(check_log_with(KEY, &text), Verdict::Valid);
+        assert_eq!(check_log_with(KEY, header), Verdict::Valid);
+        assert_eq!(
+            check_log_with(KEY, &format!("{}\n{}", header, log[1])),
+            Verdict::Unsigned
+        );
+
+        // Edited, dropped and appended lines break the chain
+        let edited = text.replace("hid InfluxDB", "hid Grafana");
+        assert!(matches!(check_log_with(KEY, &edited), Verdict::Tampered(_)));
+        let mut dropped = log.clone();
+        dropped.remove(3);
+        assert!(matches!(
+            check_log_with(KEY, &dropped.join("\n")),
+            Verdict::Tampered(_)
+        ));
+        let appended = format!("{}\nremoved Signal K (gone)", text);
+        assert_eq!(
+            check_log_with(KEY, &appended),
+            Verdict::Tampered("line 6 is not signed".to_string())
+        );
+    }
+}
@@ -1,12 +1,27 @@
 //! Adapter state persistence
+//!
+//! State is kept by a [`StateStore`]: a JSON file by default (optionally
+//! encrypted and signed), or an SQLite database with `state_backend =
+//! "sqlite"`. The backend is chosen once at startup; `State::load` and
+//! `State::save` go through it. The JSON file is replaced atomically, and a
+//! sync holds a [`SyncLock`] so concurrent syncs don't interleave writes.
 
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
+use crate::config::Config;
 use crate::error::{AdapterError, Result};
+use crate::registry::AppDefinition;
 
 /// Persistent state for the adapter
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -34,9 +49,210 @@ pub struct State {
     #[serde(default)]
     pub last_sync: Option<DateTime<Utc>>,
 
+    /// Time the last completed sync spent in each phase
+    #[serde(default)]
+    pub last_sync_timings: Option<SyncTimings>,
+
     /// Discovered apps and when they were added
     #[serde(default)]
     pub discovered_apps: std::collections::HashMap<String, DiscoveredApp>,
+
+    /// One-off apps added with `apps add`, synced alongside registry apps
+    #[serde(default)]
+    pub manual_apps: Vec<AppDefinition>,
+
+    /// Number of sync cycles that have failed in a row
+    #[serde(default)]
+    pub consecutive_sync_failures: u32,
+
+    /// Fallback icons for apps whose configured icon no longer resolves
+    /// Key: app URL, Value: icon URL used instead
+    #[serde(default)]
+    pub icon_overrides: HashMap<String, String>,
+
+    /// IDs of compose project and category sections the adapter created
+    /// Key: board ID, then lowercased section name
+    #[serde(default)]
+    pub section_ids: HashMap<String, HashMap<String, String>>,
+
+    /// When icons were last re-validated
+    #[serde(default)]
+    pub icons_checked_at: Option<DateTime<Utc>>,
+
+    /// `file://` icons and branding images uploaded to Homarr's media store
+    /// Key: configured icon or image path (`file://...`)
+    #[serde(default)]
+    pub uploaded_icons: HashMap<String, UploadedIcon>,
+
+    /// Board items of hidden apps (`hide = true`), restored when unhidden
+    /// Key: board_id, Value: app URL -> the removed board items
+    #[serde(default)]
+    pub hidden_tiles: HashMap<String, HashMap<String, Vec<serde_json::Value>>>,
+
+    /// When the next `visible_hours` window opens or closes (watch mode
+    /// syncs then)
+    #[serde(default)]
+    pub next_visibility_change: Option<DateTime<Utc>>,
+
+    /// Homarr changes that failed while Homarr was unreachable, applied in
+    /// order on the next sync
+    #[serde(default)]
+    pub pending_changes: Vec<PendingChange>,
+
+    /// How labelled containers last stopped (watch mode `die` events)
+    /// Key: container name; cleared when the container starts again
+    #[serde(default)]
+    pub container_stops: HashMap<String, ContainerStop>,
+
+    /// Homarr version detected at the last authentication, if known
+    #[serde(default)]
+    pub homarr_version: Option<String>,
+
+    /// The last sync couldn't open the Docker socket for lack of permission
+    #[serde(default)]
+    pub docker_permission_denied: bool,
+
+    /// Container apps whose container disappeared, and what was done
+    /// Key: app URL; cleared when the container is back
+    #[serde(default)]
+    pub vanished_apps: HashMap<String, VanishedApp>,
+
+    /// URLs of apps pinned with `apps pin`: created once, then left alone
+    #[serde(default)]
+    pub pinned_apps: HashSet<String>,
+
+    /// Homarr answered 429 with a long `Retry-After`: syncs pause until then
+    #[serde(default)]
+    pub rate_limited_until: Option<DateTime<Utc>>,
+
+    /// Rate-limited (429) responses from Homarr, in total
+    #[serde(default)]
+    pub rate_limited_responses: u64,
+}
+
+/// Time a sync spent in each phase, in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncTimings {
+    /// Registry, Signal K and container discovery
+    pub discovery_ms: u64,
+    /// Homarr reads, queued changes, removals, icons and board creation
+    pub planning_ms: u64,
+    /// Creating, updating and placing apps
+    pub apps_ms: u64,
+    /// Saving the staged board edits
+    pub board_save_ms: u64,
+    /// The whole sync, including authentication
+    pub total_ms: u64,
+}
+
+impl SyncTimings {
+    /// One-line breakdown for logs and `status`
+    pub fn summary(&self) -> String {
+        format!(
+            "discovery {} ms, planning {} ms, apps {} ms, board save {} ms (total {} ms)",
+            self.discovery_ms, self.planning_ms, self.apps_ms, self.board_save_ms, self.total_ms
+        )
+    }
+}
+
+/// What a sync does with the tiles of a container that is gone
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemovalPolicy {
+    /// Leave the tiles in place
+    #[default]
+    Keep,
+    /// Take the tiles off the boards until the container is back
+    Hide,
+    /// Delete the app from Homarr
+    Remove,
+}
+
+impl RemovalPolicy {
+    /// What was done to an app under this policy, for messages
+    pub fn outcome(self) -> &'static str {
+        match self {
+            RemovalPolicy::Keep => "kept",
+            RemovalPolicy::Hide => "hidden",
+            RemovalPolicy::Remove => "removed",
+        }
+    }
+}
+
+/// A container app whose container disappeared
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VanishedApp {
+    pub name: String,
+    /// Container the app came from
+    pub container: String,
+    /// What the sync did about it
+    pub action: RemovalPolicy,
+    /// When the sync noticed
+    pub at: DateTime<Utc>,
+}
+
+/// Exit status of a labelled container that stopped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContainerStop {
+    /// Exit code, if Docker reported one
+    pub exit_code: Option<i64>,
+    /// Killed by the kernel for running out of memory
+    #[serde(default)]
+    pub oom_killed: bool,
+    /// When the container died
+    pub at: DateTime<Utc>,
+}
+
+impl ContainerStop {
+    /// Why the container stopped, telling crashes from intentional stops
+    pub fn reason(&self) -> String {
+        if self.oom_killed {
+            return "killed by the OOM killer".to_string();
+        }
+        match self.exit_code {
+            Some(0) => "exited normally (exit 0)".to_string(),
+            Some(143) => "stopped (SIGTERM, exit 143)".to_string(),
+            Some(137) => "killed (SIGKILL, exit 137)".to_string(),
+            Some(code) => format!("crashed (exit {})", code),
+            None => "stopped (exit code unknown)".to_string(),
+        }
+    }
+}
+
+/// A Homarr change queued while Homarr was unreachable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PendingChange {
+    /// Create or update an app and place it on a board
+    Upsert {
+        board: String,
+        app: Box<AppDefinition>,
+    },
+    /// Delete an app, optionally marking it removed from every board so
+    /// syncs don't add it back
+    Delete {
+        url: String,
+        name: String,
+        #[serde(default)]
+        remove_from_boards: bool,
+    },
+}
+
+impl PendingChange {
+    /// URL of the app the change is about
+    pub fn url(&self) -> &str {
+        match self {
+            PendingChange::Upsert { app, .. } => &app.url,
+            PendingChange::Delete { url, .. } => url,
+        }
+    }
+}
+
+/// App URLs in the form `homarr::normalize_url` compares them in
+fn normalized_urls(urls: &HashSet<&str>) -> HashSet<String> {
+    urls.iter()
+        .map(|url| crate::homarr::normalize_url(url))
+        .collect()
 }
 
 fn default_version() -> String {
@@ -50,52 +266,582 @@ fn default_version() -> String {
 pub struct DiscoveredApp {
     pub name: String,
     pub container_id: String,
+    /// When a sync first saw the app
     pub added_at: DateTime<Utc>,
+    /// When a sync last found the app among the desired apps (unset in
+    /// state files from before it was tracked)
+    #[serde(default)]
+    pub last_seen: Option<DateTime<Utc>>,
+    /// Where the app came from
+    #[serde(default)]
+    pub source: AppSource,
+    /// Pinned by its labels or registry entry (`homarr.managed=false`)
+    #[serde(default)]
+    pub pinned: bool,
 }
 
-impl State {
-    /// Load state from file, returning default if file doesn't exist
-    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let path = path.as_ref();
+impl DiscoveredApp {
+    /// When a sync last saw the app, falling back to when it was added
+    pub fn last_seen(&self) -> DateTime<Utc> {
+        self.last_seen.unwrap_or(self.added_at)
+    }
+}
+
+/// Where a synced app comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AppSource {
+    /// Recorded before sources were tracked
+    #[default]
+    Unknown,
+    /// Labels of a container (or Kubernetes pod)
+    Container,
+    /// A file in the app registry directory
+    Registry,
+    /// A Signal K webapp
+    SignalK,
+    /// Added with `apps add`
+    Manual,
+    /// Listed in `[[apps]]` of the adapter config
+    Config,
+}
+
+impl AppSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AppSource::Unknown => "unknown",
+            AppSource::Container => "container",
+            AppSource::Registry => "registry",
+            AppSource::SignalK => "signalk",
+            AppSource::Manual => "manual",
+            AppSource::Config => "config",
+        }
+    }
+}
+
+/// A local icon file uploaded to Homarr, re-uploaded when the file changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadedIcon {
+    /// Homarr media URL serving the file
+    pub media_url: String,
+    /// File size at upload time
+    pub size: u64,
+    /// File modification time at upload time
+    pub modified: DateTime<Utc>,
+}
+
+/// Header of an encrypted state file, followed by the nonce and ciphertext
+const ENCRYPTED_HEADER: &[u8] = b"HCA-STATE-ENC1\n";
+
+/// Nonce length of ChaCha20-Poly1305
+const NONCE_LEN: usize = 12;
+
+/// Key for the state file, set once at startup when encryption is configured
+static STATE_KEY: OnceLock<Key> = OnceLock::new();
+
+/// Enable state encryption if a key file or systemd credential is configured
+///
+/// Existing plaintext state is still read, and encrypted on the next save.
+pub fn init_encryption(config: &Config) -> Result<()> {
+    let Some(path) = key_path(
+        config.state_key_file.as_deref(),
+        config.state_key_credential.as_deref(),
+        "state_key_credential",
+    )?
+    else {
+        return Ok(());
+    };
+
+    let key = read_key(&path)?;
+    let _ = STATE_KEY.set(key);
+    tracing::debug!("State file encryption enabled");
+    Ok(())
+}
+
+/// Path of a key given as a file or as a systemd credential name (the file
+/// wins); `setting` names the credential option in errors
+pub(crate) fn key_path(
+    file: Option<&str>,
+    credential: Option<&str>,
+    setting: &str,
+) -> Result<Option<PathBuf>> {
+    match (file, credential) {
+        (Some(file), _) => Ok(Some(PathBuf::from(file))),
+        (None, Some(name)) => {
+            let dir = std::env::var_os("CREDENTIALS_DIRECTORY").ok_or_else(|| {
+                AdapterError::Config(format!(
+                    "{} '{}' is set but no systemd credentials are loaded",
+                    setting, name
+                ))
+            })?;
+            Ok(Some(PathBuf::from(dir).join(name)))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// Read a 32-byte key, stored raw or as 64 hex characters
+pub(crate) fn read_key(path: &Path) -> Result<Key> {
+    let bytes = fs::read(path)
+        .map_err(|e| AdapterError::Config(format!("Cannot read key {:?}: {}", path, e)))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if fs::metadata(path)?.permissions().mode() & 0o077 != 0 {
+            tracing::warn!("Key {:?} is readable by other users", path);
+        }
+    }
+
+    let hex = String::from_utf8_lossy(&bytes);
+    let hex = hex.trim();
+    let key: Vec<u8> = if bytes.len() == 32 {
+        bytes
+    } else if hex.len() == 64 && hex.is_ascii() {
+        (0..64)
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|_| AdapterError::Config(format!("Key {:?} is not valid hex", path)))?
+    } else {
+        return Err(AdapterError::Config(format!(
+            "Key {:?} must be 32 bytes or 64 hex characters",
+            path
+        )));
+    };
+    Ok(*Key::from_slice(&key))
+}
+
+/// Encrypt serialized state
+fn encrypt(key: &Key, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(key)
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| AdapterError::State("Failed to encrypt state".to_string()))?;
+
+    let mut data = ENCRYPTED_HEADER.to_vec();
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+    Ok(data)
+}
+
+/// Decrypt a state file body (everything after the header)
+fn decrypt(key: &Key, body: &[u8]) -> Result<Vec<u8>> {
+    if body.len() < NONCE_LEN {
+        return Err(AdapterError::State(
+            "Encrypted state is truncated".to_string(),
+        ));
+    }
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| {
+            AdapterError::State("Failed to decrypt state (wrong key or corrupted file)".to_string())
+        })
+}
+
+/// Where state is kept
+///
+/// `path` is the configured `state_file`; a backend may keep state next to
+/// it instead (see [`StateStore::file`]).
+pub trait StateStore: Send + Sync {
+    /// Read state, the default if none was saved yet
+    fn load(&self, path: &Path) -> Result<State>;
+
+    /// Replace the saved state
+    fn save(&self, state: &State, path: &Path) -> Result<()>;
+
+    /// File holding the state for `path`
+    fn file(&self, path: &Path) -> PathBuf {
+        path.to_path_buf()
+    }
+}
 
+/// Backend chosen at startup; JSON when unset
+static STORE: OnceLock<Box<dyn StateStore>> = OnceLock::new();
+
+/// Select the configured state backend
+pub fn init_backend(config: &Config) -> Result<()> {
+    match config.state_backend {
+        StateBackend::Json => {}
+        StateBackend::Sqlite => {
+            let _ = STORE.set(Box::new(crate::state_sqlite::SqliteStore));
+            tracing::debug!("Keeping state in SQLite");
+        }
+    }
+    Ok(())
+}
+
+fn store() -> &'static dyn StateStore {
+    STORE.get().map_or(&JsonStore, |store| store.as_ref())
+}
+
+/// File holding the state for the configured `state_file`
+pub fn state_file(path: &Path) -> PathBuf {
+    store().file(path)
+}
+
+/// Replace `path` with `contents` without ever leaving a partial file
+///
+/// Writes a temporary file next to it, syncs it to disk and renames it over
+/// `path`, keeping the permissions of the file it replaces. New files are
+/// readable by the owner only.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let write = || -> std::io::Result<()> {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp)?;
+        if let Ok(existing) = fs::metadata(path) {
+            file.set_permissions(existing.permissions())?;
+        }
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    };
+    write().inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+/// How often a sync waiting for the lock checks again
+const LOCK_POLL: Duration = Duration::from_millis(500);
+
+/// Lock file next to the state for `path`
+fn lock_file(path: &Path) -> PathBuf {
+    let mut lock = state_file(path).into_os_string();
+    lock.push(".lock");
+    PathBuf::from(lock)
+}
+
+/// Advisory lock held for the duration of a sync
+///
+/// An `flock` on `<state file>.lock`, so a timer sync, a manual `sync` and
+/// the watch daemon take turns instead of overwriting each other's state.
+/// Released when dropped, or by the kernel if the process dies.
+pub struct SyncLock {
+    _file: fs::File,
+}
+
+impl SyncLock {
+    /// Take the lock if no other sync holds it
+    pub fn try_acquire(path: &Path) -> Result<Option<Self>> {
+        let lock_path = lock_file(path);
+        if let Some(parent) = lock_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .mode(0o600)
+            .open(&lock_path)?;
+        // SAFETY: flock only needs an open descriptor, owned by `file`
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+            return Ok(Some(Self { _file: file }));
+        }
+        let err = std::io::Error::last_os_error();
+        if err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+            Ok(None)
+        } else {
+            Err(err.into())
+        }
+    }
+
+    /// Take the lock, waiting up to `timeout` for another sync to finish
+    pub async fn acquire(path: &Path, timeout: Duration) -> Result<Self> {
+        let deadline = Instant::now() + timeout;
+        let mut waiting = false;
+        loop {
+            if let Some(lock) = Self::try_acquire(path)? {
+                return Ok(lock);
+            }
+            if Instant::now() >= deadline {
+                return Err(AdapterError::SyncLocked(
+                    lock_file(path).display().to_string(),
+                ));
+            }
+            if !waiting {
+                tracing::info!("Another sync is running, waiting for it to finish");
+                waiting = true;
+            }
+            tokio::time::sleep(LOCK_POLL).await;
+        }
+    }
+}
+
+/// How state is kept (`state_backend`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateBackend {
+    /// One JSON file, optionally encrypted and signed
+    #[default]
+    Json,
+    /// An SQLite database next to `state_file` (`state.db`)
+    Sqlite,
+}
+
+/// The state file as JSON, encrypted when a state key is configured
+pub struct JsonStore;
+
+impl StateStore for JsonStore {
+    /// Reads both encrypted and plaintext state files.
+    fn load(&self, path: &Path) -> Result<State> {
         if !path.exists() {
-            return Ok(Self::default());
+            return Ok(State::default());
+        }
+
+        let mut contents = fs::read(path)?;
+        match crate::signing::check_file(path, &contents) {
+            Some(crate::signing::Verdict::Tampered(detail)) => tracing::error!(
+                "State file {:?} was changed outside the adapter: {}",
+                path,
+                detail
+            ),
+            Some(crate::signing::Verdict::Unsigned) => {
+                tracing::warn!("State file {:?} is not signed yet", path)
+            }
+            _ => {}
         }
+        contents = plaintext(contents)?;
 
-        let contents = fs::read_to_string(path)?;
-        let state: State = serde_json::from_str(&contents).map_err(|e| {
+        let parse_error = |e: serde_json::Error| {
             tracing::warn!("Failed to parse state file, using defaults: {}", e);
             AdapterError::State(format!("Failed to parse state: {}", e))
-        })?;
+        };
+        let mut document: serde_json::Value =
+            serde_json::from_slice(&contents).map_err(parse_error)?;
+        migrate(&mut document)?;
+        let state: State = serde_json::from_value(document).map_err(parse_error)?;
 
         Ok(state)
     }
 
-    /// Save state to file
-    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        let path = path.as_ref();
-
+    fn save(&self, state: &State, path: &Path) -> Result<()> {
         // Create parent directory if needed
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        let contents = serde_json::to_string_pretty(self)?;
-        fs::write(path, contents)?;
+        back_up_older_file(path)?;
+
+        let contents = serde_json::to_vec_pretty(&state.document()?)?;
+        let contents = match STATE_KEY.get() {
+            Some(key) => encrypt(key, &contents)?,
+            None => contents,
+        };
+        write_atomic(path, &contents)?;
+        crate::signing::write_signature(path, &contents)?;
 
         Ok(())
     }
+}
+
+/// The state file's JSON, decrypted if it's encrypted
+fn plaintext(contents: Vec<u8>) -> Result<Vec<u8>> {
+    match contents.strip_prefix(ENCRYPTED_HEADER) {
+        Some(body) => {
+            let key = STATE_KEY.get().ok_or_else(|| {
+                AdapterError::State(
+                    "State file is encrypted but no state key is configured".to_string(),
+                )
+            })?;
+            decrypt(key, body)
+        }
+        None => Ok(contents),
+    }
+}
+
+/// Copy a state file an older adapter wrote to `<path>.v<version>`
+///
+/// Runs before the file is first overwritten with the current version, so
+/// the backup is what the older adapter wrote, for downgrades. Loading alone
+/// never writes it, and a backup left from an earlier upgrade is replaced.
+fn back_up_older_file(path: &Path) -> Result<()> {
+    let Ok(contents) = fs::read(path) else {
+        return Ok(());
+    };
+    let document: Option<serde_json::Value> = plaintext(contents)
+        .ok()
+        .and_then(|json| serde_json::from_slice(&json).ok());
+    let Some(fields) = document.as_ref().and_then(|d| d.as_object()) else {
+        return Ok(());
+    };
+    let version = match fields.get("version").and_then(|v| v.as_str()) {
+        None | Some("") => default_version(),
+        Some(version) => version.to_string(),
+    };
+    if MIGRATIONS.iter().any(|m| m.from == version) {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(format!(".v{}", version));
+        fs::copy(path, &backup)?;
+    }
+    Ok(())
+}
+
+/// State schema version written by this adapter
+pub const STATE_VERSION: &str = "1.2";
+
+/// Upgrade of a state document from one schema version to the next
+struct Migration {
+    from: &'static str,
+    to: &'static str,
+    apply: fn(&mut serde_json::Map<String, serde_json::Value>),
+}
+
+/// Migrations in order, each starting from the version the previous one
+/// produced
+///
+/// A change to the shape of [`State`] (a renamed field, a different map
+/// key) bumps [`STATE_VERSION`] and adds a step here that rewrites older
+/// documents, rather than letting them fail to parse.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: "1.0",
+        to: "1.1",
+        apply: version_always_saved,
+    },
+    Migration {
+        from: "1.1",
+        to: "1.2",
+        apply: config_app_source,
+    },
+];
+
+/// 1.0 → 1.1: no change in shape
+///
+/// Up to 1.0, state created from scratch was saved with an empty version;
+/// from 1.1 every save records it, so later migrations can rely on it.
+fn version_always_saved(_: &mut serde_json::Map<String, serde_json::Value>) {}
+
+/// 1.1 → 1.2: no change in shape
+///
+/// 1.2 adds the `config` app source, which a 1.1 adapter can't parse; the
+/// bump makes it refuse such a state file instead of failing on the app.
+fn config_app_source(_: &mut serde_json::Map<String, serde_json::Value>) {}
+
+/// Bring a state document up to [`STATE_VERSION`]
+///
+/// A document without a version (or with an empty one) is 1.0. Returns the
+/// version the document had when migrations ran. One with a version this
+/// adapter doesn't know, such as one saved by a newer adapter, is an error:
+/// reading it as far as it parses and saving it back would drop whatever
+/// the newer adapter added and stamp it with the older version.
+///
+/// The SQLite backend keeps some fields in tables of their own, outside the
+/// document; a migration of those fields needs a matching schema change in
+/// `state_sqlite.rs`.
+pub fn migrate(document: &mut serde_json::Value) -> Result<Option<String>> {
+    let fields = document
+        .as_object_mut()
+        .ok_or_else(|| AdapterError::State("State is not a JSON object".to_string()))?;
+    let original = match fields.get("version").and_then(|v| v.as_str()) {
+        None | Some("") => default_version(),
+        Some(version) => version.to_string(),
+    };
+
+    let mut version = original.as_str();
+    while let Some(step) = MIGRATIONS.iter().find(|m| m.from == version) {
+        (step.apply)(fields);
+        tracing::info!("Migrated state from version {} to {}", step.from, step.to);
+        version = step.to;
+    }
+    if version != STATE_VERSION {
+        return Err(AdapterError::State(format!(
+            "State version {} is unknown to this adapter (which writes {}); \
+             was it saved by a newer version?",
+            version, STATE_VERSION
+        )));
+    }
+    fields.insert(
+        "version".to_string(),
+        serde_json::Value::String(STATE_VERSION.to_string()),
+    );
+    Ok((original != STATE_VERSION).then_some(original))
+}
+
+impl State {
+    /// The state as a JSON document of the current [`STATE_VERSION`]
+    pub fn document(&self) -> Result<serde_json::Value> {
+        let mut document = serde_json::to_value(self)?;
+        if let Some(fields) = document.as_object_mut() {
+            fields.insert(
+                "version".to_string(),
+                serde_json::Value::String(STATE_VERSION.to_string()),
+            );
+        }
+        Ok(document)
+    }
+
+    /// Load state, returning default if none was saved yet
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        store().load(path.as_ref())
+    }
+
+    /// Save state
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        store().save(self, path.as_ref())
+    }
 
     /// Check if an app was removed from a specific board
     pub fn is_removed_from_board(&self, board_id: &str, app_url: &str) -> bool {
+        // Removals recorded before URLs were normalized may be spelled
+        // differently (`http://host/` for `http://host`)
         self.removed_apps_by_board
             .get(board_id)
-            .map(|apps| apps.contains(app_url))
+            .map(|apps| {
+                apps.contains(app_url) || {
+                    let url = crate::homarr::normalize_url(app_url);
+                    apps.iter().any(|u| crate::homarr::normalize_url(u) == url)
+                }
+            })
             .unwrap_or(false)
     }
 
+    /// Record a synced app, replacing any entry for the same URL spelled
+    /// differently by an older version
+    ///
+    /// An app recorded before keeps the time it was first seen.
+    pub fn record_discovered_app(&mut self, url: &str, mut app: DiscoveredApp) {
+        let normalized = crate::homarr::normalize_url(url);
+        let mut first_seen = None;
+        self.discovered_apps.retain(|key, recorded| {
+            let same = key == url || crate::homarr::normalize_url(key) == normalized;
+            if same {
+                first_seen = Some(first_seen.map_or(recorded.added_at, |t: DateTime<Utc>| {
+                    t.min(recorded.added_at)
+                }));
+            }
+            !same
+        });
+        if let Some(first_seen) = first_seen {
+            app.added_at = first_seen;
+        }
+        self.discovered_apps.insert(url.to_string(), app);
+    }
+
+    /// Whether an app is pinned, with `apps pin` or by its labels when last
+    /// seen
+    pub fn is_pinned(&self, url: &str) -> bool {
+        let normalized = crate::homarr::normalize_url(url);
+        self.pinned_apps
+            .iter()
+            .chain(
+                self.discovered_apps
+                    .iter()
+                    .filter(|(_, app)| app.pinned)
+                    .map(|(url, _)| url),
+            )
+            .any(|pinned| crate::homarr::normalize_url(pinned) == normalized)
+    }
+
     /// Mark an app as removed from a specific board
-    #[allow(dead_code)]
     pub fn mark_removed_from_board(&mut self, board_id: &str, app_url: &str) {
         self.removed_apps_by_board
             .entry(board_id.to_string())
@@ -112,6 +858,220 @@ impl State {
         }
     }
 
+    /// URLs recorded for an app given by display name or URL
+    ///
+    /// Matches synced and manually added apps by name (ignoring case), and
+    /// any recorded URL that is the same once normalized.
+    pub fn app_urls(&self, app: &str) -> Vec<String> {
+        let normalized = crate::homarr::normalize_url(app);
+        let same_url = |url: &str| url == app || crate::homarr::normalize_url(url) == normalized;
+        let mut urls: Vec<String> = self
+            .discovered_apps
+            .iter()
+            .filter(|(url, recorded)| same_url(url) || recorded.name.eq_ignore_ascii_case(app))
+            .map(|(url, _)| url.clone())
+            .chain(
+                self.manual_apps
+                    .iter()
+                    .filter(|manual| same_url(&manual.url) || manual.name.eq_ignore_ascii_case(app))
+                    .map(|manual| manual.url.clone()),
+            )
+            .chain(
+                self.removed_apps_by_board
+                    .values()
+                    .flatten()
+                    .chain(&self.pinned_apps)
+                    .filter(|url| same_url(url))
+                    .cloned(),
+            )
+            .collect();
+        urls.sort();
+        urls.dedup();
+        urls
+    }
+
+    /// Let syncs add an app to its boards again
+    ///
+    /// Clears the app's removal marks on every board and forgets that it was
+    /// synced, so a sync creates it if it's missing from Homarr instead of
+    /// taking it as deleted by a user. Returns the number of boards it was
+    /// removed from.
+    pub fn unremove_app(&mut self, url: &str) -> usize {
+        let normalized = crate::homarr::normalize_url(url);
+        let same = |recorded: &str| crate::homarr::normalize_url(recorded) == normalized;
+        let mut boards = 0;
+        self.removed_apps_by_board.retain(|_, urls| {
+            let before = urls.len();
+            urls.retain(|recorded| !same(recorded));
+            if urls.len() < before {
+                boards += 1;
+            }
+            !urls.is_empty()
+        });
+        self.discovered_apps.retain(|recorded, _| !same(recorded));
+        boards
+    }
+
+    /// Forget everything recorded about an app: removals, pin, hidden tiles,
+    /// icon fallback and whether its container vanished
+    ///
+    /// The next sync handles it like an app it has never seen. A manually
+    /// added app stays defined (`apps remove` deletes it).
+    pub fn forget_app(&mut self, url: &str) {
+        self.unremove_app(url);
+        let normalized = crate::homarr::normalize_url(url);
+        let same = |recorded: &str| crate::homarr::normalize_url(recorded) == normalized;
+        self.pinned_apps.retain(|recorded| !same(recorded));
+        self.hidden_tiles.retain(|_, tiles| {
+            tiles.retain(|recorded, _| !same(recorded));
+            !tiles.is_empty()
+        });
+        self.vanished_apps.retain(|recorded, _| !same(recorded));
+        self.icon_overrides.retain(|recorded, _| !same(recorded));
+    }
+
+    /// Remember the board items of a hidden app's tile
+    pub fn hide_tile(&mut self, board_id: &str, app_url: &str, items: Vec<serde_json::Value>) {
+        self.hidden_tiles
+            .entry(board_id.to_string())
+            .or_default()
+            .insert(app_url.to_string(), items);
+    }
+
+    /// Take the remembered board items of a hidden app's tile
+    pub fn take_hidden_tile(
+        &mut self,
+        board_id: &str,
+        app_url: &str,
+    ) -> Option<Vec<serde_json::Value>> {
+        let tiles = self.hidden_tiles.get_mut(board_id)?;
+        let items = tiles.remove(app_url);
+        if tiles.is_empty() {
+            self.hidden_tiles.remove(board_id);
+        }
+        items
+    }
+
+    /// Record why a container stopped
+    pub fn record_container_stop(&mut self, container: &str, stop: ContainerStop) {
+        self.container_stops.insert(container.to_string(), stop);
+    }
+
+    /// Forget a container's stop once it runs again, returning whether one
+    /// was recorded
+    pub fn clear_container_stop(&mut self, container: &str) -> bool {
+        self.container_stops.remove(container).is_some()
+    }
+
+    /// Container apps seen by earlier syncs that are missing from `current`
+    /// (app URLs), last seen no later than `unseen_since` and not handled
+    /// yet, as (URL, name, container)
+    pub fn vanished_container_apps(
+        &self,
+        current: &HashSet<&str>,
+        unseen_since: DateTime<Utc>,
+    ) -> Vec<(String, String, String)> {
+        let current = normalized_urls(current);
+        let mut vanished: Vec<_> = self
+            .discovered_apps
+            .iter()
+            .filter(|(url, app)| {
+                !app.container_id.is_empty()
+                    && !current.contains(&crate::homarr::normalize_url(url))
+                    && app.last_seen() <= unseen_since
+                    && !self.vanished_apps.contains_key(url.as_str())
+                    && !self.is_pinned(url)
+            })
+            .map(|(url, app)| (url.clone(), app.name.clone(), app.container_id.clone()))
+            .collect();
+        vanished.sort();
+        vanished
+    }
+
+    /// Forget vanished apps that are in `current` again, returning them
+    pub fn take_returned_apps(&mut self, current: &HashSet<&str>) -> Vec<VanishedApp> {
+        let current = normalized_urls(current);
+        let returned: Vec<String> = self
+            .vanished_apps
+            .keys()
+            .filter(|url| current.contains(&crate::homarr::normalize_url(url)))
+            .cloned()
+            .collect();
+        returned
+            .iter()
+            .filter_map(|url| self.vanished_apps.remove(url))
+            .collect()
+    }
+
+    /// Queue a change for when Homarr is reachable again
+    ///
+    /// Supersedes queued changes to the same app: a delete replaces
+    /// everything queued for its URL, an upsert replaces queued deletes and
+    /// upserts to the same board.
+    pub fn queue_change(&mut self, change: PendingChange) {
+        let url = change.url().to_string();
+        self.pending_changes.retain(|queued| {
+            if queued.url() != url {
+                return true;
+            }
+            match (&change, queued) {
+                (
+                    PendingChange::Upsert { board, .. },
+                    PendingChange::Upsert { board: queued, .. },
+                ) => board != queued,
+                _ => false,
+            }
+        });
+        self.pending_changes.push(change);
+    }
+
+    /// Add a manual app, replacing any existing one with the same name
+    pub fn upsert_manual_app(&mut self, app: AppDefinition) {
+        self.manual_apps
+            .retain(|a| !a.name.eq_ignore_ascii_case(&app.name));
+        self.manual_apps.push(app);
+    }
+
+    /// Remove a manual app by name (case-insensitive), returning it if found
+    pub fn remove_manual_app(&mut self, name: &str) -> Option<AppDefinition> {
+        let index = self
+            .manual_apps
+            .iter()
+            .position(|a| a.name.eq_ignore_ascii_case(name))?;
+        Some(self.manual_apps.remove(index))
+    }
+
+    /// Record a failed sync cycle, returning the consecutive failure count
+    pub fn record_sync_failure(&mut self) -> u32 {
+        self.consecutive_sync_failures = self.consecutive_sync_failures.saturating_add(1);
+        self.consecutive_sync_failures
+    }
+
+    /// Record a successful sync cycle, returning the failure streak it ended
+    pub fn record_sync_success(&mut self) -> u32 {
+        self.rate_limited_until = None;
+        std::mem::take(&mut self.consecutive_sync_failures)
+    }
+
+    /// When syncs may resume, if Homarr's rate limiting still pauses them
+    pub fn rate_limit_pause(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.rate_limited_until.filter(|until| *until > now)
+    }
+
+    /// Check whether the last successful sync happened within `max_age`
+    pub fn is_sync_fresh(&self, max_age: chrono::Duration, now: DateTime<Utc>) -> bool {
+        self.last_sync
+            .map(|last| now.signed_duration_since(last) <= max_age)
+            .unwrap_or(false)
+    }
+
+    /// Check whether icons are due for re-validation
+    pub fn icons_due(&self, interval: chrono::Duration, now: DateTime<Utc>) -> bool {
+        self.icons_checked_at
+            .map(|checked| now.signed_duration_since(checked) >= interval)
+            .unwrap_or(true)
+    }
+
     /// Update last sync time
     pub fn update_sync_time(&mut self) {
         self.last_sync = Some(Utc::now());
@@ -226,6 +1186,97 @@ mod tests {
         assert!(nested_path.exists());
     }
 
+    #[test]
+    fn test_migrate_versions() {
+        for old in [
+            serde_json::json!({}),
+            serde_json::json!({"version": ""}),
+            serde_json::json!({"version": "1.0"}),
+        ] {
+            let mut document = old;
+            assert_eq!(migrate(&mut document).unwrap().as_deref(), Some("1.0"));
+            assert_eq!(document["version"], STATE_VERSION);
+        }
+        let mut document = serde_json::json!({"version": "1.1"});
+        assert_eq!(migrate(&mut document).unwrap().as_deref(), Some("1.1"));
+        assert_eq!(document["version"], STATE_VERSION);
+
+        let mut current = serde_json::json!({"version": STATE_VERSION});
+        assert_eq!(migrate(&mut current).unwrap(), None);
+
+        // Saved by a newer adapter: refused rather than downgraded
+        let mut newer = serde_json::json!({"version": "9.0", "first_boot_completed": true});
+        assert!(migrate(&mut newer).is_err());
+        assert_eq!(newer["version"], "9.0");
+
+        assert!(migrate(&mut serde_json::json!([])).is_err());
+    }
+
+    #[test]
+    fn test_load_migrates_old_state_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("state.json");
+        let old = r#"{"version": "1.0", "first_boot_completed": true,
+            "removed_apps_by_board": {"board-1": ["http://app.local"]}}"#;
+        fs::write(&path, old).unwrap();
+
+        let backup = temp_dir.path().join("state.json.v1.0");
+        fs::write(&backup, "stale").unwrap();
+
+        let state = State::load(&path).unwrap();
+        assert_eq!(state.version, STATE_VERSION);
+        assert!(state.first_boot_completed);
+        assert!(state.is_removed_from_board("board-1", "http://app.local"));
+        // Loading leaves the files alone
+        assert_eq!(fs::read_to_string(&backup).unwrap(), "stale");
+
+        // The first save keeps the old file, replacing an earlier backup
+        state.save(&path).unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), old);
+        let saved: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(saved["version"], STATE_VERSION);
+
+        // Later saves don't touch it; they record the current version, even
+        // for fresh state
+        State::default().save(&path).unwrap();
+        assert_eq!(fs::read_to_string(&backup).unwrap(), old);
+        let saved: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(saved["version"], STATE_VERSION);
+    }
+
+    #[test]
+    fn test_write_atomic_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("state.json");
+        write_atomic(&path, b"{}").unwrap();
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&path), 0o600);
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        write_atomic(&path, b"{\"version\":\"1.0\"}").unwrap();
+        assert_eq!(mode(&path), 0o640);
+        assert_eq!(fs::read(&path).unwrap(), b"{\"version\":\"1.0\"}");
+        assert!(!temp_dir.path().join("state.json.tmp").exists());
+    }
+
+    #[tokio::test]
+    async fn test_sync_lock_excludes_second_sync() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("state.json");
+
+        let lock = SyncLock::try_acquire(&path).unwrap().unwrap();
+        assert!(SyncLock::try_acquire(&path).unwrap().is_none());
+        assert!(matches!(
+            SyncLock::acquire(&path, Duration::ZERO).await,
+            Err(AdapterError::SyncLocked(_))
+        ));
+
+        drop(lock);
+        assert!(SyncLock::acquire(&path, Duration::ZERO).await.is_ok());
+    }
+
     // Tests for URL-based deduplication (issue #15)
 
     #[test]
@@ -239,6 +1290,9 @@ mod tests {
                 name: "Signal K".to_string(),
                 container_id: "abc123".to_string(),
                 added_at: Utc::now(),
+                last_seen: None,
+                source: AppSource::Container,
+                pinned: false,
             },
         );
 
@@ -260,6 +1314,9 @@ mod tests {
                 name: "Signal K".to_string(),
                 container_id: "abc123".to_string(),
                 added_at: Utc::now(),
+                last_seen: None,
+                source: AppSource::Container,
+                pinned: false,
             },
         );
 
@@ -272,6 +1329,9 @@ mod tests {
                 name: "Signal K".to_string(),
                 container_id: "def456".to_string(),
                 added_at: Utc::now(),
+                last_seen: None,
+                source: AppSource::Container,
+                pinned: false,
             },
         );
 
@@ -305,4 +1365,381 @@ mod tests {
         state.clear_removed_from_board("nonexistent-board", "http://app.local");
         assert!(!state.is_removed_from_board("nonexistent-board", "http://app.local"));
     }
+
+    fn manual_app(name: &str, url: &str) -> AppDefinition {
+        AppDefinition {
+            name: name.to_string(),
+            url: url.to_string(),
+            visible: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_upsert_manual_app_replaces_by_name() {
+        let mut state = State::default();
+        state.upsert_manual_app(manual_app("Router", "http://192.168.1.1"));
+        state.upsert_manual_app(manual_app("router", "http://192.168.1.254"));
+        assert_eq!(state.manual_apps.len(), 1);
+        assert_eq!(state.manual_apps[0].url, "http://192.168.1.254");
+    }
+
+    #[test]
+    fn test_remove_manual_app() {
+        let mut state = State::default();
+        state.upsert_manual_app(manual_app("NAS", "http://nas.local"));
+        assert!(state.remove_manual_app("printer").is_none());
+        let removed = state.remove_manual_app("nas").unwrap();
+        assert_eq!(removed.url, "http://nas.local");
+        assert!(state.manual_apps.is_empty());
+    }
+
+    #[test]
+    fn test_manual_apps_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_path = temp_dir.path().join("state.json");
+
+        let mut state = State::default();
+        state.upsert_manual_app(manual_app("NAS", "http://nas.local"));
+        state.save(&state_path).unwrap();
+
+        let loaded = State::load(&state_path).unwrap();
+        assert_eq!(loaded.manual_apps.len(), 1);
+        assert_eq!(loaded.manual_apps[0].name, "NAS");
+        assert!(loaded.manual_apps[0].is_visible());
+    }
+
+    #[test]
+    fn test_sync_failure_streak() {
+        let mut state = State::default();
+        assert_eq!(state.record_sync_failure(), 1);
+        assert_eq!(state.record_sync_failure(), 2);
+        assert_eq!(state.record_sync_success(), 2);
+        assert_eq!(state.consecutive_sync_failures, 0);
+        assert_eq!(state.record_sync_success(), 0);
+    }
+
+    #[test]
+    fn test_rate_limit_pause() {
+        let now = Utc::now();
+        let mut state = State::default();
+        assert_eq!(state.rate_limit_pause(now), None);
+
+        let until = now + chrono::Duration::seconds(300);
+        state.rate_limited_until = Some(until);
+        assert_eq!(state.rate_limit_pause(now), Some(until));
+        assert_eq!(state.rate_limit_pause(until), None);
+
+        state.record_sync_success();
+        assert_eq!(state.rate_limited_until, None);
+    }
+
+    #[test]
+    fn test_is_sync_fresh() {
+        let now = Utc::now();
+        let mut state = State::default();
+        assert!(!state.is_sync_fresh(chrono::Duration::seconds(900), now));
+
+        state.last_sync = Some(now - chrono::Duration::seconds(60));
+        assert!(state.is_sync_fresh(chrono::Duration::seconds(900), now));
+        assert!(!state.is_sync_fresh(chrono::Duration::seconds(30), now));
+    }
+
+    #[test]
+    fn test_icons_due() {
+        let now = Utc::now();
+        let interval = chrono::Duration::hours(24);
+        let mut state = State::default();
+        assert!(state.icons_due(interval, now));
+
+        state.icons_checked_at = Some(now - chrono::Duration::hours(1));
+        assert!(!state.icons_due(interval, now));
+
+        state.icons_checked_at = Some(now - chrono::Duration::hours(25));
+        assert!(state.icons_due(interval, now));
+    }
+
+    #[test]
+    fn test_hidden_tiles() {
+        let mut state = State::default();
+        let items = vec![serde_json::json!({"id": "registry-grafana-1"})];
+        state.hide_tile("board-1", "http://grafana.local", items.clone());
+
+        assert_eq!(
+            state.take_hidden_tile("board-2", "http://grafana.local"),
+            None
+        );
+        assert_eq!(
+            state.take_hidden_tile("board-1", "http://grafana.local"),
+            Some(items)
+        );
+        assert_eq!(
+            state.take_hidden_tile("board-1", "http://grafana.local"),
+            None
+        );
+        assert!(state.hidden_tiles.is_empty());
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let key = Key::from([7u8; 32]);
+        let data = encrypt(&key, b"{\"api_key\": \"secret\"}").unwrap();
+        assert!(data.starts_with(ENCRYPTED_HEADER));
+        assert!(!String::from_utf8_lossy(&data).contains("secret"));
+
+        let body = &data[ENCRYPTED_HEADER.len()..];
+        assert_eq!(
+            decrypt(&key, body).unwrap(),
+            b"{\"api_key\": \"secret\"}".to_vec()
+        );
+        assert!(decrypt(&Key::from([8u8; 32]), body).is_err());
+        assert!(decrypt(&key, &body[..4]).is_err());
+    }
+
+    #[test]
+    fn test_read_key_raw_and_hex() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let raw = temp_dir.path().join("raw.key");
+        fs::write(&raw, [1u8; 32]).unwrap();
+        assert_eq!(read_key(&raw).unwrap(), Key::from([1u8; 32]));
+
+        let hex = temp_dir.path().join("hex.key");
+        fs::write(&hex, format!("{}\n", "ab".repeat(32))).unwrap();
+        assert_eq!(read_key(&hex).unwrap(), Key::from([0xab; 32]));
+
+        let short = temp_dir.path().join("short.key");
+        fs::write(&short, "abcd").unwrap();
+        assert!(read_key(&short).is_err());
+    }
+
+    #[test]
+    fn test_queue_change_deduplicates() {
+        let upsert = |url: &str, board: &str| PendingChange::Upsert {
+            board: board.to_string(),
+            app: Box::new(AppDefinition {
+                name: "App".to_string(),
+                url: url.to_string(),
+                ..Default::default()
+            }),
+        };
+        let delete = |url: &str| PendingChange::Delete {
+            url: url.to_string(),
+            name: "App".to_string(),
+            remove_from_boards: false,
+        };
+        let mut state = State::default();
+
+        state.queue_change(upsert("http://a.local", "Home"));
+        state.queue_change(upsert("http://a.local", "Marine"));
+        state.queue_change(upsert("http://b.local", "Home"));
+        state.queue_change(upsert("http://a.local", "Home"));
+        let queued: Vec<&str> = state.pending_changes.iter().map(|c| c.url()).collect();
+        assert_eq!(
+            queued,
+            vec!["http://a.local", "http://b.local", "http://a.local"]
+        );
+
+        // A delete supersedes every queued change to the app
+        state.queue_change(delete("http://a.local"));
+        assert_eq!(state.pending_changes.len(), 2);
+        assert!(matches!(
+            state.pending_changes[1],
+            PendingChange::Delete { .. }
+        ));
+
+        // And an upsert supersedes the delete
+        state.queue_change(upsert("http://a.local", "Home"));
+        assert_eq!(state.pending_changes.len(), 2);
+        assert!(matches!(
+            state.pending_changes[1],
+            PendingChange::Upsert { .. }
+        ));
+    }
+
+    #[test]
+    fn test_pending_changes_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let state_path = temp_dir.path().join("state.json");
+        let mut state = State::default();
+        state.queue_change(PendingChange::Delete {
+            url: "http://a.local".to_string(),
+            name: "A".to_string(),
+            remove_from_boards: true,
+        });
+        state.save(&state_path).unwrap();
+
+        let loaded = State::load(&state_path).unwrap();
+        assert!(matches!(
+            &loaded.pending_changes[..],
+            [PendingChange::Delete {
+                remove_from_boards: true,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_container_stop_reasons() {
+        let stop = |exit_code, oom_killed| ContainerStop {
+            exit_code,
+            oom_killed,
+            at: Utc::now(),
+        };
+        assert_eq!(stop(Some(0), false).reason(), "exited normally (exit 0)");
+        assert_eq!(
+            stop(Some(143), false).reason(),
+            "stopped (SIGTERM, exit 143)"
+        );
+        assert_eq!(stop(Some(2), false).reason(), "crashed (exit 2)");
+        assert_eq!(stop(Some(137), true).reason(), "killed by the OOM killer");
+        assert_eq!(stop(None, false).reason(), "stopped (exit code unknown)");
+
+        let mut state = State::default();
+        state.record_container_stop("grafana", stop(Some(1), false));
+        let json = serde_json::to_string(&state).unwrap();
+        let mut loaded: State = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.container_stops["grafana"].exit_code, Some(1));
+        assert!(loaded.clear_container_stop("grafana"));
+        assert!(!loaded.clear_container_stop("grafana"));
+    }
+
+    #[test]
+    fn test_vanished_container_apps() {
+        let mut state = State::default();
+        for (url, container) in [
+            ("http://grafana.local", "grafana"),
+            ("http://influx.local", "influxdb"),
+            ("http://192.168.1.1", ""),
+        ] {
+            state.discovered_apps.insert(
+                url.to_string(),
+                DiscoveredApp {
+                    name: url.to_string(),
+                    container_id: container.to_string(),
+                    added_at: Utc::now(),
+                    last_seen: None,
+                    source: AppSource::Container,
+                    pinned: false,
+                },
+            );
+        }
+
+        // Apps without a container are never treated as vanished
+        let current: HashSet<&str> = ["http://grafana.local"].into_iter().collect();
+        let vanished = state.vanished_container_apps(&current, Utc::now());
+        assert_eq!(vanished.len(), 1);
+        assert_eq!(vanished[0].0, "http://influx.local");
+        assert_eq!(vanished[0].2, "influxdb");
+
+        // Not before the grace period since it was last seen is over
+        let grace = Utc::now() - chrono::Duration::days(3);
+        assert!(state.vanished_container_apps(&current, grace).is_empty());
+        state
+            .discovered_apps
+            .get_mut("http://influx.local")
+            .unwrap()
+            .last_seen = Some(grace - chrono::Duration::hours(1));
+        assert_eq!(state.vanished_container_apps(&current, grace).len(), 1);
+
+        // Pinned apps are left alone
+        state.pinned_apps.insert("http://influx.local/".to_string());
+        assert!(state.is_pinned("http://influx.local"));
+        assert!(state
+            .vanished_container_apps(&current, Utc::now())
+            .is_empty());
+        state.pinned_apps.clear();
+
+        state.vanished_apps.insert(
+            "http://influx.local".to_string(),
+            VanishedApp {
+                name: "InfluxDB".to_string(),
+                container: "influxdb".to_string(),
+                action: RemovalPolicy::Hide,
+                at: Utc::now(),
+            },
+        );
+        assert!(state
+            .vanished_container_apps(&current, Utc::now())
+            .is_empty());
+        assert!(state.take_returned_apps(&current).is_empty());
+
+        let current: HashSet<&str> = ["http://influx.local"].into_iter().collect();
+        let returned = state.take_returned_apps(&current);
+        assert_eq!(returned.len(), 1);
+        assert_eq!(returned[0].action, RemovalPolicy::Hide);
+        assert!(state.vanished_apps.is_empty());
+    }
+
+    #[test]
+    fn test_older_url_spellings_match() {
+        let mut state = State::default();
+        let app = |name: &str| DiscoveredApp {
+            name: name.to_string(),
+            container_id: "grafana".to_string(),
+            added_at: Utc::now(),
+            last_seen: None,
+            source: AppSource::Container,
+            pinned: false,
+        };
+        state
+            .discovered_apps
+            .insert("http://Grafana.local:80/".to_string(), app("Grafana"));
+        state.mark_removed_from_board("board-1", "http://Grafana.local:80/");
+
+        // Not vanished just because discovery now normalizes the URL
+        let current: HashSet<&str> = ["http://grafana.local"].into_iter().collect();
+        assert!(state
+            .vanished_container_apps(&current, Utc::now())
+            .is_empty());
+        assert!(state.is_removed_from_board("board-1", "http://grafana.local"));
+
+        state.record_discovered_app("http://grafana.local", app("Grafana"));
+        assert_eq!(state.discovered_apps.len(), 1);
+        assert!(state.discovered_apps.contains_key("http://grafana.local"));
+    }
+
+    #[test]
+    fn test_unremove_and_forget_app() {
+        let mut state = State::default();
+        state.discovered_apps.insert(
+            "http://grafana.local".to_string(),
+            DiscoveredApp {
+                name: "Grafana".to_string(),
+                container_id: "grafana".to_string(),
+                added_at: Utc::now(),
+                last_seen: None,
+                source: AppSource::Container,
+                pinned: false,
+            },
+        );
+        state.mark_removed_from_board("board-1", "http://Grafana.local:80/");
+        state.mark_removed_from_board("board-2", "http://grafana.local");
+        state.mark_removed_from_board("board-2", "http://influx.local");
+        state.pinned_apps.insert("http://influx.local".to_string());
+        state.hide_tile("board-1", "http://influx.local", vec![]);
+
+        assert_eq!(
+            state.app_urls("grafana"),
+            vec!["http://grafana.local".to_string()]
+        );
+        assert_eq!(state.app_urls("http://grafana.local/").len(), 2);
+        assert_eq!(
+            state.app_urls("http://influx.local"),
+            vec!["http://influx.local"]
+        );
+        assert!(state.app_urls("Unknown").is_empty());
+
+        assert_eq!(state.unremove_app("http://grafana.local"), 2);
+        assert!(!state.is_removed_from_board("board-1", "http://grafana.local"));
+        assert!(!state.is_removed_from_board("board-2", "http://grafana.local"));
+        // Otherwise the next sync would take it as deleted in Homarr again
+        assert!(state.discovered_apps.is_empty());
+        assert!(state.is_removed_from_board("board-2", "http://influx.local"));
+
+        state.forget_app("http://influx.local");
+        assert!(state.removed_apps_by_board.is_empty());
+        assert!(state.pinned_apps.is_empty());
+        assert!(state.hidden_tiles.is_empty());
+    }
 }
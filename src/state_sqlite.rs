@@ -0,0 +1,374 @@
+//! SQLite state backend (`state_backend = "sqlite"`)
+//!
+//! Keeps state in `state.db` next to the configured state file. Discovered
+//! apps, per-board removals and queued changes get tables of their own, and
+//! every finished sync adds a row to `sync_runs`, so app and sync history can
+//! be queried with `sqlite3`. The remaining settings-like fields are stored
+//! as one JSON document. A JSON state file found when the database doesn't
+//! exist yet is imported, then renamed to `state.json.imported`.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+use crate::error::{AdapterError, Result};
+use crate::state::{
+    migrate, AppSource, DiscoveredApp, JsonStore, PendingChange, State, StateStore,
+};
+
+/// How long to wait for another process holding the database
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Finished syncs kept in `sync_runs`
+const SYNC_RUNS_KEPT: i64 = 1000;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    json TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS discovered_apps (
+    url TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    container_id TEXT NOT NULL,
+    added_at TEXT NOT NULL,
+    last_seen TEXT,
+    source TEXT NOT NULL,
+    pinned INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS removals (
+    board_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    PRIMARY KEY (board_id, url)
+);
+CREATE TABLE IF NOT EXISTS pending_changes (
+    seq INTEGER PRIMARY KEY,
+    change TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS sync_runs (
+    finished_at TEXT PRIMARY KEY,
+    discovery_ms INTEGER,
+    planning_ms INTEGER,
+    apps_ms INTEGER,
+    board_save_ms INTEGER,
+    total_ms INTEGER
+);
+";
+
+/// Fields of [`State`] kept in tables rather than the JSON document
+const TABLE_FIELDS: &[&str] = &[
+    "discovered_apps",
+    "removed_apps_by_board",
+    "pending_changes",
+];
+
+fn db_error(e: rusqlite::Error) -> AdapterError {
+    AdapterError::State(format!("State database: {}", e))
+}
+
+/// State in an SQLite database
+pub struct SqliteStore;
+
+impl SqliteStore {
+    fn open(&self, path: &Path) -> Result<Connection> {
+        let file = self.file(path);
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(&file).map_err(db_error)?;
+        conn.busy_timeout(BUSY_TIMEOUT).map_err(db_error)?;
+        conn.execute_batch(SCHEMA).map_err(db_error)?;
+        Ok(conn)
+    }
+
+    /// Move a JSON state file into a new database
+    fn import(&self, path: &Path) -> Result<State> {
+        let state = JsonStore.load(path)?;
+        self.save(&state, path)?;
+        let mut imported = path.as_os_str().to_owned();
+        imported.push(".imported");
+        std::fs::rename(path, &imported)?;
+        // The signature goes with the file it signs
+        let signature = crate::signing::signature_path(path);
+        if signature.exists() {
+            std::fs::rename(
+                signature,
+                crate::signing::signature_path(Path::new(&imported)),
+            )?;
+        }
+        tracing::info!("Imported state from {:?} into {:?}", path, self.file(path));
+        Ok(state)
+    }
+}
+
+impl StateStore for SqliteStore {
+    fn load(&self, path: &Path) -> Result<State> {
+        if !self.file(path).exists() {
+            if path.exists() && path != self.file(path) {
+                return self.import(path);
+            }
+            return Ok(State::default());
+        }
+        let conn = self.open(path)?;
+
+        let json: Option<String> = conn
+            .query_row("SELECT json FROM state WHERE id = 1", [], |row| row.get(0))
+            .optional()
+            .map_err(db_error)?;
+        let parse_error =
+            |e: serde_json::Error| AdapterError::State(format!("Failed to parse state: {}", e));
+        let mut state: State = match json {
+            Some(json) => {
+                let mut document = serde_json::from_str(&json).map_err(parse_error)?;
+                migrate(&mut document)?;
+                serde_json::from_value(document).map_err(parse_error)?
+            }
+            None => State::default(),
+        };
+
+        let mut apps = conn
+            .prepare(
+                "SELECT url, name, container_id, added_at, last_seen, source, pinned \
+                 FROM discovered_apps",
+            )
+            .map_err(db_error)?;
+        let rows = apps
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, DateTime<Utc>>(3)?,
+                    row.get::<_, Option<DateTime<Utc>>>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, bool>(6)?,
+                ))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (url, name, container_id, added_at, last_seen, source, pinned) =
+                row.map_err(db_error)?;
+            let source: AppSource =
+                serde_json::from_value(serde_json::Value::String(source)).unwrap_or_default();
+            state.discovered_apps.insert(
+                url,
+                DiscoveredApp {
+                    name,
+                    container_id,
+                    added_at,
+                    last_seen,
+                    source,
+                    pinned,
+                },
+            );
+        }
+
+        let mut removals = conn
+            .prepare("SELECT board_id, url FROM removals")
+            .map_err(db_error)?;
+        let rows = removals
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (board_id, url) = row.map_err(db_error)?;
+            state
+                .removed_apps_by_board
+                .entry(board_id)
+                .or_default()
+                .insert(url);
+        }
+
+        let mut pending = conn
+            .prepare("SELECT change FROM pending_changes ORDER BY seq")
+            .map_err(db_error)?;
+        let rows = pending
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(db_error)?;
+        for row in rows {
+            let change: PendingChange = serde_json::from_str(&row.map_err(db_error)?)?;
+            state.pending_changes.push(change);
+        }
+
+        Ok(state)
+    }
+
+    fn save(&self, state: &State, path: &Path) -> Result<()> {
+        let mut conn = self.open(path)?;
+        let tx = conn.transaction().map_err(db_error)?;
+        write_state(&tx, state)?;
+        tx.commit().map_err(db_error)
+    }
+
+    fn file(&self, path: &Path) -> PathBuf {
+        path.with_extension("db")
+    }
+}
+
+/// Replace the saved state within a transaction
+fn write_state(tx: &Transaction, state: &State) -> Result<()> {
+    let mut document = state.document()?;
+    if let Some(fields) = document.as_object_mut() {
+        for field in TABLE_FIELDS {
+            fields.remove(*field);
+        }
+    }
+    tx.execute(
+        "INSERT OR REPLACE INTO state (id, json) VALUES (1, ?1)",
+        params![document.to_string()],
+    )
+    .map_err(db_error)?;
+
+    tx.execute("DELETE FROM discovered_apps", [])
+        .map_err(db_error)?;
+    for (url, app) in &state.discovered_apps {
+        tx.execute(
+            "INSERT INTO discovered_apps \
+             (url, name, container_id, added_at, last_seen, source, pinned) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                url,
+                app.name,
+                app.container_id,
+                app.added_at,
+                app.last_seen,
+                app.source.as_str(),
+                app.pinned
+            ],
+        )
+        .map_err(db_error)?;
+    }
+
+    tx.execute("DELETE FROM removals", []).map_err(db_error)?;
+    for (board_id, urls) in &state.removed_apps_by_board {
+        for url in urls {
+            tx.execute(
+                "INSERT INTO removals (board_id, url) VALUES (?1, ?2)",
+                params![board_id, url],
+            )
+            .map_err(db_error)?;
+        }
+    }
+
+    tx.execute("DELETE FROM pending_changes", [])
+        .map_err(db_error)?;
+    for (seq, change) in state.pending_changes.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO pending_changes (seq, change) VALUES (?1, ?2)",
+            params![seq as i64, serde_json::to_string(change)?],
+        )
+        .map_err(db_error)?;
+    }
+
+    if let Some(finished_at) = state.last_sync {
+        let timings = state.last_sync_timings.unwrap_or_default();
+        tx.execute(
+            "INSERT OR IGNORE INTO sync_runs \
+             (finished_at, discovery_ms, planning_ms, apps_ms, board_save_ms, total_ms) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                finished_at,
+                timings.discovery_ms as i64,
+                timings.planning_ms as i64,
+                timings.apps_ms as i64,
+                timings.board_save_ms as i64,
+                timings.total_ms as i64
+            ],
+        )
+        .map_err(db_error)?;
+        tx.execute(
+            "DELETE FROM sync_runs WHERE finished_at NOT IN \
+             (SELECT finished_at FROM sync_runs ORDER BY finished_at DESC LIMIT ?1)",
+            params![SYNC_RUNS_KEPT],
+        )
+        .map_err(db_error)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample_state() -> State {
+        let mut state = State {
+            first_boot_completed: true,
+            api_key: Some("abc.def".to_string()),
+            last_sync: Some(Utc::now()),
+            ..Default::default()
+        };
+        state.discovered_apps.insert(
+            "http://halos.local:3000".to_string(),
+            DiscoveredApp {
+                name: "Grafana".to_string(),
+                container_id: "abc123".to_string(),
+                added_at: Utc::now(),
+                last_seen: None,
+                source: AppSource::Container,
+                pinned: true,
+            },
+        );
+        state.mark_removed_from_board("board-1", "http://halos.local:8080");
+        state.queue_change(PendingChange::Delete {
+            url: "http://old.local".to_string(),
+            name: "Old".to_string(),
+            remove_from_boards: false,
+        });
+        state
+    }
+
+    #[test]
+    fn test_sqlite_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("state.json");
+        let store = SqliteStore;
+        assert!(!store.load(&path).unwrap().first_boot_completed);
+
+        store.save(&sample_state(), &path).unwrap();
+        assert!(dir.path().join("state.db").exists());
+        assert!(!path.exists());
+
+        let loaded = store.load(&path).unwrap();
+        assert!(loaded.first_boot_completed);
+        assert_eq!(loaded.api_key.as_deref(), Some("abc.def"));
+        let app = &loaded.discovered_apps["http://halos.local:3000"];
+        assert_eq!(app.name, "Grafana");
+        assert_eq!(app.source, AppSource::Container);
+        assert!(app.pinned);
+        assert!(loaded.is_removed_from_board("board-1", "http://halos.local:8080"));
+        assert_eq!(loaded.pending_changes.len(), 1);
+
+        // Saving again replaces rows and records each sync once
+        store.save(&loaded, &path).unwrap();
+        let conn = store.open(&path).unwrap();
+        let count = |table: &str| -> i64 {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        assert_eq!(count("discovered_apps"), 1);
+        assert_eq!(count("sync_runs"), 1);
+    }
+
+    #[test]
+    fn test_sqlite_imports_json_state() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("state.json");
+        JsonStore.save(&sample_state(), &path).unwrap();
+        std::fs::write(dir.path().join("state.json.sig"), "signature").unwrap();
+
+        let loaded = SqliteStore.load(&path).unwrap();
+        assert!(loaded.first_boot_completed);
+        assert_eq!(loaded.discovered_apps.len(), 1);
+        assert!(!path.exists());
+        assert!(dir.path().join("state.json.imported").exists());
+        assert!(!dir.path().join("state.json.sig").exists());
+        assert!(dir.path().join("state.json.imported.sig").exists());
+        assert!(SqliteStore.load(&path).unwrap().first_boot_completed);
+    }
+}
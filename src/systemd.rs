@@ -0,0 +1,135 @@
+//! systemd service notifications (`Type=notify`)
+//!
+//! Speaks the `sd_notify` protocol directly: datagrams to the socket in
+//! `NOTIFY_SOCKET`. The watch daemon reports `READY=1` once its config is
+//! loaded, a `STATUS=` line for each sync phase, and `WATCHDOG=1` keepalives
+//! from its event loop when the unit sets `WatchdogSec=`, so systemd restarts
+//! a daemon whose loop hangs. Outside systemd every call is a no-op.
+
+use std::os::unix::net::UnixDatagram;
+use std::time::Duration;
+
+/// Send a notification such as "READY=1", if running under systemd
+pub fn notify(message: &str) {
+    let Some(socket) = std::env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    if let Err(e) = send(&socket.to_string_lossy(), message) {
+        tracing::debug!("systemd notification '{}' failed: {}", message, e);
+    }
+}
+
+fn send(socket: &str, message: &str) -> std::io::Result<()> {
+    let datagram = UnixDatagram::unbound()?;
+    match socket.strip_prefix('@') {
+        // Abstract socket namespace
+        Some(name) => {
+            use std::os::linux::net::SocketAddrExt;
+            let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+            datagram.send_to_addr(message.as_bytes(), &addr)?;
+        }
+        None => {
+            datagram.send_to(message.as_bytes(), socket)?;
+        }
+    }
+    Ok(())
+}
+
+/// Report the daemon as started
+pub fn ready() {
+    notify("READY=1");
+}
+
+/// Set the status line shown by `systemctl status`
+pub fn status(text: &str) {
+    notify(&format!("STATUS={}", text));
+}
+
+/// How often to send watchdog keepalives: half of `WatchdogSec=`, if the
+/// watchdog is enabled for this process
+pub fn watchdog_interval() -> Option<Duration> {
+    parse_watchdog(
+        std::env::var("WATCHDOG_USEC").ok().as_deref(),
+        std::env::var("WATCHDOG_PID").ok().as_deref(),
+        std::process::id(),
+    )
+}
+
+fn parse_watchdog(usec: Option<&str>, pid: Option<&str>, own_pid: u32) -> Option<Duration> {
+    // The watchdog may be meant for another process of the unit
+    if pid.is_some_and(|pid| pid.parse::<u32>().ok() != Some(own_pid)) {
+        return None;
+    }
+    let usec: u64 = usec?.parse().ok().filter(|&usec| usec > 0)?;
+    Some(Duration::from_micros(usec / 2))
+}
+
+/// Keepalive timer for an event loop; never fires when the watchdog is off
+pub struct Watchdog {
+    timer: Option<tokio::time::Interval>,
+}
+
+impl Watchdog {
+    pub fn new() -> Self {
+        Self {
+            timer: watchdog_interval().map(tokio::time::interval),
+        }
+    }
+
+    /// Wait for the next keepalive and send it
+    pub async fn tick(&mut self) {
+        match self.timer.as_mut() {
+            Some(timer) => {
+                timer.tick().await;
+                notify("WATCHDOG=1");
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Run `work` while sending keepalives
+    ///
+    /// For work inside the loop that may legitimately outlast the watchdog,
+    /// such as a sync waiting for the state lock, Homarr's `Retry-After` or
+    /// Homarr starting up.
+    pub async fn keep_alive<F: std::future::Future>(&mut self, work: F) -> F::Output {
+        tokio::pin!(work);
+        loop {
+            tokio::select! {
+                output = &mut work => return output,
+                _ = self.tick() => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_watchdog() {
+        assert_eq!(
+            parse_watchdog(Some("30000000"), None, 42),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(
+            parse_watchdog(Some("30000000"), Some("42"), 42),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(parse_watchdog(Some("30000000"), Some("7"), 42), None);
+        assert_eq!(parse_watchdog(Some("0"), None, 42), None);
+        assert_eq!(parse_watchdog(None, None, 42), None);
+    }
+
+    #[test]
+    fn test_notify_sends_datagram() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("notify");
+        let receiver = UnixDatagram::bind(&path).unwrap();
+        send(path.to_str().unwrap(), "READY=1").unwrap();
+        let mut buf = [0u8; 64];
+        let len = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"READY=1");
+    }
+}
@@ -0,0 +1,48 @@
+//! Plain-text tables for command output
+//!
+//! Columns are as wide as their widest cell and two spaces apart, with
+//! trailing spaces trimmed so the last column can run on.
+
+use std::fmt::Write;
+
+/// Render a header row and cell rows, one line each
+pub fn render<const N: usize>(header: [&str; N], rows: Vec<[String; N]>) -> String {
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(header.map(str::to_string)).chain(rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        let _ = writeln!(out, "{}", line.join("  ").trim_end());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_aligns_columns() {
+        let rows = vec![
+            ["Grafana".to_string(), "yes".to_string(), String::new()],
+            [
+                "Signal K".to_string(),
+                "-".to_string(),
+                "missing".to_string(),
+            ],
+        ];
+        assert_eq!(
+            render(["APP", "HOMARR", ""], rows),
+            "APP       HOMARR\nGrafana   yes\nSignal K  -       missing\n"
+        );
+    }
+}